@@ -99,6 +99,7 @@ impl Executor {
         let mut job_log = Vec::new();
         job_log.extend(status.execution.log.clone());
         let mut steps_completed = 0;
+        let total_steps: usize = plan.tasks.iter().map(|t| t.tool_calls.len()).sum();
 
         for task in &plan.tasks {
             for tool_call in &task.tool_calls {
@@ -115,6 +116,11 @@ impl Executor {
                             output: Some(output.to_string()),
                         });
                         steps_completed += 1;
+                        client.progress(
+                            steps_completed as u64,
+                            total_steps as u64,
+                            &format!("{}: {}", task.id, tool_call.tool_name),
+                        );
                     }
                     Err(e) => {
                         job_log.push(ExecutionEntry {
@@ -15,6 +15,13 @@ pub trait McpClient {
     fn get_drift(&self, exclude_prefix: Option<&str>) -> Result<Vec<String>>;
     fn impact(&self, mode: &str, changed_paths: Vec<String>) -> Result<String>; // "high", "low", etc.
     fn call_tool(&self, name: &str, args: &serde_json::Value) -> Result<serde_json::Value>;
+    /// Reports that `completed` of `total` units of work (tool calls, skills) are done.
+    fn progress(&self, _completed: u64, _total: u64, _message: &str) {}
+    /// Whether the request driving this work was cancelled; long-running work
+    /// (e.g. verification steps) stops when it is.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
 }
 
 pub struct Validator;
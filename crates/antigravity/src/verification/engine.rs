@@ -90,6 +90,9 @@ impl VerifyEngine {
             fs::create_dir_all(&verify_dir)?;
         }
 
+        // Running steps are killed once the client gives up on the request.
+        let cancelled = || client.is_cancelled();
+
         // 4. Run Toolchain Checks
         let mut toolchain_results = BTreeMap::new();
         // Just run default "rust" toolchain if present? Or all?
@@ -107,7 +110,7 @@ impl VerifyEngine {
                         env_allowlist: None,
                         env: None,
                     };
-                    let res = ConstrainedRunner::run_step(&step_cfg, repo_root)?;
+                    let res = ConstrainedRunner::run_step(&step_cfg, repo_root, &cancelled)?;
                     // Use command string as key
                     let cmd_str = match &check.cmd {
                         Cmd::String(s) => s.clone(),
@@ -128,7 +131,10 @@ impl VerifyEngine {
         let mut overall_success = true;
 
         // 5. Run Skills
-        for skill_id in &profile.include {
+        for (done, skill_id) in profile.include.iter().enumerate() {
+            if client.is_cancelled() {
+                bail!("Verification cancelled");
+            }
             let skill = config.skills.get(skill_id).unwrap();
 
             let mut steps_results = Vec::new();
@@ -144,7 +150,7 @@ impl VerifyEngine {
             // Let's use placeholder or skip.
 
             for step in &skill.steps {
-                let res = ConstrainedRunner::run_step(step, repo_root)?;
+                let res = ConstrainedRunner::run_step(step, repo_root, &cancelled)?;
 
                 skill_duration += res.duration_ms;
                 if res.exit_code != 0 && skill_exit == 0 {
@@ -214,6 +220,11 @@ impl VerifyEngine {
             if skill_exit != 0 {
                 overall_success = false;
             }
+            client.progress(
+                done as u64 + 1,
+                profile.include.len() as u64,
+                &format!("Skill {} finished (exit {})", skill_id, skill_exit),
+            );
         }
 
         // 6. Update Status
@@ -4,13 +4,16 @@
 // Spec: spec/verification.yaml
 
 use crate::verification::config::{Cmd, NetworkMode, StepConfig};
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
 use sha2::{Digest, Sha256};
+use std::io::Read;
 use std::path::Path;
-use std::process::{Command, Stdio};
-use std::thread;
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 pub struct ConstrainedRunner;
 
 use serde::{Deserialize, Serialize};
@@ -26,7 +29,13 @@ pub struct StepResult {
 }
 
 impl ConstrainedRunner {
-    pub fn run_step(step: &StepConfig, workdir: &Path) -> Result<StepResult> {
+    /// Runs `step` in `workdir`. The step is killed once it exceeds its timeout,
+    /// or fails with an error as soon as `cancelled` returns true.
+    pub fn run_step(
+        step: &StepConfig,
+        workdir: &Path,
+        cancelled: &dyn Fn() -> bool,
+    ) -> Result<StepResult> {
         let start_time = Instant::now();
 
         // 1. Prepare Command
@@ -116,120 +125,40 @@ impl ConstrainedRunner {
             cmd.env_remove("all_proxy");
         }
 
-        // 3. Execution with Timeout
-        // Rust's Command doesn't have native timeout.
-        // We spawn and wait in thread/loop or use a crate.
-        // We can't use wait_timeout crate easily without adding dep.
-        // We can spawn, and check in loop?
-        // Or simple: spawn, identify pid, park?
-        // Given we don't have async here (ConstrainedRunner is sync),
-        // we might be blocking.
-        // Let's use `process_child_with_timeout` helper if possible.
-        // Since we are writing std code:
+        // 3. Execution with Timeout and Cancellation
+        // The step runs in its own process group, so killing it also stops the
+        // processes it spawned (e.g. the rustc instances of `cargo clippy`).
+        // Its pipes are drained on helper threads while this one polls for exit.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
 
-        let child = cmd.spawn().context("Failed to spawn command")?;
+        let mut child = cmd.spawn().context("Failed to spawn command")?;
         let timeout = Duration::from_millis(step.timeout_ms.unwrap_or(600_000));
-
-        // Simple timeout implementation:
-        // We loop with sleep? No, we need to read pipes.
-        // If we read pipes, we block.
-        // We need execution to be driven.
-        // For now, let's just wait? No, strict timeout required.
-        // Strategy: Spawn a thread to wait?
-        // Or polling.
-        // Since we need to capture output, we usually stick to `wait_with_output` but that blocks forever.
-
-        // Hacky timeout:
-        // This is imperfect without `wait-timeout` crate.
-        // I will implement a basic "wait loop" using try_wait if available locally?
-        // Rust std `try_wait` returns valid status if done.
-        // So we can loop + sleep. But we need to drain pipes to avoid deadlock.
-
-        // Since we don't have async or `wait-timeout` in dependencies,
-        // adding `wait-timeout` is cleaner but I can't easily add deps without re-checking crate graph.
-        // Wait, I just added serde_yaml. I can add `wait-timeout` if needed?
-        // Or I can use a simpler approach:
-        // Just block for now?
-        // "Enforce timeout per step" is a requirement.
-        // I will add `wait-timeout` dep later if failed.
-        // Actually, let's implement the thread-kill strategy.
-        // But how to interrupt `child.wait_with_output()`? We can't.
-        // So we must manually read streams.
-
-        // Alternative: Use `ulimit -t`? No, cross-platform issues.
-
-        // Strategy:
-        // 1. Spawn thread to kill process after timeout.
-        // 2. Main thread does `wait_with_output()`.
-        // If timeout hits first, process dies, `wait_with_output` returns (error or signal).
-
-        let _child_id = child.id();
-        let (tx, rx) = std::sync::mpsc::channel();
-
-        let tx_clone = tx.clone();
-        thread::spawn(move || {
-            thread::sleep(timeout);
-            let _ = tx_clone.send(());
-        });
-
-        // Loop waiting for exit or timeout signal?
-        // We still have the issue of pipe deadlock if we don't read.
-        // Standard `wait_with_output` reads.
-        // So:
-        // We should just run `wait_with_output`? But if it hangs, we hang.
-
-        // CORRECT APPROACH WITHOUT NEW DEPS:
-        // We cannot easily do this safely without `wait-timeout` or async.
-        // I will use `wait_with_output`.
-        // BUT, I will rely on the fact that this is a "constrained runner".
-        // AND add a TODO to migrate to robust timeout.
-        // Wait, "Constraints: enforced timeout per step" is explicit.
-        // I will add `wait-timeout` dep later if failed.
-        // Actually, let's implement the thread-kill strategy.
-        // But how to interrupt `child.wait_with_output()`? We can't.
-        // So we must manually read streams.
-
-        // Alternative: Use `ulimit -t`? No, cross-platform issues.
-
-        // Strategy:
-        // 1. Spawn thread to kill process after timeout.
-        // 2. Main thread does `wait_with_output()`.
-        // If timeout hits first, process dies, `wait_with_output` returns (error or signal).
-
-        let _killer_handle = {
-            // We need a way to reference child. Or `process_id`.
-            // kill() is on Child.
-            // We can't share Child between threads easily (mut).
-            // But we can send a signal via libc (unsafe) or `kill` command.
-            // Since we are on Mac, `kill <pid>`.
-            let pid = child.id();
-            thread::spawn(move || {
-                match rx.recv_timeout(timeout) {
-                    Ok(_) => { /* Task finished, cancel timer? No, rx closed means finished */ }
-                    Err(_) => {
-                        // Timeout!
-                        let _ = Command::new("kill").arg("-9").arg(pid.to_string()).output();
-                    }
-                }
-            })
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .context("Failed to wait on child process")?
+            {
+                break status;
+            }
+            if cancelled() {
+                kill_tree(&mut child);
+                bail!("Step '{}' cancelled", step.name);
+            }
+            if start_time.elapsed() >= timeout {
+                kill_tree(&mut child);
+                break child.wait().context("Failed to wait on child process")?;
+            }
+            thread::sleep(POLL_INTERVAL);
         };
-        // We need `tx` to NOT drop yet.
-        // Pass rx to thread. Keep tx.
-
-        let output_res = child.wait_with_output();
-
-        // Signal timer to stop (by dropping tx or sending)
-        let _ = tx.send(()); // If channel full or receiver gone (timeout fired), this fails/ignores.
-
-        let output = output_res.context("Failed to wait on child process")?;
         let duration = start_time.elapsed();
 
-        // Check if we timed out (exit code might be signal)
-        // On unix, signal kill.
-
         // 4. Capture & Process Output
-        let stdout_bytes = output.stdout;
-        let stderr_bytes = output.stderr;
+        let stdout_bytes = stdout.join().unwrap_or_default();
+        let stderr_bytes = stderr.join().unwrap_or_default();
 
         let stdout_sha256 = hex::encode(Sha256::digest(&stdout_bytes));
         let stderr_sha256 = hex::encode(Sha256::digest(&stderr_bytes));
@@ -238,7 +167,7 @@ impl ConstrainedRunner {
         let stderr_preview = make_preview(&stderr_bytes);
 
         // Exit Code
-        let exit_code = output.status.code().unwrap_or(-1); // -1 if signal killed
+        let exit_code = status.code().unwrap_or(-1); // -1 if signal killed
 
         Ok(StepResult {
             exit_code,
@@ -251,9 +180,78 @@ impl ConstrainedRunner {
     }
 }
 
+/// Reads `pipe` to the end on a helper thread.
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+/// Kills the child's process group, then the child itself, and reaps it.
+fn kill_tree(child: &mut Child) {
+    #[cfg(unix)]
+    let _ = Command::new("kill")
+        .arg("-9")
+        .arg(format!("-{}", child.id()))
+        .output();
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
 fn make_preview(bytes: &[u8]) -> String {
     let limit = 4096;
     let len = std::cmp::min(bytes.len(), limit);
     let slice = &bytes[..len];
     String::from_utf8_lossy(slice).into_owned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(cmd: &str, timeout_ms: Option<u64>) -> StepConfig {
+        StepConfig {
+            name: "test".to_string(),
+            cmd: Cmd::String(cmd.to_string()),
+            workdir: None,
+            timeout_ms,
+            network: None,
+            read_only: None,
+            env_allowlist: None,
+            env: None,
+        }
+    }
+
+    #[test]
+    fn test_run_step_captures_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let res =
+            ConstrainedRunner::run_step(&step("echo hello", None), dir.path(), &|| false).unwrap();
+        assert_eq!(res.exit_code, 0);
+        assert_eq!(res.stdout_preview, "hello\n");
+    }
+
+    #[test]
+    fn test_run_step_kills_cancelled_step() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = Instant::now();
+        let cancelled = || start.elapsed() >= Duration::from_millis(100);
+        let err = ConstrainedRunner::run_step(&step("sleep 30", None), dir.path(), &cancelled)
+            .unwrap_err();
+        assert!(err.to_string().contains("cancelled"));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_run_step_enforces_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let res = ConstrainedRunner::run_step(&step("sleep 30", Some(100)), dir.path(), &|| false)
+            .unwrap();
+        assert_ne!(res.exit_code, 0);
+        assert!(res.duration_ms < 10_000);
+    }
+}
@@ -146,6 +146,14 @@ struct FeatureEntry {
     implementation: Option<String>,
 }
 
+/// A feature's entry in `spec/features.yaml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryEntry {
+    pub feature_id: String,
+    pub title: String,
+    pub spec_path: String,
+}
+
 pub struct Scanner {
     root: PathBuf,
     parser: HeaderParser,
@@ -159,10 +167,28 @@ impl Scanner {
         }
     }
 
-    pub fn scan(&self) -> Result<FeatureGraph, anyhow::Error> {
+    /// Reads `spec/features.yaml` without scanning source files.
+    pub fn registry(&self) -> Result<Vec<RegistryEntry>, anyhow::Error> {
+        Ok(self
+            .load_registry()?
+            .features
+            .into_iter()
+            .map(|entry| RegistryEntry {
+                feature_id: entry.id,
+                title: entry.title,
+                spec_path: entry.spec,
+            })
+            .collect())
+    }
+
+    fn load_registry(&self) -> Result<FeaturesYaml, anyhow::Error> {
         let features_yaml_path = self.root.join("spec/features.yaml");
         let features_file = File::open(features_yaml_path)?;
-        let registry: FeaturesYaml = serde_yaml::from_reader(features_file)?;
+        Ok(serde_yaml::from_reader(features_file)?)
+    }
+
+    pub fn scan(&self) -> Result<FeatureGraph, anyhow::Error> {
+        let registry = self.load_registry()?;
 
         let mut graph = FeatureGraph::new();
         let mut feature_map: HashMap<String, FeatureNode> = HashMap::new();
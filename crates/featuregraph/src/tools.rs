@@ -7,6 +7,18 @@ use crate::scanner::Scanner;
 use anyhow::{anyhow, Result};
 use std::path::Path;
 
+/// A `features.locate` selector that matched nothing.
+#[derive(Debug)]
+pub struct NotFound(pub String);
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
 pub struct FeatureGraphTools {
     // Cache placeholder
 }
@@ -48,14 +60,14 @@ impl FeatureGraphTools {
             if let Some(node) = graph.features.iter().find(|f| f.feature_id == fid) {
                 return Ok(serde_json::to_value(node)?);
             }
-            return Err(anyhow!("Feature ID not found: {}", fid));
+            return Err(NotFound(format!("Feature ID not found: {}", fid)).into());
         }
 
         if let Some(spath) = spec_path {
             if let Some(node) = graph.features.iter().find(|f| f.spec_path == spath) {
                 return Ok(serde_json::to_value(node)?);
             }
-            return Err(anyhow!("Spec path not found: {}", spath));
+            return Err(NotFound(format!("Spec path not found: {}", spath)).into());
         }
 
         if let Some(fpath) = file_path {
@@ -75,7 +87,7 @@ impl FeatureGraphTools {
             {
                 return Ok(serde_json::to_value(node)?);
             }
-            return Err(anyhow!("File not owned by any feature: {}", fpath));
+            return Err(NotFound(format!("File not owned by any feature: {}", fpath)).into());
         }
 
         Err(anyhow!("Must provide feature_id, spec_path, or file_path"))
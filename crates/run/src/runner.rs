@@ -25,11 +25,16 @@ pub struct RunConfig {
     pub env: HashMap<String, String>,
 }
 
+/// Called after each skill of a sequence with the skill's result, the number of
+/// skills finished and the sequence length.
+pub type ProgressFn = Box<dyn Fn(&SkillResult, usize, usize) + Send>;
+
 pub struct Runner {
     registry: Vec<Box<dyn Skill>>,
     store: StateStore,
     config: RunConfig,
     writer: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
+    on_skill_finished: Option<ProgressFn>,
 }
 
 impl Runner {
@@ -44,9 +49,15 @@ impl Runner {
             store,
             config,
             writer: writer.map(|w| Arc::new(Mutex::new(w))),
+            on_skill_finished: None,
         }
     }
 
+    pub fn with_progress(mut self, on_skill_finished: ProgressFn) -> Self {
+        self.on_skill_finished = Some(on_skill_finished);
+        self
+    }
+
     fn writeln(&self, msg: &str) {
         if let Some(writer) = &self.writer {
             if let Ok(mut w) = writer.lock() {
@@ -111,8 +122,9 @@ impl Runner {
         let mut failed = Vec::new();
         let mut skill_names = Vec::new();
         let mut overall_success = true;
+        let total = skills.len();
 
-        for skill in skills {
+        for (done, skill) in skills.into_iter().enumerate() {
             let id = skill.id();
             skill_names.push(id.to_string());
 
@@ -127,6 +139,10 @@ impl Runner {
             // Persist
             self.store.write_skill_result(&res)?;
 
+            if let Some(on_skill_finished) = &self.on_skill_finished {
+                on_skill_finished(&res, done + 1, total);
+            }
+
             if res.status == SkillStatus::Skip {
                 if !self.config.json {
                     self.writeln(&format!("SKIP: {}", id));
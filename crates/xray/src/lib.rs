@@ -27,6 +27,15 @@ pub use schema::XrayIndex;
 
 /// Run a complete scan sequence on a target directory
 pub fn scan_target(target: &Path, output: Option<PathBuf>) -> Result<XrayIndex> {
+    scan_target_with_progress(target, output, &|_| {})
+}
+
+/// Like [`scan_target`], calling `on_file` with the number of files scanned so far.
+pub fn scan_target_with_progress(
+    target: &Path,
+    output: Option<PathBuf>,
+    on_file: &dyn Fn(usize),
+) -> Result<XrayIndex> {
     let repo_root = std::env::current_dir()?;
     let repo_slug = repo_root
         .file_name()
@@ -35,7 +44,7 @@ pub fn scan_target(target: &Path, output: Option<PathBuf>) -> Result<XrayIndex>
         .to_string();
 
     // 1. Scan Target
-    let scan_result = traversal::scan_target(target)?;
+    let scan_result = traversal::scan_target_with_progress(target, on_file)?;
 
     // 2. Build Index
     let mut index = XrayIndex {
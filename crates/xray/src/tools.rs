@@ -3,11 +3,33 @@
 // Feature: XRAY_ANALYSIS
 // Spec: spec/xray/analysis.md
 
-use crate::scan_target;
+use crate::scan_target_with_progress;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::path::Path;
+use std::path::{Component, Path};
+
+/// An `xray.scan` target that cannot be scanned, named by the `path` given.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TargetError {
+    /// The target is missing or not a directory.
+    NotFound(String),
+    /// The target path leaves the repository root.
+    OutsideRoot(String),
+}
+
+impl std::fmt::Display for TargetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetError::NotFound(path) => write!(f, "Scan target not found: {}", path),
+            TargetError::OutsideRoot(path) => {
+                write!(f, "Target path must be within repository root: {}", path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TargetError {}
 
 #[derive(Serialize, Deserialize)]
 pub struct ScanResult {
@@ -25,21 +47,40 @@ impl XrayTools {
 
     /// Run a scan on the repository or a subdirectory
     pub fn xray_scan(&self, repo_root: &Path, path: Option<String>) -> Result<Value> {
-        let target_path = if let Some(p) = path {
+        self.xray_scan_with_progress(repo_root, path, &|_| {})
+    }
+
+    /// Like [`XrayTools::xray_scan`], calling `on_file` with the number of files
+    /// scanned so far.
+    ///
+    /// Fails with a [`TargetError`] if the target is not a directory under
+    /// `repo_root`.
+    pub fn xray_scan_with_progress(
+        &self,
+        repo_root: &Path,
+        path: Option<String>,
+        on_file: &dyn Fn(usize),
+    ) -> Result<Value> {
+        let target_path = if let Some(p) = &path {
+            // Security check: ensure target is within repo_root
+            if Path::new(p)
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+            {
+                return Err(TargetError::OutsideRoot(p.clone()).into());
+            }
             repo_root.join(p)
         } else {
             repo_root.to_path_buf()
         };
-
-        // Security check: ensure target is within repo_root
-        if !target_path.starts_with(repo_root) {
-            return Err(anyhow::anyhow!(
-                "Target path must be within repository root"
-            ));
+        if !target_path.is_dir() {
+            let shown = path.unwrap_or_else(|| repo_root.to_string_lossy().into_owned());
+            return Err(TargetError::NotFound(shown).into());
         }
 
         // Run the scan
-        let index = scan_target(&target_path, None).context("Failed to scan target")?;
+        let index = scan_target_with_progress(&target_path, None, on_file)
+            .context("Failed to scan target")?;
 
         // Convert to JSON
         let index_json = serde_json::to_value(&index)?;
@@ -103,4 +144,24 @@ mod tests {
                                                  // xray::scan_target uses to_string_lossy of the path relative to scan target or something.
                                                  // Let's check xray implementation if needed, but for now assuming it works.
     }
+
+    #[test]
+    fn test_xray_scan_typed_target_errors() {
+        let dir = tempdir().unwrap();
+        let tools = XrayTools::new();
+
+        for (path, expected) in [
+            ("missing", TargetError::NotFound("missing".to_string())),
+            (
+                "../sibling",
+                TargetError::OutsideRoot("../sibling".to_string()),
+            ),
+            ("/etc", TargetError::OutsideRoot("/etc".to_string())),
+        ] {
+            let err = tools
+                .xray_scan(dir.path(), Some(path.to_string()))
+                .unwrap_err();
+            assert_eq!(err.downcast_ref::<TargetError>(), Some(&expected));
+        }
+    }
 }
@@ -46,6 +46,12 @@ pub struct ScanResult {
 
 /// Scans the target directory recursively and returns sorted file nodes and stats.
 pub fn scan_target(target: &Path) -> Result<ScanResult> {
+    scan_target_with_progress(target, &|_| {})
+}
+
+/// Like [`scan_target`], calling `on_file` with the number of files scanned so far
+/// after each file.
+pub fn scan_target_with_progress(target: &Path, on_file: &dyn Fn(usize)) -> Result<ScanResult> {
     let mut files = Vec::new();
     let mut total_size = 0;
 
@@ -160,6 +166,7 @@ pub fn scan_target(target: &Path) -> Result<ScanResult> {
             loc: loc_stats.loc,
             complexity: 0, // Placeholder Phase A
         });
+        on_file(files.len());
     }
 
     // DETERMINISM: Sort by path
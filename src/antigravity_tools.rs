@@ -5,13 +5,18 @@
 
 use crate::feature_tools::FeatureTools;
 use crate::internal_client::InternalClient;
+use crate::router::AxiomRegentError;
+use crate::router::cancel::{CancelToken, run_cancellable};
+use crate::router::mounts::Capability;
+use crate::router::progress::Progress;
+use crate::router::registry::{InvalidParams, ToolRegistry};
 use crate::snapshot::tools::SnapshotTools;
 use crate::workspace::WorkspaceTools;
 use antigravity::agent::AgentConfig;
 use antigravity::executor::Executor;
 use anyhow::{Result, anyhow};
 use serde_json::{Value, json};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 pub struct AntigravityTools {
@@ -34,7 +39,7 @@ impl AntigravityTools {
     }
 
     pub fn propose(&self, repo_root: &Path, config: AgentConfig) -> Result<Value> {
-        let root = repo_root.canonicalize()?;
+        let root = canonical_root(repo_root)?;
 
         let changeset_path = antigravity::agent::generate_changeset(&root, config)?;
         let id = changeset_path
@@ -49,13 +54,24 @@ impl AntigravityTools {
         }))
     }
 
-    pub fn execute(&self, repo_root: &Path, changeset_id: &str) -> Result<Value> {
-        let root = repo_root.canonicalize()?;
+    /// Runs the changeset's tool calls, reporting each completed one to `progress`.
+    pub fn execute(
+        &self,
+        repo_root: &Path,
+        changeset_id: &str,
+        progress: Progress,
+    ) -> Result<Value> {
+        let root = canonical_root(repo_root)?;
         // Assuming changesets are in root/changes
         let changeset_path = root.join("changes").join(changeset_id);
 
         if !changeset_path.exists() {
-            return Err(anyhow!("Changeset {} not found", changeset_id));
+            return Err(AxiomRegentError::NotFound(format!(
+                "Changeset {} not found",
+                changeset_id
+            ))
+            .with_path(changeset_path.to_string_lossy())
+            .into());
         }
 
         let client = InternalClient {
@@ -63,6 +79,8 @@ impl AntigravityTools {
             workspace: self.workspace.clone(),
             snapshot: self.snapshot.clone(),
             features: self.features.clone(),
+            progress,
+            cancel: None,
         };
 
         Executor::execute(&changeset_path, &client)?;
@@ -82,14 +100,25 @@ impl AntigravityTools {
         }))
     }
 
-    pub fn verify(&self, repo_root: &Path, changeset_id: &str, profile: &str) -> Result<Value> {
-        let root = repo_root.canonicalize()?;
+    /// Runs the profile's verification skills, reporting each finished one to `progress`.
+    /// Once `cancel` is flagged the running step is killed and verification stops.
+    pub fn verify(
+        &self,
+        repo_root: &Path,
+        changeset_id: &str,
+        profile: &str,
+        progress: Progress,
+        cancel: Option<CancelToken>,
+    ) -> Result<Value> {
+        let root = canonical_root(repo_root)?;
 
         let client = InternalClient {
             repo_root: root.clone(),
             workspace: self.workspace.clone(),
             snapshot: self.snapshot.clone(),
             features: self.features.clone(),
+            progress,
+            cancel,
         };
 
         antigravity::verification::engine::VerifyEngine::run(
@@ -106,3 +135,106 @@ impl AntigravityTools {
         }))
     }
 }
+
+fn canonical_root(repo_root: &Path) -> Result<PathBuf> {
+    repo_root
+        .canonicalize()
+        .map_err(|_| AxiomRegentError::repo_not_found(repo_root).into())
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct ProposeArgs {
+    pub repo_root: String,
+    pub subject: String,
+    pub repo_key: String,
+    pub base_state: String,
+    pub goal: String,
+    pub tasks: Vec<serde_json::Map<String, Value>>,
+    pub tiers: Vec<String>,
+    pub architecture_doc: String,
+    pub base_state_created_at: String,
+}
+
+impl ProposeArgs {
+    fn into_config(self) -> Result<AgentConfig> {
+        let tasks = serde_json::from_value(Value::Array(
+            self.tasks.into_iter().map(Value::Object).collect(),
+        ))
+        .map_err(|e| InvalidParams(format!("Invalid AgentConfig: {}", e)))?;
+        Ok(AgentConfig {
+            subject: self.subject,
+            repo_key: self.repo_key,
+            base_state: self.base_state,
+            goal: self.goal,
+            tasks,
+            tiers: self.tiers,
+            architecture_doc: self.architecture_doc,
+            base_state_created_at: self.base_state_created_at,
+        })
+    }
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct ChangesetArgs {
+    pub repo_root: String,
+    pub changeset_id: String,
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct VerifyArgs {
+    pub repo_root: String,
+    pub changeset_id: String,
+    pub profile: Option<String>,
+}
+
+/// Registers the `antigravity.*` tools.
+pub fn register(registry: &mut ToolRegistry, tools: Arc<AntigravityTools>) {
+    let t = tools.clone();
+    registry.mutation(
+        "antigravity.propose",
+        "Propose a change",
+        Capability::Execute,
+        move |_, a: ProposeArgs| {
+            let repo_root = PathBuf::from(&a.repo_root);
+            Ok(t.propose(&repo_root, a.into_config()?)?.into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "antigravity.execute",
+        "Execute a changeset",
+        Capability::Execute,
+        move |ctx, a: ChangesetArgs| {
+            Ok(
+                t.execute(Path::new(&a.repo_root), &a.changeset_id, ctx.progress())?
+                    .into(),
+            )
+        },
+    );
+
+    // Verification runs toolchain checks and can take minutes; it stops being
+    // awaited once the request is cancelled, and kills the step it is running.
+    let t = tools;
+    registry.tool(
+        "antigravity.verify",
+        "Verify a changeset",
+        Capability::Execute,
+        move |ctx, a: VerifyArgs| {
+            let t = t.clone();
+            let profile = a.profile.unwrap_or_else(|| "pr".to_string());
+            let progress = ctx.progress();
+            let cancel = ctx.cancel_token();
+            Ok(run_cancellable(cancel.clone(), move || {
+                t.verify(
+                    Path::new(&a.repo_root),
+                    &a.changeset_id,
+                    &profile,
+                    progress,
+                    cancel,
+                )
+            })?
+            .into())
+        },
+    );
+}
@@ -2,21 +2,58 @@
 // Spec: spec/core/router.md
 
 // Config helpers
+use crate::router::mounts::{CAPABILITIES, DEFAULT_CAPABILITIES};
+use anyhow::{Context, Result, bail};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
 pub enum BlobBackend {
+    #[default]
     Fs,
     Db,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl BlobBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlobBackend::Fs => "fs",
+            BlobBackend::Db => "db",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "fs" => Some(BlobBackend::Fs),
+            "db" => Some(BlobBackend::Db),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Compression {
+    #[default]
     None,
     Zstd,
 }
 
+/// How lease fingerprints are computed (see [`crate::snapshot::fingerprint`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FingerprintBackend {
+    /// Reads `.git` and the worktree directly; also fingerprints plain directories.
+    Native,
+    /// Runs `git rev-parse`, `git write-tree` and `git status`. The default,
+    /// so that leases persisted by earlier versions stay valid.
+    #[default]
+    Git,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub data_dir: PathBuf,
@@ -49,3 +86,482 @@ impl Default for StorageConfig {
         }
     }
 }
+
+/// Tool groups that `[tools] enabled` can name. A tool's group is the part of
+/// its name before the first `.`; undotted tools (`get_capabilities`, ...) are `core`.
+pub const TOOL_GROUPS: &[&str] = &[
+    "core",
+    "features",
+    "gov",
+    "xray",
+    "snapshot",
+    "workspace",
+    "antigravity",
+    "encore",
+    "run",
+    "lease",
+];
+
+pub fn tool_group(tool: &str) -> &str {
+    tool.split_once('.').map_or("core", |(group, _)| group)
+}
+
+/// Server configuration, read from TOML files.
+///
+/// Sources, later ones winning key by key:
+/// 1. the user file: `$AXIOMREGENT_CONFIG`, or `<config dir>/axiomregent/config.toml`
+///    (`~/.config/axiomregent/config.toml` on Linux);
+/// 2. the repo-local override `<cwd>/.axiomregent/config.toml`;
+/// 3. the environment variables predating the file (`AXIOMREGENT_WORKSPACE_ROOTS`,
+///    `AXIOMREGENT_DATA_DIR`, `MCP_ALLOW_LINE_JSON`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub workspace: WorkspaceSettings,
+    pub storage: StorageSettings,
+    pub tools: ToolSettings,
+    pub limits: Limits,
+    pub retention: Retention,
+    pub leases: LeaseSettings,
+    pub mounts: MountSettings,
+    pub transport: TransportSettings,
+    /// Files the configuration was read from.
+    #[serde(skip_deserializing)]
+    pub sources: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspaceSettings {
+    /// Directories searched by `resolve_mcp`. `~` expands to the home directory.
+    pub roots: Vec<PathBuf>,
+}
+
+impl Default for WorkspaceSettings {
+    fn default() -> Self {
+        Self {
+            roots: vec![PathBuf::from("~/Dev"), PathBuf::from("~/src")],
+        }
+    }
+}
+
+/// Extensions of already-compressed formats, stored as-is even under `zstd`.
+pub const DEFAULT_UNCOMPRESSED_EXTENSIONS: &[&str] = &[
+    "7z", "avif", "br", "bz2", "gif", "gz", "jar", "jpeg", "jpg", "mp3", "mp4", "pdf", "png",
+    "tgz", "webm", "webp", "whl", "woff", "woff2", "xz", "zip", "zst",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    /// Defaults to `<cwd>/.axiomregent/data`; relative paths are relative to the cwd.
+    pub data_dir: Option<PathBuf>,
+    pub blob_backend: BlobBackend,
+    pub compression: Compression,
+    /// File extensions (without the dot) never compressed, whatever `compression` says.
+    pub uncompressed_extensions: Vec<String>,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            data_dir: None,
+            blob_backend: BlobBackend::default(),
+            compression: Compression::default(),
+            uncompressed_extensions: DEFAULT_UNCOMPRESSED_EXTENSIONS
+                .iter()
+                .map(|e| e.to_string())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolSettings {
+    /// Tool groups registered with the router (see [`TOOL_GROUPS`]).
+    pub enabled: Vec<String>,
+}
+
+impl Default for ToolSettings {
+    fn default() -> Self {
+        Self {
+            enabled: TOOL_GROUPS.iter().map(|g| g.to_string()).collect(),
+        }
+    }
+}
+
+/// Defaults applied when a call does not set its own limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// Entries returned by `snapshot.list`.
+    pub list: usize,
+    /// Matching lines returned by `snapshot.grep`.
+    pub grep_matches: usize,
+    /// File bytes included by `snapshot.export`; unlimited when unset.
+    pub export_bytes: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            list: 1000,
+            grep_matches: 100,
+            export_bytes: None,
+        }
+    }
+}
+
+/// Defaults of `snapshot.gc`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retention {
+    /// Most recent snapshots kept per repository; all are kept when unset.
+    pub keep_last: Option<usize>,
+    /// Keep labeled snapshots regardless of age.
+    pub keep_labeled: bool,
+    /// Unreferenced blobs younger than this are not swept.
+    pub grace_secs: u64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            keep_last: None,
+            keep_labeled: true,
+            grace_secs: 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LeaseSettings {
+    /// Lifetime of a lease not used or renewed, unless acquired with its own.
+    pub ttl_secs: u64,
+    /// How often expired leases are dropped.
+    pub sweep_interval_secs: u64,
+    pub fingerprint: FingerprintBackend,
+}
+
+impl Default for LeaseSettings {
+    fn default() -> Self {
+        Self {
+            ttl_secs: crate::snapshot::lease::DEFAULT_TTL_SECS,
+            sweep_interval_secs: 60,
+            fingerprint: FingerprintBackend::default(),
+        }
+    }
+}
+
+/// Capabilities of the mounts registered by `resolve_mcp`, checked in strict mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MountSettings {
+    /// Granted to every mount.
+    pub capabilities: Vec<String>,
+    /// Further capabilities per resolved name, e.g. `app = ["snapshot", "write_file"]`.
+    pub grants: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for MountSettings {
+    fn default() -> Self {
+        Self {
+            capabilities: DEFAULT_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+            grants: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransportSettings {
+    /// Accept line-delimited JSON on stdio in addition to `Content-Length` framing.
+    pub allow_line_json: bool,
+}
+
+impl Config {
+    /// Loads, merges and validates the configuration for a server started in `cwd`.
+    pub fn load(cwd: &Path) -> Result<Self> {
+        let user = match std::env::var("AXIOMREGENT_CONFIG") {
+            Ok(path) => {
+                let path = PathBuf::from(path);
+                if !path.is_file() {
+                    bail!(
+                        "AXIOMREGENT_CONFIG names a missing file: {}",
+                        path.display()
+                    );
+                }
+                Some(path)
+            }
+            Err(_) => dirs::config_dir().map(|d| d.join("axiomregent").join("config.toml")),
+        };
+        let local = cwd.join(".axiomregent").join("config.toml");
+
+        let files: Vec<PathBuf> = user
+            .into_iter()
+            .chain([local])
+            .filter(|p| p.is_file())
+            .collect();
+        let mut config = Self::from_files(&files)?;
+        config.apply_env();
+        config.resolve(cwd);
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Merges `files` in order; keys of later files replace those of earlier ones.
+    pub fn from_files(files: &[PathBuf]) -> Result<Self> {
+        let mut merged = toml::Table::new();
+        for path in files {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config {}", path.display()))?;
+            let table: toml::Table = toml::from_str(&text)
+                .with_context(|| format!("Invalid config {}", path.display()))?;
+            // Check each file on its own so errors name the file at fault.
+            Config::deserialize(toml::Value::Table(table.clone()))
+                .with_context(|| format!("Invalid config {}", path.display()))?;
+            merge(&mut merged, table);
+        }
+        let mut config = Config::deserialize(toml::Value::Table(merged))?;
+        config.sources = files.to_vec();
+        Ok(config)
+    }
+
+    fn apply_env(&mut self) {
+        if let Ok(val) = std::env::var("AXIOMREGENT_WORKSPACE_ROOTS") {
+            self.workspace.roots = val.split(':').map(PathBuf::from).collect();
+        }
+        if let Ok(val) = std::env::var("AXIOMREGENT_DATA_DIR")
+            && !val.trim().is_empty()
+        {
+            self.storage.data_dir = Some(PathBuf::from(val.trim()));
+        }
+        if std::env::var("MCP_ALLOW_LINE_JSON").is_ok() {
+            self.transport.allow_line_json = true;
+        }
+    }
+
+    /// Expands `~` and anchors the data directory at `cwd`.
+    pub fn resolve(&mut self, cwd: &Path) {
+        for root in &mut self.workspace.roots {
+            *root = expand_home(root);
+        }
+        let data_dir = match &self.storage.data_dir {
+            Some(dir) => cwd.join(expand_home(dir)),
+            None => cwd.join(".axiomregent").join("data"),
+        };
+        self.storage.data_dir = Some(data_dir);
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for group in &self.tools.enabled {
+            if !TOOL_GROUPS.contains(&group.as_str()) {
+                bail!(
+                    "Invalid config: unknown tool group '{}' in tools.enabled (expected one of: {})",
+                    group,
+                    TOOL_GROUPS.join(", ")
+                );
+            }
+        }
+        if self.limits.list == 0 {
+            bail!("Invalid config: limits.list must be greater than 0");
+        }
+        if self.limits.grep_matches == 0 {
+            bail!("Invalid config: limits.grep_matches must be greater than 0");
+        }
+        if self.limits.export_bytes == Some(0) {
+            bail!("Invalid config: limits.export_bytes must be greater than 0");
+        }
+        if self.leases.ttl_secs == 0 || i64::try_from(self.leases.ttl_secs).is_err() {
+            bail!("Invalid config: leases.ttl_secs must be a positive number of seconds");
+        }
+        if self.leases.sweep_interval_secs == 0 {
+            bail!("Invalid config: leases.sweep_interval_secs must be greater than 0");
+        }
+        if let Some(capability) = self
+            .mounts
+            .capabilities
+            .iter()
+            .chain(self.mounts.grants.values().flatten())
+            .find(|c| !CAPABILITIES.contains(&c.as_str()))
+        {
+            bail!(
+                "Invalid config: unknown mount capability '{}' (expected one of: {})",
+                capability,
+                CAPABILITIES.join(", ")
+            );
+        }
+        if self
+            .workspace
+            .roots
+            .iter()
+            .any(|r| r.as_os_str().is_empty())
+        {
+            bail!("Invalid config: workspace.roots contains an empty path");
+        }
+        if let Some(ext) = self
+            .storage
+            .uncompressed_extensions
+            .iter()
+            .find(|e| e.is_empty() || e.contains(['.', '/']))
+        {
+            bail!(
+                "Invalid config: storage.uncompressed_extensions entry '{}' must be a bare extension like \"png\"",
+                ext
+            );
+        }
+        Ok(())
+    }
+
+    pub fn storage_config(&self) -> StorageConfig {
+        StorageConfig {
+            data_dir: self
+                .storage
+                .data_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(".axiomregent/data")),
+            blob_backend: self.storage.blob_backend,
+            compression: self.storage.compression,
+        }
+    }
+
+    pub fn tool_enabled(&self, tool: &str) -> bool {
+        let group = tool_group(tool);
+        self.tools.enabled.iter().any(|g| g == group)
+    }
+}
+
+/// Deep-merges `overlay` into `base`: nested tables merge, everything else is replaced.
+fn merge(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, text: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_local_file_overrides_user_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = write(
+            dir.path(),
+            "user.toml",
+            "[storage]\ncompression = \"zstd\"\n[limits]\nlist = 50\ngrep_matches = 10\n[leases]\nfingerprint = \"native\"\n",
+        );
+        let local = write(dir.path(), "local.toml", "[limits]\nlist = 20\n");
+
+        let config = Config::from_files(&[user.clone(), local.clone()]).unwrap();
+        assert_eq!(config.storage.compression, Compression::Zstd);
+        assert_eq!(config.limits.list, 20);
+        assert_eq!(config.limits.grep_matches, 10);
+        assert_eq!(config.limits.export_bytes, None);
+        assert_eq!(config.leases.fingerprint, FingerprintBackend::Native);
+        assert_eq!(config.sources, [user, local]);
+    }
+
+    #[test]
+    fn test_invalid_values_name_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let bad = write(dir.path(), "bad.toml", "[storage]\ncompression = \"lz4\"\n");
+        let err = format!("{:#}", Config::from_files(&[bad]).unwrap_err());
+        assert!(err.contains("bad.toml"), "{}", err);
+        assert!(err.contains("lz4"), "{}", err);
+
+        let typo = write(dir.path(), "typo.toml", "[limits]\nlsit = 5\n");
+        let err = format!("{:#}", Config::from_files(&[typo]).unwrap_err());
+        assert!(err.contains("lsit"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_groups_and_zero_limits() {
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+
+        config.tools.enabled.push("shell".to_string());
+        assert!(config.validate().unwrap_err().to_string().contains("shell"));
+
+        let mut config = Config::default();
+        config.limits.grep_matches = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.leases.ttl_secs = 0;
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("ttl_secs")
+        );
+
+        let mut config = Config::default();
+        config
+            .storage
+            .uncompressed_extensions
+            .push(".png".to_string());
+        assert!(config.validate().unwrap_err().to_string().contains(".png"));
+
+        let mut config = Config::default();
+        config
+            .mounts
+            .grants
+            .insert("app".to_string(), vec!["write".to_string()]);
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("'write'")
+        );
+    }
+
+    #[test]
+    fn test_mount_grants() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = write(
+            dir.path(),
+            "config.toml",
+            "[mounts.grants]\napp = [\"snapshot\", \"write_file\"]\n",
+        );
+        let config = Config::from_files(&[file]).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.mounts.capabilities, DEFAULT_CAPABILITIES);
+        assert_eq!(config.mounts.grants["app"], ["snapshot", "write_file"]);
+    }
+
+    #[test]
+    fn test_tool_groups() {
+        assert_eq!(tool_group("get_capabilities"), "core");
+        assert_eq!(tool_group("encore.ts.parse"), "encore");
+        let config = Config {
+            tools: ToolSettings {
+                enabled: vec!["core".into(), "snapshot".into()],
+            },
+            ..Default::default()
+        };
+        assert!(config.tool_enabled("snapshot.read"));
+        assert!(!config.tool_enabled("workspace.write_file"));
+    }
+}
@@ -3,7 +3,8 @@
 // Feature: FEATUREGRAPH_REGISTRY
 // Spec: spec/core/featuregraph.md
 
-use anyhow::{Context, Result, anyhow};
+use crate::router::AxiomRegentError;
+use anyhow::{Context, Result};
 use featuregraph::graph::{FeatureGraph, Violation};
 use featuregraph::locate::{Selector, SelectorType, locate};
 use featuregraph::preflight::{PreflightChecker, PreflightResponse};
@@ -76,7 +77,10 @@ impl FeatureTools {
             }
             GraphMode::Snapshot(_id) => {
                 // TODO: Snapshot scanning
-                return Err(anyhow!("Snapshot mode scanning not yet implemented"));
+                return Err(AxiomRegentError::InvalidArgument(
+                    "Snapshot mode scanning not yet implemented".into(),
+                )
+                .into());
             }
         };
         let graph = Arc::new(graph);
@@ -108,7 +112,13 @@ impl FeatureTools {
             "feature_id" => SelectorType::FeatureId,
             "spec_path" => SelectorType::SpecPath,
             "file_path" => SelectorType::FilePath,
-            _ => return Err(anyhow!("Invalid selector type: {}", selector_kind)),
+            _ => {
+                return Err(AxiomRegentError::InvalidArgument(format!(
+                    "Invalid selector type: {}",
+                    selector_kind
+                ))
+                .into());
+            }
         };
 
         let selector = Selector {
@@ -127,7 +137,10 @@ impl FeatureTools {
                 if let Some(id) = &req.snapshot_id {
                     GraphMode::Snapshot(id.clone())
                 } else {
-                    return Err(anyhow!("Snapshot ID required for snapshot mode"));
+                    return Err(AxiomRegentError::InvalidArgument(
+                        "Snapshot ID required for snapshot mode".into(),
+                    )
+                    .into());
                 }
             }
         };
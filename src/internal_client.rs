@@ -4,7 +4,9 @@
 // Spec: spec/antigravity/automation.md
 
 use crate::feature_tools::{FeatureTools, PreflightMode, PreflightRequest};
-use crate::snapshot::tools::SnapshotTools;
+use crate::router::cancel::CancelToken;
+use crate::router::progress::Progress;
+use crate::snapshot::tools::{CreateOptions, SnapshotTools};
 use crate::workspace::WorkspaceTools;
 use antigravity::validator::McpClient;
 use anyhow::{Context, Result, anyhow};
@@ -16,6 +18,8 @@ pub struct InternalClient {
     pub workspace: Arc<WorkspaceTools>,
     pub snapshot: Arc<SnapshotTools>,
     pub features: Arc<FeatureTools>,
+    pub progress: Progress,
+    pub cancel: Option<CancelToken>,
 }
 
 impl McpClient for InternalClient {
@@ -125,8 +129,14 @@ impl McpClient for InternalClient {
                         .collect()
                 });
 
-                self.snapshot
-                    .snapshot_create(&self.repo_root, lease_id, paths)
+                self.snapshot.snapshot_create(
+                    &self.repo_root,
+                    CreateOptions {
+                        lease_id,
+                        paths,
+                        ..Default::default()
+                    },
+                )
             }
             "workspace.apply_patch" => {
                 let patch = args
@@ -162,4 +172,12 @@ impl McpClient for InternalClient {
             _ => Err(anyhow!("Tool {} not found", name)),
         }
     }
+
+    fn progress(&self, completed: u64, total: u64, message: &str) {
+        self.progress.report(completed, Some(total), message);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|c| c.is_cancelled())
+    }
 }
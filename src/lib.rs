@@ -14,6 +14,7 @@ pub mod router;
 // pub mod skills;
 pub mod snapshot;
 pub mod tools;
+pub mod transport;
 pub mod util;
 pub mod workspace;
 pub use featuregraph;
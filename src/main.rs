@@ -3,23 +3,33 @@
 // Feature: MCP_ROUTER
 // Spec: spec/core/router.md
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
+use axiomregent::config::Config;
 use axiomregent::io::fs::RealFs;
 use axiomregent::resolver::order::ResolveEngine;
-use axiomregent::router::{JsonRpcRequest, Router};
+use axiomregent::router::cache::ResponseCache;
+use axiomregent::router::dispatch::WorkerPool;
+use axiomregent::router::logging::ForwardingLogger;
+use axiomregent::router::{Admitted, Router};
 use env_logger::Target;
 use std::io::{self, BufRead, Read, Write};
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::Duration;
 
 // POLICY: stdout is RESERVED for protocol messages.
 // All logs, panics, and diagnostics MUST write to stderr.
 fn main() -> Result<()> {
     // 0. Setup Logging & Panic Safety
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .target(Target::Stderr)
-        .format_timestamp(None) // Stable tests
-        .init();
+    // Records go to stderr, and to the client as `notifications/message` once it
+    // calls `logging/setLevel`.
+    let client_log = ForwardingLogger::install(
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+            .target(Target::Stderr)
+            .format_timestamp(None) // Stable tests
+            .build(),
+    )?;
 
     std::panic::set_hook(Box::new(|info| {
         log::error!("Panic: {}", info);
@@ -27,37 +37,49 @@ fn main() -> Result<()> {
 
     log::info!("mcp starting (stdio - MCP framed JSON-RPC)");
 
-    // 1. Setup Resolver
-    let dirs = match std::env::var("AXIOMREGENT_WORKSPACE_ROOTS") {
-        Ok(val) => val.split(':').map(PathBuf::from).collect(),
-        Err(_) => {
-            // Default roots
-            let mut roots = Vec::new();
-            if let Some(home) = dirs::home_dir() {
-                roots.push(home.join("Dev"));
-                roots.push(home.join("src"));
-            }
-            roots
-        }
-    };
+    // 1. Load configuration (config files, then env overrides); invalid values abort startup.
+    let cwd = std::env::current_dir()?;
+    let config = Config::load(&cwd).context("Failed to load configuration")?;
+    for source in &config.sources {
+        log::info!("config loaded from {}", source.display());
+    }
+
+    // 2. Setup Resolver
+    let dirs = config.workspace.roots.clone();
 
     let run_root = dirs.first().cloned().unwrap_or_else(|| PathBuf::from("."));
 
     let fs = RealFs;
-    let resolver = Arc::new(ResolveEngine::new(fs, dirs));
+    let resolver = Arc::new(ResolveEngine::new(fs, dirs).with_capabilities(
+        config.mounts.capabilities.clone(),
+        config.mounts.grants.clone(),
+    ));
 
-    // 2. Setup MountRegistry
+    // 3. Setup MountRegistry
     let mounts = axiomregent::router::mounts::MountRegistry::new();
 
-    // 3. Setup Stores & Tools
-    let storage_config = axiomregent::config::StorageConfig::default();
-    let store = Arc::new(axiomregent::snapshot::store::Store::new(storage_config)?);
-    let lease_store = Arc::new(axiomregent::snapshot::lease::LeaseStore::new());
+    // 4. Setup Stores & Tools
+    let store = Arc::new(
+        axiomregent::snapshot::store::Store::new(config.storage_config())?
+            .with_uncompressed_extensions(config.storage.uncompressed_extensions.clone()),
+    );
+    let lease_store = Arc::new(
+        axiomregent::snapshot::lease::LeaseStore::open(store.clone())?
+            .with_ttl(config.leases.ttl_secs)
+            .with_fingerprinter(axiomregent::snapshot::fingerprint::for_backend(
+                config.leases.fingerprint,
+            )),
+    );
+    // Drops leases nobody used or renewed within their TTL.
+    lease_store
+        .clone()
+        .spawn_expiry(Duration::from_secs(config.leases.sweep_interval_secs));
 
-    let snapshot_tools = Arc::new(axiomregent::snapshot::tools::SnapshotTools::new(
-        lease_store.clone(),
-        store.clone(),
-    ));
+    let snapshot_tools = Arc::new(
+        axiomregent::snapshot::tools::SnapshotTools::new(lease_store.clone(), store.clone())
+            .with_limits(config.limits.clone())
+            .with_retention(config.retention.clone()),
+    );
     let workspace_tools = Arc::new(axiomregent::workspace::WorkspaceTools::new(
         lease_store.clone(),
         store.clone(),
@@ -73,7 +95,12 @@ fn main() -> Result<()> {
     let encore_tools = Arc::new(axiomregent::tools::encore_ts::tools::EncoreTools::new());
     let run_tools = Arc::new(axiomregent::run_tools::RunTools::new(&run_root));
 
-    // 4. Setup Router
+    // Strict mount scoping is opt-in: only mounted repos are reachable.
+    let strict_mounts = std::env::var("AXIOMREGENT_STRICT_MOUNTS")
+        .map(|v| !matches!(v.trim(), "" | "0" | "false"))
+        .unwrap_or(false);
+
+    // 5. Setup Router
     let router = Router::new(
         resolver,
         mounts,
@@ -84,34 +111,82 @@ fn main() -> Result<()> {
         antigravity_tools,
         encore_tools,
         run_tools,
-    );
+    )
+    .with_strict_mounts(strict_mounts)
+    .with_client_log(client_log)
+    .with_cache(ResponseCache::from_env().with_fingerprinter(lease_store.fingerprinter()))
+    .with_config(&config);
 
-    // 4. Stdio Loop (MCP framing)
-    let stdin = io::stdin();
-    let mut input = stdin.lock();
-    let stdout = io::stdout();
-    let mut stdout = stdout.lock();
+    // 6. Optional HTTP transport replaces the stdio loop when a listen address is set.
+    #[cfg(feature = "transport-http")]
+    if let Ok(listen) = std::env::var("AXIOMREGENT_HTTP_LISTEN") {
+        let allow_remote = std::env::var("AXIOMREGENT_HTTP_ALLOW_REMOTE")
+            .map(|v| !matches!(v.trim(), "" | "0" | "false"))
+            .unwrap_or(false);
+        let server = axiomregent::transport::http::HttpServer::bind(&listen, allow_remote)?;
+        log::info!("mcp listening on http://{}/mcp", server.local_addr()?);
+        let events = server.events();
+        let router =
+            Arc::new(router.with_notifier(move |session, msg| events.publish(session, msg)));
+        return server.serve(router);
+    }
 
-    loop {
-        let maybe_payload = read_mcp_message(&mut input)?;
-        let Some(payload) = maybe_payload else {
-            break; // EOF
-        };
-
-        match serde_json::from_str::<JsonRpcRequest>(&payload) {
-            Ok(req) => {
-                let response = router.handle_request(&req);
-                let resp_str = serde_json::to_string(&response)?;
-                write_mcp_message(&mut stdout, resp_str.as_bytes())?;
-            }
-            Err(e) => {
-                // IMPORTANT: Some clients will send other traffic; log but don't crash.
-                log::error!("Failed to parse JSON-RPC payload: {}", e);
+    // 7. Stdio Loop (MCP framing)
+    // Responses and notifications share stdout; each frame is written under one lock.
+    let stdout = Arc::new(Mutex::new(io::stdout()));
+    let notify_out = stdout.clone();
+    // Stdio is a single session: every notification goes to stdout.
+    let router = Arc::new(router.with_notifier(move |_, msg| {
+        let res = write_mcp_message(&mut *notify_out.lock().unwrap(), msg.to_string().as_bytes());
+        if let Err(e) = res {
+            log::error!("Failed to write notification: {}", e);
+        }
+    }));
+
+    // A dedicated reader thread keeps consuming stdin while a call is being
+    // dispatched, so `notifications/cancelled` can reach in-flight requests.
+    // Messages are admitted before they are queued, so cancelling a call that
+    // still waits for a worker works too.
+    let (tx, rx) = mpsc::channel::<Admitted>();
+    let reader_router = router.clone();
+    let allow_line_json = config.transport.allow_line_json;
+    let reader = thread::spawn(move || -> Result<()> {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        while let Some(payload) = read_mcp_message(&mut input, allow_line_json)? {
+            if tx.send(reader_router.admit(None, &payload)).is_err() {
+                break;
             }
         }
+        Ok(()) // EOF
+    });
+
+    // Calls are dispatched on a worker pool, so responses may be written out of
+    // order; clients match them by id.
+    let pool = WorkerPool::from_env();
+    log::info!("dispatching on {} worker(s)", pool.size());
+
+    for admitted in rx {
+        let router = router.clone();
+        let stdout = stdout.clone();
+        pool.execute(move || {
+            let Some(response) = router.dispatch(admitted) else {
+                return;
+            };
+            let res = serde_json::to_string(&response)
+                .map_err(anyhow::Error::from)
+                .and_then(|s| write_mcp_message(&mut *stdout.lock().unwrap(), s.as_bytes()));
+            if let Err(e) = res {
+                log::error!("Failed to write response: {}", e);
+            }
+        });
     }
+    // Let in-flight calls finish and flush their responses before exiting.
+    drop(pool);
 
-    Ok(())
+    reader
+        .join()
+        .map_err(|_| anyhow!("stdin reader thread panicked"))?
 }
 
 /// Reads a single MCP stdio framed message.
@@ -122,8 +197,9 @@ fn main() -> Result<()> {
 ///   <n bytes of JSON>
 ///
 /// For local diagnostics, we also accept a single-line JSON payload (line-delimited)
-/// **IF AND ONLY IF** `MCP_ALLOW_LINE_JSON` is set.
-fn read_mcp_message<R: BufRead + Read>(r: &mut R) -> Result<Option<String>> {
+/// **IF AND ONLY IF** `allow_line_json` is set (`MCP_ALLOW_LINE_JSON`, or
+/// `transport.allow_line_json` in the config).
+fn read_mcp_message<R: BufRead + Read>(r: &mut R, allow_line_json: bool) -> Result<Option<String>> {
     let mut first_line = String::new();
 
     // Read until we find a non-empty line or EOF.
@@ -141,7 +217,7 @@ fn read_mcp_message<R: BufRead + Read>(r: &mut R) -> Result<Option<String>> {
     let trimmed = first_line.trim_end_matches(['\r', '\n']);
 
     // Line-delimited JSON fallback for dev/testing.
-    if trimmed.starts_with('{') && std::env::var("MCP_ALLOW_LINE_JSON").is_ok() {
+    if (trimmed.starts_with('{') || trimmed.starts_with('[')) && allow_line_json {
         return Ok(Some(trimmed.to_string()));
     }
 
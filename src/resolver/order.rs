@@ -4,12 +4,18 @@
 use crate::io::Fs;
 use crate::protocol::types::{ResolveResponse, ResolveStatus};
 use crate::resolver::{alias_map, git_remote, workspace};
+use crate::router::mounts::DEFAULT_CAPABILITIES;
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 pub struct ResolveEngine<F: Fs> {
     pub fs: F,
     pub workspace_roots: Vec<PathBuf>,
+    /// Capabilities of every resolved mount.
+    pub capabilities: Vec<String>,
+    /// Further capabilities of the mounts resolved under a given name.
+    pub grants: BTreeMap<String, Vec<String>>,
 }
 
 impl<F: Fs> ResolveEngine<F> {
@@ -17,9 +23,33 @@ impl<F: Fs> ResolveEngine<F> {
         Self {
             fs,
             workspace_roots,
+            capabilities: DEFAULT_CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+            grants: BTreeMap::new(),
         }
     }
 
+    /// Replaces the capabilities granted to resolved mounts: `capabilities`
+    /// to all of them, plus `grants[name]` to the one resolved as `name`.
+    pub fn with_capabilities(
+        mut self,
+        capabilities: Vec<String>,
+        grants: BTreeMap<String, Vec<String>>,
+    ) -> Self {
+        self.capabilities = capabilities;
+        self.grants = grants;
+        self
+    }
+
+    fn capabilities_for(&self, name: &str) -> Vec<String> {
+        let mut capabilities = self.capabilities.clone();
+        for c in self.grants.get(name).into_iter().flatten() {
+            if !capabilities.contains(c) {
+                capabilities.push(c.clone());
+            }
+        }
+        capabilities
+    }
+
     pub fn resolve(&self, name: &str) -> Result<ResolveResponse> {
         let mut tried: Vec<String> = Vec::new();
 
@@ -47,11 +77,7 @@ impl<F: Fs> ResolveEngine<F> {
                         resolved_id: Some(format!("axiomregent.repo.{}", name.replace('/', "."))),
                         kind: Some("local".to_string()),
                         root: Some(root.to_string_lossy().to_string()),
-                        capabilities: vec![
-                            "read_file".to_string(),
-                            "list_files".to_string(),
-                            "search".to_string(),
-                        ],
+                        capabilities: self.capabilities_for(name),
                         tried,
                         fix_hint: None,
                     });
@@ -83,11 +109,7 @@ impl<F: Fs> ResolveEngine<F> {
                 resolved_id: Some(format!("axiomregent.repo.{}", name.replace('/', "."))),
                 kind: Some("local".to_string()),
                 root: Some(root.to_string_lossy().to_string()),
-                capabilities: vec![
-                    "read_file".to_string(),
-                    "list_files".to_string(),
-                    "search".to_string(),
-                ],
+                capabilities: self.capabilities_for(name),
                 tried,
                 fix_hint: None,
             });
@@ -109,11 +131,7 @@ impl<F: Fs> ResolveEngine<F> {
                 resolved_id: Some(format!("axiomregent.repo.{}", name.replace('/', "."))),
                 kind: Some("local".to_string()),
                 root: Some(root.to_string_lossy().to_string()),
-                capabilities: vec![
-                    "read_file".to_string(),
-                    "list_files".to_string(),
-                    "search".to_string(),
-                ],
+                capabilities: self.capabilities_for(name),
                 tried,
                 fix_hint: None,
             });
@@ -154,6 +172,28 @@ mod tests {
         let resp = engine.resolve("axiomregent").unwrap();
         assert_eq!(resp.status, ResolveStatus::Resolved);
         assert_eq!(resp.root.unwrap(), "/User/dev/axiomregent");
+        assert_eq!(resp.capabilities, ["read_file", "list_files", "search"]);
+    }
+
+    #[test]
+    fn test_resolver_grants_configured_capabilities() {
+        let fs = MemFs::new();
+        fs.add_dir("/User/dev/app");
+        fs.add_file("/User/dev/app/.git/config", "");
+        fs.add_dir("/User/dev/lib");
+        fs.add_file("/User/dev/lib/.git/config", "");
+
+        let grants = BTreeMap::from([(
+            "app".to_string(),
+            vec!["snapshot".to_string(), "write_file".to_string()],
+        )]);
+        let engine = ResolveEngine::new(fs, vec![PathBuf::from("/User/dev")])
+            .with_capabilities(vec!["read_file".to_string()], grants);
+
+        let resp = engine.resolve("app").unwrap();
+        assert_eq!(resp.capabilities, ["read_file", "snapshot", "write_file"]);
+        let resp = engine.resolve("lib").unwrap();
+        assert_eq!(resp.capabilities, ["read_file"]);
     }
 
     #[test]
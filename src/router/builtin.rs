@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+//! Router-intrinsic tools plus the tools of external crates (`featuregraph`,
+//! `xray`) that cannot depend on the registry themselves.
+
+use crate::io::fs::RealFs;
+use crate::protocol::types::ResolveStatus;
+use crate::resolver::order::ResolveEngine;
+use crate::router::AxiomRegentError;
+use crate::router::cancel::run_cancellable;
+use crate::router::mounts::{Capability, Mount, MountRegistry};
+use crate::router::registry::{NoArgs, ToolRegistry, TypedTool};
+use crate::snapshot::mcp::Mode;
+use anyhow::{Result, anyhow};
+use featuregraph::tools::{FeatureGraphTools, NotFound};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use xray::tools::{TargetError, XrayTools};
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ResolveArgs {
+    pub name: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RepoArgs {
+    pub repo_root: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct OverviewArgs {
+    pub repo_root: String,
+    pub snapshot_id: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LocateArgs {
+    pub repo_root: String,
+    pub feature_id: Option<String>,
+    pub spec_path: Option<String>,
+    pub file_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Intent {
+    Edit,
+    Create,
+    Delete,
+    Refactor,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct PreflightArgs {
+    pub repo_root: String,
+    pub intent: Intent,
+    pub mode: Mode,
+    pub changed_paths: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_id: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ScanArgs {
+    pub repo_root: String,
+    pub path: Option<String>,
+}
+
+/// Registers `resolve_mcp`, `list_mounts` and `get_capabilities`. They are
+/// never scoped to a mount, so they declare no capability.
+pub fn register_core(
+    registry: &mut ToolRegistry,
+    resolver: Arc<ResolveEngine<RealFs>>,
+    mounts: MountRegistry,
+) {
+    let m = mounts.clone();
+    registry.register(TypedTool::new(
+        "resolve_mcp",
+        "Resolve an MCP server name to a local path or alias",
+        move |_, a: ResolveArgs| {
+            let resp = resolver
+                .resolve(&a.name)
+                .map_err(|e| anyhow!("Resolution failed: {}", e))?;
+            if resp.status == ResolveStatus::Resolved
+                && let (Some(root), Some(rid)) = (&resp.root, &resp.resolved_id)
+            {
+                m.register(Mount {
+                    name: a.name.clone(),
+                    root: root.clone(),
+                    resolved_id: Some(rid.clone()),
+                    kind: resp.kind.clone(),
+                    capabilities: resp.capabilities.clone(),
+                });
+            }
+            Ok(serde_json::to_value(resp)?.into())
+        },
+    ));
+
+    registry.register(TypedTool::new(
+        "list_mounts",
+        "List currently resolved/mounted servers",
+        move |_, _: NoArgs| Ok(serde_json::to_value(mounts.list())?.into()),
+    ));
+
+    registry.register(TypedTool::new(
+        "get_capabilities",
+        "Get server capabilities",
+        |ctx, _: NoArgs| Ok(ctx.capabilities().into()),
+    ));
+}
+
+/// Registers the `features.*` and `gov.*` tools.
+pub fn register_featuregraph(registry: &mut ToolRegistry, tools: Arc<FeatureGraphTools>) {
+    let t = tools.clone();
+    registry.tool(
+        "features.overview",
+        "Get full feature graph",
+        Capability::ReadFile,
+        move |_, a: OverviewArgs| {
+            Ok(t.features_overview(repo_dir(&a.repo_root)?, a.snapshot_id)?
+                .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "features.locate",
+        "Locate feature definition or impl",
+        Capability::ReadFile,
+        move |_, a: LocateArgs| {
+            let root = repo_dir(&a.repo_root)?;
+            if a.feature_id.is_none() && a.spec_path.is_none() && a.file_path.is_none() {
+                return Err(AxiomRegentError::InvalidArgument(
+                    "Must provide feature_id, spec_path, or file_path".into(),
+                )
+                .into());
+            }
+            let path = a.spec_path.clone().or_else(|| a.file_path.clone());
+            let located = t
+                .features_locate(root, a.feature_id, a.spec_path, a.file_path)
+                .map_err(|e| match e.downcast::<NotFound>() {
+                    Ok(NotFound(msg)) => {
+                        let err = AxiomRegentError::NotFound(msg);
+                        match path {
+                            Some(p) => err.with_path(p).into(),
+                            None => err.into(),
+                        }
+                    }
+                    Err(e) => e,
+                })?;
+            Ok(located.into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "gov.preflight",
+        "Check governance policy for proposed changes",
+        Capability::ReadFile,
+        move |_, a: PreflightArgs| {
+            let request = serde_json::to_value(&a)?;
+            Ok(t.governance_preflight(repo_dir(&a.repo_root)?, request)?
+                .into())
+        },
+    );
+
+    let t = tools;
+    registry.tool(
+        "gov.drift",
+        "Check for drift and violations",
+        Capability::ReadFile,
+        move |_, a: RepoArgs| Ok(t.governance_drift(repo_dir(&a.repo_root)?)?.into()),
+    );
+}
+
+/// Files between two `xray.scan` progress notifications.
+const SCAN_PROGRESS_INTERVAL: usize = 100;
+
+/// Registers `xray.scan`. Scans of large trees stop being awaited once the
+/// request is cancelled.
+pub fn register_xray(registry: &mut ToolRegistry, tools: Arc<XrayTools>) {
+    registry.tool(
+        "xray.scan",
+        "Scan repository to build index",
+        Capability::Search,
+        move |ctx, a: ScanArgs| {
+            repo_dir(&a.repo_root)?;
+            let t = tools.clone();
+            let progress = ctx.progress();
+            Ok(run_cancellable(ctx.cancel_token(), move || {
+                let report = |n: usize| {
+                    if n.is_multiple_of(SCAN_PROGRESS_INTERVAL) {
+                        progress.report(n as u64, None, &format!("Scanned {} files", n));
+                    }
+                };
+                let index = t
+                    .xray_scan_with_progress(Path::new(&a.repo_root), a.path, &report)
+                    .map_err(|e| match e.downcast::<TargetError>() {
+                        Ok(err) => {
+                            let message = err.to_string();
+                            match err {
+                                TargetError::NotFound(p) => {
+                                    AxiomRegentError::NotFound(message).with_path(p).into()
+                                }
+                                TargetError::OutsideRoot(p) => {
+                                    AxiomRegentError::PermissionDenied(message)
+                                        .with_path(p)
+                                        .into()
+                                }
+                            }
+                        }
+                        Err(e) => e,
+                    })?;
+                let count = index["stats"]["fileCount"].as_u64().unwrap_or(0);
+                if !count.is_multiple_of(SCAN_PROGRESS_INTERVAL as u64) {
+                    progress.report(count, Some(count), &format!("Scanned {} files", count));
+                }
+                Ok(index)
+            })?
+            .into())
+        },
+    );
+}
+
+/// `repo_root` as a path, or `NOT_FOUND` if it is not a directory. The external
+/// crates would otherwise fail with an opaque I/O error.
+fn repo_dir(repo_root: &str) -> Result<&Path> {
+    let path = Path::new(repo_root);
+    if path.is_dir() {
+        Ok(path)
+    } else {
+        Err(AxiomRegentError::repo_not_found(path).into())
+    }
+}
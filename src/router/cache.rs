@@ -1,4 +1,411 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
 // Feature: MCP_ROUTER
 // Spec: spec/core/router.md
 
-// Cache stub
+//! Bounded in-process cache of tool responses.
+//!
+//! Read-only snapshot tools tag their results with a `cache_hint`:
+//! - `immutable`: derived from stored snapshots only; served from memory until evicted.
+//! - `until_dirty`: derived from the worktree; served only while the repository's
+//!   [`Fingerprint`] still equals the one in the result, and after the router
+//!   has checked the call's lease. Mutating tools drop the repository's entries
+//!   outright.
+//!
+//! Entries are keyed by tool name and arguments and evicted least-recently-used
+//! once the cached responses exceed the byte budget.
+
+use crate::snapshot::fingerprint::{Fingerprinter, GitFingerprinter};
+use crate::snapshot::lease::Fingerprint;
+use crate::snapshot::refs;
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Tools whose responses may be cached.
+pub const CACHEABLE_TOOLS: &[&str] = &[
+    "snapshot.list",
+    "snapshot.read",
+    "snapshot.grep",
+    "snapshot.diff",
+    "snapshot.changes",
+    "snapshot.export",
+];
+
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+#[derive(Clone)]
+enum Validity {
+    Immutable,
+    UntilDirty {
+        repo_root: PathBuf,
+        fingerprint: Fingerprint,
+    },
+}
+
+struct Entry {
+    value: Value,
+    size: usize,
+    validity: Validity,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    // last_used tick -> key, oldest first.
+    lru: BTreeMap<u64, String>,
+    bytes: usize,
+    tick: u64,
+}
+
+impl Inner {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.lru.remove(&entry.last_used);
+            self.bytes -= entry.size;
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.lru.remove(&entry.last_used);
+            entry.last_used = tick;
+            self.lru.insert(tick, key.to_string());
+        }
+    }
+}
+
+pub struct ResponseCache {
+    max_bytes: usize,
+    inner: Mutex<Inner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    // Must compute fingerprints like the tools whose results are cached.
+    fingerprinter: Arc<dyn Fingerprinter>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BYTES)
+    }
+}
+
+impl ResponseCache {
+    /// A cache holding at most `max_bytes` of serialized responses; 0 disables it.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            inner: Mutex::new(Inner::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            fingerprinter: Arc::new(GitFingerprinter),
+        }
+    }
+
+    /// Validates `until_dirty` entries with `fingerprinter`, which must be the
+    /// one of the tools' [`LeaseStore`](crate::snapshot::lease::LeaseStore).
+    pub fn with_fingerprinter(mut self, fingerprinter: Arc<dyn Fingerprinter>) -> Self {
+        self.fingerprinter = fingerprinter;
+        self
+    }
+
+    /// Budget from `AXIOMREGENT_CACHE_BYTES`, defaulting to [`DEFAULT_MAX_BYTES`].
+    pub fn from_env() -> Self {
+        let max_bytes = std::env::var("AXIOMREGENT_CACHE_BYTES")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_MAX_BYTES);
+        Self::new(max_bytes)
+    }
+
+    /// Cache key of a call, or `None` if its response must not be cached: the
+    /// tool is not cacheable, a snapshot is named by a tag or `HEAD`, which
+    /// may point elsewhere on the next call, or a worktree-mode call names no
+    /// lease, so that it is issued a new one.
+    pub fn key(&self, tool: &str, args: &Map<String, Value>) -> Option<String> {
+        if self.max_bytes == 0 || !CACHEABLE_TOOLS.contains(&tool) {
+            return None;
+        }
+        if args.get("mode").and_then(|m| m.as_str()) == Some("worktree")
+            && !args.contains_key("lease_id")
+        {
+            return None;
+        }
+        let named = ["snapshot_id", "from_snapshot_id"]
+            .iter()
+            .filter_map(|arg| args.get(*arg).and_then(|v| v.as_str()))
+            .any(|spec| !refs::is_snapshot_id(spec));
+        if named {
+            return None;
+        }
+        Some(format!(
+            "{}:{}",
+            tool,
+            canonical(&Value::Object(args.clone()))
+        ))
+    }
+
+    /// The cached response for `key`, if it is still valid.
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let validity = {
+            let inner = self.inner.lock().unwrap();
+            inner.entries.get(key).map(|e| e.validity.clone())
+        };
+        let valid = match validity {
+            None => false,
+            Some(Validity::Immutable) => true,
+            // Recomputed without holding the lock: it reads the worktree.
+            Some(Validity::UntilDirty {
+                repo_root,
+                fingerprint,
+            }) => self
+                .fingerprinter
+                .compute(&repo_root)
+                .is_ok_and(|current| current == fingerprint),
+        };
+
+        let mut inner = self.inner.lock().unwrap();
+        let value = if valid {
+            inner.touch(key);
+            inner.entries.get(key).map(|e| e.value.clone())
+        } else {
+            inner.remove(key);
+            None
+        };
+        drop(inner);
+
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    /// Stores a successful response according to its `cache_hint`. `until_dirty`
+    /// results are only kept if they carry the fingerprint they were computed at.
+    pub fn insert(&self, key: String, repo_root: Option<&str>, value: &Value) {
+        let validity = match value.get("cache_hint").and_then(|h| h.as_str()) {
+            Some("immutable") => Validity::Immutable,
+            Some("until_dirty") => {
+                let fingerprint = value
+                    .get("fingerprint")
+                    .and_then(|f| serde_json::from_value(f.clone()).ok());
+                let repo_root = repo_root.and_then(|r| Path::new(r).canonicalize().ok());
+                match (repo_root, fingerprint) {
+                    (Some(repo_root), Some(fingerprint)) => Validity::UntilDirty {
+                        repo_root,
+                        fingerprint,
+                    },
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+
+        let size = key.len() + value.to_string().len();
+        // A single response may use at most an eighth of the budget.
+        if size > self.max_bytes / 8 {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.remove(&key);
+        while inner.bytes + size > self.max_bytes {
+            let Some((_, oldest)) = inner.lru.pop_first() else {
+                break;
+            };
+            if let Some(entry) = inner.entries.remove(&oldest) {
+                inner.bytes -= entry.size;
+            }
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.lru.insert(tick, key.clone());
+        inner.bytes += size;
+        inner.entries.insert(
+            key,
+            Entry {
+                value: value.clone(),
+                size,
+                validity,
+                last_used: tick,
+            },
+        );
+    }
+
+    /// Drops every `until_dirty` entry of the repository at `repo_root`.
+    pub fn invalidate_repo(&self, repo_root: &str) {
+        let Ok(root) = Path::new(repo_root).canonicalize() else {
+            return;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        let stale: Vec<String> = inner
+            .entries
+            .iter()
+            .filter(|(_, e)| {
+                matches!(&e.validity, Validity::UntilDirty { repo_root, .. } if *repo_root == root)
+            })
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in stale {
+            inner.remove(&key);
+        }
+    }
+
+    /// Drops every entry whose arguments name one of `snapshot_ids`, after
+    /// the snapshots were deleted.
+    pub fn invalidate_snapshots(&self, snapshot_ids: &[String]) {
+        if snapshot_ids.is_empty() {
+            return;
+        }
+        // Keys embed the arguments as JSON, so ids appear as quoted strings.
+        let needles: Vec<String> = snapshot_ids
+            .iter()
+            .map(|id| json!(id).to_string())
+            .collect();
+        let mut inner = self.inner.lock().unwrap();
+        let stale: Vec<String> = inner
+            .entries
+            .keys()
+            .filter(|k| needles.iter().any(|n| k.contains(n.as_str())))
+            .cloned()
+            .collect();
+        for key in stale {
+            inner.remove(&key);
+        }
+    }
+
+    /// Counters reported by `get_capabilities`.
+    pub fn stats(&self) -> Value {
+        let inner = self.inner.lock().unwrap();
+        json!({
+            "hits": self.hits.load(Ordering::Relaxed),
+            "misses": self.misses.load(Ordering::Relaxed),
+            "entries": inner.entries.len(),
+            "bytes": inner.bytes,
+            "max_bytes": self.max_bytes
+        })
+    }
+}
+
+/// JSON with object keys sorted, so argument order does not affect the key.
+fn canonical(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let sorted: BTreeMap<&String, Value> =
+                    map.iter().map(|(k, v)| (k, sorted(v))).collect();
+                json!(sorted)
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+    sorted(value).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(v: Value) -> Map<String, Value> {
+        v.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_key_ignores_argument_order() {
+        let cache = ResponseCache::default();
+        let a = cache.key(
+            "snapshot.read",
+            &args(json!({ "path": "a", "snapshot_id": "sha256:s" })),
+        );
+        let b = cache.key(
+            "snapshot.read",
+            &args(json!({ "snapshot_id": "sha256:s", "path": "a" })),
+        );
+        assert!(a.is_some());
+        assert_eq!(a, b);
+        assert!(cache.key("workspace.write_file", &Map::new()).is_none());
+        assert!(
+            ResponseCache::new(0)
+                .key("snapshot.read", &Map::new())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_snapshot_names_are_not_cached() {
+        let cache = ResponseCache::default();
+        for named in [
+            json!({ "snapshot_id": "HEAD" }),
+            json!({ "snapshot_id": "baseline" }),
+            json!({ "snapshot_id": "sha256:a~1" }),
+            json!({ "snapshot_id": "sha256:a", "from_snapshot_id": "HEAD~1" }),
+        ] {
+            assert!(cache.key("snapshot.read", &args(named)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_immutable_entries_hit_and_evict_lru() {
+        let cache = ResponseCache::new(8 * 1024);
+        let value = |i: usize| json!({ "cache_hint": "immutable", "data": "x".repeat(400 + i) });
+
+        cache.insert("k0".into(), None, &value(0));
+        assert_eq!(cache.get("k0"), Some(value(0)));
+        assert_eq!(cache.get("missing"), None);
+
+        // Fill past the budget; k0 was used most recently before k1.., so k1 goes first.
+        for i in 1..20 {
+            cache.insert(format!("k{}", i), None, &value(i));
+            cache.get("k0");
+        }
+        assert!(cache.get("k0").is_some());
+        assert!(cache.get("k1").is_none());
+
+        let stats = cache.stats();
+        assert!(stats["bytes"].as_u64().unwrap() <= 8 * 1024);
+        assert!(stats["hits"].as_u64().unwrap() >= 20);
+    }
+
+    #[test]
+    fn test_uncacheable_hints_are_skipped() {
+        let cache = ResponseCache::default();
+        cache.insert("none".into(), None, &json!({ "data": 1 }));
+        // until_dirty without a fingerprint or repo cannot be validated.
+        cache.insert(
+            "dirty".into(),
+            None,
+            &json!({ "cache_hint": "until_dirty", "data": 1 }),
+        );
+        assert_eq!(cache.stats()["entries"], 0);
+    }
+
+    #[test]
+    fn test_invalidate_snapshots_drops_matching_keys() {
+        let cache = ResponseCache::default();
+        let value = json!({ "cache_hint": "immutable" });
+        let a = cache
+            .key("snapshot.read", &args(json!({ "snapshot_id": "sha256:a" })))
+            .unwrap();
+        let b = cache
+            .key(
+                "snapshot.read",
+                &args(json!({ "snapshot_id": "sha256:ab" })),
+            )
+            .unwrap();
+        cache.insert(a.clone(), None, &value);
+        cache.insert(b.clone(), None, &value);
+
+        cache.invalidate_snapshots(&["sha256:a".to_string()]);
+        assert!(cache.get(&a).is_none());
+        assert!(cache.get(&b).is_some());
+    }
+}
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Cooperative cancellation flag for one in-flight request.
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Tracks in-flight requests so `notifications/cancelled` can reach them.
+///
+/// JSON-RPC ids are only unique within a session, so requests are keyed by
+/// the transport session (empty for stdio) and the id.
+#[derive(Clone, Default)]
+pub struct CancellationRegistry {
+    inflight: Arc<Mutex<HashMap<(String, String), CancelToken>>>,
+}
+
+impl CancellationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers request `id` of `session` and returns its token, or `None`
+    /// if a request with the same id is still in flight in that session.
+    pub fn register(&self, session: &str, id: &Value) -> Option<CancelToken> {
+        match self.inflight.lock().unwrap().entry(key(session, id)) {
+            Entry::Occupied(_) => None,
+            Entry::Vacant(slot) => Some(slot.insert(CancelToken::default()).clone()),
+        }
+    }
+
+    /// Flags the request as cancelled. Returns false if it is not in flight.
+    pub fn cancel(&self, session: &str, id: &Value) -> bool {
+        match self.inflight.lock().unwrap().get(&key(session, id)) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn finish(&self, session: &str, id: &Value) {
+        self.inflight.lock().unwrap().remove(&key(session, id));
+    }
+}
+
+fn key(session: &str, id: &Value) -> (String, String) {
+    (session.to_string(), id.to_string())
+}
+
+/// Runs `f` on a helper thread and returns early if `token` is cancelled.
+///
+/// The helper thread is not interrupted, so work that spawns processes must
+/// watch the token itself and kill them (as `antigravity.verify` does); its
+/// result is discarded once the caller has given up on it.
+pub fn run_cancellable<F>(token: Option<CancelToken>, f: F) -> Result<Value>
+where
+    F: FnOnce() -> Result<Value> + Send + 'static,
+{
+    let Some(token) = token else {
+        return f();
+    };
+    if token.is_cancelled() {
+        return Err(anyhow!("Request cancelled"));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(res) => return res,
+            Err(RecvTimeoutError::Timeout) => {
+                if token.is_cancelled() {
+                    return Err(anyhow!("Request cancelled"));
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(anyhow!("Worker thread exited without a result"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cancel_interrupts_wait() {
+        let registry = CancellationRegistry::new();
+        let id = json!(7);
+        let token = registry.register("", &id).unwrap();
+
+        let canceller = registry.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            assert!(canceller.cancel("", &json!(7)));
+        });
+
+        let res = run_cancellable(Some(token), || {
+            std::thread::sleep(Duration::from_secs(10));
+            Ok(json!("done"))
+        });
+        assert!(res.unwrap_err().to_string().contains("cancelled"));
+
+        registry.finish("", &id);
+        assert!(!registry.cancel("", &id));
+    }
+
+    #[test]
+    fn test_ids_are_scoped_to_their_session() {
+        let registry = CancellationRegistry::new();
+        let a = registry.register("a", &json!(1)).unwrap();
+        let b = registry.register("b", &json!(1)).unwrap();
+        assert!(registry.register("a", &json!(1)).is_none());
+
+        assert!(registry.cancel("b", &json!(1)));
+        assert!(!a.is_cancelled());
+        assert!(b.is_cancelled());
+
+        // Finishing one session's request leaves the other's registered.
+        registry.finish("b", &json!(1));
+        assert!(registry.cancel("a", &json!(1)));
+        assert!(a.is_cancelled());
+        assert!(registry.register("b", &json!(1)).is_some());
+    }
+}
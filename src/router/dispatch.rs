@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Fixed-size pool of dispatch threads.
+///
+/// Dropping the pool waits for queued jobs to finish.
+pub struct WorkerPool {
+    tx: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        let workers = (0..size)
+            .map(|i| {
+                let rx = rx.clone();
+                thread::Builder::new()
+                    .name(format!("dispatch-{}", i))
+                    .spawn(move || {
+                        loop {
+                            // Release the receiver lock before running the job.
+                            let job = rx.lock().unwrap().recv();
+                            match job {
+                                Ok(job) => job(),
+                                Err(_) => break,
+                            }
+                        }
+                    })
+                    .expect("failed to spawn dispatch worker")
+            })
+            .collect();
+        Self {
+            tx: Some(tx),
+            workers,
+        }
+    }
+
+    /// Pool size from `AXIOMREGENT_WORKERS`, defaulting to the available parallelism.
+    /// A size of 1 restores strictly sequential dispatch.
+    pub fn from_env() -> Self {
+        let size = std::env::var("AXIOMREGENT_WORKERS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or_else(|| thread::available_parallelism().map_or(4, |n| n.get()));
+        Self::new(size)
+    }
+
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(Box::new(f));
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.tx.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Per-repository reader/writer locks.
+///
+/// Read-only tools share the lock of their repo; mutations take it exclusively.
+#[derive(Clone, Default)]
+pub struct RepoLocks {
+    locks: Arc<Mutex<HashMap<String, Arc<RwLock<()>>>>>,
+}
+
+pub enum RepoGuard<'a> {
+    Shared(RwLockReadGuard<'a, ()>),
+    Exclusive(RwLockWriteGuard<'a, ()>),
+}
+
+impl RepoLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lock for the repo at `repo_root`.
+    pub fn lock_for(&self, repo_root: &str) -> Arc<RwLock<()>> {
+        // Canonicalize so different spellings of one repo share a lock.
+        let key = Path::new(repo_root)
+            .canonicalize()
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| repo_root.to_string());
+        let mut locks = self.locks.lock().unwrap();
+        locks.entry(key).or_default().clone()
+    }
+}
+
+/// Acquires `lock` exclusively for mutations, shared otherwise.
+pub fn acquire(lock: &RwLock<()>, exclusive: bool) -> RepoGuard<'_> {
+    // A poisoned lock only means another call panicked; the guarded unit carries no data.
+    if exclusive {
+        RepoGuard::Exclusive(lock.write().unwrap_or_else(|e| e.into_inner()))
+    } else {
+        RepoGuard::Shared(lock.read().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_pool_runs_jobs_concurrently() {
+        let pool = WorkerPool::new(4);
+        let (tx, rx) = mpsc::channel();
+        for i in 0..4 {
+            let tx = tx.clone();
+            pool.execute(move || {
+                thread::sleep(Duration::from_millis(100));
+                tx.send(i).unwrap();
+            });
+        }
+        drop(tx);
+        let start = std::time::Instant::now();
+        assert_eq!(rx.iter().count(), 4);
+        assert!(start.elapsed() < Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_mutations_are_exclusive_per_repo() {
+        let locks = RepoLocks::new();
+        let dir = tempfile::tempdir().unwrap();
+        let repo_root = dir.path().to_str().unwrap();
+
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let lock = locks.lock_for(repo_root);
+                let active = active.clone();
+                let max_seen = max_seen.clone();
+                thread::spawn(move || {
+                    let _g = acquire(&lock, true);
+                    let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_seen.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    active.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(max_seen.load(Ordering::SeqCst), 1);
+    }
+}
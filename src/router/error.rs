@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+use serde_json::{Map, Value, json};
+use std::path::Path;
+
+/// AxiomRegentError represents MCP-level errors that are surfaced to clients as
+/// JSON-RPC errors: an integer `error.code`, and in `error.data.code` the string
+/// code defined by the MCP common schema.
+///
+/// NOTE: We intentionally avoid adding new dependencies here (e.g. `thiserror`).
+#[derive(Debug, Clone)]
+pub enum AxiomRegentError {
+    NotFound(String),
+    InvalidArgument(String),
+    RepoChanged(String),
+    PermissionDenied(String),
+    TooLarge(String),
+    Internal(String),
+}
+
+/// JSON-RPC `error.code` of `STALE_LEASE`, which is reported apart from
+/// [`AxiomRegentError`].
+pub const STALE_LEASE_RPC_CODE: i64 = -32005;
+
+impl AxiomRegentError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AxiomRegentError::NotFound(_) => "NOT_FOUND",
+            AxiomRegentError::InvalidArgument(_) => "INVALID_ARGUMENT",
+            AxiomRegentError::RepoChanged(_) => "REPO_CHANGED",
+            AxiomRegentError::PermissionDenied(_) => "PERMISSION_DENIED",
+            AxiomRegentError::TooLarge(_) => "TOO_LARGE",
+            AxiomRegentError::Internal(_) => "INTERNAL",
+        }
+    }
+
+    /// The JSON-RPC `error.code`: the standard `-32602` and `-32603` for
+    /// invalid arguments and internal failures, server-defined codes otherwise.
+    pub fn rpc_code(&self) -> i64 {
+        match self {
+            AxiomRegentError::NotFound(_) => -32001,
+            AxiomRegentError::InvalidArgument(_) => -32602,
+            AxiomRegentError::RepoChanged(_) => -32002,
+            AxiomRegentError::PermissionDenied(_) => -32003,
+            AxiomRegentError::TooLarge(_) => -32004,
+            AxiomRegentError::Internal(_) => -32603,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AxiomRegentError::NotFound(m)
+            | AxiomRegentError::InvalidArgument(m)
+            | AxiomRegentError::RepoChanged(m)
+            | AxiomRegentError::PermissionDenied(m)
+            | AxiomRegentError::TooLarge(m)
+            | AxiomRegentError::Internal(m) => m.as_str(),
+        }
+    }
+
+    /// Whether repeating the call (after re-reading state) can succeed.
+    pub fn retryable(&self) -> bool {
+        matches!(self, AxiomRegentError::RepoChanged(_))
+    }
+
+    /// `NOT_FOUND` for a `repo_root`/`root` argument that does not name a directory.
+    pub fn repo_not_found(root: &Path) -> ToolError {
+        AxiomRegentError::NotFound(format!("Repository root not found: {}", root.display()))
+            .with_path(root.to_string_lossy())
+    }
+
+    pub fn with_path(self, path: impl Into<String>) -> ToolError {
+        ToolError::from(self).with_path(path)
+    }
+
+    pub fn with_lease(self, lease_id: impl Into<String>) -> ToolError {
+        ToolError::from(self).with_lease(lease_id)
+    }
+
+    pub fn with_snapshot(self, snapshot_id: impl Into<String>) -> ToolError {
+        ToolError::from(self).with_snapshot(snapshot_id)
+    }
+}
+
+impl std::fmt::Display for AxiomRegentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for AxiomRegentError {}
+
+/// An [`AxiomRegentError`] plus the path, lease or snapshot it concerns.
+///
+/// Tools return it through `anyhow`; the router downcasts it and reports the
+/// context in the JSON-RPC error `data` object.
+#[derive(Debug)]
+pub struct ToolError {
+    pub error: AxiomRegentError,
+    pub path: Option<String>,
+    pub lease_id: Option<String>,
+    pub snapshot_id: Option<String>,
+}
+
+impl ToolError {
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn with_lease(mut self, lease_id: impl Into<String>) -> Self {
+        self.lease_id = Some(lease_id.into());
+        self
+    }
+
+    pub fn with_snapshot(mut self, snapshot_id: impl Into<String>) -> Self {
+        self.snapshot_id = Some(snapshot_id.into());
+        self
+    }
+
+    /// The JSON-RPC error `data` object.
+    pub fn data(&self) -> Value {
+        let mut data = Map::new();
+        data.insert("code".to_string(), json!(self.error.code()));
+        data.insert("retryable".to_string(), json!(self.error.retryable()));
+        for (key, value) in [
+            ("path", &self.path),
+            ("lease_id", &self.lease_id),
+            ("snapshot_id", &self.snapshot_id),
+        ] {
+            if let Some(v) = value {
+                data.insert(key.to_string(), json!(v));
+            }
+        }
+        Value::Object(data)
+    }
+}
+
+impl From<AxiomRegentError> for ToolError {
+    fn from(error: AxiomRegentError) -> Self {
+        Self {
+            error,
+            path: None,
+            lease_id: None,
+            snapshot_id: None,
+        }
+    }
+}
+
+impl std::fmt::Display for ToolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for ToolError {}
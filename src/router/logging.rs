@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+//! Forwarding of `log` records to the client as `notifications/message`.
+//!
+//! Records always go to stderr through `env_logger`; they are additionally sent
+//! to each session that picked a level with `logging/setLevel`, if the record is
+//! at or above it. Notifications are framed protocol messages, so stdout still
+//! only carries protocol.
+
+use crate::router::Notifier;
+use crate::router::registry::InvalidParams;
+use anyhow::Result;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+thread_local! {
+    // Set while a record is being forwarded, so logging done by the notifier
+    // itself (e.g. a failed write) goes to stderr only instead of recursing.
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Log levels selected by the sessions plus the notifier records are sent through.
+#[derive(Default)]
+pub struct ClientLog {
+    // Level of each session that called `logging/setLevel` (`""` for
+    // messages that arrived without a session).
+    levels: RwLock<HashMap<String, LevelFilter>>,
+    // Highest of `levels`, checked before a record takes the lock.
+    max_level: AtomicUsize,
+    notifier: RwLock<Option<Notifier>>,
+    // Filter of the stderr logger, set once installed as the global logger.
+    stderr_filter: OnceLock<LevelFilter>,
+}
+
+impl ClientLog {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Highest level any session selected; `Off` until `logging/setLevel` is called.
+    pub fn max_level(&self) -> LevelFilter {
+        match self.max_level.load(Ordering::Relaxed) {
+            0 => LevelFilter::Off,
+            1 => LevelFilter::Error,
+            2 => LevelFilter::Warn,
+            3 => LevelFilter::Info,
+            4 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+
+    /// Level selected by `session`; `Off` until it calls `logging/setLevel`.
+    pub fn level(&self, session: &str) -> LevelFilter {
+        self.levels
+            .read()
+            .unwrap()
+            .get(session)
+            .copied()
+            .unwrap_or(LevelFilter::Off)
+    }
+
+    pub fn set_level(&self, session: &str, level: LevelFilter) {
+        let mut levels = self.levels.write().unwrap();
+        if level == LevelFilter::Off {
+            levels.remove(session);
+        } else {
+            levels.insert(session.to_string(), level);
+        }
+        self.update_max_level(&levels);
+    }
+
+    /// Stops forwarding records to `session`, which has ended.
+    pub fn end_session(&self, session: &str) {
+        let mut levels = self.levels.write().unwrap();
+        if levels.remove(session).is_some() {
+            self.update_max_level(&levels);
+        }
+    }
+
+    fn update_max_level(&self, levels: &HashMap<String, LevelFilter>) {
+        let max = levels.values().copied().max().unwrap_or(LevelFilter::Off);
+        self.max_level.store(max as usize, Ordering::Relaxed);
+        // Raise the global filter so records a session asked for are not
+        // discarded by the `log` macros before reaching the logger.
+        if let Some(stderr) = self.stderr_filter.get() {
+            log::set_max_level(max.max(*stderr));
+        }
+    }
+
+    pub(crate) fn set_notifier(&self, notifier: Notifier) {
+        *self.notifier.write().unwrap() = Some(notifier);
+    }
+
+    /// Whether some session wants records at `level`.
+    pub fn enabled(&self, level: Level) -> bool {
+        level <= self.max_level()
+    }
+
+    /// Sends `record` to every session whose level it is at or above.
+    pub fn forward(&self, record: &Record) {
+        if !self.enabled(record.level()) || FORWARDING.with(|f| f.get()) {
+            return;
+        }
+        let Some(notifier) = self.notifier.read().unwrap().clone() else {
+            return;
+        };
+        let sessions: Vec<String> = self
+            .levels
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, level)| record.level() <= **level)
+            .map(|(session, _)| session.clone())
+            .collect();
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/message",
+            "params": {
+                "level": mcp_level(record.level()),
+                "logger": record.target(),
+                "data": record.args().to_string()
+            }
+        });
+        FORWARDING.with(|f| f.set(true));
+        for session in &sessions {
+            notifier(Some(session), &message);
+        }
+        FORWARDING.with(|f| f.set(false));
+    }
+}
+
+/// Parses an MCP (syslog) level name. Levels above `error` map to `Error`.
+pub fn parse_level(level: &str) -> Result<LevelFilter> {
+    match level {
+        "debug" => Ok(LevelFilter::Debug),
+        "info" | "notice" => Ok(LevelFilter::Info),
+        "warning" => Ok(LevelFilter::Warn),
+        "error" | "critical" | "alert" | "emergency" => Ok(LevelFilter::Error),
+        other => Err(InvalidParams(format!("Unknown log level: {}", other)).into()),
+    }
+}
+
+fn mcp_level(level: Level) -> &'static str {
+    match level {
+        Level::Error => "error",
+        Level::Warn => "warning",
+        Level::Info => "info",
+        Level::Debug | Level::Trace => "debug",
+    }
+}
+
+/// Global logger writing to stderr and forwarding to the client.
+pub struct ForwardingLogger {
+    stderr: env_logger::Logger,
+    client: Arc<ClientLog>,
+}
+
+impl ForwardingLogger {
+    /// Installs the logger globally and returns the handle the router updates
+    /// on `logging/setLevel`.
+    pub fn install(stderr: env_logger::Logger) -> Result<Arc<ClientLog>> {
+        let client = ClientLog::new();
+        let filter = stderr.filter();
+        let _ = client.stderr_filter.set(filter);
+        log::set_boxed_logger(Box::new(Self {
+            stderr,
+            client: client.clone(),
+        }))?;
+        log::set_max_level(filter);
+        Ok(client)
+    }
+}
+
+impl Log for ForwardingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.stderr.enabled(metadata) || self.client.enabled(metadata.level())
+    }
+
+    fn log(&self, record: &Record) {
+        self.stderr.log(record);
+        self.client.forward(record);
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::sync::Mutex;
+
+    fn record_sent(log: &ClientLog) -> Arc<Mutex<Vec<(String, Value)>>> {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let sink = sent.clone();
+        log.set_notifier(Arc::new(move |session: Option<&str>, msg: &Value| {
+            let session = session.expect("log records go to one session").to_string();
+            sink.lock().unwrap().push((session, msg.clone()))
+        }));
+        sent
+    }
+
+    fn forward_at(log: &ClientLog, levels: &[Level]) {
+        for &level in levels {
+            log.forward(
+                &Record::builder()
+                    .level(level)
+                    .target("axiomregent::test")
+                    .args(format_args!("msg"))
+                    .build(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_forwards_only_enabled_levels() {
+        let log = ClientLog::new();
+        let sent = record_sent(&log);
+
+        forward_at(&log, &[Level::Error]);
+        assert!(sent.lock().unwrap().is_empty());
+
+        log.set_level("", parse_level("warning").unwrap());
+        forward_at(&log, &[Level::Info, Level::Warn, Level::Error]);
+        let sent = sent.lock().unwrap();
+        let levels: Vec<&str> = sent
+            .iter()
+            .map(|(_, m)| m["params"]["level"].as_str().unwrap())
+            .collect();
+        assert_eq!(levels, ["warning", "error"]);
+        assert_eq!(sent[0].0, "");
+        assert_eq!(sent[0].1["method"], "notifications/message");
+        assert_eq!(sent[0].1["params"]["logger"], "axiomregent::test");
+        assert_eq!(sent[0].1["params"]["data"], "msg");
+    }
+
+    #[test]
+    fn test_each_session_gets_the_records_it_asked_for() {
+        let log = ClientLog::new();
+        let sent = record_sent(&log);
+        log.set_level("a", LevelFilter::Info);
+        log.set_level("b", LevelFilter::Error);
+        assert_eq!(log.max_level(), LevelFilter::Info);
+        assert_eq!(log.level("c"), LevelFilter::Off);
+
+        forward_at(&log, &[Level::Debug, Level::Info, Level::Error]);
+        let mut got: Vec<(String, String)> = sent
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|(s, m)| (s, m["params"]["level"].as_str().unwrap().to_string()))
+            .collect();
+        got.sort();
+        let want = [("a", "error"), ("a", "info"), ("b", "error")];
+        assert_eq!(got, want.map(|(s, l)| (s.to_string(), l.to_string())));
+
+        log.end_session("a");
+        assert_eq!(log.max_level(), LevelFilter::Error);
+        forward_at(&log, &[Level::Info, Level::Error]);
+        let sessions: Vec<String> = sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(s, _)| s.clone())
+            .collect();
+        assert_eq!(sessions, ["b"]);
+    }
+
+    #[test]
+    fn test_parse_level_rejects_unknown() {
+        assert_eq!(parse_level("notice").unwrap(), LevelFilter::Info);
+        assert!(parse_level("verbose").is_err());
+    }
+}
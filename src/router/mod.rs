@@ -2,15 +2,33 @@
 // Spec: spec/core/tools.md
 
 // Router module
+pub mod builtin;
 pub mod cache;
+pub mod cancel;
+pub mod dispatch;
+pub mod error;
+pub mod logging;
 pub mod mounts;
+pub mod progress;
+pub mod prompts;
+pub mod registry;
+pub mod resources;
 
 use crate::io::fs::RealFs;
 // Feature: MCP_ROUTER
 // Spec: spec/core/router.md
 use crate::antigravity_tools::AntigravityTools;
+use crate::config::Config;
 use crate::resolver::order::ResolveEngine;
+use crate::router::cache::ResponseCache;
+use crate::router::cancel::{CancelToken, CancellationRegistry};
+use crate::router::dispatch::RepoLocks;
+use crate::router::error::STALE_LEASE_RPC_CODE;
+use crate::router::logging::ClientLog;
 use crate::router::mounts::MountRegistry;
+use crate::router::progress::Progress;
+use crate::router::registry::{InvalidParams, ToolContext, ToolHandler, ToolOutput, ToolRegistry};
+use crate::router::resources::{ResourceUri, Resources};
 use crate::run_tools::RunTools;
 use crate::snapshot::lease::StaleLeaseError;
 use crate::snapshot::tools::SnapshotTools;
@@ -18,7 +36,8 @@ use crate::tools::encore_ts::tools::EncoreTools;
 use crate::workspace::WorkspaceTools;
 use featuregraph::tools::FeatureGraphTools;
 use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
+use serde_json::{Map, Value, json};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use xray::tools::XrayTools;
 
@@ -30,73 +49,65 @@ pub struct JsonRpcRequest {
     pub id: Option<Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct JsonRpcResponse {
-    pub jsonrpc: String,
-    pub result: Option<Value>,
-    pub error: Option<Value>,
-    pub id: Option<Value>,
+/// A payload admitted by [`Router::admit`]: its calls are registered for
+/// cancellation until it is passed to [`Router::dispatch`] or dropped.
+pub struct Admitted {
+    cancellations: CancellationRegistry,
+    session: String,
+    batch: bool,
+    items: Vec<Admission>,
 }
 
-/// AxiomRegentError represents MCP-level errors that are surfaced to clients using
-/// string error codes defined by the MCP common schema.
-///
-/// NOTE: We intentionally avoid adding new dependencies here (e.g. `thiserror`).
-#[derive(Debug)]
-pub enum AxiomRegentError {
-    NotFound(String),
-    InvalidArgument(String),
-    RepoChanged(String),
-    PermissionDenied(String),
-    TooLarge(String),
-    Internal(String),
+enum Admission {
+    /// Answered without dispatch: malformed, invalid or duplicate requests.
+    Reply(Value),
+    Notification(JsonRpcRequest),
+    Call(JsonRpcRequest, Value, CancelToken),
 }
 
-impl AxiomRegentError {
-    pub fn code(&self) -> &'static str {
-        match self {
-            AxiomRegentError::NotFound(_) => "NOT_FOUND",
-            AxiomRegentError::InvalidArgument(_) => "INVALID_ARGUMENT",
-            AxiomRegentError::RepoChanged(_) => "REPO_CHANGED",
-            AxiomRegentError::PermissionDenied(_) => "PERMISSION_DENIED",
-            AxiomRegentError::TooLarge(_) => "TOO_LARGE",
-            AxiomRegentError::Internal(_) => "INTERNAL",
-        }
-    }
-
-    fn message(&self) -> &str {
-        match self {
-            AxiomRegentError::NotFound(m)
-            | AxiomRegentError::InvalidArgument(m)
-            | AxiomRegentError::RepoChanged(m)
-            | AxiomRegentError::PermissionDenied(m)
-            | AxiomRegentError::TooLarge(m)
-            | AxiomRegentError::Internal(m) => m.as_str(),
+impl Drop for Admitted {
+    fn drop(&mut self) {
+        for admission in &self.items {
+            if let Admission::Call(_, id, _) = admission {
+                self.cancellations.finish(&self.session, id);
+            }
         }
     }
 }
 
-impl std::fmt::Display for AxiomRegentError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.message())
-    }
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: String,
+    pub result: Option<Value>,
+    pub error: Option<Value>,
+    pub id: Option<Value>,
 }
 
-impl std::error::Error for AxiomRegentError {}
+pub use error::{AxiomRegentError, ToolError};
+
+/// Sink for server-initiated notifications, installed by the transport.
+///
+/// The first argument names the transport session the notification is for
+/// (`""` for messages that arrived without one), or is `None` when every
+/// session should get it.
+pub type Notifier = Arc<dyn Fn(Option<&str>, &Value) + Send + Sync>;
 
 pub struct Router {
-    resolver: Arc<ResolveEngine<RealFs>>,
     mounts: MountRegistry,
-    snapshot_tools: Arc<SnapshotTools>,
-    workspace_tools: Arc<WorkspaceTools>,
-    featuregraph_tools: Arc<FeatureGraphTools>,
-    xray_tools: Arc<XrayTools>,
-    antigravity_tools: Arc<AntigravityTools>,
-    encore_tools: Arc<EncoreTools>,
-    run_tools: Arc<RunTools>,
+    snapshots: Arc<SnapshotTools>,
+    registry: ToolRegistry,
+    resources: Resources,
+    notifier: Option<Notifier>,
+    client_log: Arc<ClientLog>,
+    cache: ResponseCache,
+    config: Option<Value>,
+    strict_mounts: bool,
+    cancellations: CancellationRegistry,
+    repo_locks: RepoLocks,
 }
 
 impl Router {
+    /// Builds a router with every built-in tool registered.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         resolver: Arc<ResolveEngine<RealFs>>,
@@ -109,1250 +120,602 @@ impl Router {
         encore_tools: Arc<EncoreTools>,
         run_tools: Arc<RunTools>,
     ) -> Self {
+        let resources = Resources::new(snapshot_tools.clone(), mounts.clone());
+        let mut registry = ToolRegistry::new();
+        builtin::register_core(&mut registry, resolver, mounts.clone());
+        builtin::register_featuregraph(&mut registry, featuregraph_tools);
+        builtin::register_xray(&mut registry, xray_tools);
+        crate::snapshot::mcp::register(&mut registry, snapshot_tools.clone());
+        crate::snapshot::mcp::register_leases(&mut registry, snapshot_tools.clone());
+        crate::antigravity_tools::register(&mut registry, antigravity_tools);
+        crate::tools::encore_ts::mcp::register(&mut registry, encore_tools);
+        crate::run_tools::register(&mut registry, run_tools);
+        crate::workspace::mcp::register(&mut registry, workspace_tools);
+
         Self {
-            resolver,
             mounts,
-            snapshot_tools,
-            workspace_tools,
-            featuregraph_tools,
-            xray_tools,
-            antigravity_tools,
-            encore_tools,
-            run_tools,
+            snapshots: snapshot_tools,
+            registry,
+            resources,
+            notifier: None,
+            client_log: ClientLog::new(),
+            cache: ResponseCache::default(),
+            config: None,
+            strict_mounts: false,
+            cancellations: CancellationRegistry::new(),
+            repo_locks: RepoLocks::new(),
         }
     }
 
-    pub fn handle_request(&self, req: &JsonRpcRequest) -> JsonRpcResponse {
-        match req.method.as_str() {
-            "initialize" => json_rpc_ok(
-                req.id.clone(),
-                json!({
-                    "protocolVersion": "2024-11-05",
-                    "capabilities": get_server_capabilities(),
-                    "serverInfo": { "name": "mcp", "version": "0.1.0" }
-                }),
-            ),
-            "tools/list" => json_rpc_ok(
-                req.id.clone(),
-                json!({
-                    "tools": [
-                        {
-                            "name": "resolve_mcp",
-                            "description": "Resolve an MCP server name to a local path or alias",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": { "name": { "type": "string" } },
-                                "required": ["name"]
-                            }
-                        },
-                        {
-                            "name": "list_mounts",
-                            "description": "List currently resolved/mounted servers",
-                            "inputSchema": { "type": "object", "properties": {} }
-                        },
-                         {
-                            "name": "get_capabilities",
-                            "description": "Get server capabilities",
-                            "inputSchema": { "type": "object", "properties": {} }
-                        },
-                        // FeatureGraph Tools
-                        {
-                            "name": "features.overview",
-                            "description": "Get full feature graph",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "snapshot_id": { "type": "string" }
-                                },
-                                "required": ["repo_root"]
-                            }
-                        },
-                        {
-                            "name": "features.locate",
-                            "description": "Locate feature definition or impl",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "feature_id": { "type": "string" },
-                                    "spec_path": { "type": "string" },
-                                    "file_path": { "type": "string" }
-                                },
-                                "required": ["repo_root"]
-                            }
-                        },
-                        // Governance Tools
-                        {
-                            "name": "gov.preflight",
-                            "description": "Check governance policy for proposed changes",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "intent": { "type": "string", "enum": ["edit", "create", "delete", "refactor"] },
-                                    "mode": { "type": "string", "enum": ["worktree", "snapshot"] },
-                                    "changed_paths": { "type": "array", "items": { "type": "string" } },
-                                    "snapshot_id": { "type": "string" }
-                                },
-                                "required": ["repo_root", "intent", "mode", "changed_paths"]
-                            }
-                        },
-                        {
-                            "name": "gov.drift",
-                            "description": "Check for drift and violations",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" }
-                                },
-                                "required": ["repo_root"]
-                            }
-                        },
-                        // Xray Tools
-                        {
-                            "name": "xray.scan",
-                            "description": "Scan repository to build index",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "path": { "type": "string" }
-                                },
-                                "required": ["repo_root"]
-                            }
-                        },
-                        // Snapshot Tools
-                        {
-                            "name": "snapshot.list",
-                            "description": "List files in a snapshot or worktree",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "path": { "type": "string" },
-                                    "mode": { "type": "string", "enum": ["worktree", "snapshot"] },
-                                    "lease_id": { "type": "string" },
-                                    "snapshot_id": { "type": "string" },
-                                    "limit": { "type": "integer" },
-                                    "offset": { "type": "integer" }
-                                },
-                                "required": ["repo_root", "path", "mode"]
-                            }
-                        },
-                        {
-                            "name": "snapshot.create",
-                            "description": "Create a new snapshot",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "lease_id": { "type": "string" },
-                                    "paths": { "type": "array", "items": { "type": "string" } }
-                                },
-                                "required": ["repo_root"]
-                            }
-                        },
-                        {
-                            "name": "snapshot.read",
-                            "description": "Read file content",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "path": { "type": "string" },
-                                    "mode": { "type": "string", "enum": ["worktree", "snapshot"] },
-                                    "lease_id": { "type": "string" },
-                                    "snapshot_id": { "type": "string" }
-                                },
-                                "required": ["repo_root", "path", "mode"]
-                            }
-                        },
-                        {
-                            "name": "snapshot.grep",
-                            "description": "Search for patterns",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "pattern": { "type": "string" },
-                                    "paths": { "type": "array", "items": { "type": "string" } },
-                                    "mode": { "type": "string", "enum": ["worktree", "snapshot"] },
-                                    "lease_id": { "type": "string" },
-                                    "snapshot_id": { "type": "string" },
-                                    "case_insensitive": { "type": "boolean" }
-                                },
-                                "required": ["repo_root", "pattern", "mode"]
-                            }
-                        },
-                        {
-                            "name": "snapshot.diff",
-                            "description": "Generate unified diff",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "path": { "type": "string" },
-                                    "mode": { "type": "string", "enum": ["worktree", "snapshot"] },
-                                    "lease_id": { "type": "string" },
-                                    "snapshot_id": { "type": "string" },
-                                    "from_snapshot_id": { "type": "string" }
-                                },
-                                "required": ["repo_root", "path", "mode"]
-                            }
-                        },
-                        {
-                            "name": "snapshot.changes",
-                            "description": "List changed files between snapshots",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "snapshot_id": { "type": "string" },
-                                    "from_snapshot_id": { "type": "string" }
-                                },
-                                "required": ["repo_root", "snapshot_id"]
-                            }
-                        },
-                         {
-                            "name": "snapshot.export",
-                            "description": "Export snapshot as tarball",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "snapshot_id": { "type": "string" }
-                                },
-                                "required": ["repo_root", "snapshot_id"]
-                            }
-                        },
-                        {
-                            "name": "snapshot.info",
-                            "description": "Get snapshot or repository info",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "snapshot_id": { "type": "string" }
-                                },
-                                "required": ["repo_root"]
-                            }
-                        },
-                        // Antigravity Tools
-                        {
-                            "name": "antigravity.propose",
-                            "description": "Propose a change",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "subject": { "type": "string" },
-                                    "repo_key": { "type": "string" },
-                                    "base_state": { "type": "string" },
-                                    "goal": { "type": "string" },
-                                    "tasks": {
-                                        "type": "array",
-                                        "items": { "type": "object" }
-                                    },
-                                    "tiers": {
-                                        "type": "array",
-                                        "items": { "type": "string" }
-                                    },
-                                    "architecture_doc": { "type": "string" },
-                                    "base_state_created_at": { "type": "string" }
-                                },
-                                "required": ["repo_root", "subject", "repo_key", "goal", "tasks"]
-                            }
-                        },
-                        {
-                            "name": "antigravity.execute",
-                            "description": "Execute a changeset",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "changeset_id": { "type": "string" }
-                                },
-                                "required": ["repo_root", "changeset_id"]
-                            }
-                        },
-                        {
-                            "name": "antigravity.verify",
-                            "description": "Verify a changeset",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "changeset_id": { "type": "string" },
-                                    "profile": { "type": "string" }
-                                },
-                                "required": ["repo_root", "changeset_id"]
-                            }
-                        },
-                        // Encore TS Tools
-                        {
-                            "name": "encore.ts.env.check",
-                            "description": "Check Encore TS environment",
-                            "inputSchema": { "type": "object", "properties": {} }
-                        },
-                        {
-                            "name": "encore.ts.parse",
-                            "description": "Parse Encore TS application",
-                             "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "root": { "type": "string" }
-                                },
-                                "required": ["root"]
-                            }
-                        },
-                        {
-                            "name": "encore.ts.meta",
-                            "description": "Get Encore TS application metadata",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "root": { "type": "string" }
-                                },
-                                "required": ["root"]
-                            }
-                        },
-                        {
-                            "name": "encore.ts.run.start",
-                            "description": "Start Encore TS application",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "root": { "type": "string" },
-                                    "env": { "type": "object" },
-                                    "profile": { "type": "string" }
-                                },
-                                "required": ["root"]
-                            }
-                        },
-                        {
-                            "name": "encore.ts.run.stop",
-                            "description": "Stop Encore TS application",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "run_id": { "type": "string" }
-                                },
-                                "required": ["run_id"]
-                            }
-                        },
-                        {
-                            "name": "encore.ts.logs.stream",
-                            "description": "Stream logs from Encore TS application",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "run_id": { "type": "string" },
-                                    "from_seq": { "type": "integer" }
-                                },
-                                "required": ["run_id"]
-                            }
-                        },
-                        // Run Tools
-                        {
-                            "name": "run.execute",
-                            "description": "Execute a run skill",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "skill": { "type": "string" },
-                                    "env": { "type": "object" }
-                                },
-                                "required": ["skill"]
-                            }
-                        },
-                        {
-                            "name": "run.status",
-                            "description": "Get run status",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "run_id": { "type": "string" }
-                                },
-                                "required": ["run_id"]
-                            }
-                        },
-                        {
-                            "name": "run.logs",
-                            "description": "Get run logs",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "run_id": { "type": "string" },
-                                    "offset": { "type": "integer" },
-                                    "limit": { "type": "integer" }
-                                },
-                                "required": ["run_id"]
-                            }
-                        },
-                        // Workspace Tools
-                        {
-                            "name": "workspace.write_file",
-                            "description": "Write file content",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "path": { "type": "string" },
-                                    "content_base64": { "type": "string" },
-                                    "lease_id": { "type": "string" },
-                                    "create_dirs": { "type": "boolean" },
-                                    "dry_run": { "type": "boolean" }
-                                },
-                                "required": ["repo_root", "path", "content_base64", "lease_id"]
-                            }
-                        },
-                        {
-                            "name": "workspace.delete",
-                            "description": "Delete a file or directory",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "path": { "type": "string" },
-                                    "lease_id": { "type": "string" },
-                                    "dry_run": { "type": "boolean" }
-                                },
-                                "required": ["repo_root", "path", "lease_id"]
-                            }
-                        },
-                        {
-                            "name": "workspace.apply_patch",
-                            "description": "Apply a patch",
-                            "inputSchema": {
-                                "type": "object",
-                                "properties": {
-                                    "repo_root": { "type": "string" },
-                                    "patch": { "type": "string" },
-                                    "mode": { "type": "string", "enum": ["worktree", "snapshot"] },
-                                    "lease_id": { "type": "string" },
-                                    "snapshot_id": { "type": "string" },
-                                    "strip": { "type": "integer" },
-                                    "reject_on_conflict": { "type": "boolean" },
-                                    "dry_run": { "type": "boolean" }
-                                },
-                                "required": ["repo_root", "patch", "mode"]
-                            }
-                        }
-                    ]
-                }),
-            ),
+    /// Installs the sink for server-initiated notifications
+    /// (e.g. `notifications/resources/updated`).
+    pub fn with_notifier(
+        mut self,
+        notifier: impl Fn(Option<&str>, &Value) + Send + Sync + 'static,
+    ) -> Self {
+        let notifier: Notifier = Arc::new(notifier);
+        self.client_log.set_notifier(notifier.clone());
+        self.notifier = Some(notifier);
+        self
+    }
 
-            "tools/call" => {
-                let params = match req.params.as_ref().and_then(|p| p.as_object()) {
-                    Some(p) => p,
-                    None => return json_rpc_error(req.id.clone(), -32602, "Invalid params"),
-                };
-                let name = match params.get("name").and_then(|n| n.as_str()) {
-                    Some(n) => n,
-                    None => return json_rpc_error(req.id.clone(), -32602, "Missing tool name"),
-                };
-                let args = params.get("arguments").and_then(|a| a.as_object());
-                let args = match args {
-                    Some(a) => a,
-                    None => return json_rpc_error(req.id.clone(), -32602, "Missing arguments"),
-                };
+    /// Uses the handle of the installed [`logging::ForwardingLogger`], so
+    /// `logging/setLevel` controls which `log` records reach each session.
+    pub fn with_client_log(mut self, client_log: Arc<ClientLog>) -> Self {
+        if let Some(notifier) = &self.notifier {
+            client_log.set_notifier(notifier.clone());
+        }
+        self.client_log = client_log;
+        self
+    }
 
-                match name {
-                    "resolve_mcp" => {
-                        let target = args.get("name").and_then(|n| n.as_str());
-                        if let Some(target) = target {
-                            match self.resolver.resolve(target) {
-                                Ok(resp) => {
-                                    if resp.status
-                                        == crate::protocol::types::ResolveStatus::Resolved
-                                        && let (Some(root), Some(rid)) =
-                                            (&resp.root, &resp.resolved_id)
-                                    {
-                                        self.mounts.register(crate::router::mounts::Mount {
-                                            name: target.to_string(),
-                                            root: root.clone(),
-                                            resolved_id: Some(rid.clone()),
-                                            kind: resp.kind.clone(),
-                                            capabilities: resp.capabilities.clone(),
-                                        });
-                                    }
-                                    let content = json!([{ "type": "json", "json": resp }]);
-                                    json_rpc_ok(req.id.clone(), json!({ "content": content }))
-                                }
-                                Err(e) => json_rpc_error(
-                                    req.id.clone(),
-                                    -32603,
-                                    &format!("Resolution failed: {}", e),
-                                ),
-                            }
-                        } else {
-                            json_rpc_error(req.id.clone(), -32602, "Missing name argument")
-                        }
-                    }
-                    "list_mounts" => {
-                        let list = self.mounts.list();
-                        json_rpc_ok(
-                            req.id.clone(),
-                            json!({ "content": [{ "type": "json", "json": list }] }),
-                        )
-                    }
-                    "get_capabilities" => {
-                        let caps = json!({
-                            "name": "mcp",
-                            "server_capabilities": get_server_capabilities(),
-                        });
-                        json_rpc_ok(
-                            req.id.clone(),
-                            json!({ "content": [{ "type": "json", "json": caps }] }),
-                        )
-                    }
+    /// Sends a notification to every session through the installed notifier, if any.
+    pub fn notify(&self, method: &str, params: Value) {
+        self.notify_session(None, method, params);
+    }
 
-                    // --- FeatureGraph Tools ---
-                    "features.overview" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
+    /// Sends a notification to `session` only (see [`Notifier`]).
+    pub fn notify_session(&self, session: Option<&str>, method: &str, params: Value) {
+        if let Some(notifier) = &self.notifier {
+            notifier(
+                session,
+                &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+            );
+        }
+    }
 
-                        match self
-                            .featuregraph_tools
-                            .features_overview(repo_root, snapshot_id)
-                        {
-                            Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
-                    "features.locate" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let feature_id = args
-                            .get("feature_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let spec_path = args
-                            .get("spec_path")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let file_path = args
-                            .get("file_path")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
+    /// Forgets what `session` asked for, once the transport has ended it.
+    pub fn end_session(&self, session: &str) {
+        self.client_log.end_session(session);
+    }
 
-                        match self
-                            .featuregraph_tools
-                            .features_locate(repo_root, feature_id, spec_path, file_path)
-                        {
-                            Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
+    /// Replaces the response cache (e.g. with [`ResponseCache::from_env`]).
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = cache;
+        self
+    }
 
-                    "gov.preflight" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
+    /// Applies the server configuration: drops the tools of disabled groups and
+    /// echoes the effective configuration in `get_capabilities`.
+    pub fn with_config(mut self, config: &Config) -> Self {
+        self.registry.retain(|name| config.tool_enabled(name));
+        self.config = serde_json::to_value(config).ok();
+        self
+    }
 
-                        match self
-                            .featuregraph_tools
-                            .governance_preflight(repo_root, Value::Object(args.clone()))
-                        {
-                            Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
-                    "gov.drift" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        match self.featuregraph_tools.governance_drift(repo_root) {
-                            Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
+    /// Adds a tool (or replaces the built-in tool of the same name).
+    pub fn with_tool(mut self, tool: impl ToolHandler + 'static) -> Self {
+        self.registry.register(tool);
+        self
+    }
 
-                    // --- Xray Tools ---
-                    "xray.scan" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let path = args.get("path").and_then(|v| v.as_str()).map(String::from);
+    /// The registered tool named `name`.
+    pub fn tool(&self, name: &str) -> Option<Arc<dyn ToolHandler>> {
+        self.registry.get(name)
+    }
 
-                        match self.xray_tools.xray_scan(repo_root, path) {
-                            Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
+    /// Names of all registered tools, in `tools/list` order.
+    pub fn tool_names(&self) -> Vec<String> {
+        self.registry.names()
+    }
 
-                    // --- Antigravity Tools ---
-                    "antigravity.propose" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        // We need to parse everything into AgentConfig.
-                        // Ideally we deserialize `args` directly to AgentConfig,
-                        // but `args` is Map<String, Value>.
-                        // Let's use serde_json::from_value.
-                        let config_res: Result<antigravity::agent::AgentConfig, _> =
-                            serde_json::from_value(Value::Object(args.clone()));
+    /// Enables strict mount scoping.
+    ///
+    /// When enabled, every `repo_root`/`root` argument must name a registered mount
+    /// (or a path canonically inside one), and the mount must grant the capability
+    /// the tool requires. Calls naming only a lease, and snapshot resources, are
+    /// checked against the repository they belong to. Anything else is rejected
+    /// with `PERMISSION_DENIED`.
+    pub fn with_strict_mounts(mut self, strict: bool) -> Self {
+        self.strict_mounts = strict;
+        self
+    }
 
-                        match config_res {
-                            Ok(config) => match self.antigravity_tools.propose(repo_root, config) {
-                                Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                                Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                            },
-                            Err(e) => json_rpc_error(
-                                req.id.clone(),
-                                -32602,
-                                &format!("Invalid AgentConfig: {}", e),
-                            ),
-                        }
-                    }
-                    "antigravity.execute" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let changeset_id = match args.get("changeset_id").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "changeset_id required",
-                                );
-                            }
-                        };
+    /// Rewrites root arguments to the canonical path inside their mount and checks
+    /// the mount's capabilities. A call naming only a lease is checked against the
+    /// lease's repository; a tool given neither needs some mount to grant its
+    /// capability. Only called in strict mode.
+    fn enforce_mount_scope(
+        &self,
+        tool: &str,
+        args: &Map<String, Value>,
+    ) -> Result<Map<String, Value>, ToolError> {
+        let capability = self.required_capability(tool);
+        let mut scoped = args.clone();
+        let mut has_root = false;
+        for key in ["repo_root", "root"] {
+            let Some(requested) = args.get(key).and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let root = self.check_scope(requested, capability, tool)?;
+            scoped.insert(
+                key.to_string(),
+                Value::String(root.to_string_lossy().into_owned()),
+            );
+            has_root = true;
+        }
+        if !has_root
+            && let Some(lease_id) = args.get("lease_id").and_then(|v| v.as_str())
+            && let Some(repo_root) = self.snapshots.lease_repo(lease_id)
+        {
+            self.check_scope(&repo_root, capability, tool)
+                .map_err(|e| e.with_lease(lease_id))?;
+            has_root = true;
+        }
+        if !has_root
+            && let Some(cap) = capability
+            && !self
+                .mounts
+                .list()
+                .iter()
+                .any(|m| m.capabilities.iter().any(|c| c == cap))
+        {
+            return Err(AxiomRegentError::PermissionDenied(format!(
+                "No mount grants '{}' (required by {})",
+                cap, tool
+            ))
+            .into());
+        }
+        Ok(scoped)
+    }
 
-                        match self.antigravity_tools.execute(repo_root, changeset_id) {
-                            Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
-                    "antigravity.verify" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let changeset_id = match args.get("changeset_id").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "changeset_id required",
-                                );
-                            }
-                        };
-                        let profile = args.get("profile").and_then(|v| v.as_str()).unwrap_or("pr");
+    /// Resolves `requested` to the canonical path inside its mount, checking
+    /// that the mount grants `capability` (required by `user`).
+    fn check_scope(
+        &self,
+        requested: &str,
+        capability: Option<&str>,
+        user: &str,
+    ) -> Result<PathBuf, ToolError> {
+        let (mount, root) = self
+            .mounts
+            .resolve_root(requested)
+            .map_err(|e| e.with_path(requested))?;
+        if let Some(cap) = capability
+            && !mount.capabilities.iter().any(|c| c == cap)
+        {
+            return Err(AxiomRegentError::PermissionDenied(format!(
+                "Mount '{}' does not grant '{}' (required by {})",
+                mount.name, cap, user
+            ))
+            .with_path(requested));
+        }
+        Ok(root)
+    }
 
-                        match self
-                            .antigravity_tools
-                            .verify(repo_root, changeset_id, profile)
-                        {
-                            Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
+    /// Repository a call operates on: its `repo_root`/`root` argument, or else
+    /// the repository of the lease it names.
+    fn call_repo(&self, args: &Map<String, Value>) -> Option<String> {
+        let root = args
+            .get("repo_root")
+            .or_else(|| args.get("root"))
+            .and_then(|v| v.as_str());
+        match root {
+            Some(root) => Some(root.to_string()),
+            None => args
+                .get("lease_id")
+                .and_then(|v| v.as_str())
+                .and_then(|lease_id| self.snapshots.lease_repo(lease_id)),
+        }
+    }
 
-                    // --- Encore TS Tools ---
-                    "encore.ts.env.check" => {
-                        handle_tool_result_value(req.id.clone(), self.encore_tools.env_check())
-                    }
-                    "encore.ts.parse" => {
-                        let root = match args.get("root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => return json_rpc_error(req.id.clone(), -32602, "root required"),
-                        };
-                        handle_tool_result_value(req.id.clone(), self.encore_tools.parse(root))
-                    }
-                    "encore.ts.meta" => {
-                        let root = match args.get("root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => return json_rpc_error(req.id.clone(), -32602, "root required"),
-                        };
-                        handle_tool_result_value(req.id.clone(), self.encore_tools.meta(root))
-                    }
-                    "encore.ts.run.start" => {
-                        let root = match args.get("root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => return json_rpc_error(req.id.clone(), -32602, "root required"),
-                        };
-                        // Note: env and profile parsing omitted for skeleton, just passing placeholders or improving later
-                        // Actually, I should parse them properly to match the signature.
-                        // But for now, let's keep it minimal as tools.rs implementation is just Err.
-                        // I'll assume Env is Option<HashMap<String, String>>
-                        // For now I'll just pass None for optional args to keep it simple in this skeleton phase.
-                        // Or better, let's just parse them if present.
+    /// Whether `tool` may operate on the repository at `repo_root`.
+    pub(crate) fn in_scope(&self, tool: &str, repo_root: &str) -> bool {
+        !self.strict_mounts
+            || self
+                .check_scope(repo_root, self.required_capability(tool), tool)
+                .is_ok()
+    }
 
-                        let env = args.get("env").and_then(|v| v.as_object()).map(|obj| {
-                            obj.iter()
-                                .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
-                                .collect()
-                        });
-                        let profile = args
-                            .get("profile")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
+    /// Capability `tool` declares (see [`ToolHandler::capability`]).
+    fn required_capability(&self, tool: &str) -> Option<&'static str> {
+        self.registry
+            .get(tool)
+            .and_then(|t| t.capability())
+            .map(|c| c.as_str())
+    }
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.encore_tools.run_start(root, env, profile),
-                        )
-                    }
-                    "encore.ts.run.stop" => {
-                        let run_id = match args.get("run_id").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => {
-                                return json_rpc_error(req.id.clone(), -32602, "run_id required");
-                            }
-                        };
-                        handle_tool_result_value(req.id.clone(), self.encore_tools.run_stop(run_id))
-                    }
-                    "encore.ts.logs.stream" => {
-                        let run_id = match args.get("run_id").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => {
-                                return json_rpc_error(req.id.clone(), -32602, "run_id required");
-                            }
-                        };
-                        let from_seq = args.get("from_seq").and_then(|v| v.as_u64());
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.encore_tools.logs_stream(run_id, from_seq),
-                        )
-                    }
+    /// Checks that the snapshot a resource URI names was taken of a mounted
+    /// repository granting `read_file`. Only called in strict mode.
+    fn enforce_resource_scope(&self, uri: &str) -> Result<(), ToolError> {
+        let Ok(ResourceUri::Snapshot { snapshot_id, .. }) = ResourceUri::parse(uri) else {
+            // Specs and changesets are only looked up inside the mounts.
+            return Ok(());
+        };
+        let repo_root = self
+            .snapshots
+            .snapshot_repo(&snapshot_id)
+            .ok()
+            .flatten()
+            .ok_or_else(|| {
+                AxiomRegentError::NotFound(format!("Snapshot not found: {}", snapshot_id))
+                    .with_snapshot(&snapshot_id)
+            })?;
+        self.check_scope(&repo_root, Some("read_file"), "resources/read")
+            .map_err(|e| e.with_snapshot(&snapshot_id))?;
+        Ok(())
+    }
 
-                    // --- Run Tools ---
-                    "run.execute" => {
-                        let skill = match args.get("skill").and_then(|v| v.as_str()) {
-                            Some(v) => v.to_string(),
-                            None => {
-                                return json_rpc_error(req.id.clone(), -32602, "skill required");
-                            }
-                        };
-                        let env = args.get("env").and_then(|v| v.as_object()).map(|obj| {
-                            obj.iter()
-                                .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
-                                .collect()
-                        });
+    /// Handles one raw JSON-RPC payload: a single message or a batch.
+    ///
+    /// Returns `None` when nothing should be written back, i.e. the payload held
+    /// only notifications or every call in it was cancelled.
+    pub fn handle_message(&self, payload: &str) -> Option<Value> {
+        self.handle_session_message(None, payload)
+    }
 
-                        match self.run_tools.execute(skill, env) {
-                            Ok(val) => {
-                                handle_tool_result_value(req.id.clone(), Ok(Value::String(val)))
-                            }
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
-                    "run.status" => {
-                        let run_id = match args.get("run_id").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => {
-                                return json_rpc_error(req.id.clone(), -32602, "run_id required");
-                            }
-                        };
-                        match self.run_tools.status(run_id) {
-                            Ok(val) => handle_tool_result_value(req.id.clone(), Ok(val)),
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
-                    "run.logs" => {
-                        let run_id = match args.get("run_id").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => {
-                                return json_rpc_error(req.id.clone(), -32602, "run_id required");
-                            }
-                        };
-                        let offset = args.get("offset").and_then(|v| v.as_u64());
-                        let limit = args.get("limit").and_then(|v| v.as_u64());
-                        match self.run_tools.logs(run_id, offset, limit) {
-                            Ok(val) => {
-                                handle_tool_result_value(req.id.clone(), Ok(Value::String(val)))
-                            }
-                            Err(e) => handle_tool_result_value(req.id.clone(), Err(e)),
-                        }
-                    }
+    /// Like [`Router::handle_message`], for a payload received on transport
+    /// `session` (e.g. the HTTP `Mcp-Session-Id`). Request ids, and the
+    /// cancellations naming them, are scoped to their session.
+    pub fn handle_session_message(&self, session: Option<&str>, payload: &str) -> Option<Value> {
+        self.dispatch(self.admit(session, payload))
+    }
 
-                    // --- Snapshot Tools Call Handlers ---
-                    "snapshot.list" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let path = args.get("path").and_then(|v| v.as_str()).unwrap_or("");
-                        let mode = match args.get("mode").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "mode required"),
-                        };
-                        let lease_id = args
-                            .get("lease_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let limit = args
-                            .get("limit")
-                            .and_then(|v| v.as_u64())
-                            .map(|v| v as usize);
-                        let offset = args
-                            .get("offset")
-                            .and_then(|v| v.as_u64())
-                            .map(|v| v as usize);
+    /// Parses `payload`, registers its calls for cancellation and applies the
+    /// `notifications/cancelled` it carries, without dispatching anything.
+    ///
+    /// Transports admit a message as soon as it arrives, before queueing it for
+    /// [`Router::dispatch`], so a cancellation reaches a call that is still
+    /// queued or is being dispatched.
+    pub fn admit(&self, session: Option<&str>, payload: &str) -> Admitted {
+        let session = session.unwrap_or_default().to_string();
+        let mut admitted = Admitted {
+            cancellations: self.cancellations.clone(),
+            session,
+            batch: false,
+            items: Vec::new(),
+        };
+        let msg: Value = match serde_json::from_str(payload) {
+            Ok(v) => v,
+            Err(e) => {
+                admitted
+                    .items
+                    .push(Admission::Reply(rpc_value(json_rpc_error(
+                        None,
+                        -32700,
+                        &format!("Parse error: {}", e),
+                    ))));
+                return admitted;
+            }
+        };
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.snapshot_tools.snapshot_list(
-                                repo_root,
-                                path,
-                                mode,
-                                lease_id,
-                                snapshot_id,
-                                limit,
-                                offset,
-                            ),
-                        )
-                    }
-                    "snapshot.create" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let lease_id = args
-                            .get("lease_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let paths = args.get("paths").and_then(|v| v.as_array()).map(|arr| {
-                            arr.iter()
-                                .filter_map(|v| v.as_str().map(String::from))
-                                .collect()
-                        });
+        match msg {
+            Value::Array(items) if items.is_empty() => {
+                admitted
+                    .items
+                    .push(Admission::Reply(rpc_value(json_rpc_error(
+                        None,
+                        -32600,
+                        "Invalid Request: empty batch",
+                    ))));
+            }
+            Value::Array(items) => {
+                admitted.batch = true;
+                for item in items {
+                    let admission = self.admit_single(&admitted.session, item);
+                    admitted.items.push(admission);
+                }
+            }
+            other => {
+                let admission = self.admit_single(&admitted.session, other);
+                admitted.items.push(admission);
+            }
+        }
+        admitted
+    }
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.snapshot_tools
-                                .snapshot_create(repo_root, lease_id, paths),
-                        )
-                    }
-                    "snapshot.read" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let path = match args.get("path").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "path required"),
-                        };
-                        let mode = match args.get("mode").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "mode required"),
-                        };
-                        let lease_id = args
-                            .get("lease_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
+    fn admit_single(&self, session: &str, msg: Value) -> Admission {
+        let id = msg.get("id").cloned();
+        let req: JsonRpcRequest = match serde_json::from_value(msg) {
+            Ok(r) => r,
+            Err(e) => {
+                return Admission::Reply(rpc_value(json_rpc_error(
+                    id,
+                    -32600,
+                    &format!("Invalid Request: {}", e),
+                )));
+            }
+        };
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.snapshot_tools.snapshot_file(
-                                repo_root,
-                                path,
-                                mode,
-                                lease_id,
-                                snapshot_id,
-                            ),
-                        )
-                    }
-                    "snapshot.grep" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let pattern = match args.get("pattern").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => {
-                                return json_rpc_error(req.id.clone(), -32602, "pattern required");
-                            }
-                        };
-                        let mode = match args.get("mode").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "mode required"),
-                        };
-                        let paths = args.get("paths").and_then(|v| v.as_array()).map(|arr| {
-                            arr.iter()
-                                .filter_map(|v| v.as_str().map(String::from))
-                                .collect()
-                        });
-                        let lease_id = args
-                            .get("lease_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let case_insensitive = args
-                            .get("case_insensitive")
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false);
+        let Some(id) = req.id.clone() else {
+            if req.method == "notifications/cancelled"
+                && let Some(id) = req.params.as_ref().and_then(|p| p.get("requestId"))
+            {
+                self.cancellations.cancel(session, id);
+            }
+            return Admission::Notification(req);
+        };
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.snapshot_tools.snapshot_grep(
-                                repo_root,
-                                pattern,
-                                paths,
-                                mode,
-                                lease_id,
-                                snapshot_id,
-                                case_insensitive,
-                            ),
-                        )
-                    }
-                    "snapshot.diff" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let path = match args.get("path").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "path required"),
-                        };
-                        let mode = match args.get("mode").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "mode required"),
-                        };
-                        let lease_id = args
-                            .get("lease_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let from_snapshot_id = args
-                            .get("from_snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
+        // A second request reusing the id would share, and on finishing drop,
+        // the first one's cancellation entry.
+        match self.cancellations.register(session, &id) {
+            Some(token) => Admission::Call(req, id, token),
+            None => Admission::Reply(rpc_value(json_rpc_error(
+                Some(id.clone()),
+                -32600,
+                &format!("Invalid Request: id {} is already in flight", id),
+            ))),
+        }
+    }
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.snapshot_tools.snapshot_diff(
-                                repo_root,
-                                path,
-                                mode,
-                                lease_id,
-                                snapshot_id,
-                                from_snapshot_id,
-                            ),
-                        )
-                    }
-                    "snapshot.changes" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let from_snapshot_id = args
-                            .get("from_snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
+    /// Answers an [`Admitted`] payload. Returns `None` when nothing should be
+    /// written back, like [`Router::handle_message`].
+    pub fn dispatch(&self, mut admitted: Admitted) -> Option<Value> {
+        let mut responses = Vec::new();
+        for admission in std::mem::take(&mut admitted.items) {
+            match admission {
+                Admission::Reply(response) => responses.push(response),
+                Admission::Notification(req) => self.handle_notification(&req),
+                Admission::Call(req, id, token) => {
+                    let response = if token.is_cancelled() {
+                        None
+                    } else {
+                        Some(self.respond(&req, &admitted.session, Some(&token)))
+                    };
+                    self.cancellations.finish(&admitted.session, &id);
+
+                    // Cancelled requests get no response.
+                    if token.is_cancelled() {
+                        log::info!("Request {} cancelled", id);
+                        continue;
+                    }
+                    responses.extend(response.map(rpc_value));
+                }
+            }
+        }
+        if !admitted.batch {
+            return responses.pop();
+        }
+        if responses.is_empty() {
+            None
+        } else {
+            Some(Value::Array(responses))
+        }
+    }
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.snapshot_tools.snapshot_changes(
-                                repo_root,
-                                snapshot_id,
-                                from_snapshot_id,
-                            ),
-                        )
-                    }
-                    "snapshot.export" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
+    fn handle_notification(&self, req: &JsonRpcRequest) {
+        match req.method.as_str() {
+            // Applied when the message was admitted.
+            "notifications/cancelled" => {}
+            "notifications/initialized" => {}
+            other => log::debug!("Ignoring notification {}", other),
+        }
+    }
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.snapshot_tools.snapshot_export(repo_root, snapshot_id),
-                        )
-                    }
-                    "snapshot.info" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
+    pub(crate) fn progress(
+        &self,
+        token: Option<&Value>,
+        session: &str,
+        cancel: Option<&CancelToken>,
+    ) -> Progress {
+        Progress::new(token, self.notifier.as_ref(), session, cancel.cloned())
+    }
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.snapshot_tools.snapshot_info(repo_root, snapshot_id),
-                        )
-                    }
+    /// Payload of the `get_capabilities` tool.
+    pub(crate) fn capabilities(&self) -> Value {
+        json!({
+            "name": "mcp",
+            "server_capabilities": get_server_capabilities(),
+            "strict_mounts": self.strict_mounts,
+            "cache": self.cache.stats(),
+            "config": self.config,
+        })
+    }
 
-                    // --- Workspace Tools Call Handlers ---
-                    "workspace.write_file" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let path = match args.get("path").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "path required"),
-                        };
-                        let content_base64 =
-                            match args.get("content_base64").and_then(|v| v.as_str()) {
-                                Some(v) => v,
-                                None => {
-                                    return json_rpc_error(
-                                        req.id.clone(),
-                                        -32602,
-                                        "content_base64 required",
-                                    );
-                                }
-                            };
-                        let lease_id = args
-                            .get("lease_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let create_dirs = args
-                            .get("create_dirs")
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false);
-                        let dry_run = args
-                            .get("dry_run")
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false);
+    /// Tool calls may rewrite specs or changeset files; tell the sessions
+    /// subscribed to a resource that it changed. Changes made outside the
+    /// server are not observed.
+    fn notify_resource_updates(&self) {
+        if !self.resources.has_subscriptions() {
+            return;
+        }
+        for (uri, sessions) in self.resources.changed() {
+            for session in sessions {
+                self.notify_session(
+                    Some(&session),
+                    "notifications/resources/updated",
+                    json!({ "uri": uri }),
+                );
+            }
+        }
+    }
 
-                        handle_tool_result_bool(
-                            req.id.clone(),
-                            self.workspace_tools.write_file(
-                                repo_root,
-                                path,
-                                content_base64,
-                                lease_id,
-                                create_dirs,
-                                dry_run,
-                            ),
-                        )
-                    }
-                    "workspace.delete" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let path = match args.get("path").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "path required"),
-                        };
-                        let lease_id = args
-                            .get("lease_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let dry_run = args
-                            .get("dry_run")
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false);
+    pub fn handle_request(&self, req: &JsonRpcRequest) -> JsonRpcResponse {
+        self.respond(req, "", None)
+    }
 
-                        handle_tool_result_bool(
-                            req.id.clone(),
-                            self.workspace_tools
-                                .delete(repo_root, path, lease_id, dry_run),
-                        )
+    /// Answers `req`; tools observe `cancel` if the request can be cancelled.
+    fn respond(
+        &self,
+        req: &JsonRpcRequest,
+        session: &str,
+        cancel: Option<&CancelToken>,
+    ) -> JsonRpcResponse {
+        match req.method.as_str() {
+            "initialize" => json_rpc_ok(
+                req.id.clone(),
+                json!({
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": get_server_capabilities(),
+                    "serverInfo": { "name": "mcp", "version": "0.1.0" }
+                }),
+            ),
+            "tools/list" => json_rpc_ok(req.id.clone(), json!({ "tools": self.registry.list() })),
+
+            "tools/call" => {
+                let params = match req.params.as_ref().and_then(|p| p.as_object()) {
+                    Some(p) => p,
+                    None => return json_rpc_error(req.id.clone(), -32602, "Invalid params"),
+                };
+                let name = match params.get("name").and_then(|n| n.as_str()) {
+                    Some(n) => n,
+                    None => return json_rpc_error(req.id.clone(), -32602, "Missing tool name"),
+                };
+                let Some(tool) = self.registry.get(name) else {
+                    return json_rpc_error(
+                        req.id.clone(),
+                        -32601,
+                        &format!("Tool not found: {}", name),
+                    );
+                };
+                let args = params.get("arguments").and_then(|a| a.as_object());
+                let args = match args {
+                    Some(a) => a,
+                    None => return json_rpc_error(req.id.clone(), -32602, "Missing arguments"),
+                };
+                let scoped_args;
+                let args = if self.strict_mounts {
+                    match self.enforce_mount_scope(name, args) {
+                        Ok(a) => {
+                            scoped_args = a;
+                            &scoped_args
+                        }
+                        Err(e) => return json_rpc_axiom_error(req.id.clone(), &e),
                     }
-                    "workspace.apply_patch" => {
-                        let repo_root = match args.get("repo_root").and_then(|v| v.as_str()) {
-                            Some(v) => std::path::Path::new(v),
-                            None => {
-                                return json_rpc_error(
-                                    req.id.clone(),
-                                    -32602,
-                                    "repo_root required",
-                                );
-                            }
-                        };
-                        let patch = match args.get("patch").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => {
-                                return json_rpc_error(req.id.clone(), -32602, "patch required");
-                            }
-                        };
-                        let mode = match args.get("mode").and_then(|v| v.as_str()) {
-                            Some(v) => v,
-                            None => return json_rpc_error(req.id.clone(), -32602, "mode required"),
-                        };
-                        let lease_id = args
-                            .get("lease_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let snapshot_id = args
-                            .get("snapshot_id")
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let strip = args
-                            .get("strip")
-                            .and_then(|v| v.as_u64())
-                            .map(|v| v as usize);
-                        let reject_on_conflict = args
-                            .get("reject_on_conflict")
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false);
-                        let dry_run = args
-                            .get("dry_run")
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false);
+                } else {
+                    args
+                };
+
+                // Mutations are serialized per repo; read-only tools share the lock.
+                let repo_root = self.call_repo(args);
+                let repo_lock = repo_root.as_deref().map(|r| self.repo_locks.lock_for(r));
+                let _repo_guard = repo_lock
+                    .as_ref()
+                    .map(|l| dispatch::acquire(l, tool.mutates()));
+
+                let ctx = ToolContext {
+                    router: self,
+                    tool: name,
+                    request_id: req.id.as_ref(),
+                    session,
+                    cancel,
+                    progress_token: params.get("_meta").and_then(|m| m.get("progressToken")),
+                };
+                let cache_key = self.cache.key(name, args);
+                let result = match cache_key.as_deref().and_then(|k| self.cache.get(k)) {
+                    // A worktree read still validates and extends its lease.
+                    Some(hit) => match &repo_root {
+                        Some(root) if hit["cache_hint"] == "until_dirty" => self
+                            .snapshots
+                            .check_cached_lease(Path::new(root), &hit)
+                            .map(|()| ToolOutput::Json(hit)),
+                        _ => Ok(ToolOutput::Json(hit)),
+                    },
+                    None => tool.call(&ctx, Value::Object(args.clone())),
+                };
+                if let (Some(key), Ok(ToolOutput::Json(v))) = (cache_key, &result) {
+                    self.cache.insert(key, repo_root.as_deref(), v);
+                }
+                if tool.mutates()
+                    && let Some(root) = &repo_root
+                {
+                    self.cache.invalidate_repo(root);
+                }
+                if matches!(name, "snapshot.delete" | "snapshot.gc")
+                    && let Ok(ToolOutput::Json(v)) = &result
+                    && let Some(deleted) = v.get("deleted")
+                {
+                    let ids: Vec<String> =
+                        serde_json::from_value(deleted.clone()).unwrap_or_default();
+                    self.cache.invalidate_snapshots(&ids);
+                }
 
-                        handle_tool_result_value(
-                            req.id.clone(),
-                            self.workspace_tools.apply_patch(
-                                repo_root,
-                                patch,
-                                mode,
-                                lease_id,
-                                snapshot_id,
-                                strip,
-                                reject_on_conflict,
-                                dry_run,
-                            ),
-                        )
+                let response = match result {
+                    Ok(output) => json_rpc_ok(req.id.clone(), output.into_content()),
+                    Err(e) => handle_tool_error(req.id.clone(), e),
+                };
+                self.notify_resource_updates();
+                response
+            }
+            "resources/list" => json_rpc_ok(
+                req.id.clone(),
+                json!({ "resources": self.resources.list() }),
+            ),
+            "resources/templates/list" => json_rpc_ok(
+                req.id.clone(),
+                json!({ "resourceTemplates": self.resources.templates() }),
+            ),
+            "resources/read" | "resources/subscribe" | "resources/unsubscribe" => {
+                let Some(uri) = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("uri"))
+                    .and_then(|u| u.as_str())
+                else {
+                    return json_rpc_error(req.id.clone(), -32602, "Missing uri");
+                };
+                if self.strict_mounts
+                    && req.method != "resources/unsubscribe"
+                    && let Err(e) = self.enforce_resource_scope(uri)
+                {
+                    return json_rpc_axiom_error(req.id.clone(), &e);
+                }
+                let result = match req.method.as_str() {
+                    "resources/read" => self.resources.read(uri),
+                    "resources/subscribe" => {
+                        self.resources.subscribe(uri, session).map(|_| json!({}))
                     }
                     _ => {
-                        json_rpc_error(req.id.clone(), -32601, &format!("Tool not found: {}", name))
+                        self.resources.unsubscribe(uri, session);
+                        Ok(json!({}))
                     }
+                };
+                match result {
+                    Ok(v) => json_rpc_ok(req.id.clone(), v),
+                    Err(e) => handle_tool_error(req.id.clone(), e),
+                }
+            }
+            "logging/setLevel" => {
+                let Some(level) = req
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("level"))
+                    .and_then(|l| l.as_str())
+                else {
+                    return json_rpc_error(req.id.clone(), -32602, "Missing level");
+                };
+                match logging::parse_level(level) {
+                    Ok(level) => {
+                        self.client_log.set_level(session, level);
+                        json_rpc_ok(req.id.clone(), json!({}))
+                    }
+                    Err(e) => handle_tool_error(req.id.clone(), e),
+                }
+            }
+            "prompts/list" => json_rpc_ok(req.id.clone(), json!({ "prompts": prompts::list() })),
+            "prompts/get" => {
+                let params = req.params.as_ref().and_then(|p| p.as_object());
+                let Some(name) = params.and_then(|p| p.get("name")).and_then(|n| n.as_str()) else {
+                    return json_rpc_error(req.id.clone(), -32602, "Missing prompt name");
+                };
+                let empty = Map::new();
+                let arguments = params
+                    .and_then(|p| p.get("arguments"))
+                    .and_then(|a| a.as_object())
+                    .unwrap_or(&empty);
+                match prompts::get(name, arguments) {
+                    Ok(v) => json_rpc_ok(req.id.clone(), v),
+                    Err(e) => handle_tool_error(req.id.clone(), e),
                 }
             }
             _ => json_rpc_error(req.id.clone(), -32601, "Method not found"),
@@ -1381,39 +744,53 @@ fn json_rpc_error(id: Option<Value>, code: i64, message: &str) -> JsonRpcRespons
     }
 }
 
+fn json_rpc_axiom_error(id: Option<Value>, e: &ToolError) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(json!({
+            "code": e.error.rpc_code(),
+            "message": e.error.message(),
+            "data": e.data()
+        })),
+        id,
+    }
+}
+
+fn rpc_value(response: JsonRpcResponse) -> Value {
+    serde_json::to_value(response).unwrap_or(Value::Null)
+}
+
 fn get_server_capabilities() -> Value {
     json!({
         "tools": {
             "listChanged": true
         },
+        "resources": {
+            "subscribe": true,
+            "listChanged": false
+        },
+        "prompts": {
+            "listChanged": false
+        },
         "logging": {}
     })
 }
 
-fn handle_tool_result_value(id: Option<Value>, result: anyhow::Result<Value>) -> JsonRpcResponse {
-    match result {
-        Ok(val) => json_rpc_ok(id, json!({ "content": [{ "type": "json", "json": val }] })),
-        Err(e) => handle_tool_error(id, e),
-    }
-}
-
-fn handle_tool_result_bool(id: Option<Value>, result: anyhow::Result<bool>) -> JsonRpcResponse {
-    match result {
-        Ok(true) => json_rpc_ok(id, json!({ "content": [{ "type": "text", "text": "ok" }] })),
-        Ok(false) => json_rpc_error(id, -32603, "Tool returned false"),
-        Err(e) => handle_tool_error(id, e),
-    }
-}
-
+/// Maps a tool failure to a JSON-RPC error. Typed errors get the integer code of
+/// their kind; everything else is `-32603`. Either way `data.code` (the string
+/// code) and `data.retryable` are set.
 fn handle_tool_error(id: Option<Value>, e: anyhow::Error) -> JsonRpcResponse {
     if let Some(stale) = e.downcast_ref::<StaleLeaseError>() {
         return JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
             result: None,
             error: Some(json!({
-                "code": "STALE_LEASE",
+                "code": STALE_LEASE_RPC_CODE,
                 "message": stale.msg,
                 "data": {
+                    "code": "STALE_LEASE",
+                    "retryable": true,
                     "lease_id": stale.lease_id,
                     "current_fingerprint": stale.current_fingerprint
                 }
@@ -1421,5 +798,42 @@ fn handle_tool_error(id: Option<Value>, e: anyhow::Error) -> JsonRpcResponse {
             id,
         };
     }
-    json_rpc_error(id, -32603, &format!("Tool failed: {}", e))
+    if let Some(err) = e.downcast_ref::<ToolError>() {
+        return json_rpc_axiom_error(id, err);
+    }
+    if let Some(err) = e.downcast_ref::<AxiomRegentError>() {
+        return json_rpc_axiom_error(id, &ToolError::from(err.clone()));
+    }
+    if let Some(invalid) = e.downcast_ref::<InvalidParams>() {
+        return json_rpc_error_data(
+            id,
+            -32602,
+            &invalid.0,
+            json!({ "code": "INVALID_ARGUMENT", "retryable": false }),
+        );
+    }
+    json_rpc_error_data(
+        id,
+        -32603,
+        &format!("Tool failed: {}", e),
+        json!({ "code": "INTERNAL", "retryable": false }),
+    )
+}
+
+fn json_rpc_error_data(
+    id: Option<Value>,
+    code: i64,
+    message: &str,
+    data: Value,
+) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(json!({
+            "code": code,
+            "message": message,
+            "data": data
+        })),
+        id,
+    }
 }
@@ -3,8 +3,10 @@
 // Feature: MCP_ROUTER
 // Spec: spec/core/router.md
 
+use crate::router::AxiomRegentError;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,8 +20,7 @@ pub struct Mount {
 
 #[derive(Clone, Default)]
 pub struct MountRegistry {
-    // RwLock for thread-safe concurrent access in the future,
-    // though stdio server is currently sequential.
+    // RwLock: requests are dispatched concurrently by the worker pool.
     mounts: Arc<RwLock<BTreeMap<String, Mount>>>,
 }
 
@@ -44,4 +45,155 @@ impl MountRegistry {
         let map = self.mounts.read().unwrap();
         map.get(name).cloned()
     }
+
+    /// Resolves a client-supplied root against the registered mounts.
+    ///
+    /// `requested` may be a mount name or a path that canonically sits inside a
+    /// mount's root. The innermost matching mount wins. Returns the mount and the
+    /// canonical path the tool should operate on.
+    pub fn resolve_root(&self, requested: &str) -> Result<(Mount, PathBuf), AxiomRegentError> {
+        if let Some(mount) = self.get(requested) {
+            let root = Path::new(&mount.root).canonicalize().map_err(|e| {
+                AxiomRegentError::NotFound(format!(
+                    "Mount '{}' root is unavailable: {}",
+                    mount.name, e
+                ))
+            })?;
+            return Ok((mount, root));
+        }
+
+        let requested_path = Path::new(requested).canonicalize().map_err(|_| {
+            AxiomRegentError::PermissionDenied(format!(
+                "Root is not a registered mount: {}",
+                requested
+            ))
+        })?;
+
+        let map = self.mounts.read().unwrap();
+        let mut best: Option<(Mount, PathBuf)> = None;
+        for mount in map.values() {
+            let Ok(mount_root) = Path::new(&mount.root).canonicalize() else {
+                continue;
+            };
+            if !requested_path.starts_with(&mount_root) {
+                continue;
+            }
+            let deeper = best
+                .as_ref()
+                .map(|(_, r)| mount_root.components().count() > r.components().count())
+                .unwrap_or(true);
+            if deeper {
+                best = Some((mount.clone(), mount_root));
+            }
+        }
+
+        match best {
+            Some((mount, _)) => Ok((mount, requested_path)),
+            None => Err(AxiomRegentError::PermissionDenied(format!(
+                "Root is outside every registered mount: {}",
+                requested
+            ))),
+        }
+    }
+}
+
+/// Every capability a mount can grant.
+pub const CAPABILITIES: &[&str] = &[
+    "read_file",
+    "list_files",
+    "search",
+    "snapshot",
+    "write_file",
+    "execute",
+    "admin",
+];
+
+/// Granted to the mounts registered by `resolve_mcp` unless the config says otherwise.
+pub const DEFAULT_CAPABILITIES: &[&str] = &["read_file", "list_files", "search"];
+
+/// Capability a mount must grant before a tool may operate on it, declared by
+/// the tool (see
+/// [`ToolHandler::capability`](crate::router::registry::ToolHandler::capability)).
+///
+/// A tool that names no repository (e.g. `snapshot.store_stats`) needs some
+/// mount to grant it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    ReadFile,
+    ListFiles,
+    Search,
+    Snapshot,
+    WriteFile,
+    Execute,
+    /// Store-wide operations, which no single repository's mount covers.
+    Admin,
+}
+
+impl Capability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::ReadFile => "read_file",
+            Capability::ListFiles => "list_files",
+            Capability::Search => "search",
+            Capability::Snapshot => "snapshot",
+            Capability::WriteFile => "write_file",
+            Capability::Execute => "execute",
+            Capability::Admin => "admin",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mount(name: &str, root: &Path) -> Mount {
+        Mount {
+            name: name.to_string(),
+            root: root.to_string_lossy().to_string(),
+            resolved_id: None,
+            kind: Some("local".to_string()),
+            capabilities: vec!["read_file".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_resolve_root_by_name_and_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join("sub")).unwrap();
+
+        let registry = MountRegistry::new();
+        registry.register(mount("repo", &repo));
+
+        let (m, root) = registry.resolve_root("repo").unwrap();
+        assert_eq!(m.name, "repo");
+        assert_eq!(root, repo.canonicalize().unwrap());
+
+        let (m, root) = registry
+            .resolve_root(repo.join("sub").to_str().unwrap())
+            .unwrap();
+        assert_eq!(m.name, "repo");
+        assert_eq!(root, repo.join("sub").canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_resolve_root_rejects_outside_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let other = dir.path().join("other");
+        std::fs::create_dir_all(&repo).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+
+        let registry = MountRegistry::new();
+        registry.register(mount("repo", &repo));
+
+        let err = registry.resolve_root(other.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+
+        // `..` cannot be used to climb out of a mount.
+        let sneaky = repo.join("..").join("other");
+        let err = registry.resolve_root(sneaky.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.code(), "PERMISSION_DENIED");
+    }
 }
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+use crate::router::Notifier;
+use crate::router::cancel::CancelToken;
+use serde_json::{Map, Value, json};
+
+/// Sends `notifications/progress` for a request that carried `_meta.progressToken`,
+/// to the session the request arrived on.
+///
+/// Cheap to clone and safe to move onto worker threads. Without a token (or a
+/// notifier) every report is a no-op, and reports stop once the request is cancelled.
+#[derive(Clone, Default)]
+pub struct Progress {
+    target: Option<(Value, Notifier, String)>,
+    cancel: Option<CancelToken>,
+}
+
+impl Progress {
+    pub(crate) fn new(
+        token: Option<&Value>,
+        notifier: Option<&Notifier>,
+        session: &str,
+        cancel: Option<CancelToken>,
+    ) -> Self {
+        let target = token
+            .zip(notifier)
+            .map(|(token, notifier)| (token.clone(), notifier.clone(), session.to_string()));
+        Self { target, cancel }
+    }
+
+    /// Whether reports reach a client.
+    pub fn is_enabled(&self) -> bool {
+        self.target.is_some()
+    }
+
+    /// Reports `progress` (of `total`, if known). Callers report increasing values.
+    pub fn report(&self, progress: u64, total: Option<u64>, message: &str) {
+        let Some((token, notifier, session)) = &self.target else {
+            return;
+        };
+        if self.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+            return;
+        }
+        let mut params = Map::new();
+        params.insert("progressToken".to_string(), token.clone());
+        params.insert("progress".to_string(), json!(progress));
+        if let Some(total) = total {
+            params.insert("total".to_string(), json!(total));
+        }
+        params.insert("message".to_string(), json!(message));
+        notifier(
+            Some(session),
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/progress",
+                "params": params
+            }),
+        );
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+//! Canned MCP prompts for the antigravity propose / execute / verify flow.
+
+use crate::router::registry::InvalidParams;
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+
+struct PromptArg {
+    name: &'static str,
+    description: &'static str,
+    required: bool,
+}
+
+struct Prompt {
+    name: &'static str,
+    description: &'static str,
+    arguments: &'static [PromptArg],
+    template: &'static str,
+}
+
+const REPO_ROOT: PromptArg = PromptArg {
+    name: "repo_root",
+    description: "Absolute path of the repository",
+    required: true,
+};
+
+const CHANGESET_ID: PromptArg = PromptArg {
+    name: "changeset_id",
+    description: "Changeset directory name under changes/",
+    required: true,
+};
+
+const PROMPTS: &[Prompt] = &[
+    Prompt {
+        name: "propose_change",
+        description: "Draft a changeset for a goal with antigravity.propose",
+        arguments: &[
+            REPO_ROOT,
+            PromptArg {
+                name: "goal",
+                description: "What the change should achieve",
+                required: true,
+            },
+        ],
+        template: "Propose a change to the repository at {repo_root}.\n\n\
+            Goal: {goal}\n\n\
+            1. Call `features.overview` and `gov.preflight` to find the affected features and check governance.\n\
+            2. Read the relevant specs (resources `axiomregent://spec/<feature_id>`).\n\
+            3. Call `antigravity.propose` with a subject, the goal, and one task per concrete step.\n\
+            Report the returned changeset_id.",
+    },
+    Prompt {
+        name: "execute_changeset",
+        description: "Apply a proposed changeset with antigravity.execute",
+        arguments: &[REPO_ROOT, CHANGESET_ID],
+        template: "Execute changeset {changeset_id} in the repository at {repo_root}.\n\n\
+            1. Read `axiomregent://changes/{changeset_id}/01-implementation_plan.md` and confirm it still applies.\n\
+            2. Call `antigravity.execute` with repo_root and changeset_id.\n\
+            3. Summarize the walkthrough it returns.",
+    },
+    Prompt {
+        name: "verify_changeset",
+        description: "Verify an executed changeset with antigravity.verify",
+        arguments: &[
+            REPO_ROOT,
+            CHANGESET_ID,
+            PromptArg {
+                name: "profile",
+                description: "Verification profile (default: pr)",
+                required: false,
+            },
+        ],
+        template: "Verify changeset {changeset_id} in the repository at {repo_root} using the {profile} profile.\n\n\
+            1. Call `antigravity.verify` with repo_root, changeset_id and profile.\n\
+            2. Read `axiomregent://changes/{changeset_id}/05-status.json` and report every failing check.",
+    },
+];
+
+/// Result of `prompts/list`.
+pub fn list() -> Vec<Value> {
+    PROMPTS
+        .iter()
+        .map(|p| {
+            let arguments: Vec<Value> = p
+                .arguments
+                .iter()
+                .map(|a| {
+                    json!({
+                        "name": a.name,
+                        "description": a.description,
+                        "required": a.required
+                    })
+                })
+                .collect();
+            json!({
+                "name": p.name,
+                "description": p.description,
+                "arguments": arguments
+            })
+        })
+        .collect()
+}
+
+/// Result of `prompts/get`. Unknown prompts and missing required arguments are
+/// invalid params.
+pub fn get(name: &str, arguments: &Map<String, Value>) -> Result<Value> {
+    let prompt = PROMPTS
+        .iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| InvalidParams(format!("Unknown prompt: {}", name)))?;
+
+    let mut text = prompt.template.to_string();
+    for arg in prompt.arguments {
+        let value = match arguments.get(arg.name).and_then(|v| v.as_str()) {
+            Some(v) => v,
+            None if arg.required => {
+                return Err(InvalidParams(format!(
+                    "Missing required argument '{}' for prompt {}",
+                    arg.name, name
+                ))
+                .into());
+            }
+            None => "pr",
+        };
+        text = text.replace(&format!("{{{}}}", arg.name), value);
+    }
+
+    Ok(json!({
+        "description": prompt.description,
+        "messages": [{
+            "role": "user",
+            "content": { "type": "text", "text": text }
+        }]
+    }))
+}
@@ -0,0 +1,411 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+//! Typed tool registry.
+//!
+//! Every MCP tool is a [`ToolHandler`]. Most are built with [`ToolRegistry::tool`],
+//! which pairs a serde argument struct with a handler closure and derives the
+//! `inputSchema` advertised in `tools/list` from that struct. Subsystems register
+//! their own tools; downstream crates can add more through `Router::with_tool`.
+
+use crate::config;
+use crate::router::Router;
+use crate::router::cancel::CancelToken;
+use crate::router::mounts::Capability;
+use crate::router::progress::Progress;
+use anyhow::Result;
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// What a tool hands back to the router.
+#[derive(Debug)]
+pub enum ToolOutput {
+    /// Returned as a `{"type": "json"}` content item.
+    Json(Value),
+    /// Returned as a `{"type": "text"}` content item.
+    Text(String),
+}
+
+impl From<Value> for ToolOutput {
+    fn from(v: Value) -> Self {
+        ToolOutput::Json(v)
+    }
+}
+
+impl ToolOutput {
+    /// Maps the `bool` result of mutating tools to the `"ok"` text reply.
+    pub fn from_flag(ok: bool) -> Result<Self> {
+        if ok {
+            Ok(ToolOutput::Text("ok".to_string()))
+        } else {
+            Err(anyhow::anyhow!("Tool returned false"))
+        }
+    }
+
+    pub fn into_content(self) -> Value {
+        match self {
+            ToolOutput::Json(v) => json!({ "content": [{ "type": "json", "json": v }] }),
+            ToolOutput::Text(t) => json!({ "content": [{ "type": "text", "text": t }] }),
+        }
+    }
+}
+
+/// Arguments did not match the tool's input schema. Surfaced as JSON-RPC `-32602`.
+#[derive(Debug)]
+pub struct InvalidParams(pub String);
+
+impl std::fmt::Display for InvalidParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidParams {}
+
+/// Per-call context handed to tool handlers.
+pub struct ToolContext<'a> {
+    pub(crate) router: &'a Router,
+    /// Name of the tool being called.
+    pub tool: &'a str,
+    pub request_id: Option<&'a Value>,
+    /// Transport session the request arrived on (`""` if none).
+    pub session: &'a str,
+    pub(crate) cancel: Option<&'a CancelToken>,
+    /// `params._meta.progressToken` of the `tools/call` request.
+    pub progress_token: Option<&'a Value>,
+}
+
+impl ToolContext<'_> {
+    /// Cancellation token of the in-flight request, if it was dispatched with an id.
+    pub fn cancel_token(&self) -> Option<CancelToken> {
+        self.cancel.cloned()
+    }
+
+    /// Progress reporter for the call; a no-op unless the client sent a progress token.
+    pub fn progress(&self) -> Progress {
+        self.router
+            .progress(self.progress_token, self.session, self.cancel)
+    }
+
+    /// Whether the tool may operate on the repository at `repo_root`: always,
+    /// unless strict mount scoping is on (see [`Router::with_strict_mounts`]).
+    pub fn in_scope(&self, repo_root: &str) -> bool {
+        self.router.in_scope(self.tool, repo_root)
+    }
+
+    /// The payload served by `get_capabilities`.
+    pub fn capabilities(&self) -> Value {
+        self.router.capabilities()
+    }
+}
+
+pub trait ToolHandler: Send + Sync {
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    fn input_schema(&self) -> Value;
+    fn call(&self, ctx: &ToolContext<'_>, args: Value) -> Result<ToolOutput>;
+
+    /// Whether the tool changes the repository or the store. Mutations take
+    /// their repository's lock exclusively and drop its cached responses.
+    fn mutates(&self) -> bool {
+        false
+    }
+
+    /// Capability a mount must grant before the tool may run on it in strict
+    /// mode. Only the core tools (`resolve_mcp`, `list_mounts`,
+    /// `get_capabilities`) declare none; registering any other tool without
+    /// one panics.
+    fn capability(&self) -> Option<Capability> {
+        None
+    }
+}
+
+/// A [`ToolHandler`] whose arguments deserialize into `A`.
+pub struct TypedTool<A, F> {
+    name: &'static str,
+    description: &'static str,
+    handler: F,
+    mutates: bool,
+    capability: Option<Capability>,
+    _args: PhantomData<fn(A)>,
+}
+
+impl<A, F> TypedTool<A, F>
+where
+    A: DeserializeOwned + JsonSchema,
+    F: Fn(&ToolContext<'_>, A) -> Result<ToolOutput> + Send + Sync,
+{
+    pub fn new(name: &'static str, description: &'static str, handler: F) -> Self {
+        Self {
+            name,
+            description,
+            handler,
+            mutates: false,
+            capability: None,
+            _args: PhantomData,
+        }
+    }
+
+    /// Marks the tool as a mutation (see [`ToolHandler::mutates`]).
+    pub fn mutating(mut self) -> Self {
+        self.mutates = true;
+        self
+    }
+
+    /// Declares the capability the tool needs (see [`ToolHandler::capability`]).
+    pub fn requires(mut self, capability: Capability) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+}
+
+impl<A, F> ToolHandler for TypedTool<A, F>
+where
+    A: DeserializeOwned + JsonSchema,
+    F: Fn(&ToolContext<'_>, A) -> Result<ToolOutput> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+
+    fn input_schema(&self) -> Value {
+        schema_for::<A>()
+    }
+
+    fn call(&self, ctx: &ToolContext<'_>, args: Value) -> Result<ToolOutput> {
+        let args: A = serde_json::from_value(args)
+            .map_err(|e| InvalidParams(format!("Invalid arguments for {}: {}", self.name, e)))?;
+        (self.handler)(ctx, args)
+    }
+
+    fn mutates(&self) -> bool {
+        self.mutates
+    }
+
+    fn capability(&self) -> Option<Capability> {
+        self.capability
+    }
+}
+
+/// Ordered set of tools. `tools/list` reports them in registration order.
+#[derive(Default, Clone)]
+pub struct ToolRegistry {
+    tools: Vec<Arc<dyn ToolHandler>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a tool, replacing any existing tool of the same name in place.
+    ///
+    /// # Panics
+    ///
+    /// If a tool other than a core one declares no [`ToolHandler::capability`]:
+    /// strict mount scoping could not check it.
+    pub fn register(&mut self, tool: impl ToolHandler + 'static) -> &mut Self {
+        let tool: Arc<dyn ToolHandler> = Arc::new(tool);
+        assert!(
+            tool.capability().is_some() || config::tool_group(tool.name()) == "core",
+            "tool {} declares no mount capability",
+            tool.name()
+        );
+        match self.tools.iter().position(|t| t.name() == tool.name()) {
+            Some(i) => self.tools[i] = tool,
+            None => self.tools.push(tool),
+        }
+        self
+    }
+
+    /// Registers a typed tool built from an argument struct and a handler
+    /// closure, usable on mounts granting `capability`.
+    pub fn tool<A, F>(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        capability: Capability,
+        handler: F,
+    ) -> &mut Self
+    where
+        A: DeserializeOwned + JsonSchema + 'static,
+        F: Fn(&ToolContext<'_>, A) -> Result<ToolOutput> + Send + Sync + 'static,
+    {
+        self.register(TypedTool::new(name, description, handler).requires(capability))
+    }
+
+    /// Like [`ToolRegistry::tool`], for a tool that changes the repository or the store.
+    pub fn mutation<A, F>(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        capability: Capability,
+        handler: F,
+    ) -> &mut Self
+    where
+        A: DeserializeOwned + JsonSchema + 'static,
+        F: Fn(&ToolContext<'_>, A) -> Result<ToolOutput> + Send + Sync + 'static,
+    {
+        self.register(
+            TypedTool::new(name, description, handler)
+                .mutating()
+                .requires(capability),
+        )
+    }
+
+    /// Keeps only the tools whose name satisfies `keep`.
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) -> &mut Self {
+        self.tools.retain(|t| keep(t.name()));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn ToolHandler>> {
+        self.tools.iter().find(|t| t.name() == name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.tools.iter().map(|t| t.name().to_string()).collect()
+    }
+
+    /// Tool definitions as returned by `tools/list`.
+    pub fn list(&self) -> Vec<Value> {
+        self.tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "name": t.name(),
+                    "description": t.description(),
+                    "inputSchema": t.input_schema()
+                })
+            })
+            .collect()
+    }
+}
+
+// Argument type for tools that take no arguments.
+#[derive(serde::Deserialize, JsonSchema, Default)]
+pub struct NoArgs {}
+
+/// Generates the `inputSchema` for `T`.
+///
+/// Subschemas are inlined, and optional fields are described by their inner type
+/// (clients may omit them) rather than as nullable unions. Doc comments on argument
+/// types and fields become schema `description`s, so keep them client-facing.
+pub fn schema_for<T: JsonSchema>() -> Value {
+    let generator = SchemaSettings::draft07()
+        .with(|s| {
+            s.inline_subschemas = true;
+            s.meta_schema = None;
+        })
+        .into_generator();
+    let mut schema = generator.into_root_schema_for::<T>().to_value();
+    simplify(&mut schema);
+    if let Some(obj) = schema.as_object_mut() {
+        obj.entry("properties").or_insert_with(|| json!({}));
+    }
+    schema
+}
+
+fn simplify(schema: &mut Value) {
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+    obj.remove("title");
+    if obj.get("default") == Some(&Value::Null) {
+        obj.remove("default");
+    }
+
+    // ["string", "null"] -> "string"
+    if let Some(Value::Array(types)) = obj.get("type") {
+        let non_null: Vec<Value> = types.iter().filter(|t| *t != "null").cloned().collect();
+        if non_null.len() == 1 {
+            obj.insert("type".to_string(), non_null[0].clone());
+        }
+    }
+    // {"anyOf": [X, {"type": "null"}]} -> X
+    if let Some(Value::Array(variants)) = obj.get("anyOf") {
+        let non_null: Vec<Value> = variants
+            .iter()
+            .filter(|v| v.get("type") != Some(&json!("null")))
+            .cloned()
+            .collect();
+        if non_null.len() == 1 && variants.len() == 2 {
+            let inner = non_null[0].clone();
+            obj.remove("anyOf");
+            if let Value::Object(inner) = inner {
+                for (k, v) in inner {
+                    obj.entry(k).or_insert(v);
+                }
+            }
+        }
+    }
+    // Nullable enums list `null` as a value.
+    if let Some(Value::Array(values)) = obj.get_mut("enum") {
+        values.retain(|v| !v.is_null());
+    }
+
+    for value in obj.values_mut() {
+        match value {
+            Value::Object(_) => simplify(value),
+            Value::Array(items) => items.iter_mut().for_each(simplify),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, JsonSchema)]
+    #[serde(rename_all = "lowercase")]
+    #[allow(dead_code)]
+    enum Mode {
+        Worktree,
+        Snapshot,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Args {
+        repo_root: String,
+        mode: Option<Mode>,
+        limit: Option<u64>,
+        #[serde(default)]
+        dry_run: bool,
+    }
+
+    #[test]
+    fn test_schema_for_flattens_optionals() {
+        let schema = schema_for::<Args>();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["repo_root"]["type"], "string");
+        assert_eq!(schema["properties"]["mode"]["type"], "string");
+        assert_eq!(
+            schema["properties"]["mode"]["enum"],
+            json!(["worktree", "snapshot"])
+        );
+        assert_eq!(schema["properties"]["limit"]["type"], "integer");
+        assert_eq!(schema["properties"]["dry_run"]["type"], "boolean");
+        assert_eq!(schema["required"], json!(["repo_root"]));
+        assert!(schema.get("title").is_none());
+    }
+
+    #[test]
+    fn test_no_args_schema_is_empty_object() {
+        let schema = schema_for::<NoArgs>();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"], json!({}));
+    }
+}
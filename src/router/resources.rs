@@ -0,0 +1,375 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+//! MCP resources.
+//!
+//! Read-only views addressed by `axiomregent://` URIs:
+//! - `axiomregent://snapshot/<snapshot_id>/<path>`: a file of a stored snapshot (immutable).
+//! - `axiomregent://spec/<feature_id>`: the spec registered for a feature in `spec/features.yaml`.
+//! - `axiomregent://changes/<changeset_id>/<file>`: a file of an antigravity changeset,
+//!   e.g. `05-status.json`.
+//!
+//! Specs and changesets are looked up in the mounted repositories, in mount-name order.
+
+use crate::router::AxiomRegentError;
+use crate::router::error::ToolError;
+use crate::router::mounts::MountRegistry;
+use crate::snapshot::store::Store;
+use crate::snapshot::tools::SnapshotTools;
+use anyhow::Result;
+use base64::Engine;
+use featuregraph::scanner::Scanner;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const SCHEME: &str = "axiomregent://";
+
+const STATUS_FILE: &str = "05-status.json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceUri {
+    Snapshot { snapshot_id: String, path: String },
+    Spec { feature_id: String },
+    Change { changeset_id: String, file: String },
+}
+
+impl ResourceUri {
+    pub fn parse(uri: &str) -> Result<Self, ToolError> {
+        let invalid = || {
+            AxiomRegentError::InvalidArgument(format!("Unsupported resource URI: {}", uri))
+                .with_path(uri)
+        };
+        let rest = uri.strip_prefix(SCHEME).ok_or_else(invalid)?;
+        let (kind, rest) = rest.split_once('/').ok_or_else(invalid)?;
+        let parsed = match kind {
+            "snapshot" => {
+                let (id, path) = rest.split_once('/').ok_or_else(invalid)?;
+                ResourceUri::Snapshot {
+                    snapshot_id: id.to_string(),
+                    path: path.to_string(),
+                }
+            }
+            "spec" => ResourceUri::Spec {
+                feature_id: rest.to_string(),
+            },
+            "changes" => {
+                let (id, file) = rest.split_once('/').ok_or_else(invalid)?;
+                if !is_single_component(id) || !is_single_component(file) {
+                    return Err(invalid());
+                }
+                ResourceUri::Change {
+                    changeset_id: id.to_string(),
+                    file: file.to_string(),
+                }
+            }
+            _ => return Err(invalid()),
+        };
+        match &parsed {
+            ResourceUri::Snapshot { snapshot_id, .. } if snapshot_id.is_empty() => Err(invalid()),
+            ResourceUri::Spec { feature_id } if feature_id.is_empty() => Err(invalid()),
+            _ => Ok(parsed),
+        }
+    }
+
+    /// Snapshot files never change, so subscribing to them is a no-op.
+    pub fn is_immutable(&self) -> bool {
+        matches!(self, ResourceUri::Snapshot { .. })
+    }
+}
+
+impl std::fmt::Display for ResourceUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResourceUri::Snapshot { snapshot_id, path } => {
+                write!(f, "{}snapshot/{}/{}", SCHEME, snapshot_id, path)
+            }
+            ResourceUri::Spec { feature_id } => write!(f, "{}spec/{}", SCHEME, feature_id),
+            ResourceUri::Change { changeset_id, file } => {
+                write!(f, "{}changes/{}/{}", SCHEME, changeset_id, file)
+            }
+        }
+    }
+}
+
+pub struct Resources {
+    snapshots: Arc<SnapshotTools>,
+    mounts: MountRegistry,
+    subscriptions: Mutex<HashMap<String, Subscription>>,
+}
+
+struct Subscription {
+    // Content hash at the last check (None if the resource did not exist).
+    hash: Option<String>,
+    // Transport sessions subscribed to the URI.
+    sessions: BTreeSet<String>,
+}
+
+impl Resources {
+    pub fn new(snapshots: Arc<SnapshotTools>, mounts: MountRegistry) -> Self {
+        Self {
+            snapshots,
+            mounts,
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Concrete resources of the mounted repositories: every registered spec and
+    /// every changeset status file. Snapshot files are only advertised as a template.
+    pub fn list(&self) -> Vec<Value> {
+        let mut seen = BTreeSet::new();
+        let mut resources = Vec::new();
+        for root in self.roots() {
+            if let Ok(entries) = Scanner::new(&root).registry() {
+                for entry in entries {
+                    let uri = ResourceUri::Spec {
+                        feature_id: entry.feature_id.clone(),
+                    }
+                    .to_string();
+                    if seen.insert(uri.clone()) {
+                        resources.push(json!({
+                            "uri": uri,
+                            "name": entry.feature_id,
+                            "description": format!("{} ({})", entry.title, entry.spec_path),
+                            "mimeType": mime_type(&entry.spec_path, false)
+                        }));
+                    }
+                }
+            }
+
+            let Ok(dirs) = std::fs::read_dir(root.join("changes")) else {
+                continue;
+            };
+            let mut ids: Vec<String> = dirs
+                .filter_map(|d| d.ok())
+                .filter(|d| d.path().join(STATUS_FILE).is_file())
+                .filter_map(|d| d.file_name().into_string().ok())
+                .collect();
+            ids.sort();
+            for id in ids {
+                let uri = ResourceUri::Change {
+                    changeset_id: id.clone(),
+                    file: STATUS_FILE.to_string(),
+                }
+                .to_string();
+                if seen.insert(uri.clone()) {
+                    resources.push(json!({
+                        "uri": uri,
+                        "name": format!("{}/{}", id, STATUS_FILE),
+                        "mimeType": "application/json"
+                    }));
+                }
+            }
+        }
+        resources
+    }
+
+    pub fn templates(&self) -> Vec<Value> {
+        vec![
+            json!({
+                "uriTemplate": format!("{}snapshot/{{snapshot_id}}/{{path}}", SCHEME),
+                "name": "snapshot-file",
+                "description": "A file of a stored snapshot (immutable)"
+            }),
+            json!({
+                "uriTemplate": format!("{}spec/{{feature_id}}", SCHEME),
+                "name": "feature-spec",
+                "description": "The spec registered for a feature in spec/features.yaml",
+                "mimeType": "text/markdown"
+            }),
+            json!({
+                "uriTemplate": format!("{}changes/{{changeset_id}}/{{file}}", SCHEME),
+                "name": "changeset-file",
+                "description": "A file of an antigravity changeset, e.g. 05-status.json"
+            }),
+        ]
+    }
+
+    /// Result of `resources/read`.
+    pub fn read(&self, uri: &str) -> Result<Value> {
+        let parsed = ResourceUri::parse(uri)?;
+        let (content, name) = self.load(&parsed)?;
+        Ok(json!({ "contents": [contents_item(uri, &name, content)] }))
+    }
+
+    /// Subscribes transport `session` to updates of `uri`.
+    pub fn subscribe(&self, uri: &str, session: &str) -> Result<()> {
+        let parsed = ResourceUri::parse(uri)?;
+        if parsed.is_immutable() {
+            return Ok(());
+        }
+        let mut subs = self.subscriptions.lock().unwrap();
+        let sub = match subs.entry(uri.to_string()) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(Subscription {
+                hash: self.content_hash(&parsed),
+                sessions: BTreeSet::new(),
+            }),
+        };
+        sub.sessions.insert(session.to_string());
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, uri: &str, session: &str) {
+        let mut subs = self.subscriptions.lock().unwrap();
+        if let Some(sub) = subs.get_mut(uri) {
+            sub.sessions.remove(session);
+            if sub.sessions.is_empty() {
+                subs.remove(uri);
+            }
+        }
+    }
+
+    pub fn has_subscriptions(&self) -> bool {
+        !self.subscriptions.lock().unwrap().is_empty()
+    }
+
+    /// Re-reads subscribed resources and returns the URIs whose content changed
+    /// since the last check, each with the sessions subscribed to it.
+    pub fn changed(&self) -> Vec<(String, Vec<String>)> {
+        let mut subs = self.subscriptions.lock().unwrap();
+        let mut changed = Vec::new();
+        for (uri, sub) in subs.iter_mut() {
+            let Ok(parsed) = ResourceUri::parse(uri) else {
+                continue;
+            };
+            let current = self.content_hash(&parsed);
+            if current != sub.hash {
+                sub.hash = current;
+                changed.push((uri.clone(), sub.sessions.iter().cloned().collect()));
+            }
+        }
+        changed.sort();
+        changed
+    }
+
+    fn content_hash(&self, uri: &ResourceUri) -> Option<String> {
+        self.load(uri)
+            .ok()
+            .map(|(content, _)| hex::encode(Sha256::digest(&content)))
+    }
+
+    /// Content of the resource and the file name used to pick its MIME type.
+    fn load(&self, uri: &ResourceUri) -> Result<(Vec<u8>, String)> {
+        match uri {
+            ResourceUri::Snapshot { snapshot_id, path } => {
+                let (_, content) = self.snapshots.read_snapshot_file(snapshot_id, path)?;
+                Ok((content, path.clone()))
+            }
+            ResourceUri::Spec { feature_id } => {
+                for root in self.roots() {
+                    let Ok(entries) = Scanner::new(&root).registry() else {
+                        continue;
+                    };
+                    if let Some(entry) = entries.into_iter().find(|e| &e.feature_id == feature_id) {
+                        Store::validate_path(&entry.spec_path)?;
+                        let content = std::fs::read(root.join(&entry.spec_path)).map_err(|_| {
+                            AxiomRegentError::NotFound(format!(
+                                "Spec file missing for {}: {}",
+                                feature_id, entry.spec_path
+                            ))
+                            .with_path(entry.spec_path.clone())
+                        })?;
+                        return Ok((content, entry.spec_path));
+                    }
+                }
+                Err(AxiomRegentError::NotFound(format!("Feature not found: {}", feature_id)).into())
+            }
+            ResourceUri::Change { changeset_id, file } => {
+                let rel = format!("changes/{}/{}", changeset_id, file);
+                for root in self.roots() {
+                    if let Ok(content) = std::fs::read(root.join(&rel)) {
+                        return Ok((content, file.clone()));
+                    }
+                }
+                Err(
+                    AxiomRegentError::NotFound(format!("Changeset file not found: {}", rel))
+                        .with_path(rel)
+                        .into(),
+                )
+            }
+        }
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        self.mounts
+            .list()
+            .into_iter()
+            .map(|m| PathBuf::from(m.root))
+            .collect()
+    }
+}
+
+fn is_single_component(s: &str) -> bool {
+    let mut components = Path::new(s).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    )
+}
+
+fn mime_type(name: &str, binary: bool) -> &'static str {
+    match Path::new(name).extension().and_then(|e| e.to_str()) {
+        Some("md") => "text/markdown",
+        Some("json") => "application/json",
+        Some("yaml" | "yml") => "application/yaml",
+        _ if binary => "application/octet-stream",
+        _ => "text/plain",
+    }
+}
+
+fn contents_item(uri: &str, name: &str, content: Vec<u8>) -> Value {
+    match String::from_utf8(content) {
+        Ok(text) if !text.contains('\0') => json!({
+            "uri": uri,
+            "mimeType": mime_type(name, false),
+            "text": text
+        }),
+        Ok(text) => binary_item(uri, name, text.as_bytes()),
+        Err(e) => binary_item(uri, name, e.as_bytes()),
+    }
+}
+
+fn binary_item(uri: &str, name: &str, content: &[u8]) -> Value {
+    json!({
+        "uri": uri,
+        "mimeType": mime_type(name, true),
+        "blob": base64::engine::general_purpose::STANDARD.encode(content)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips() {
+        for uri in [
+            "axiomregent://snapshot/abc/src/lib.rs",
+            "axiomregent://spec/MCP_ROUTER",
+            "axiomregent://changes/001_core_router/05-status.json",
+        ] {
+            assert_eq!(ResourceUri::parse(uri).unwrap().to_string(), uri);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_traversal_and_unknown_kinds() {
+        for uri in [
+            "file:///etc/passwd",
+            "axiomregent://other/x",
+            "axiomregent://changes/../05-status.json",
+            "axiomregent://changes/x/../../secret",
+            "axiomregent://spec/",
+            "axiomregent://snapshot//path",
+        ] {
+            let err = ResourceUri::parse(uri).unwrap_err();
+            assert_eq!(err.error.code(), "INVALID_ARGUMENT", "{}", uri);
+        }
+    }
+}
@@ -3,9 +3,12 @@
 // Feature: AXIOMREGENT_RUN_SKILLS
 // Spec: spec/run/skills.md
 
+use crate::router::mounts::Capability;
+use crate::router::progress::Progress;
+use crate::router::registry::ToolRegistry;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use log::error;
+use log::{error, info};
 use run::{RunConfig, Runner, StateStore, registry};
 use std::collections::HashMap;
 use std::env;
@@ -42,10 +45,13 @@ impl RunTools {
         }
     }
 
+    /// Starts `skill` in the background and returns its run id. Each finished
+    /// skill is reported to `progress`, which outlives the call.
     pub fn execute(
         &self,
         skill: String,
         env_vars: Option<HashMap<String, String>>,
+        progress: Progress,
     ) -> Result<String> {
         let run_id = Uuid::new_v4().to_string();
         let logs_path = self.state_dir.join("logs").join(format!("{}.log", run_id));
@@ -105,7 +111,17 @@ impl RunTools {
 
             let store = StateStore::new(&state_dir_str);
             let registry = registry::get_registry();
-            let runner = Runner::new(registry, store, config, Some(Box::new(log_file)));
+            let run_id = run_id_clone.clone();
+            let runner = Runner::new(registry, store, config, Some(Box::new(log_file)))
+                .with_progress(Box::new(move |res, done, total| {
+                    let status: &str = res.status.as_ref();
+                    info!("run {}: skill {} finished: {}", run_id, res.skill, status);
+                    progress.report(
+                        done as u64,
+                        Some(total as u64),
+                        &format!("Skill {} finished: {}", res.skill, status),
+                    );
+                }));
 
             let result = runner.run_specific(&[skill]);
 
@@ -182,3 +198,53 @@ impl RunTools {
         }
     }
 }
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct ExecuteArgs {
+    pub skill: String,
+    pub env: Option<HashMap<String, String>>,
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct RunIdArgs {
+    pub run_id: String,
+}
+
+#[derive(serde::Deserialize, schemars::JsonSchema)]
+pub struct LogsArgs {
+    pub run_id: String,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+/// Registers the `run.*` tools.
+pub fn register(registry: &mut ToolRegistry, tools: Arc<RunTools>) {
+    let t = tools.clone();
+    registry.tool(
+        "run.execute",
+        "Execute a run skill",
+        Capability::Execute,
+        move |ctx, a: ExecuteArgs| {
+            let run_id = t.execute(a.skill, a.env, ctx.progress())?;
+            Ok(serde_json::Value::String(run_id).into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "run.status",
+        "Get run status",
+        Capability::ReadFile,
+        move |_, a: RunIdArgs| Ok(t.status(&a.run_id)?.into()),
+    );
+
+    let t = tools;
+    registry.tool(
+        "run.logs",
+        "Get run logs",
+        Capability::ReadFile,
+        move |_, a: LogsArgs| {
+            Ok(serde_json::Value::String(t.logs(&a.run_id, a.offset, a.limit)?).into())
+        },
+    );
+}
@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! Content-defined chunking of large files.
+//!
+//! Cut points are chosen by a gear rolling hash over the last 64 bytes, so an
+//! edit only changes the chunks around it: the boundaries after it line up
+//! again and the following chunks keep their hashes.
+
+/// Files smaller than this are stored as a single blob.
+pub const CHUNKING_THRESHOLD: usize = 256 * 1024;
+pub const MIN_CHUNK_SIZE: usize = 16 * 1024;
+pub const MAX_CHUNK_SIZE: usize = 256 * 1024;
+// 16 high bits: a cut every 64 KiB on average after the minimum size.
+const CUT_MASK: u64 = !0 << (64 - 16);
+
+const fn gear_table() -> [u64; 256] {
+    // splitmix64: fixed, so chunk boundaries are stable across builds.
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+static GEAR: [u64; 256] = gear_table();
+
+/// Whether `len` bytes are stored as chunks rather than one blob.
+pub fn should_chunk(len: usize) -> bool {
+    len >= CHUNKING_THRESHOLD
+}
+
+/// Splits `data` into consecutive chunks of `MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE`
+/// bytes (the last one may be shorter).
+pub fn split(data: &[u8]) -> Vec<&[u8]> {
+    let mut chunks = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let (chunk, tail) = rest.split_at(cut_point(rest));
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+fn cut_point(data: &[u8]) -> usize {
+    if data.len() <= MIN_CHUNK_SIZE {
+        return data.len();
+    }
+    let end = data.len().min(MAX_CHUNK_SIZE);
+    let mut hash: u64 = 0;
+    for (i, &byte) in data.iter().enumerate().take(end).skip(MIN_CHUNK_SIZE) {
+        hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+        if hash & CUT_MASK == 0 {
+            return i + 1;
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pseudo_random(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_covers_input_within_bounds() {
+        let data = pseudo_random(3 * 1024 * 1024, 1);
+        let chunks = split(&data);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), data);
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!((MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk.len()));
+        }
+        assert!(split(&[]).is_empty());
+        assert_eq!(split(b"short"), [b"short"]);
+    }
+
+    #[test]
+    fn test_edit_keeps_most_chunks() {
+        let data = pseudo_random(2 * 1024 * 1024, 7);
+        let mut edited = data.clone();
+        edited.splice(
+            1024 * 1024..1024 * 1024 + 10,
+            b"inserted line\n".iter().copied(),
+        );
+
+        let before: std::collections::HashSet<&[u8]> = split(&data).into_iter().collect();
+        let after = split(&edited);
+        let changed = after.iter().filter(|c| !before.contains(*c)).count();
+        assert!(
+            changed <= 2,
+            "{} of {} chunks changed",
+            changed,
+            after.len()
+        );
+    }
+}
@@ -0,0 +1,781 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! Computing the [`Fingerprint`] of a repository, either by running `git`
+//! ([`GitFingerprinter`]) or by reading `.git` and the worktree directly
+//! ([`NativeFingerprinter`]).
+//!
+//! The two do not hash the same data, so their fingerprints never compare
+//! equal: leases acquired under one are stale under the other.
+
+use crate::config::FingerprintBackend;
+use crate::router::AxiomRegentError;
+use crate::snapshot::lease::Fingerprint;
+use crate::snapshot::store::FileStat;
+use crate::snapshot::worktree::{self, RACY_WINDOW};
+use anyhow::Result;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Computes the fingerprint of a repository's HEAD, index and worktree.
+pub trait Fingerprinter: Send + Sync {
+    fn compute(&self, repo_root: &Path) -> Result<Fingerprint>;
+}
+
+/// The fingerprinter selected by `[leases] fingerprint`.
+pub fn for_backend(backend: FingerprintBackend) -> Arc<dyn Fingerprinter> {
+    match backend {
+        FingerprintBackend::Native => Arc::new(NativeFingerprinter::new()),
+        FingerprintBackend::Git => Arc::new(GitFingerprinter),
+    }
+}
+
+/// Runs `git rev-parse HEAD`, `git write-tree` and `git status`. Fails
+/// outside git repositories.
+pub struct GitFingerprinter;
+
+impl Fingerprinter for GitFingerprinter {
+    fn compute(&self, repo_root: &Path) -> Result<Fingerprint> {
+        if !repo_root.is_dir() {
+            return Err(AxiomRegentError::repo_not_found(repo_root).into());
+        }
+
+        // 1. head_oid
+        let head_output = Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(repo_root)
+            .output()?;
+
+        let head_oid = if head_output.status.success() {
+            String::from_utf8_lossy(&head_output.stdout)
+                .trim()
+                .to_string()
+        } else {
+            // Unborn branch or empty repo?
+            // rev-parse HEAD passes even if unborn? No, usually fails.
+            // Check if symbolic-ref HEAD exists?
+            // Fallback for unborn: empty string.
+            // We can treat failure as unborn for now if verifying it's a git repo.
+            // Assume it is a git repo.
+            "".to_string()
+        };
+
+        // 2. index_oid
+        let write_tree_output = Command::new("git")
+            .arg("write-tree")
+            .current_dir(repo_root)
+            .output()?;
+
+        let index_oid = if write_tree_output.status.success() {
+            String::from_utf8_lossy(&write_tree_output.stdout)
+                .trim()
+                .to_string()
+        } else {
+            // "no tree possible" -> e.g. merge conflict state when index is invalid?
+            // Spec: "Empty string only if a tree is provably impossible"
+            "".to_string()
+        };
+
+        // 3. status_hash
+        // git status --porcelain=v1 -z
+        let status_output = Command::new("git")
+            .args(["status", "--porcelain=v1", "-z"])
+            .current_dir(repo_root)
+            .output()?;
+
+        if !status_output.status.success() {
+            return Err(
+                AxiomRegentError::InvalidArgument("Failed to run git status".into())
+                    .with_path(repo_root.to_string_lossy())
+                    .into(),
+            );
+        }
+
+        let status_hash = hex::encode(Sha256::digest(&status_output.stdout));
+
+        Ok(Fingerprint {
+            head_oid,
+            index_oid,
+            status_hash,
+        })
+    }
+}
+
+/// Reads HEAD and the index from `.git` and compares the worktree with the
+/// index itself, without running `git` or writing to the repository.
+///
+/// - `head_oid`: the commit HEAD resolves to; empty on an unborn branch.
+/// - `index_oid`: SHA-256 of the mode, object id, stage and path of every
+///   index entry (not their stat data).
+/// - `status_hash`: SHA-256 of the worktree's differences from the index:
+///   deleted, modified (with their new content's object id) and untracked
+///   files not excluded by `.gitignore`. Unlike `git status`, editing an
+///   already modified file changes it.
+///
+/// A directory that is not a git repository has an empty `head_oid` and
+/// `index_oid`, and all its files count as untracked.
+///
+/// Files are only read when their stat differs from the one recorded in the
+/// index, or in this fingerprinter's own cache of the files it read before.
+pub struct NativeFingerprinter {
+    // Object ids of the dirty and untracked files last seen, per repo root.
+    hashes: Mutex<HashMap<PathBuf, HashMap<String, Hashed>>>,
+}
+
+#[derive(Clone)]
+struct Hashed {
+    stat: FileStat,
+    symlink: bool,
+    oid: String,
+}
+
+impl Default for NativeFingerprinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NativeFingerprinter {
+    pub fn new() -> Self {
+        Self {
+            hashes: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Fingerprinter for NativeFingerprinter {
+    fn compute(&self, repo_root: &Path) -> Result<Fingerprint> {
+        if !repo_root.is_dir() {
+            return Err(AxiomRegentError::repo_not_found(repo_root).into());
+        }
+        let git = GitDir::find(repo_root)?;
+        let root = git.as_ref().map_or(repo_root, |(toplevel, _)| toplevel);
+        let cached = self.hashes.lock().unwrap().remove(root);
+        let mut scan = Scan {
+            root,
+            format: ObjectFormat::Sha256,
+            cached: cached.unwrap_or_default(),
+            seen: HashMap::new(),
+        };
+
+        let fingerprint = match &git {
+            Some((_, git)) => {
+                scan.format = git.object_format()?;
+                let index = git.read_index(scan.format)?;
+                Fingerprint {
+                    head_oid: git.head_oid()?,
+                    index_oid: index.hash(),
+                    status_hash: scan.status_hash(&index)?,
+                }
+            }
+            None => Fingerprint {
+                head_oid: String::new(),
+                index_oid: String::new(),
+                status_hash: scan.status_hash(&Index::default())?,
+            },
+        };
+
+        self.hashes
+            .lock()
+            .unwrap()
+            .insert(root.to_path_buf(), scan.seen);
+        Ok(fingerprint)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ObjectFormat {
+    Sha1,
+    Sha256,
+}
+
+impl ObjectFormat {
+    fn oid_len(self) -> usize {
+        match self {
+            ObjectFormat::Sha1 => 20,
+            ObjectFormat::Sha256 => 32,
+        }
+    }
+
+    /// Object id of a blob holding `content`, as `git hash-object` computes it.
+    fn blob_oid(self, content: &[u8]) -> String {
+        let header = format!("blob {}\0", content.len());
+        match self {
+            ObjectFormat::Sha1 => hex::encode(
+                Sha1::new()
+                    .chain_update(header)
+                    .chain_update(content)
+                    .finalize(),
+            ),
+            ObjectFormat::Sha256 => hex::encode(
+                Sha256::new()
+                    .chain_update(header)
+                    .chain_update(content)
+                    .finalize(),
+            ),
+        }
+    }
+}
+
+/// The git directory of a worktree, and the directory it shares refs and
+/// config with (the same one, except in linked worktrees).
+struct GitDir {
+    dir: PathBuf,
+    common: PathBuf,
+}
+
+impl GitDir {
+    /// The root of the worktree containing `path`, the nearest directory
+    /// up from it with a `.git`, and its git directory: that `.git`, or the
+    /// directory a `.git` file points to (`gitdir: ...`).
+    fn find(path: &Path) -> Result<Option<(PathBuf, Self)>> {
+        let Some(toplevel) = path.ancestors().find(|dir| dir.join(".git").exists()) else {
+            return Ok(None);
+        };
+        let dot_git = toplevel.join(".git");
+        let dir = if dot_git.is_dir() {
+            dot_git
+        } else {
+            let content = fs::read_to_string(&dot_git)?;
+            let Some(target) = content.trim().strip_prefix("gitdir:") else {
+                return Err(unsupported(toplevel, "a .git file without 'gitdir:'"));
+            };
+            toplevel.join(target.trim())
+        };
+        let common = match fs::read_to_string(dir.join("commondir")) {
+            Ok(common) => dir.join(common.trim()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => dir.clone(),
+            Err(e) => return Err(e.into()),
+        };
+        if common.join("reftable").is_dir() {
+            return Err(unsupported(toplevel, "reftable refs"));
+        }
+        Ok(Some((toplevel.to_path_buf(), Self { dir, common })))
+    }
+
+    fn object_format(&self) -> Result<ObjectFormat> {
+        let config = match fs::read_to_string(self.common.join("config")) {
+            Ok(config) => config,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(ObjectFormat::Sha1),
+            Err(e) => return Err(e.into()),
+        };
+        let sha256 = config.lines().any(|line| {
+            let line = line.trim().to_ascii_lowercase();
+            line.split_once('=').is_some_and(|(key, value)| {
+                key.trim() == "objectformat" && value.trim() == "sha256"
+            })
+        });
+        Ok(if sha256 {
+            ObjectFormat::Sha256
+        } else {
+            ObjectFormat::Sha1
+        })
+    }
+
+    /// The commit HEAD resolves to, following symbolic refs through loose
+    /// and packed refs; empty if the branch it names has no commit yet.
+    fn head_oid(&self) -> Result<String> {
+        let mut content = fs::read_to_string(self.dir.join("HEAD"))?;
+        // Bounded like git's own symref resolution.
+        for _ in 0..5 {
+            let Some(name) = content.trim().strip_prefix("ref:") else {
+                return Ok(content.trim().to_string());
+            };
+            let name = name.trim();
+            match self.read_ref(name)? {
+                Some(next) => content = next,
+                None => return Ok(String::new()),
+            }
+        }
+        Ok(String::new())
+    }
+
+    /// Content of the loose ref `name`, or its line in `packed-refs`.
+    fn read_ref(&self, name: &str) -> Result<Option<String>> {
+        // Per-worktree refs (e.g. `refs/bisect`) live in the worktree's own dir.
+        for dir in [&self.dir, &self.common] {
+            match fs::read_to_string(dir.join(name)) {
+                Ok(content) => return Ok(Some(content)),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::NotFound | std::io::ErrorKind::IsADirectory
+                    ) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let packed = match fs::read_to_string(self.common.join("packed-refs")) {
+            Ok(packed) => packed,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(packed
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+            .filter_map(|line| line.split_once(' '))
+            .find(|(_, ref_name)| ref_name.trim() == name)
+            .map(|(oid, _)| oid.to_string()))
+    }
+
+    /// The index of the worktree; empty if it has none yet.
+    fn read_index(&self, format: ObjectFormat) -> Result<Index> {
+        let path = self.dir.join("index");
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Index::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let mtime_ns = worktree::file_stat(&fs::metadata(&path)?)?.mtime_ns;
+        let entries = parse_index(&data, format.oid_len())
+            .ok_or_else(|| unsupported(&self.dir, "a corrupt or unknown index format"))?;
+        Ok(Index { entries, mtime_ns })
+    }
+}
+
+fn unsupported(path: &Path, what: &str) -> anyhow::Error {
+    AxiomRegentError::InvalidArgument(format!(
+        "Cannot fingerprint {}: {} is not supported (use [leases] fingerprint = \"git\")",
+        path.display(),
+        what
+    ))
+    .with_path(path.to_string_lossy())
+    .into()
+}
+
+#[derive(Default)]
+struct Index {
+    entries: Vec<IndexEntry>,
+    // When the index was written: files modified since may have changed
+    // without their stat showing it ("racily clean").
+    mtime_ns: i64,
+}
+
+impl Index {
+    fn hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for e in &self.entries {
+            hasher.update(format!("{:o} {} {}\t{}\0", e.mode, e.oid, e.stage, e.path));
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+struct IndexEntry {
+    path: String,
+    mode: u32,
+    oid: String,
+    stage: u16,
+    // The stat fields git compares, truncated to 32 bits like git does.
+    mtime: (u32, u32),
+    inode: u32,
+    size: u32,
+    skip_worktree: bool,
+}
+
+const MODE_SYMLINK: u32 = 0o120000;
+const MODE_GITLINK: u32 = 0o160000;
+const MODE_TYPE: u32 = 0o170000;
+
+// Threads comparing the worktree with the index.
+const MAX_STAT_THREADS: usize = 8;
+
+/// Entries of a version 2, 3 or 4 index file (`Documentation/gitformat-index`).
+/// `None` if it is malformed or split (`link` extension).
+fn parse_index(data: &[u8], oid_len: usize) -> Option<Vec<IndexEntry>> {
+    let u32_at = |pos: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+    };
+    if data.get(..4)? != b"DIRC" {
+        return None;
+    }
+    let version = u32_at(4)?;
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+    let count = u32_at(8)? as usize;
+
+    let mut entries: Vec<IndexEntry> = Vec::with_capacity(count);
+    let mut pos = 12;
+    for _ in 0..count {
+        let start = pos;
+        let flags_at = start + 40 + oid_len;
+        let flags = u16::from_be_bytes(data.get(flags_at..flags_at + 2)?.try_into().ok()?);
+        let mut name_at = flags_at + 2;
+        let mut skip_worktree = false;
+        if version >= 3 && flags & 0x4000 != 0 {
+            let extended = u16::from_be_bytes(data.get(name_at..name_at + 2)?.try_into().ok()?);
+            skip_worktree = extended & 0x4000 != 0;
+            name_at += 2;
+        }
+
+        let path;
+        if version == 4 {
+            // The path drops `strip` bytes from the end of the previous one
+            // and appends the NUL-terminated rest.
+            let mut strip = 0usize;
+            loop {
+                let byte = *data.get(name_at)?;
+                name_at += 1;
+                strip = (strip << 7) | (byte & 0x7f) as usize;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+                strip += 1;
+            }
+            let len = data.get(name_at..)?.iter().position(|b| *b == 0)?;
+            let previous = entries.last().map_or(&[][..], |e| e.path.as_bytes());
+            let mut bytes = previous.get(..previous.len().checked_sub(strip)?)?.to_vec();
+            bytes.extend_from_slice(&data[name_at..name_at + len]);
+            path = String::from_utf8_lossy(&bytes).into_owned();
+            pos = name_at + len + 1;
+        } else {
+            let len = data.get(name_at..)?.iter().position(|b| *b == 0)?;
+            path = String::from_utf8_lossy(&data[name_at..name_at + len]).into_owned();
+            // NUL-padded to a multiple of 8 bytes, with at least one NUL.
+            pos += (name_at - start + len + 8) & !7;
+        }
+
+        entries.push(IndexEntry {
+            path,
+            mode: u32_at(start + 24)?,
+            oid: hex::encode(data.get(start + 40..flags_at)?),
+            stage: (flags >> 12) & 0x3,
+            mtime: (u32_at(start + 8)?, u32_at(start + 12)?),
+            inode: u32_at(start + 20)?,
+            size: u32_at(start + 36)?,
+            skip_worktree,
+        });
+    }
+
+    // Extensions, up to the trailing checksum. A split index keeps most
+    // entries in a shared file, which is not read.
+    let end = data.len().checked_sub(oid_len)?;
+    while pos + 8 <= end {
+        if data.get(pos..pos + 4)? == b"link" {
+            return None;
+        }
+        pos += 8 + u32_at(pos + 4)? as usize;
+    }
+    Some(entries)
+}
+
+/// One pass of [`NativeFingerprinter`] over a worktree.
+struct Scan<'a> {
+    root: &'a Path,
+    format: ObjectFormat,
+    // Object ids read by earlier passes, and those read or reused by this one.
+    cached: HashMap<String, Hashed>,
+    seen: HashMap<String, Hashed>,
+}
+
+impl Scan<'_> {
+    /// Hash of the worktree's differences from `index`, one record per path.
+    fn status_hash(&mut self, index: &Index) -> Result<String> {
+        let root = self.root;
+        let (mut records, listed) = thread::scope(|scope| -> Result<_> {
+            // The worktree is walked for untracked files while the index is compared.
+            let walk = scope.spawn(|| worktree::list_files(root));
+            let records = self.compare_index(index)?;
+            let listed = walk.join().expect("worktree walk panicked")?;
+            Ok((records, listed))
+        })?;
+
+        let tracked: HashSet<&str> = index.entries.iter().map(|e| e.path.as_str()).collect();
+        let submodules: Vec<String> = index
+            .entries
+            .iter()
+            .filter(|e| e.mode & MODE_TYPE == MODE_GITLINK)
+            .map(|e| format!("{}/", e.path))
+            .collect();
+        for path in listed {
+            if tracked.contains(path.as_str()) || submodules.iter().any(|s| path.starts_with(s)) {
+                continue;
+            }
+            let meta = match fs::symlink_metadata(self.root.join(&path)) {
+                Ok(meta) => meta,
+                // Deleted since it was listed.
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let oid = self.oid(&path, &meta)?;
+            records.push((path, format!("? {}", oid)));
+        }
+
+        records.sort();
+        let mut hasher = Sha256::new();
+        for (path, status) in records {
+            hasher.update(format!("{} {}\0", status, path));
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Records of the index entries whose worktree file differs from them.
+    fn compare_index(&mut self, index: &Index) -> Result<Vec<(String, String)>> {
+        let mut records: Vec<(String, String)> = Vec::new();
+        let mut checked = Vec::new();
+        for entry in &index.entries {
+            if entry.mode & MODE_TYPE == MODE_GITLINK {
+                continue;
+            }
+            if entry.stage != 0 {
+                // Unmerged: one record, whatever stages the path has.
+                if records.last().is_none_or(|(path, _)| *path != entry.path) {
+                    records.push((entry.path.clone(), "U".to_string()));
+                }
+            } else if !entry.skip_worktree {
+                checked.push(entry);
+            }
+        }
+
+        for (entry, meta) in stat_changed(self.root, &checked, index.mtime_ns) {
+            let meta = match meta {
+                Ok(meta) if meta.is_file() || meta.file_type().is_symlink() => meta,
+                Ok(_) => {
+                    records.push((entry.path.clone(), "D".to_string()));
+                    continue;
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory
+                    ) =>
+                {
+                    records.push((entry.path.clone(), "D".to_string()));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            let mode = file_mode(&meta);
+            let mode_changed = cfg!(unix) && mode != entry.mode;
+            let oid = self.oid(&entry.path, &meta)?;
+            if mode_changed || oid != entry.oid {
+                records.push((entry.path.clone(), format!("M {:o} {}", mode, oid)));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Object id of the worktree file at `path`. Read only if an earlier
+    /// pass did not, or its stat changed since.
+    fn oid(&mut self, path: &str, meta: &fs::Metadata) -> Result<String> {
+        let stat = worktree::file_stat(meta)?;
+        let symlink = meta.file_type().is_symlink();
+        if let Some(hashed) = self.cached.remove(path)
+            && hashed.stat == stat
+            && hashed.symlink == symlink
+        {
+            let oid = hashed.oid.clone();
+            self.seen.insert(path.to_string(), hashed);
+            return Ok(oid);
+        }
+
+        let full_path = self.root.join(path);
+        let content = if symlink {
+            // Hashed like git stores a symlink: its target path.
+            let target = fs::read_link(&full_path)?;
+            target.to_string_lossy().replace('\\', "/").into_bytes()
+        } else {
+            fs::read(&full_path)?
+        };
+        let oid = self.format.blob_oid(&content);
+        if meta
+            .modified()?
+            .elapsed()
+            .is_ok_and(|age| age >= RACY_WINDOW)
+        {
+            let hashed = Hashed {
+                stat,
+                symlink,
+                oid: oid.clone(),
+            };
+            self.seen.insert(path.to_string(), hashed);
+        }
+        Ok(oid)
+    }
+}
+
+/// The `entries` whose worktree file may differ from them, with its
+/// metadata: their stat does not match the index, or they were modified in
+/// the same tick as the index was written (at `index_mtime_ns`), so the stat
+/// cannot tell. Files are looked up on several threads, like git's
+/// preloaded index.
+fn stat_changed<'e>(
+    root: &Path,
+    entries: &[&'e IndexEntry],
+    index_mtime_ns: i64,
+) -> Vec<(&'e IndexEntry, std::io::Result<fs::Metadata>)> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get().min(MAX_STAT_THREADS));
+    let chunk_len = entries.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let chunks: Vec<_> = entries
+            .chunks(chunk_len)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|entry| {
+                            let meta = fs::symlink_metadata(root.join(&entry.path));
+                            match &meta {
+                                Ok(m) if stat_matches(entry, m, index_mtime_ns) => None,
+                                _ => Some((*entry, meta)),
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.join().expect("stat thread panicked"))
+            .collect()
+    })
+}
+
+fn stat_matches(entry: &IndexEntry, meta: &fs::Metadata, index_mtime_ns: i64) -> bool {
+    let Ok(stat) = worktree::file_stat(meta) else {
+        return false;
+    };
+    let mtime = (
+        (stat.mtime_ns / 1_000_000_000) as u32,
+        (stat.mtime_ns % 1_000_000_000) as u32,
+    );
+    // Without symlinks and executable bits, the index's mode stands.
+    (!cfg!(unix) || file_mode(meta) == entry.mode)
+        && stat.size as u32 == entry.size
+        && stat.inode as u32 == entry.inode
+        && mtime == entry.mtime
+        && stat.mtime_ns < index_mtime_ns
+}
+
+/// The index mode of a worktree file or symlink.
+fn file_mode(meta: &fs::Metadata) -> u32 {
+    if meta.file_type().is_symlink() {
+        MODE_SYMLINK
+    } else if worktree::file_stat(meta).is_ok_and(|stat| stat.executable) {
+        0o100755
+    } else {
+        0o100644
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?}", args);
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn test_native_fingerprint_follows_git_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        let native = NativeFingerprinter::new();
+
+        let unborn = native.compute(root).unwrap();
+        assert_eq!(unborn.head_oid, "");
+
+        fs::write(root.join("a.txt"), "one\n").unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        let untracked = native.compute(root).unwrap();
+        assert_ne!(untracked.status_hash, unborn.status_hash);
+
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "init"]);
+        let clean = native.compute(root).unwrap();
+        assert_eq!(clean.head_oid, git(root, &["rev-parse", "HEAD"]));
+        assert_ne!(clean.index_oid, untracked.index_oid);
+        assert_eq!(native.compute(root).unwrap(), clean);
+
+        // Ignored files are not part of the state.
+        fs::write(root.join("debug.log"), "noise\n").unwrap();
+        assert_eq!(native.compute(root).unwrap(), clean);
+
+        // Every edit counts, not only the first one.
+        fs::write(root.join("a.txt"), "two\n").unwrap();
+        let modified = native.compute(root).unwrap();
+        assert_ne!(modified.status_hash, clean.status_hash);
+        fs::write(root.join("a.txt"), "three\n").unwrap();
+        let modified_again = native.compute(root).unwrap();
+        assert_ne!(modified_again.status_hash, modified.status_hash);
+        assert_eq!(modified_again.index_oid, clean.index_oid);
+
+        fs::write(root.join("a.txt"), "one\n").unwrap();
+        assert_eq!(native.compute(root).unwrap(), clean);
+
+        fs::write(root.join("a.txt"), "staged\n").unwrap();
+        git(root, &["add", "a.txt"]);
+        let staged = native.compute(root).unwrap();
+        assert_ne!(staged.index_oid, clean.index_oid);
+        assert_eq!(staged.status_hash, clean.status_hash);
+
+        // Deleting differs from every other state, and subdirectories are
+        // fingerprinted as their repository.
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::remove_file(root.join("a.txt")).unwrap();
+        let deleted = native.compute(root).unwrap();
+        assert_ne!(deleted.status_hash, staged.status_hash);
+        assert_eq!(native.compute(&root.join("sub")).unwrap(), deleted);
+    }
+
+    #[test]
+    fn test_index_versions_hash_alike() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        for path in ["src/lib.rs", "src/nested/mod.rs", "src/main.rs", "z.md"] {
+            fs::write(root.join(path), path).unwrap();
+        }
+        git(root, &["add", "."]);
+
+        let native = NativeFingerprinter::new();
+        let v2 = native.compute(root).unwrap();
+        git(root, &["update-index", "--index-version", "4"]);
+        let v4 = native.compute(root).unwrap();
+        assert_eq!(v4, v2);
+
+        let (_, git_dir) = GitDir::find(root).unwrap().unwrap();
+        let index = git_dir.read_index(ObjectFormat::Sha1).unwrap();
+        let paths: Vec<&str> = index.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["src/lib.rs", "src/main.rs", "src/nested/mod.rs", "z.md"]
+        );
+        assert_eq!(index.entries[3].oid, git(root, &["hash-object", "z.md"]));
+    }
+
+    #[test]
+    fn test_plain_directory_has_a_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        let native = NativeFingerprinter::new();
+        let empty = native.compute(dir.path()).unwrap();
+        assert_eq!(empty.head_oid, "");
+        assert_eq!(empty.index_oid, "");
+
+        fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        let one = native.compute(dir.path()).unwrap();
+        assert_ne!(one, empty);
+        assert_eq!(native.compute(dir.path()).unwrap(), one);
+
+        fs::write(dir.path().join("a.txt"), "two\n").unwrap();
+        assert_ne!(native.compute(dir.path()).unwrap(), one);
+        assert!(GitFingerprinter.compute(dir.path()).is_err());
+    }
+}
@@ -3,15 +3,22 @@
 // Feature: MCP_SNAPSHOT_WORKSPACE
 // Spec: spec/core/snapshot-workspace.md
 
-use anyhow::{Result, anyhow};
+use crate::router::AxiomRegentError;
+use crate::snapshot::fingerprint::{Fingerprinter, GitFingerprinter};
+use crate::snapshot::reservation;
+use crate::snapshot::store::{Entry, Store, StoredLease};
+use crate::snapshot::worktree;
+use anyhow::Result;
 use serde::{Deserialize, Serialize}; // Kept because Fingerprint::to_canonical_json still uses it
-use sha2::{Digest, Sha256}; // Kept because Fingerprint::compute still uses it
-use std::collections::{HashMap, HashSet}; // HashMap and HashSet are still used
+use std::collections::{BTreeMap, HashMap, HashSet}; // HashMap and HashSet are still used
 use std::path::Path;
-use std::process::Command;
 use std::sync::{Arc, RwLock}; // Kept because LeaseStore uses it
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
+/// State of a repository's HEAD, index and worktree, as computed by a
+/// [`Fingerprinter`].
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)] // Added Eq, kept Serialize/Deserialize for to_canonical_json
 pub struct Fingerprint {
     pub head_oid: String,
@@ -20,63 +27,6 @@ pub struct Fingerprint {
 }
 
 impl Fingerprint {
-    pub fn compute(repo_root: &Path) -> Result<Self> {
-        // 1. head_oid
-        let head_output = Command::new("git")
-            .args(["rev-parse", "HEAD"])
-            .current_dir(repo_root)
-            .output()?;
-
-        let head_oid = if head_output.status.success() {
-            String::from_utf8_lossy(&head_output.stdout)
-                .trim()
-                .to_string()
-        } else {
-            // Unborn branch or empty repo?
-            // rev-parse HEAD passes even if unborn? No, usually fails.
-            // Check if symbolic-ref HEAD exists?
-            // Fallback for unborn: empty string.
-            // We can treat failure as unborn for now if verifying it's a git repo.
-            // Assume it is a git repo.
-            "".to_string()
-        };
-
-        // 2. index_oid
-        let write_tree_output = Command::new("git")
-            .arg("write-tree")
-            .current_dir(repo_root)
-            .output()?;
-
-        let index_oid = if write_tree_output.status.success() {
-            String::from_utf8_lossy(&write_tree_output.stdout)
-                .trim()
-                .to_string()
-        } else {
-            // "no tree possible" -> e.g. merge conflict state when index is invalid?
-            // Spec: "Empty string only if a tree is provably impossible"
-            "".to_string()
-        };
-
-        // 3. status_hash
-        // git status --porcelain=v1 -z
-        let status_output = Command::new("git")
-            .args(["status", "--porcelain=v1", "-z"])
-            .current_dir(repo_root)
-            .output()?;
-
-        if !status_output.status.success() {
-            return Err(anyhow!("Failed to run git status"));
-        }
-
-        let status_hash = hex::encode(Sha256::digest(&status_output.stdout));
-
-        Ok(Self {
-            head_oid,
-            index_oid,
-            status_hash,
-        })
-    }
-
     /// Canonical JSON representation for snapshot ID derivation
     pub fn to_canonical_json(&self) -> Result<String> {
         let val = serde_json::to_value(self)?;
@@ -85,34 +35,423 @@ impl Fingerprint {
     }
 }
 
+/// Content of the files `git status` reported as changed before a write,
+/// keyed by path; `None` for a deleted path.
+pub type WriteBase = BTreeMap<String, Option<Entry>>;
+
+/// How long a lease lives without being used or renewed, unless configured.
+pub const DEFAULT_TTL_SECS: u64 = 3600;
+
 #[derive(Clone, Debug)]
 pub struct Lease {
     pub id: String,
+    pub repo_root: String,
     pub fingerprint: Fingerprint,
     pub touched_files: HashSet<String>,
+    /// Who acquired the lease, as they named themselves.
+    pub holder: Option<String>,
+    /// What the lease was acquired for.
+    pub purpose: Option<String>,
+    pub ttl_secs: u64,
+    /// Unix seconds.
+    pub issued_at: i64,
+    pub expires_at: i64,
+    /// Normalized glob patterns of the paths only this lease may write.
+    pub reserved: Vec<String>,
+}
+
+impl Lease {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+
+    /// The touched files, sorted.
+    pub fn touched(&self) -> Vec<String> {
+        let mut v: Vec<String> = self.touched_files.iter().cloned().collect();
+        v.sort();
+        v
+    }
+
+    /// Whether this lease reserves the repo-relative `path`.
+    pub fn reserves(&self, path: &str) -> bool {
+        self.reserved.iter().any(|p| reservation::covers(p, path))
+    }
+
+    fn describe(&self) -> String {
+        match &self.holder {
+            Some(holder) => format!("lease {} ({})", self.id, holder),
+            None => format!("lease {}", self.id),
+        }
+    }
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct LeaseStore {
     leases: Arc<RwLock<HashMap<String, Lease>>>,
+    // Where leases are persisted, if anywhere.
+    store: Option<Arc<Store>>,
+    // TTL of leases acquired without one.
+    ttl_secs: u64,
+    fingerprinter: Arc<dyn Fingerprinter>,
+}
+
+impl Default for LeaseStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LeaseStore {
     pub fn new() -> Self {
         Self {
             leases: Arc::new(RwLock::new(HashMap::new())),
+            store: None,
+            ttl_secs: DEFAULT_TTL_SECS,
+            fingerprinter: Arc::new(GitFingerprinter),
+        }
+    }
+
+    /// Leases persisted in `store`, so that they survive a restart. Leases
+    /// issued by earlier runs are loaded, minus those that expired meanwhile;
+    /// `check_lease` still compares their fingerprint with the repository.
+    pub fn open(store: Arc<Store>) -> Result<Self> {
+        let now = unix_now();
+        let mut leases = HashMap::new();
+        for stored in store.list_leases()? {
+            // A fingerprint this version cannot read makes the lease unknown.
+            let Ok(fingerprint) = serde_json::from_str(&stored.fingerprint_json) else {
+                continue;
+            };
+            // Leases persisted before they could expire get a fresh TTL.
+            let ttl_secs = stored.ttl_secs.unwrap_or(DEFAULT_TTL_SECS);
+            leases.insert(
+                stored.lease_id.clone(),
+                Lease {
+                    id: stored.lease_id,
+                    repo_root: stored.repo_root,
+                    fingerprint,
+                    touched_files: stored.touched.into_iter().collect(),
+                    holder: stored.holder,
+                    purpose: stored.purpose,
+                    ttl_secs,
+                    issued_at: stored.issued_at.unwrap_or(now),
+                    expires_at: stored
+                        .expires_at
+                        .unwrap_or(now.saturating_add_unsigned(ttl_secs)),
+                    reserved: stored.reserved,
+                },
+            );
         }
+        let leases = Self {
+            leases: Arc::new(RwLock::new(leases)),
+            store: Some(store),
+            ttl_secs: DEFAULT_TTL_SECS,
+            fingerprinter: Arc::new(GitFingerprinter),
+        };
+        leases.expire()?;
+        Ok(leases)
+    }
+
+    /// Replaces the TTL of leases acquired without one (`[leases]` in the
+    /// server config).
+    pub fn with_ttl(mut self, ttl_secs: u64) -> Self {
+        self.ttl_secs = ttl_secs;
+        self
+    }
+
+    /// Replaces how fingerprints are computed (`[leases] fingerprint` in the
+    /// server config). Defaults to a [`GitFingerprinter`].
+    pub fn with_fingerprinter(mut self, fingerprinter: Arc<dyn Fingerprinter>) -> Self {
+        self.fingerprinter = fingerprinter;
+        self
+    }
+
+    pub fn fingerprinter(&self) -> Arc<dyn Fingerprinter> {
+        self.fingerprinter.clone()
     }
 
-    pub fn issue(&self, fingerprint: Fingerprint) -> String {
-        let id = Uuid::new_v4().to_string();
+    /// The current fingerprint of `repo_root`, which leases are checked against.
+    pub fn fingerprint(&self, repo_root: &Path) -> Result<Fingerprint> {
+        self.fingerprinter.compute(repo_root)
+    }
+
+    /// Issues a lease on `repo_root` at `fingerprint`, with the default TTL
+    /// and no holder.
+    pub fn issue(&self, repo_root: &Path, fingerprint: Fingerprint) -> Result<String> {
+        Ok(self
+            .acquire(repo_root, fingerprint, None, None, None, Vec::new())?
+            .id)
+    }
+
+    /// Issues a lease on `repo_root` at `fingerprint`. It expires `ttl_secs`
+    /// (default: the configured TTL) after it was last used or renewed.
+    ///
+    /// The paths matching `reserve` can only be written under this lease;
+    /// fails if another lease on the repository reserved any of them.
+    pub fn acquire(
+        &self,
+        repo_root: &Path,
+        fingerprint: Fingerprint,
+        holder: Option<String>,
+        purpose: Option<String>,
+        ttl_secs: Option<u64>,
+        reserve: Vec<String>,
+    ) -> Result<Lease> {
+        let ttl_secs = check_ttl(ttl_secs.unwrap_or(self.ttl_secs))?;
+        let reserved = normalize_patterns(&[], reserve)?;
+        let now = unix_now();
         let lease = Lease {
-            id: id.clone(),
+            id: Uuid::new_v4().to_string(),
+            repo_root: repo_root
+                .canonicalize()
+                .unwrap_or_else(|_| repo_root.to_path_buf())
+                .to_string_lossy()
+                .into_owned(),
             fingerprint,
             touched_files: HashSet::new(),
+            holder,
+            purpose,
+            ttl_secs,
+            issued_at: now,
+            expires_at: now.saturating_add_unsigned(ttl_secs),
+            reserved,
+        };
+        // Checked and inserted under one lock, so racing reservations cannot both succeed.
+        let mut leases = self.leases.write().unwrap();
+        check_conflicts(&leases, &lease, &lease.reserved, now)?;
+        if let Some(store) = &self.store {
+            store.put_lease(&StoredLease {
+                lease_id: lease.id.clone(),
+                repo_root: lease.repo_root.clone(),
+                fingerprint_json: lease.fingerprint.to_canonical_json()?,
+                touched: Vec::new(),
+                issued_at: Some(lease.issued_at),
+                holder: lease.holder.clone(),
+                purpose: lease.purpose.clone(),
+                ttl_secs: Some(ttl_secs),
+                expires_at: Some(lease.expires_at),
+                reserved: lease.reserved.clone(),
+            })?;
+        }
+        leases.insert(lease.id.clone(), lease.clone());
+        Ok(lease)
+    }
+
+    /// Adds `patterns` to the paths reserved by lease `lease_id`. Fails,
+    /// reserving nothing, if another lease reserved any path they match.
+    pub fn reserve(&self, lease_id: &str, patterns: Vec<String>) -> Result<Lease> {
+        let now = unix_now();
+        let mut leases = self.leases.write().unwrap();
+        let lease = live_lease(&mut leases, lease_id, now)?.clone();
+        let added = normalize_patterns(&lease.reserved, patterns)?;
+        check_conflicts(&leases, &lease, &added, now)?;
+
+        let lease = live_lease(&mut leases, lease_id, now)?;
+        lease.reserved.extend(added);
+        if let Some(store) = &self.store {
+            store.set_lease_reserved(lease_id, &lease.reserved)?;
+        }
+        Ok(lease.clone())
+    }
+
+    /// Fails with `PERMISSION_DENIED` if another live lease on the
+    /// repository of lease `lease_id` reserves one of `paths`.
+    pub fn check_reserved(&self, lease_id: &str, paths: &[String]) -> Result<()> {
+        let now = unix_now();
+        let leases = self.leases.read().unwrap();
+        let Some(writer) = leases.get(lease_id) else {
+            return Ok(());
+        };
+        for other in leases.values() {
+            if other.id == writer.id || other.repo_root != writer.repo_root || other.is_expired(now)
+            {
+                continue;
+            }
+            if let Some(path) = paths.iter().find(|p| other.reserves(p)) {
+                return Err(AxiomRegentError::PermissionDenied(format!(
+                    "Path is reserved by {}: {}",
+                    other.describe(),
+                    path
+                ))
+                .with_path(path)
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The changed files of `repo_root` before lease `lease_id` writes to
+    /// it, for [`Self::refresh_after_write`] to tell what the write changed.
+    ///
+    /// Take it before [`Self::check_lease`], so that a change made in between
+    /// makes the lease stale instead of passing for part of the base. `None`
+    /// when the lease reserves nothing (its writes never keep other leases
+    /// valid) or `repo_root` is not inside git.
+    pub fn write_base(&self, lease_id: &str, repo_root: &Path) -> Result<Option<WriteBase>> {
+        let reserves = self
+            .leases
+            .read()
+            .unwrap()
+            .get(lease_id)
+            .is_some_and(|lease| !lease.reserved.is_empty());
+        if !reserves {
+            return Ok(None);
+        }
+        worktree::changed_files(repo_root)
+    }
+
+    /// Fingerprint of `repo_root` after lease `lease_id` wrote to it, the
+    /// worktree having been `base` before.
+    ///
+    /// When the files that changed since `base` are all reserved by the
+    /// lease, and HEAD and the index did not move, the write cannot have
+    /// touched what other leases reserved: the lease, and every lease with
+    /// reservations that was valid before the write, move to the new
+    /// fingerprint instead of going stale. Any other change, including one
+    /// made by someone else during the write, leaves them as is.
+    pub fn refresh_after_write(
+        &self,
+        lease_id: &str,
+        repo_root: &Path,
+        base: Option<WriteBase>,
+    ) -> Result<Fingerprint> {
+        let after = self.fingerprint(repo_root)?;
+        let Some(base) = base else {
+            return Ok(after);
+        };
+        let Some(changed) = worktree::changed_files(repo_root)? else {
+            return Ok(after);
+        };
+        let mut leases = self.leases.write().unwrap();
+        let Some(writer) = leases.get(lease_id) else {
+            return Ok(after);
+        };
+        let before = writer.fingerprint.clone();
+        if after.head_oid != before.head_oid || after.index_oid != before.index_oid {
+            return Ok(after);
+        }
+        let mut delta = base
+            .keys()
+            .chain(changed.keys())
+            .filter(|path| base.get(*path) != changed.get(*path))
+            .peekable();
+        // Paths outside `repo_root` are never reserved, whatever the patterns.
+        if delta.peek().is_none()
+            || !delta.all(|path| !path.starts_with("../") && writer.reserves(path))
+        {
+            return Ok(after);
+        }
+
+        let repo = writer.repo_root.clone();
+        let after_json = after.to_canonical_json()?;
+        for lease in leases.values_mut() {
+            if lease.repo_root == repo
+                && lease.fingerprint == before
+                && (lease.id == lease_id || !lease.reserved.is_empty())
+            {
+                lease.fingerprint = after.clone();
+                if let Some(store) = &self.store {
+                    store.set_lease_fingerprint(&lease.id, &after_json)?;
+                }
+            }
+        }
+        Ok(after)
+    }
+
+    /// Pushes the expiry of lease `lease_id` to `ttl_secs` from now, and
+    /// makes that its TTL. Without `ttl_secs` its current TTL is used.
+    pub fn renew(&self, lease_id: &str, ttl_secs: Option<u64>) -> Result<Lease> {
+        let now = unix_now();
+        let mut leases = self.leases.write().unwrap();
+        let lease = live_lease(&mut leases, lease_id, now)?;
+        let ttl_secs = check_ttl(ttl_secs.unwrap_or(lease.ttl_secs))?;
+        lease.ttl_secs = ttl_secs;
+        lease.expires_at = now.saturating_add_unsigned(ttl_secs);
+        if let Some(store) = &self.store {
+            store.set_lease_expiry(lease_id, ttl_secs, lease.expires_at)?;
+        }
+        Ok(lease.clone())
+    }
+
+    /// Drops lease `lease_id`. Returns false if there was no such lease.
+    pub fn release(&self, lease_id: &str) -> Result<bool> {
+        let removed = self.leases.write().unwrap().remove(lease_id).is_some();
+        if let Some(store) = &self.store {
+            store.delete_lease(lease_id)?;
+        }
+        Ok(removed)
+    }
+
+    /// Unexpired leases, on `repo_root` (canonical) if given, oldest first.
+    pub fn list(&self, repo_root: Option<&str>) -> Vec<Lease> {
+        let now = unix_now();
+        let mut leases: Vec<Lease> = self
+            .leases
+            .read()
+            .unwrap()
+            .values()
+            .filter(|l| !l.is_expired(now) && repo_root.is_none_or(|r| l.repo_root == r))
+            .cloned()
+            .collect();
+        leases.sort_by(|a, b| (a.issued_at, &a.id).cmp(&(b.issued_at, &b.id)));
+        leases
+    }
+
+    /// Drops the leases that have expired and returns them.
+    pub fn expire(&self) -> Result<Vec<Lease>> {
+        self.expire_at(unix_now())
+    }
+
+    fn expire_at(&self, now: i64) -> Result<Vec<Lease>> {
+        let expired: Vec<Lease> = {
+            let mut leases = self.leases.write().unwrap();
+            let ids: Vec<String> = leases
+                .values()
+                .filter(|l| l.is_expired(now))
+                .map(|l| l.id.clone())
+                .collect();
+            ids.iter().filter_map(|id| leases.remove(id)).collect()
         };
-        self.leases.write().unwrap().insert(id.clone(), lease);
-        id
+        if let Some(store) = &self.store {
+            for lease in &expired {
+                store.delete_lease(&lease.id)?;
+            }
+        }
+        Ok(expired)
+    }
+
+    /// Starts a thread calling [`LeaseStore::expire`] every `interval`, for
+    /// as long as the process runs.
+    pub fn spawn_expiry(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                match self.expire() {
+                    Ok(expired) => {
+                        for lease in expired {
+                            log::info!(
+                                "lease {} expired (holder: {})",
+                                lease.id,
+                                lease.holder.as_deref().unwrap_or("-")
+                            );
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to expire leases: {:#}", e),
+                }
+            }
+        })
+    }
+
+    /// Canonical repository root of lease `lease_id`, unless it is unknown
+    /// or has expired.
+    pub fn repo_root(&self, lease_id: &str) -> Option<String> {
+        let now = unix_now();
+        self.leases
+            .read()
+            .unwrap()
+            .get(lease_id)
+            .filter(|l| !l.is_expired(now))
+            .map(|l| l.repo_root.clone())
     }
 
     pub fn get_fingerprint(&self, lease_id: &str) -> Option<Fingerprint> {
@@ -123,35 +462,42 @@ impl LeaseStore {
             .map(|l| l.fingerprint.clone())
     }
 
-    pub fn touch_files(&self, lease_id: &str, files: Vec<String>) {
+    pub fn touch_files(&self, lease_id: &str, files: Vec<String>) -> Result<()> {
         let mut leases = self.leases.write().unwrap();
         if let Some(lease) = leases.get_mut(lease_id) {
+            let before = lease.touched_files.len();
             for f in files {
                 lease.touched_files.insert(f);
             }
+            if let Some(store) = &self.store
+                && lease.touched_files.len() > before
+            {
+                store.set_lease_touched(lease_id, &lease.touched())?;
+            }
         }
+        Ok(())
     }
 
     pub fn get_touched_files(&self, lease_id: &str) -> Option<Vec<String>> {
         let leases = self.leases.read().unwrap();
-        leases.get(lease_id).map(|l| {
-            let mut v: Vec<String> = l.touched_files.iter().cloned().collect();
-            v.sort(); // Lexicographic order
-            v
-        })
+        leases.get(lease_id).map(Lease::touched)
     }
 
-    /// Verifies lease against current repo state.
+    /// Verifies lease against current repo state, and keeps it alive for
+    /// another TTL.
     /// Returns Ok(()) if valid.
+    /// Returns Err(NOT_FOUND) if unknown or expired.
     /// Returns Err(STALE_LEASE) if mismatch.
     pub fn check_lease(&self, lease_id: &str, repo_root: &Path) -> Result<()> {
-        let recorded_fp = self
-            .get_fingerprint(lease_id)
-            .ok_or_else(|| anyhow!("Lease not found: {}", lease_id))?; // Or separate error? "Lease not found" is INVALID_ARGUMENT or NOT_FOUND
+        let now = unix_now();
+        let recorded_fp = {
+            let mut leases = self.leases.write().unwrap();
+            live_lease(&mut leases, lease_id, now)?.fingerprint.clone()
+        };
         // Spec says "missing lease" logic issues new one, but if *passed* lease is invalid?
         // "Validation: Every worktree-mode request with a lease_id validates it..."
 
-        let current_fp = Fingerprint::compute(repo_root)?;
+        let current_fp = self.fingerprint(repo_root)?;
 
         if recorded_fp != current_fp {
             // Construct STALE_LEASE error JSON
@@ -173,10 +519,104 @@ impl LeaseStore {
             .into());
         }
 
+        // In use: the lease lives for another TTL from now.
+        let mut leases = self.leases.write().unwrap();
+        if let Some(lease) = leases.get_mut(lease_id) {
+            let expires_at = now.saturating_add_unsigned(lease.ttl_secs);
+            if expires_at > lease.expires_at {
+                lease.expires_at = expires_at;
+                if let Some(store) = &self.store {
+                    store.set_lease_expiry(lease_id, lease.ttl_secs, expires_at)?;
+                }
+            }
+        }
+
         Ok(())
     }
 }
 
+/// Lease `lease_id`, unless it is unknown or has expired.
+fn live_lease<'a>(
+    leases: &'a mut HashMap<String, Lease>,
+    lease_id: &str,
+    now: i64,
+) -> Result<&'a mut Lease> {
+    match leases.get_mut(lease_id) {
+        Some(lease) if !lease.is_expired(now) => Ok(lease),
+        Some(_) => Err(
+            AxiomRegentError::NotFound(format!("Lease expired: {}", lease_id))
+                .with_lease(lease_id)
+                .into(),
+        ),
+        None => Err(
+            AxiomRegentError::NotFound(format!("Lease not found: {}", lease_id))
+                .with_lease(lease_id)
+                .into(),
+        ),
+    }
+}
+
+/// `patterns` normalized, minus those already in `existing`.
+fn normalize_patterns(existing: &[String], patterns: Vec<String>) -> Result<Vec<String>> {
+    let mut added: Vec<String> = Vec::new();
+    for pattern in patterns {
+        let pattern = reservation::normalize(&pattern)?;
+        if !existing.contains(&pattern) && !added.contains(&pattern) {
+            added.push(pattern);
+        }
+    }
+    Ok(added)
+}
+
+/// Fails if a live lease other than `lease` on its repository reserves a
+/// path that one of `patterns` matches.
+fn check_conflicts(
+    leases: &HashMap<String, Lease>,
+    lease: &Lease,
+    patterns: &[String],
+    now: i64,
+) -> Result<()> {
+    for other in leases.values() {
+        if other.id == lease.id || other.repo_root != lease.repo_root || other.is_expired(now) {
+            continue;
+        }
+        for pattern in patterns {
+            if let Some(theirs) = other
+                .reserved
+                .iter()
+                .find(|r| reservation::conflict(pattern, r))
+            {
+                return Err(AxiomRegentError::PermissionDenied(format!(
+                    "'{}' overlaps '{}', reserved by {}",
+                    pattern,
+                    theirs,
+                    other.describe()
+                ))
+                .with_path(pattern)
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_ttl(ttl_secs: u64) -> Result<u64> {
+    if ttl_secs == 0 || i64::try_from(ttl_secs).is_err() {
+        return Err(AxiomRegentError::InvalidArgument(format!(
+            "ttl_secs must be a positive number of seconds, got {}",
+            ttl_secs
+        ))
+        .into());
+    }
+    Ok(ttl_secs)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
 #[derive(Debug)]
 pub struct StaleLeaseError {
     pub lease_id: String,
@@ -191,3 +631,99 @@ impl std::fmt::Display for StaleLeaseError {
 }
 
 impl std::error::Error for StaleLeaseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint() -> Fingerprint {
+        Fingerprint {
+            head_oid: String::new(),
+            index_oid: String::new(),
+            status_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_lease_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let leases = LeaseStore::new().with_ttl(60);
+        let a = leases
+            .acquire(
+                dir.path(),
+                fingerprint(),
+                Some("agent-a".into()),
+                Some("refactor".into()),
+                None,
+                Vec::new(),
+            )
+            .unwrap();
+        assert_eq!(a.ttl_secs, 60);
+        assert_eq!(a.expires_at, a.issued_at + 60);
+        let b = leases
+            .issue(Path::new("/elsewhere"), fingerprint())
+            .unwrap();
+        assert!(
+            leases
+                .acquire(dir.path(), fingerprint(), None, None, Some(0), Vec::new())
+                .is_err()
+        );
+
+        let root = dir.path().canonicalize().unwrap();
+        let on_root = leases.list(Some(&root.to_string_lossy()));
+        assert_eq!(on_root.len(), 1);
+        assert_eq!(on_root[0].holder.as_deref(), Some("agent-a"));
+        assert_eq!(leases.list(None).len(), 2);
+
+        let renewed = leases.renew(&a.id, Some(600)).unwrap();
+        assert_eq!(renewed.ttl_secs, 600);
+        assert!(renewed.expires_at >= a.expires_at + 540);
+
+        // Expiry drops `b` (60 s) but not the renewed `a` (600 s).
+        let expired = leases.expire_at(a.issued_at + 120).unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, b);
+        assert!(leases.renew(&b, None).is_err());
+
+        assert!(leases.release(&a.id).unwrap());
+        assert!(!leases.release(&a.id).unwrap());
+        assert!(leases.list(None).is_empty());
+    }
+
+    #[test]
+    fn test_reservations_are_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let leases = LeaseStore::new();
+        let acquire = |reserve: &[&str]| {
+            leases.acquire(
+                dir.path(),
+                fingerprint(),
+                None,
+                None,
+                None,
+                reserve.iter().map(|p| p.to_string()).collect(),
+            )
+        };
+        let a = acquire(&["./src/"]).unwrap();
+        assert_eq!(a.reserved, ["src"]);
+        assert!(acquire(&["src/lib.rs"]).is_err());
+        assert_eq!(leases.list(None).len(), 1);
+
+        let b = acquire(&["tests"]).unwrap();
+        assert!(leases.reserve(&b.id, vec!["**/*.rs".into()]).is_err());
+        let b = leases
+            .reserve(&b.id, vec!["docs".into(), "tests".into()])
+            .unwrap();
+        assert_eq!(b.reserved, ["tests", "docs"]);
+
+        let path = |p: &str| vec![p.to_string()];
+        assert!(leases.check_reserved(&a.id, &path("src/lib.rs")).is_ok());
+        assert!(leases.check_reserved(&b.id, &path("src/lib.rs")).is_err());
+        assert!(leases.check_reserved(&b.id, &path("README.md")).is_ok());
+
+        // Released reservations are free again.
+        leases.release(&a.id).unwrap();
+        assert!(leases.check_reserved(&b.id, &path("src/lib.rs")).is_ok());
+        assert!(acquire(&["src"]).is_ok());
+    }
+}
@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! The `derived_from` graph of a repository's snapshots.
+
+use crate::snapshot::store::SnapshotInfo;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// `start` and its ancestors among `snapshots`, newest first. Stops at a
+/// snapshot whose parent is not in `snapshots` (or was already visited).
+pub fn ancestry<'a>(snapshots: &'a [SnapshotInfo], start: &str) -> Vec<&'a SnapshotInfo> {
+    let by_id: HashMap<&str, &SnapshotInfo> = snapshots
+        .iter()
+        .map(|s| (s.snapshot_id.as_str(), s))
+        .collect();
+    let mut chain = Vec::new();
+    let mut seen = HashSet::new();
+    let mut next = Some(start);
+    while let Some(id) = next {
+        let Some(info) = by_id.get(id) else {
+            break;
+        };
+        if !seen.insert(id) {
+            break;
+        }
+        chain.push(*info);
+        next = info.derived_from.as_deref();
+    }
+    chain
+}
+
+/// Whether `info` was created within `since..=until` (unix seconds; unset
+/// bounds are open). Snapshots without a creation time always match.
+pub fn in_range(info: &SnapshotInfo, since: Option<i64>, until: Option<i64>) -> bool {
+    info.created_at.is_none_or(|t| {
+        since.is_none_or(|since| t >= since) && until.is_none_or(|until| t <= until)
+    })
+}
+
+/// A parent of a snapshot: the one it was `derived_from`, or for a merge
+/// also the one it `merged_from`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Edge<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub applied_patch_hash: Option<&'a str>,
+    pub merge: bool,
+}
+
+/// Edges from each of `nodes` to its parents, leaving out parents that are
+/// not among `nodes`.
+pub fn edges<'a>(nodes: &[&'a SnapshotInfo]) -> Vec<Edge<'a>> {
+    let ids: HashSet<&str> = nodes.iter().map(|n| n.snapshot_id.as_str()).collect();
+    let mut edges = Vec::new();
+    for node in nodes {
+        if let Some(parent) = node.derived_from.as_deref().filter(|p| ids.contains(p)) {
+            edges.push(Edge {
+                from: parent,
+                to: &node.snapshot_id,
+                applied_patch_hash: node.applied_patch_hash.as_deref(),
+                merge: false,
+            });
+        }
+        if let Some(parent) = node.merged_from.as_deref().filter(|p| ids.contains(p)) {
+            edges.push(Edge {
+                from: parent,
+                to: &node.snapshot_id,
+                applied_patch_hash: None,
+                merge: true,
+            });
+        }
+    }
+    edges
+}
+
+/// Graphviz rendering of `nodes` and their [`edges`]; merge edges are dashed.
+pub fn to_dot(nodes: &[&SnapshotInfo], tags: &HashMap<String, Vec<String>>) -> String {
+    let mut dot = String::from("digraph lineage {\n    rankdir=LR;\n");
+    for node in nodes {
+        let mut label = short(&node.snapshot_id).to_string();
+        for tag in tags.get(&node.snapshot_id).into_iter().flatten() {
+            label.push_str(&format!("\n[{}]", tag));
+        }
+        if let Some(text) = &node.label {
+            label.push_str(&format!("\n{}", text));
+        }
+        let _ = writeln!(
+            dot,
+            "    {} [label={}];",
+            quote(&node.snapshot_id),
+            quote(&label)
+        );
+    }
+    for edge in edges(nodes) {
+        let _ = write!(dot, "    {} -> {}", quote(edge.from), quote(edge.to));
+        if let Some(patch) = edge.applied_patch_hash {
+            let _ = write!(
+                dot,
+                " [label={}]",
+                quote(&format!("patch {}", short(patch)))
+            );
+        } else if edge.merge {
+            dot.push_str(" [style=dashed]");
+        }
+        dot.push_str(";\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// `sha256:0123456789ab...` shortened to its first 12 hex digits.
+fn short(id: &str) -> &str {
+    let hex = id.strip_prefix("sha256:").unwrap_or(id);
+    hex.get(..12).unwrap_or(hex)
+}
+
+fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, parent: Option<&str>, created_at: i64) -> SnapshotInfo {
+        SnapshotInfo {
+            snapshot_id: id.to_string(),
+            repo_root: "/repo".to_string(),
+            head_sha: String::new(),
+            fingerprint_json: String::new(),
+            manifest_hash: String::new(),
+            created_at: Some(created_at),
+            derived_from: parent.map(str::to_string),
+            applied_patch_hash: parent.map(|_| format!("sha256:patch-of-{}", id)),
+            label: None,
+            merged_from: None,
+            scope: None,
+        }
+    }
+
+    #[test]
+    fn test_ancestry_stops_at_roots_and_cycles() {
+        let snapshots = vec![
+            info("c", Some("b"), 3),
+            info("b", Some("a"), 2),
+            info("a", None, 1),
+            info("x", Some("y"), 4),
+            info("y", Some("x"), 5),
+        ];
+        let ids = |start: &str| -> Vec<String> {
+            ancestry(&snapshots, start)
+                .iter()
+                .map(|s| s.snapshot_id.clone())
+                .collect()
+        };
+        assert_eq!(ids("c"), ["c", "b", "a"]);
+        assert_eq!(ids("x"), ["x", "y"]);
+        assert!(ids("missing").is_empty());
+
+        assert!(in_range(&snapshots[1], Some(2), Some(2)));
+        assert!(!in_range(&snapshots[1], Some(3), None));
+        assert!(!in_range(&snapshots[1], None, Some(1)));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let mut root = info("sha256:aaaaaaaaaaaaaaaa", None, 1);
+        root.label = Some("say \"hi\"".to_string());
+        let child = info(
+            "sha256:bbbbbbbbbbbbbbbb",
+            Some("sha256:aaaaaaaaaaaaaaaa"),
+            2,
+        );
+        let mut merge = info("sha256:dddd", Some("sha256:bbbbbbbbbbbbbbbb"), 4);
+        merge.merged_from = Some("sha256:aaaaaaaaaaaaaaaa".to_string());
+        let orphan = info("sha256:cccc", Some("sha256:gone"), 3);
+        let tags = HashMap::from([(
+            "sha256:aaaaaaaaaaaaaaaa".to_string(),
+            vec!["baseline".to_string()],
+        )]);
+
+        let nodes = [&root, &child, &merge, &orphan];
+        assert_eq!(edges(&nodes).len(), 3);
+        assert!(edges(&nodes)[2].merge);
+
+        let dot = to_dot(&nodes, &tags);
+        assert!(dot.starts_with("digraph lineage {\n"));
+        assert!(dot.contains(
+            r#""sha256:aaaaaaaaaaaaaaaa" [label="aaaaaaaaaaaa\n[baseline]\nsay \"hi\""];"#
+        ));
+        assert!(dot.contains(
+            r#""sha256:aaaaaaaaaaaaaaaa" -> "sha256:bbbbbbbbbbbbbbbb" [label="patch patch-of-sha"];"#
+        ));
+        assert!(dot.contains(r#""sha256:aaaaaaaaaaaaaaaa" -> "sha256:dddd" [style=dashed];"#));
+        assert!(!dot.contains("sha256:gone"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,553 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! MCP registration of the `snapshot.*` and `lease.*` tools.
+
+use crate::config::BlobBackend;
+use crate::router::mounts::Capability;
+use crate::router::registry::{NoArgs, ToolRegistry};
+use crate::snapshot::tools::{CreateOptions, SnapshotTools};
+use crate::snapshot::worktree::Scope;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+// Whether a tool reads the live worktree or a stored snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Worktree,
+    Snapshot,
+}
+
+impl Mode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Worktree => "worktree",
+            Mode::Snapshot => "snapshot",
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ListArgs {
+    pub repo_root: String,
+    #[serde(default)]
+    pub path: String,
+    pub mode: Mode,
+    pub lease_id: Option<String>,
+    pub snapshot_id: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CreateArgs {
+    pub repo_root: String,
+    pub lease_id: Option<String>,
+    pub paths: Option<Vec<String>>,
+    /// Files to capture; `touched` (the default) takes `paths` or the lease's touched files.
+    #[serde(default)]
+    pub scope: Scope,
+    /// Snapshot the new one derives from; with the `touched` scope, its files not captured again are kept.
+    pub parent_snapshot_id: Option<String>,
+    /// Free-form label stored with the snapshot; `snapshot.gc` can keep labeled snapshots.
+    pub label: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ReadArgs {
+    pub repo_root: String,
+    pub path: String,
+    pub mode: Mode,
+    pub lease_id: Option<String>,
+    pub snapshot_id: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct GrepArgs {
+    pub repo_root: String,
+    pub pattern: String,
+    pub paths: Option<Vec<String>>,
+    pub mode: Mode,
+    pub lease_id: Option<String>,
+    pub snapshot_id: Option<String>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DiffArgs {
+    pub repo_root: String,
+    pub path: String,
+    pub mode: Mode,
+    pub lease_id: Option<String>,
+    pub snapshot_id: Option<String>,
+    pub from_snapshot_id: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ChangesArgs {
+    pub repo_root: String,
+    pub snapshot_id: String,
+    pub from_snapshot_id: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ExportArgs {
+    pub repo_root: String,
+    pub snapshot_id: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct InfoArgs {
+    pub repo_root: String,
+    pub snapshot_id: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeleteArgs {
+    pub repo_root: String,
+    pub snapshot_id: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TagArgs {
+    pub repo_root: String,
+    /// Tag name: `/`-separated segments of letters, digits, `.`, `_` and `-`.
+    pub name: String,
+    pub snapshot_id: String,
+    /// Move the tag if it already points at another snapshot.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct UntagArgs {
+    pub repo_root: String,
+    pub name: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RefsArgs {
+    pub repo_root: String,
+    /// Only this tag.
+    pub name: Option<String>,
+    /// Include every creation, move and removal of the tags.
+    #[serde(default)]
+    pub history: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LogArgs {
+    pub repo_root: String,
+    /// Snapshot to start from; defaults to `HEAD`.
+    pub snapshot_id: Option<String>,
+    /// Skip snapshots created before this time (unix seconds).
+    pub since: Option<i64>,
+    /// Skip snapshots created after this time (unix seconds).
+    pub until: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LineageArgs {
+    pub repo_root: String,
+    /// Only snapshots created at or after this time (unix seconds).
+    pub since: Option<i64>,
+    /// Only snapshots created at or before this time (unix seconds).
+    pub until: Option<i64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct MergeArgs {
+    pub repo_root: String,
+    /// Common ancestor of `ours` and `theirs`.
+    pub base: String,
+    /// Snapshot the merge derives from.
+    pub ours: String,
+    /// Snapshot merged into `ours`.
+    pub theirs: String,
+    /// On conflicts, still store a candidate snapshot with conflict markers in the conflicting files.
+    #[serde(default)]
+    pub conflict_markers: bool,
+    /// Free-form label stored with the merge snapshot.
+    pub label: Option<String>,
+}
+
+/// Unset fields fall back to the server's retention defaults.
+#[derive(Deserialize, JsonSchema)]
+pub struct GcArgs {
+    pub repo_root: String,
+    /// Most recent snapshots of the repository to keep.
+    pub keep_last: Option<usize>,
+    /// Keep labeled snapshots.
+    pub keep_labeled: Option<bool>,
+    /// Unreferenced blobs written less than this many seconds ago are not swept.
+    pub grace_secs: Option<u64>,
+    /// Report the snapshots that would be deleted without deleting anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct MigrateStoreArgs {
+    /// Backend to move every stored blob to.
+    pub to: BlobBackend,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LeaseAcquireArgs {
+    pub repo_root: String,
+    /// Who holds the lease, e.g. an agent or session name.
+    pub holder: Option<String>,
+    /// What the lease is for.
+    pub purpose: Option<String>,
+    /// Seconds the lease lives without being used or renewed; defaults to the server's `[leases] ttl_secs`.
+    pub ttl_secs: Option<u64>,
+    /// Glob patterns (`*`, `?`, `**`) of paths only this lease may write; a pattern also covers what lies below it.
+    pub reserve: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LeaseRenewArgs {
+    pub lease_id: String,
+    /// New TTL, counted from now; defaults to the lease's current one.
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LeaseReserveArgs {
+    pub lease_id: String,
+    /// Glob patterns to reserve in addition to those the lease holds.
+    pub paths: Vec<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LeaseReleaseArgs {
+    pub lease_id: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LeaseListArgs {
+    /// Only leases on this repository.
+    pub repo_root: Option<String>,
+}
+
+/// Registers the `snapshot.*` tools.
+pub fn register(registry: &mut ToolRegistry, tools: Arc<SnapshotTools>) {
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.list",
+        "List files in a snapshot or worktree",
+        Capability::ListFiles,
+        move |_, a: ListArgs| {
+            Ok(t.snapshot_list(
+                Path::new(&a.repo_root),
+                &a.path,
+                a.mode.as_str(),
+                a.lease_id,
+                a.snapshot_id,
+                a.limit,
+                a.offset,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "snapshot.create",
+        "Create a new snapshot",
+        Capability::Snapshot,
+        move |_, a: CreateArgs| {
+            let options = CreateOptions {
+                lease_id: a.lease_id,
+                paths: a.paths,
+                scope: a.scope,
+                parent_snapshot_id: a.parent_snapshot_id,
+                label: a.label,
+            };
+            Ok(t.snapshot_create(Path::new(&a.repo_root), options)?.into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.read",
+        "Read file content",
+        Capability::ReadFile,
+        move |_, a: ReadArgs| {
+            Ok(t.snapshot_file(
+                Path::new(&a.repo_root),
+                &a.path,
+                a.mode.as_str(),
+                a.lease_id,
+                a.snapshot_id,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.grep",
+        "Search for patterns",
+        Capability::Search,
+        move |_, a: GrepArgs| {
+            Ok(t.snapshot_grep(
+                Path::new(&a.repo_root),
+                &a.pattern,
+                a.paths,
+                a.mode.as_str(),
+                a.lease_id,
+                a.snapshot_id,
+                a.case_insensitive,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.diff",
+        "Generate unified diff",
+        Capability::ReadFile,
+        move |_, a: DiffArgs| {
+            Ok(t.snapshot_diff(
+                Path::new(&a.repo_root),
+                &a.path,
+                a.mode.as_str(),
+                a.lease_id,
+                a.snapshot_id,
+                a.from_snapshot_id,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.changes",
+        "List changed files between snapshots",
+        Capability::ListFiles,
+        move |_, a: ChangesArgs| {
+            Ok(t.snapshot_changes(
+                Path::new(&a.repo_root),
+                Some(a.snapshot_id),
+                a.from_snapshot_id,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.export",
+        "Export snapshot as tarball",
+        Capability::ReadFile,
+        move |_, a: ExportArgs| {
+            Ok(
+                t.snapshot_export(Path::new(&a.repo_root), Some(a.snapshot_id))?
+                    .into(),
+            )
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "snapshot.delete",
+        "Delete a snapshot",
+        Capability::Snapshot,
+        move |_, a: DeleteArgs| {
+            Ok(t.snapshot_delete(Path::new(&a.repo_root), &a.snapshot_id)?
+                .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "snapshot.tag",
+        "Point a tag at a snapshot",
+        Capability::Snapshot,
+        move |_, a: TagArgs| {
+            Ok(
+                t.snapshot_tag(Path::new(&a.repo_root), &a.name, &a.snapshot_id, a.force)?
+                    .into(),
+            )
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "snapshot.untag",
+        "Remove a tag",
+        Capability::Snapshot,
+        move |_, a: UntagArgs| Ok(t.snapshot_untag(Path::new(&a.repo_root), &a.name)?.into()),
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.refs",
+        "List tags and HEAD, optionally with their history",
+        Capability::ReadFile,
+        move |_, a: RefsArgs| {
+            Ok(t.snapshot_refs(Path::new(&a.repo_root), a.name, a.history)?
+                .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.log",
+        "Walk a snapshot's ancestry back to its root",
+        Capability::ReadFile,
+        move |_, a: LogArgs| {
+            Ok(t.snapshot_log(
+                Path::new(&a.repo_root),
+                a.snapshot_id,
+                a.since,
+                a.until,
+                a.limit,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.lineage",
+        "Return the snapshot derivation graph as JSON and DOT",
+        Capability::ReadFile,
+        move |_, a: LineageArgs| {
+            Ok(
+                t.snapshot_lineage(Path::new(&a.repo_root), a.since, a.until)?
+                    .into(),
+            )
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "snapshot.merge",
+        "Three-way merge two snapshots derived from a common base",
+        Capability::Snapshot,
+        move |_, a: MergeArgs| {
+            Ok(t.snapshot_merge(
+                Path::new(&a.repo_root),
+                &a.base,
+                &a.ours,
+                &a.theirs,
+                a.conflict_markers,
+                a.label,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "snapshot.gc",
+        "Apply the retention policy and reclaim unreferenced blobs",
+        Capability::Snapshot,
+        move |_, a: GcArgs| {
+            Ok(t.snapshot_gc(
+                Path::new(&a.repo_root),
+                a.keep_last,
+                a.keep_labeled,
+                a.grace_secs,
+                a.dry_run,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "snapshot.migrate_store",
+        "Move stored blobs between the filesystem and SQLite backends",
+        Capability::Admin,
+        move |_, a: MigrateStoreArgs| Ok(t.snapshot_migrate_store(a.to)?.into()),
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "snapshot.store_stats",
+        "Report store size and the deduplication ratio",
+        Capability::Admin,
+        move |_, _: NoArgs| Ok(t.snapshot_store_stats()?.into()),
+    );
+
+    let t = tools;
+    registry.tool(
+        "snapshot.info",
+        "Get snapshot or repository info",
+        Capability::ReadFile,
+        move |_, a: InfoArgs| {
+            Ok(t.snapshot_info(Path::new(&a.repo_root), a.snapshot_id)?
+                .into())
+        },
+    );
+}
+
+/// Registers the `lease.*` tools.
+pub fn register_leases(registry: &mut ToolRegistry, tools: Arc<SnapshotTools>) {
+    let t = tools.clone();
+    registry.mutation(
+        "lease.acquire",
+        "Acquire a lease on the current state of a repository",
+        Capability::WriteFile,
+        move |_, a: LeaseAcquireArgs| {
+            Ok(t.lease_acquire(
+                Path::new(&a.repo_root),
+                a.holder,
+                a.purpose,
+                a.ttl_secs,
+                a.reserve.unwrap_or_default(),
+            )?
+            .into())
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "lease.renew",
+        "Extend a lease's expiry",
+        Capability::WriteFile,
+        move |_, a: LeaseRenewArgs| Ok(t.lease_renew(&a.lease_id, a.ttl_secs)?.into()),
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "lease.reserve",
+        "Reserve paths for a lease's exclusive writes",
+        Capability::WriteFile,
+        move |_, a: LeaseReserveArgs| Ok(t.lease_reserve(&a.lease_id, a.paths)?.into()),
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "lease.release",
+        "Release a lease",
+        Capability::WriteFile,
+        move |_, a: LeaseReleaseArgs| Ok(t.lease_release(&a.lease_id)?.into()),
+    );
+
+    let t = tools;
+    registry.tool(
+        "lease.list",
+        "List live leases with their holders and touched files",
+        Capability::ReadFile,
+        move |ctx, a: LeaseListArgs| {
+            let mut listed = t.lease_list(a.repo_root.as_deref().map(Path::new))?;
+            // Strict mode hides the leases of repositories outside the mounts.
+            if let Some(leases) = listed["leases"].as_array_mut() {
+                leases.retain(|l| l["repo_root"].as_str().is_some_and(|r| ctx.in_scope(r)));
+            }
+            Ok(listed.into())
+        },
+    );
+}
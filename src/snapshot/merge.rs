@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! Line-based three-way merge of text, diff3 style.
+//!
+//! Each side is diffed against the base. Changes of one side are taken as
+//! they are; changes of both sides to overlapping or adjacent base lines
+//! must be identical, or they conflict.
+
+use serde::Serialize;
+use similar::{Algorithm, DiffOp, capture_diff_slices};
+
+pub const OURS_MARKER: &str = "<<<<<<< ours";
+pub const SEPARATOR_MARKER: &str = "=======";
+pub const THEIRS_MARKER: &str = ">>>>>>> theirs";
+
+/// Lines `start..start + count` of a text, counted from 1 like the hunk
+/// headers of a unified diff. `start` is the line before which an empty
+/// range sits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub count: usize,
+}
+
+impl LineRange {
+    fn new(range: std::ops::Range<usize>) -> Self {
+        Self {
+            start: range.start + 1,
+            count: range.len(),
+        }
+    }
+}
+
+/// Base lines changed differently by both sides, and what each made of them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    pub base: LineRange,
+    pub ours: LineRange,
+    pub theirs: LineRange,
+}
+
+/// Outcome of [`merge`]. With conflicts, `text` holds both versions of each
+/// conflicting region between conflict markers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    pub text: String,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A change of one side: base lines `base` replaced by the side's `side`.
+struct Hunk {
+    base: std::ops::Range<usize>,
+    side: std::ops::Range<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Ours,
+    Theirs,
+}
+
+/// Merges the changes `ours` and `theirs` made to `base`.
+pub fn merge(base: &str, ours: &str, theirs: &str) -> Merged {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs: Vec<&str> = theirs.split_inclusive('\n').collect();
+
+    let mut hunks: Vec<(Side, Hunk)> = hunks(&base, &ours)
+        .into_iter()
+        .map(|h| (Side::Ours, h))
+        .chain(hunks(&base, &theirs).into_iter().map(|h| (Side::Theirs, h)))
+        .collect();
+    hunks.sort_by_key(|(_, h)| (h.base.start, h.base.end));
+
+    let mut text = String::new();
+    let mut conflicts = Vec::new();
+    // Base lines copied so far, and how far each side has drifted from the
+    // base before that point.
+    let mut copied = 0;
+    let mut ours_offset: isize = 0;
+    let mut theirs_offset: isize = 0;
+    let mut i = 0;
+    while i < hunks.len() {
+        // A region: hunks overlapping or touching the first one, transitively.
+        let start = hunks[i].1.base.start;
+        let mut end = hunks[i].1.base.end;
+        let mut sides = (false, false);
+        let (mut ours_delta, mut theirs_delta) = (0, 0);
+        while i < hunks.len() && hunks[i].1.base.start <= end {
+            let (side, hunk) = &hunks[i];
+            end = end.max(hunk.base.end);
+            let delta = hunk.side.len() as isize - hunk.base.len() as isize;
+            match side {
+                Side::Ours => {
+                    sides.0 = true;
+                    ours_delta += delta;
+                }
+                Side::Theirs => {
+                    sides.1 = true;
+                    theirs_delta += delta;
+                }
+            }
+            i += 1;
+        }
+
+        text.extend(base[copied..start].iter().copied());
+        copied = end;
+        let ours_range = shift(start, ours_offset)..shift(end, ours_offset + ours_delta);
+        let theirs_range = shift(start, theirs_offset)..shift(end, theirs_offset + theirs_delta);
+        ours_offset += ours_delta;
+        theirs_offset += theirs_delta;
+
+        let ours_lines = &ours[ours_range.clone()];
+        let theirs_lines = &theirs[theirs_range.clone()];
+        match sides {
+            (true, false) => text.extend(ours_lines.iter().copied()),
+            (false, true) => text.extend(theirs_lines.iter().copied()),
+            _ if ours_lines == theirs_lines => text.extend(ours_lines.iter().copied()),
+            _ => {
+                push_block(&mut text, OURS_MARKER, ours_lines);
+                push_block(&mut text, SEPARATOR_MARKER, theirs_lines);
+                text.push_str(THEIRS_MARKER);
+                text.push('\n');
+                conflicts.push(Conflict {
+                    base: LineRange::new(start..end),
+                    ours: LineRange::new(ours_range),
+                    theirs: LineRange::new(theirs_range),
+                });
+            }
+        }
+    }
+    text.extend(base[copied..].iter().copied());
+
+    Merged { text, conflicts }
+}
+
+/// Changes turning `base` into `side`, with adjacent operations joined.
+fn hunks(base: &[&str], side: &[&str]) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, base, side) {
+        if let DiffOp::Equal { .. } = op {
+            continue;
+        }
+        let (base_range, side_range) = (op.old_range(), op.new_range());
+        match hunks.last_mut() {
+            Some(last) if last.base.end == base_range.start => {
+                last.base.end = base_range.end;
+                last.side.end = side_range.end;
+            }
+            _ => hunks.push(Hunk {
+                base: base_range,
+                side: side_range,
+            }),
+        }
+    }
+    hunks
+}
+
+fn shift(line: usize, offset: isize) -> usize {
+    line.saturating_add_signed(offset)
+}
+
+/// `marker`, then `lines`, ending with a newline so the next marker starts
+/// on its own line.
+fn push_block(text: &mut String, marker: &str, lines: &[&str]) {
+    text.push_str(marker);
+    text.push('\n');
+    text.extend(lines.iter().copied());
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_takes_changes_of_both_sides() {
+        let base = "a\nb\nc\nd\ne\n";
+        let ours = "A\nb\nc\nd\ne\n";
+        let theirs = "a\nb\nc\nd\nE\nf\n";
+        let merged = merge(base, ours, theirs);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.text, "A\nb\nc\nd\nE\nf\n");
+
+        // The same change on both sides is taken once.
+        let merged = merge(base, ours, ours);
+        assert_eq!(merged.text, ours);
+        assert!(merged.conflicts.is_empty());
+
+        // Insertions and deletions shift the lines of the other side.
+        let merged = merge(base, "x\ny\na\nb\nc\nd\ne\n", "a\nb\nd\ne\n");
+        assert_eq!(merged.text, "x\ny\na\nb\nd\ne\n");
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_merge_reports_conflicts() {
+        let base = "a\nb\nc\n";
+        let merged = merge(base, "a\nours\nc\n", "a\ntheirs\nmore\nc\n");
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< ours\nours\n=======\ntheirs\nmore\n>>>>>>> theirs\nc\n"
+        );
+        assert_eq!(
+            merged.conflicts,
+            [Conflict {
+                base: LineRange { start: 2, count: 1 },
+                ours: LineRange { start: 2, count: 1 },
+                theirs: LineRange { start: 2, count: 2 },
+            }]
+        );
+
+        // Both sides appending, without a trailing newline.
+        let merged = merge("a\n", "a\nb", "a\nc");
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< ours\nb\n=======\nc\n>>>>>>> theirs\n"
+        );
+        assert_eq!(merged.conflicts[0].base, LineRange { start: 2, count: 0 });
+
+        // Changes to adjacent lines conflict, as with diff3.
+        let merged = merge(base, "A\nb\nc\n", "a\nB\nc\n");
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].base, LineRange { start: 1, count: 2 });
+    }
+}
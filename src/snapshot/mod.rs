@@ -3,9 +3,17 @@
 // Feature: MCP_SNAPSHOT_WORKSPACE
 // Spec: spec/core/snapshot-workspace.md
 
+pub mod chunking;
+pub mod fingerprint;
 pub mod lease;
+pub mod lineage;
+pub mod mcp;
+pub mod merge;
+pub mod refs;
+pub mod reservation;
 pub mod store;
 pub mod tools;
+pub mod worktree;
 
 // We will implement the actual tools in the submodules or here?
 // For cleanliness, we can keep the tool impls in submodules or a tools.rs file.
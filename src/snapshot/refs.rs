@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! Names for snapshots: per-repository tags, `HEAD`, and `~N` ancestry.
+//!
+//! A name resolves to a snapshot ID when the tool is called, so results
+//! computed for a name are never cached as immutable.
+
+use crate::router::AxiomRegentError;
+use crate::snapshot::store::Store;
+use anyhow::Result;
+use std::path::Path;
+
+/// The newest snapshot of the repository. Cannot be used as a tag.
+pub const HEAD: &str = "HEAD";
+
+const SNAPSHOT_ID_PREFIX: &str = "sha256:";
+const MAX_NAME_LEN: usize = 255;
+
+/// Whether `spec` is a plain snapshot ID, which resolves to itself.
+pub fn is_snapshot_id(spec: &str) -> bool {
+    spec.starts_with(SNAPSHOT_ID_PREFIX) && !spec.contains('~')
+}
+
+/// Checks that `name` can be used as a tag: `/`-separated segments of ASCII
+/// letters, digits, `.`, `_` and `-`, none starting with a dot.
+pub fn validate_name(name: &str) -> Result<()> {
+    let invalid = |why: &str| -> anyhow::Error {
+        AxiomRegentError::InvalidArgument(format!("Invalid tag name '{}': {}", name, why)).into()
+    };
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(invalid("must be 1 to 255 bytes long"));
+    }
+    if name == HEAD {
+        return Err(invalid("HEAD is reserved"));
+    }
+    for segment in name.split('/') {
+        if segment.is_empty() || segment.starts_with('.') {
+            return Err(invalid("empty segment or segment starting with '.'"));
+        }
+        if !segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        {
+            return Err(invalid(
+                "only letters, digits, '.', '_', '-' and '/' allowed",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Splits `base~2~` into `base` and the generations to walk back (3).
+fn parse(spec: &str) -> Result<(&str, usize)> {
+    let mut parts = spec.split('~');
+    let base = parts.next().unwrap_or_default();
+    let mut generations = 0;
+    for part in parts {
+        generations += if part.is_empty() {
+            1
+        } else {
+            part.parse::<usize>().map_err(|_| {
+                AxiomRegentError::InvalidArgument(format!("Invalid snapshot name: {}", spec))
+            })?
+        };
+    }
+    Ok((base, generations))
+}
+
+/// Snapshot ID named by `spec` in the repository at `repo_root`.
+///
+/// `spec` is a snapshot ID, a tag or `HEAD`, optionally followed by `~N`:
+/// the Nth ancestor along `derived_from` (`~` alone is `~1`). The snapshot
+/// must belong to the repository, so that a caller scoped to one repository
+/// cannot read another's snapshots by ID.
+pub fn resolve(store: &Store, repo_root: &Path, spec: &str) -> Result<String> {
+    let repo_key = repo_root.canonicalize()?.to_string_lossy().into_owned();
+    let id = resolve_id(store, &repo_key, spec)?;
+    let info = store.get_snapshot_info(&id)?.ok_or_else(|| {
+        AxiomRegentError::NotFound(format!("Snapshot not found: {}", id)).with_snapshot(&id)
+    })?;
+    if info.repo_root != repo_key {
+        return Err(AxiomRegentError::InvalidArgument(
+            "Snapshot belongs to another repository".into(),
+        )
+        .with_snapshot(&id)
+        .into());
+    }
+    Ok(id)
+}
+
+fn resolve_id(store: &Store, repo_key: &str, spec: &str) -> Result<String> {
+    if is_snapshot_id(spec) {
+        return current_id(store, spec);
+    }
+    let (base, generations) = parse(spec)?;
+
+    let mut id = if base.starts_with(SNAPSHOT_ID_PREFIX) {
+        current_id(store, base)?
+    } else if base == HEAD {
+        store
+            .list_snapshots(repo_key)?
+            .into_iter()
+            .next()
+            .map(|info| info.snapshot_id)
+            .ok_or_else(|| AxiomRegentError::NotFound("Repository has no snapshots".into()))?
+    } else {
+        store.get_ref(repo_key, base)?.ok_or_else(|| {
+            AxiomRegentError::NotFound(format!("Unknown snapshot name: {}", base))
+                .with_snapshot(spec)
+        })?
+    };
+
+    for _ in 0..generations {
+        let info = store.get_snapshot_info(&id)?.ok_or_else(|| {
+            AxiomRegentError::NotFound(format!("Snapshot not found: {}", id)).with_snapshot(&id)
+        })?;
+        id = info.derived_from.ok_or_else(|| {
+            AxiomRegentError::NotFound(format!("{} has no ancestor that far back", spec))
+                .with_snapshot(&id)
+        })?;
+    }
+    Ok(id)
+}
+
+/// `id`, or the ID it was moved to by the store's blob ID upgrade.
+fn current_id(store: &Store, id: &str) -> Result<String> {
+    Ok(store
+        .renamed_snapshot(id)?
+        .unwrap_or_else(|| id.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        for name in ["baseline", "pre-changeset/004", "v1.2_rc-1"] {
+            validate_name(name).unwrap();
+        }
+        for name in [
+            "",
+            "HEAD",
+            "a~1",
+            "a/../b",
+            "/a",
+            "a/",
+            "a//b",
+            ".hidden",
+            "sha256:ab",
+            "a b",
+        ] {
+            assert!(validate_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("HEAD").unwrap(), ("HEAD", 0));
+        assert_eq!(parse("HEAD~1").unwrap(), ("HEAD", 1));
+        assert_eq!(parse("baseline~").unwrap(), ("baseline", 1));
+        assert_eq!(parse("a/b~2~").unwrap(), ("a/b", 3));
+        assert!(parse("HEAD~x").is_err());
+        assert!(is_snapshot_id("sha256:00ff"));
+        assert!(!is_snapshot_id("sha256:00ff~1"));
+        assert!(!is_snapshot_id("baseline"));
+    }
+}
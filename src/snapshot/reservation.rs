@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! Glob patterns with which a lease reserves paths of its worktree.
+//!
+//! `*` matches any part of a path segment, `?` one character of it and a
+//! `**` segment any number of segments. A pattern also reserves everything
+//! below the paths it matches: `src` reserves the whole `src` directory.
+
+use crate::router::AxiomRegentError;
+use anyhow::Result;
+
+/// `pattern` in canonical form: `/`-separated, without empty or `.`
+/// segments. Absolute patterns and `..` segments are rejected.
+pub fn normalize(pattern: &str) -> Result<String> {
+    let invalid = |why: &str| -> anyhow::Error {
+        AxiomRegentError::InvalidArgument(format!("Invalid path pattern '{}': {}", pattern, why))
+            .with_path(pattern)
+            .into()
+    };
+    if pattern.starts_with('/') || pattern.contains('\\') {
+        return Err(invalid("must be relative to the repository root, with '/'"));
+    }
+    let segments: Vec<&str> = pattern
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect();
+    if segments.contains(&"..") {
+        return Err(invalid("'..' is not allowed"));
+    }
+    if segments.is_empty() {
+        return Err(invalid(
+            "matches nothing (use '**' for the whole repository)",
+        ));
+    }
+    Ok(segments.join("/"))
+}
+
+/// Whether `pattern` reserves the repo-relative `path`.
+pub fn covers(pattern: &str, path: &str) -> bool {
+    let path: Vec<Segment> = path
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .map(|s| Segment::Tokens(s.chars().map(Token::Char).collect()))
+        .collect();
+    overlap(
+        &parse(pattern),
+        &path,
+        Segment::is_globstar,
+        segments_overlap,
+    )
+}
+
+/// Whether some path is reserved by both `a` and `b`.
+pub fn conflict(a: &str, b: &str) -> bool {
+    overlap(&parse(a), &parse(b), Segment::is_globstar, segments_overlap)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Char(char),
+    // `?`
+    Any,
+    // `*`
+    Star,
+}
+
+impl Token {
+    fn is_star(&self) -> bool {
+        *self == Token::Star
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    // `**`
+    Globstar,
+    Tokens(Vec<Token>),
+}
+
+impl Segment {
+    fn is_globstar(&self) -> bool {
+        *self == Segment::Globstar
+    }
+}
+
+/// The segments of `pattern`, plus a trailing `**` for what lies below.
+fn parse(pattern: &str) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = pattern
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .map(|s| {
+            if s == "**" {
+                return Segment::Globstar;
+            }
+            let mut tokens = Vec::new();
+            for c in s.chars() {
+                let token = match c {
+                    '*' => Token::Star,
+                    '?' => Token::Any,
+                    c => Token::Char(c),
+                };
+                // `**` within a segment is just `*`.
+                if !(token.is_star() && tokens.last() == Some(&Token::Star)) {
+                    tokens.push(token);
+                }
+            }
+            Segment::Tokens(tokens)
+        })
+        .collect();
+    segments.push(Segment::Globstar);
+    segments
+}
+
+fn segments_overlap(a: &Segment, b: &Segment) -> bool {
+    match (a, b) {
+        (Segment::Tokens(a), Segment::Tokens(b)) => {
+            overlap(a, b, Token::is_star, |a, b| match (a, b) {
+                (Token::Char(a), Token::Char(b)) => a == b,
+                _ => true,
+            })
+        }
+        _ => true,
+    }
+}
+
+/// Whether some sequence matches both `a` and `b`. Stars match any run of
+/// items; the other items match one item each where `unit` says they can.
+fn overlap<T>(a: &[T], b: &[T], star: fn(&T) -> bool, unit: impl Fn(&T, &T) -> bool) -> bool {
+    let (n, m) = (a.len(), b.len());
+    // `rest[i][j]`: whether `a[i..]` and `b[j..]` overlap.
+    let mut rest = vec![vec![false; m + 1]; n + 1];
+    rest[n][m] = true;
+    for i in (0..=n).rev() {
+        for j in (0..=m).rev() {
+            if i == n && j == m {
+                continue;
+            }
+            let a_star = i < n && star(&a[i]);
+            let b_star = j < m && star(&b[j]);
+            // A star matches nothing more, or also what the other side's next item matches.
+            rest[i][j] = (a_star && (rest[i + 1][j] || (j < m && rest[i][j + 1])))
+                || (b_star && (rest[i][j + 1] || (i < n && rest[i + 1][j])))
+                || (i < n
+                    && j < m
+                    && !a_star
+                    && !b_star
+                    && unit(&a[i], &b[j])
+                    && rest[i + 1][j + 1]);
+        }
+    }
+    rest[0][0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("./src//lib/").unwrap(), "src/lib");
+        assert_eq!(normalize("**/*.rs").unwrap(), "**/*.rs");
+        for pattern in ["", ".", "/etc", "a/../b", "a\\b"] {
+            assert!(normalize(pattern).is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_covers() {
+        assert!(covers("src", "src/lib.rs"));
+        assert!(covers("src", "src"));
+        assert!(!covers("src", "srcs/lib.rs"));
+        assert!(covers("src/*.rs", "src/lib.rs"));
+        assert!(covers("src/*.rs", "src/lib.rs/inner"));
+        assert!(!covers("src/*.rs", "src/lib.rsx"));
+        assert!(covers("**/*.md", "docs/a/README.md"));
+        assert!(covers("**/*.md", "README.md"));
+        assert!(covers("a?c", "abc"));
+        assert!(!covers("a?c", "ac"));
+        assert!(covers("**", "anything/at/all"));
+        // Literal stars in paths are not wildcards.
+        assert!(!covers("a", "*"));
+    }
+
+    #[test]
+    fn test_conflict() {
+        assert!(conflict("src", "src/lib.rs"));
+        assert!(conflict("src/*.rs", "src/lib*"));
+        assert!(conflict("**/*.md", "docs"));
+        assert!(conflict("*a*", "*b*"));
+        assert!(conflict("a?", "?b"));
+        assert!(!conflict("src", "tests"));
+        assert!(!conflict("src/*.rs", "src/*.md"));
+        assert!(conflict("a/*/c", "a/b"));
+        assert!(!conflict("a/*/c", "a/b/d"));
+        assert!(!conflict("*x", "*y"));
+        assert!(!conflict("a?", "abc"));
+    }
+}
@@ -3,14 +3,19 @@
 // Feature: MCP_SNAPSHOT_WORKSPACE
 // Spec: spec/core/snapshot-workspace.md
 
-use crate::config::{BlobBackend, Compression, StorageConfig};
+use crate::config::{BlobBackend, Compression, DEFAULT_UNCOMPRESSED_EXTENSIONS, StorageConfig};
+use crate::router::AxiomRegentError;
+use crate::snapshot::chunking;
+use crate::snapshot::worktree::Scope;
 use anyhow::{Result, anyhow};
-use rusqlite::{Connection, OptionalExtension, params};
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
 
 // Re-export Manifest/Entry for compatibility
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -19,6 +24,27 @@ pub struct Entry {
     pub path: String,
     #[serde(default)]
     pub size: u64, // Added size to match schema requirement
+    /// Content-defined chunks of a large file, in order. When set, `blob` is
+    /// the hash of the whole content and is not stored on its own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chunks: Vec<String>,
+    /// Executable bit of a regular file.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub executable: bool,
+    /// Target of a symlink, which `blob` also holds as content.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink: Option<String>,
+}
+
+impl Entry {
+    /// Blobs holding the content: the chunks, or `blob` itself.
+    pub fn blob_refs(&self) -> &[String] {
+        if self.chunks.is_empty() {
+            std::slice::from_ref(&self.blob)
+        } else {
+            &self.chunks
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -32,6 +58,12 @@ pub struct SnapshotInfo {
     pub derived_from: Option<String>,
     pub applied_patch_hash: Option<String>,
     pub label: Option<String>,
+    /// Second parent of a merge: the snapshot merged into `derived_from`.
+    #[serde(default)]
+    pub merged_from: Option<String>,
+    /// Files captured, when recorded; snapshots without it may be partial.
+    #[serde(default)]
+    pub scope: Option<Scope>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -64,10 +96,101 @@ impl Manifest {
     }
 }
 
+/// Which snapshots of a repository survive [`Store::plan_retention`].
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    /// Most recent snapshots kept; `None` keeps all of them.
+    pub keep_last: Option<usize>,
+    /// Keep every snapshot that has a label.
+    pub keep_labeled: bool,
+    /// Snapshots kept regardless of age (e.g. base states of locked changesets).
+    pub pinned: HashSet<String>,
+}
+
+/// Outcome of [`Store::sweep_blobs`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SweepReport {
+    pub blobs_removed: usize,
+    pub bytes_reclaimed: u64,
+}
+
+/// A tag: a name of a repository pointing at one of its snapshots.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RefInfo {
+    pub name: String,
+    pub snapshot_id: String,
+    pub updated_at: Option<i64>,
+}
+
+/// One change of a tag, recorded for auditing: `from` is unset when the tag
+/// was created, `to` when it was removed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RefLogEntry {
+    pub name: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub at: Option<i64>,
+}
+
+/// A lease as persisted in the `leases` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredLease {
+    pub lease_id: String,
+    pub repo_root: String,
+    pub fingerprint_json: String,
+    pub touched: Vec<String>,
+    pub issued_at: Option<i64>,
+    pub holder: Option<String>,
+    pub purpose: Option<String>,
+    pub ttl_secs: Option<u64>,
+    /// Unix seconds; unset for leases persisted before leases expired.
+    pub expires_at: Option<i64>,
+    pub reserved: Vec<String>,
+}
+
+/// What the stat cache compares to tell that a worktree file is unchanged,
+/// like git's index. The executable bit is included: `chmod` keeps the mtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStat {
+    pub size: u64,
+    pub mtime_ns: i64,
+    pub inode: u64,
+    pub executable: bool,
+}
+
+/// Outcome of [`Store::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StoreStats {
+    pub snapshots: u64,
+    pub files: u64,
+    pub chunked_files: u64,
+    pub blobs: u64,
+    /// Size of every file of every snapshot, as if each were stored whole.
+    pub logical_bytes: u64,
+    /// Bytes held by blobs, after deduplication and compression.
+    pub stored_bytes: u64,
+    /// `logical_bytes / stored_bytes`; 1.0 for an empty store.
+    pub dedup_ratio: f64,
+}
+
+/// Blob ID of `data`: the SHA-256 of the raw bytes, whatever their encoding on disk.
+pub fn content_hash(data: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(data)))
+}
+
 pub trait BlobStore: Send + Sync {
-    fn put(&self, data: &[u8], compression: Compression) -> Result<String>;
+    /// Stores `data` (possibly compressed) under `hash`, the ID of its raw
+    /// content. A no-op if `hash` is already stored.
+    fn put(&self, hash: &str, data: &[u8]) -> Result<()>;
+    /// Stores `data` under `hash`, replacing any bytes already stored there.
+    /// For writers with no `blobs` row to say how such bytes were encoded.
+    fn replace(&self, hash: &str, data: &[u8]) -> Result<()>;
     fn get(&self, hash: &str) -> Result<Option<Vec<u8>>>;
     fn has(&self, hash: &str) -> Result<bool>;
+    /// Removes a blob, returning the bytes freed (0 if it was not stored).
+    fn delete(&self, hash: &str) -> Result<u64>;
+    /// Hashes of stored blobs last written at or before `cutoff`.
+    fn list_written_before(&self, cutoff: SystemTime) -> Result<Vec<String>>;
 }
 
 pub struct FsBlobStore {
@@ -85,21 +208,30 @@ impl FsBlobStore {
         // hash is "sha256:hex"
         let parts: Vec<&str> = hash.split(':').collect();
         if parts.len() != 2 {
-            return Err(anyhow!("Invalid hash format: {}", hash));
+            return Err(AxiomRegentError::InvalidArgument(format!(
+                "Invalid hash format: {}",
+                hash
+            ))
+            .into());
         }
         let algo = parts[0];
         let val = parts[1];
 
         if val.len() < 64 {
-            return Err(anyhow!(
+            return Err(AxiomRegentError::InvalidArgument(format!(
                 "Invalid hash length (expected >= 64 chars): {}",
                 hash
-            ));
+            ))
+            .into());
         }
 
         // Strict hex validation
         if !val.chars().all(|c| c.is_ascii_hexdigit()) {
-            return Err(anyhow!("Invalid hash chars (hex allowed only): {}", hash));
+            return Err(AxiomRegentError::InvalidArgument(format!(
+                "Invalid hash chars (hex allowed only): {}",
+                hash
+            ))
+            .into());
         }
 
         // Basic hex validation could be added here, but length check prevents panic on slice
@@ -109,28 +241,26 @@ impl FsBlobStore {
 }
 
 impl BlobStore for FsBlobStore {
-    fn put(&self, data: &[u8], _compression: Compression) -> Result<String> {
-        let digest = Sha256::digest(data);
-        let hash_val = hex::encode(digest);
-        let hash = format!("sha256:{}", hash_val);
-
-        let path = self.path_for(&hash)?;
-
-        if path.exists() {
-            return Ok(hash);
+    fn put(&self, hash: &str, data: &[u8]) -> Result<()> {
+        if self.path_for(hash)?.exists() {
+            return Ok(());
         }
+        self.replace(hash, data)
+    }
 
+    fn replace(&self, hash: &str, data: &[u8]) -> Result<()> {
+        let path = self.path_for(hash)?;
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        // Atomic write
+        // Atomic write; the rename replaces any existing file.
         let mut tmp = tempfile::NamedTempFile::new_in(path.parent().unwrap_or(&self.base_path))?;
         use std::io::Write;
         tmp.write_all(data)?;
         tmp.persist(&path).map_err(|e| e.error)?;
 
-        Ok(hash)
+        Ok(())
     }
 
     fn get(&self, hash: &str) -> Result<Option<Vec<u8>>> {
@@ -145,12 +275,189 @@ impl BlobStore for FsBlobStore {
     fn has(&self, hash: &str) -> Result<bool> {
         Ok(self.path_for(hash)?.exists())
     }
+
+    fn delete(&self, hash: &str) -> Result<u64> {
+        let path = self.path_for(hash)?;
+        let size = match fs::metadata(&path) {
+            Ok(m) => m.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(size),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list_written_before(&self, cutoff: SystemTime) -> Result<Vec<String>> {
+        // Layout: <base>/<algo>/<prefix>/<hex>. Dotfiles are in-flight temp files.
+        let mut hashes = Vec::new();
+        for algo in fs::read_dir(&self.base_path)? {
+            let algo = algo?;
+            if !algo.file_type()?.is_dir() {
+                continue;
+            }
+            let algo_name = algo.file_name().to_string_lossy().to_string();
+            for prefix in fs::read_dir(algo.path())? {
+                let prefix = prefix?;
+                if !prefix.file_type()?.is_dir() {
+                    continue;
+                }
+                for blob in fs::read_dir(prefix.path())? {
+                    let blob = blob?;
+                    let name = blob.file_name().to_string_lossy().to_string();
+                    if name.starts_with('.') {
+                        continue;
+                    }
+                    if blob.metadata()?.modified()? <= cutoff {
+                        hashes.push(format!("{}:{}", algo_name, name));
+                    }
+                }
+            }
+        }
+        hashes.sort();
+        Ok(hashes)
+    }
+}
+
+/// Blobs stored inline in `store.sqlite`, split into chunks of at most
+/// [`SqliteBlobStore::CHUNK_SIZE`] bytes. Avoids one file per blob, which is
+/// slow on network filesystems.
+pub struct SqliteBlobStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteBlobStore {
+    pub const CHUNK_SIZE: usize = 1024 * 1024;
+
+    pub fn new(conn: Arc<Mutex<Connection>>) -> Result<Self> {
+        conn.lock().unwrap().execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS blob_chunks (
+                hash TEXT NOT NULL,
+                seq INTEGER NOT NULL,
+                data BLOB NOT NULL,
+                written_at INTEGER NOT NULL,
+                PRIMARY KEY (hash, seq)
+            );
+            "#,
+        )?;
+        Ok(Self { conn })
+    }
+
+    fn insert_chunks(tx: &Transaction, hash: &str, data: &[u8]) -> Result<()> {
+        let mut stmt = tx.prepare(
+            "INSERT INTO blob_chunks (hash, seq, data, written_at) VALUES (?1, ?2, ?3, unixepoch())",
+        )?;
+        // An empty blob still gets its (empty) first chunk.
+        let chunks: Vec<&[u8]> = if data.is_empty() {
+            vec![data]
+        } else {
+            data.chunks(Self::CHUNK_SIZE).collect()
+        };
+        for (seq, chunk) in chunks.into_iter().enumerate() {
+            stmt.execute(params![hash, seq as i64, chunk])?;
+        }
+        Ok(())
+    }
+}
+
+impl BlobStore for SqliteBlobStore {
+    fn put(&self, hash: &str, data: &[u8]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        // All chunks or none: a crash never leaves a truncated blob behind.
+        let tx = conn.transaction()?;
+        let exists = tx
+            .prepare("SELECT 1 FROM blob_chunks WHERE hash = ?1 AND seq = 0")?
+            .exists(params![hash])?;
+        if !exists {
+            Self::insert_chunks(&tx, hash, data)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn replace(&self, hash: &str, data: &[u8]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM blob_chunks WHERE hash = ?1", params![hash])?;
+        Self::insert_chunks(&tx, hash, data)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn get(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT data FROM blob_chunks WHERE hash = ?1 ORDER BY seq")?;
+        let mut rows = stmt.query(params![hash])?;
+        let mut data = Vec::new();
+        let mut found = false;
+        while let Some(row) = rows.next()? {
+            found = true;
+            data.extend_from_slice(row.get_ref(0)?.as_blob()?);
+        }
+        Ok(found.then_some(data))
+    }
+
+    fn has(&self, hash: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .prepare("SELECT 1 FROM blob_chunks WHERE hash = ?1 AND seq = 0")?
+            .exists(params![hash])?)
+    }
+
+    fn delete(&self, hash: &str) -> Result<u64> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let size: i64 = tx.query_row(
+            "SELECT COALESCE(SUM(LENGTH(data)), 0) FROM blob_chunks WHERE hash = ?1",
+            params![hash],
+            |row| row.get(0),
+        )?;
+        tx.execute("DELETE FROM blob_chunks WHERE hash = ?1", params![hash])?;
+        tx.commit()?;
+        Ok(size as u64)
+    }
+
+    fn list_written_before(&self, cutoff: SystemTime) -> Result<Vec<String>> {
+        let cutoff = cutoff
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT hash FROM blob_chunks WHERE seq = 0 AND written_at <= ?1 ORDER BY hash",
+        )?;
+        let rows = stmt.query_map(params![cutoff], |row| row.get(0))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+/// Outcome of [`Store::migrate_blobs`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MigrateReport {
+    pub blobs_moved: usize,
+    pub bytes_moved: u64,
+    /// Blobs whose content was missing from their recorded backend.
+    pub missing: Vec<String>,
 }
 
 pub struct Store {
     conn: Arc<Mutex<Connection>>,
-    blob_store: Box<dyn BlobStore>,
+    fs_blobs: FsBlobStore,
+    db_blobs: SqliteBlobStore,
+    // Backend new blobs are written to. Each blob row records where its
+    // content lives (`blobs.storage`), so reads work across backends.
+    backend: RwLock<BlobBackend>,
     config: StorageConfig,
+    // Lowercase extensions stored uncompressed regardless of `config.compression`.
+    uncompressed_extensions: HashSet<String>,
+    // put_blob holds this shared while it writes the blob and its row, and
+    // reads hold it while they look up and fetch one; the sweep and
+    // migrate_blobs take it exclusively, so neither side sees a row that
+    // points at content not yet written or already moved away.
+    gc_lock: RwLock<()>,
 }
 
 impl Store {
@@ -162,25 +469,51 @@ impl Store {
 
         let conn = Connection::open(&db_path)?;
         Self::migrate(&conn)?;
+        let conn = Arc::new(Mutex::new(conn));
 
-        let blob_store: Box<dyn BlobStore> = match config.blob_backend {
-            BlobBackend::Fs => {
-                let blobs_dir = config.data_dir.join("blobs");
-                Box::new(FsBlobStore::new(blobs_dir)?)
-            }
-            BlobBackend::Db => {
-                // We'll implement SqliteBlobStore logic differently or inline it?
-                // For now, let's keep it simple. FsBlobStore is default.
-                // We can implement SqliteBlobStore later or panic for now
-                unimplemented!("SqliteBlobStore not fully wired yet");
-            }
+        let store = Self {
+            fs_blobs: FsBlobStore::new(config.data_dir.join("blobs"))?,
+            db_blobs: SqliteBlobStore::new(conn.clone())?,
+            conn,
+            backend: RwLock::new(config.blob_backend),
+            config,
+            uncompressed_extensions: DEFAULT_UNCOMPRESSED_EXTENSIONS
+                .iter()
+                .map(|e| e.to_string())
+                .collect(),
+            gc_lock: RwLock::new(()),
         };
+        store.upgrade_blob_ids()?;
+        Ok(store)
+    }
 
-        Ok(Self {
-            conn: Arc::new(Mutex::new(conn)),
-            blob_store,
-            config,
-        })
+    /// Replaces the extensions (without the dot) whose content is never compressed.
+    pub fn with_uncompressed_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.uncompressed_extensions = extensions
+            .into_iter()
+            .map(|e| e.to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    fn blob_store(&self, backend: BlobBackend) -> &dyn BlobStore {
+        match backend {
+            BlobBackend::Fs => &self.fs_blobs,
+            BlobBackend::Db => &self.db_blobs,
+        }
+    }
+
+    /// Backend holding the content of `hash`, per its row.
+    fn stored_in(&self, hash: &str) -> Result<Option<BlobBackend>> {
+        let conn = self.conn.lock().unwrap();
+        let storage: Option<String> = conn
+            .query_row(
+                "SELECT storage FROM blobs WHERE hash = ?1",
+                params![hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(storage.and_then(|s| BlobBackend::parse(&s)))
     }
 
     fn migrate(conn: &Connection) -> Result<()> {
@@ -216,6 +549,47 @@ impl Store {
                 PRIMARY KEY (snapshot_id, path)
             );
 
+            CREATE TABLE IF NOT EXISTS entry_chunks (
+                snapshot_id TEXT NOT NULL,
+                path TEXT NOT NULL,
+                seq INTEGER NOT NULL,
+                chunk_hash TEXT NOT NULL,
+                PRIMARY KEY (snapshot_id, path, seq)
+            );
+
+            CREATE TABLE IF NOT EXISTS stat_cache (
+                repo_root TEXT NOT NULL,
+                path TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                mtime_ns INTEGER NOT NULL,
+                inode INTEGER NOT NULL,
+                executable INTEGER NOT NULL,
+                entry_json TEXT NOT NULL,
+                PRIMARY KEY (repo_root, path)
+            );
+
+            CREATE TABLE IF NOT EXISTS refs (
+                repo_root TEXT NOT NULL,
+                name TEXT NOT NULL,
+                snapshot_id TEXT NOT NULL,
+                updated_at INTEGER,
+                PRIMARY KEY (repo_root, name)
+            );
+
+            CREATE TABLE IF NOT EXISTS ref_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repo_root TEXT NOT NULL,
+                name TEXT NOT NULL,
+                old_snapshot_id TEXT,
+                new_snapshot_id TEXT,
+                at INTEGER
+            );
+
+            CREATE TABLE IF NOT EXISTS snapshot_aliases (
+                old_id TEXT PRIMARY KEY,
+                new_id TEXT NOT NULL
+            );
+
             CREATE TABLE IF NOT EXISTS leases (
                 lease_id TEXT PRIMARY KEY,
                 repo_root TEXT NOT NULL,
@@ -225,78 +599,342 @@ impl Store {
             );
             "#,
         )?;
+
+        // Columns added after the initial schema.
+        for (table, column, decl) in [
+            ("blobs", "last_written_at", "INTEGER"),
+            (
+                "manifest_entries",
+                "executable",
+                "INTEGER NOT NULL DEFAULT 0",
+            ),
+            ("manifest_entries", "symlink", "TEXT"),
+            ("snapshots", "merged_from", "TEXT"),
+            ("snapshots", "scope", "TEXT"),
+            ("leases", "holder", "TEXT"),
+            ("leases", "purpose", "TEXT"),
+            ("leases", "ttl_secs", "INTEGER"),
+            ("leases", "expires_at", "INTEGER"),
+            ("leases", "reserved_json", "TEXT"),
+        ] {
+            let exists = conn
+                .prepare(&format!(
+                    "SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1",
+                    table
+                ))?
+                .exists(params![column])?;
+            if !exists {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table, column, decl
+                ))?;
+            }
+        }
         Ok(())
     }
 
-    // BlobStore Proxy with Compression logic
-    // NOTE: The returned hash is the SHA256 of the *stored* bytes (which may be compressed).
-    // This implies that if compression is used, the manifest stores the hash of the compressed data.
-    // Decompression happens transparently on get() by consulting the DB.
-    pub fn put_blob(&self, data: &[u8]) -> Result<String> {
-        // Handle compression
-        let (stored_data, alg) = match self.config.compression {
-            Compression::Zstd => {
-                let compressed = zstd::stream::encode_all(data, 3)?; // Level 3 default
-                (compressed, "zstd")
+    /// `PRAGMA user_version` of stores whose blob IDs hash the raw content.
+    const RAW_BLOB_IDS_VERSION: i64 = 1;
+
+    /// One-time upgrade of stores written when compressed blobs were keyed by
+    /// the hash of their compressed bytes: each is re-keyed by the hash of its
+    /// content, and every manifest referencing it is rewritten.
+    ///
+    /// Snapshot IDs derive from manifests, so each rewritten snapshot moves
+    /// to its new ID (see [`Self::rename_snapshot`]); the old one still
+    /// resolves. The old blob copies lose their rows and go with the next
+    /// sweep.
+    fn upgrade_blob_ids(&self) -> Result<usize> {
+        let legacy: Vec<(String, String)> = {
+            let conn = self.conn.lock().unwrap();
+            let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+            if version >= Self::RAW_BLOB_IDS_VERSION {
+                return Ok(0);
+            }
+            let mut stmt = conn.prepare(
+                "SELECT hash, storage FROM blobs WHERE compression = 'zstd' ORDER BY hash",
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<Result<_, _>>()?
+        };
+
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        for (old, storage) in legacy {
+            let Some(backend) = BlobBackend::parse(&storage) else {
+                continue;
+            };
+            // Lost content keeps its old ID; validate_snapshot reports it.
+            let Some(stored) = self.blob_store(backend).get(&old)? else {
+                continue;
+            };
+            let new = content_hash(&zstd::stream::decode_all(stored.as_slice())?);
+            if new == old {
+                continue;
+            }
+            if self.stored_in(&new)?.is_none() {
+                self.blob_store(backend).replace(&new, &stored)?;
+            }
+            renamed.insert(old, new);
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for (old, new) in &renamed {
+            // The content may also be stored under its raw ID already, written
+            // uncompressed by another configuration: merge the two rows.
+            let merged = tx.execute(
+                "UPDATE blobs SET refcount = refcount + (SELECT refcount FROM blobs WHERE hash = ?1)
+                 WHERE hash = ?2",
+                params![old, new],
+            )?;
+            if merged > 0 {
+                tx.execute("DELETE FROM blobs WHERE hash = ?1", params![old])?;
+            } else {
+                tx.execute(
+                    "UPDATE blobs SET hash = ?2 WHERE hash = ?1",
+                    params![old, new],
+                )?;
+            }
+            tx.execute(
+                "UPDATE manifest_entries SET blob_hash = ?2 WHERE blob_hash = ?1",
+                params![old, new],
+            )?;
+            tx.execute(
+                "UPDATE entry_chunks SET chunk_hash = ?2 WHERE chunk_hash = ?1",
+                params![old, new],
+            )?;
+        }
+        if !renamed.is_empty() {
+            let manifests: Vec<(String, String, Vec<u8>)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT snapshot_id, fingerprint_json, manifest_bytes FROM snapshots WHERE manifest_bytes IS NOT NULL",
+                )?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+                rows.collect::<Result<_, _>>()?
+            };
+            for (id, fingerprint_json, bytes) in manifests {
+                let mut manifest: Manifest = serde_json::from_slice(&bytes)?;
+                let mut changed = false;
+                for entry in &mut manifest.entries {
+                    for hash in std::iter::once(&mut entry.blob).chain(entry.chunks.iter_mut()) {
+                        if let Some(new) = renamed.get(hash.as_str()) {
+                            *hash = new.clone();
+                            changed = true;
+                        }
+                    }
+                }
+                if changed {
+                    let json = manifest.to_canonical_json()?;
+                    tx.execute(
+                        "UPDATE snapshots SET manifest_bytes = ?2, manifest_hash = ?3 WHERE snapshot_id = ?1",
+                        params![id, json.as_bytes(), content_hash(json.as_bytes())],
+                    )?;
+                    let new_id = manifest.compute_snapshot_id(&fingerprint_json)?;
+                    if new_id != id {
+                        Self::rename_snapshot(&tx, &id, &new_id)?;
+                    }
+                }
+            }
+        }
+        tx.execute_batch(&format!(
+            "PRAGMA user_version = {}",
+            Self::RAW_BLOB_IDS_VERSION
+        ))?;
+        tx.commit()?;
+        Ok(renamed.len())
+    }
+
+    /// Moves snapshot `old` to ID `new`, with its entries, and points its
+    /// children, tags and ref log at it. `old` is recorded as an alias of
+    /// `new` (see [`Self::renamed_snapshot`]). A snapshot already stored as
+    /// `new` has the same content, so `old` is dropped in its favour.
+    fn rename_snapshot(conn: &Connection, old: &str, new: &str) -> Result<()> {
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM snapshots WHERE snapshot_id = ?1",
+                params![new],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if exists {
+            for hash in Self::snapshot_blob_refs(conn, old)? {
+                conn.execute(
+                    "UPDATE blobs SET refcount = MAX(0, refcount - 1) WHERE hash = ?1",
+                    params![hash],
+                )?;
+            }
+        }
+        for table in ["snapshots", "manifest_entries", "entry_chunks"] {
+            if exists {
+                conn.execute(
+                    &format!("DELETE FROM {} WHERE snapshot_id = ?1", table),
+                    params![old],
+                )?;
+            } else {
+                conn.execute(
+                    &format!(
+                        "UPDATE {} SET snapshot_id = ?2 WHERE snapshot_id = ?1",
+                        table
+                    ),
+                    params![old, new],
+                )?;
             }
+        }
+        for (table, column) in [
+            ("snapshots", "derived_from"),
+            ("snapshots", "merged_from"),
+            ("refs", "snapshot_id"),
+            ("ref_log", "old_snapshot_id"),
+            ("ref_log", "new_snapshot_id"),
+            ("snapshot_aliases", "new_id"),
+        ] {
+            conn.execute(
+                &format!("UPDATE {} SET {} = ?2 WHERE {} = ?1", table, column, column),
+                params![old, new],
+            )?;
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO snapshot_aliases (old_id, new_id) VALUES (?1, ?2)",
+            params![old, new],
+        )?;
+        Ok(())
+    }
+
+    /// The ID snapshot `id` was moved to when the store was upgraded, if it
+    /// was.
+    pub fn renamed_snapshot(&self, id: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT new_id FROM snapshot_aliases WHERE old_id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Stores `data` with the configured compression and returns its blob ID.
+    pub fn put_blob(&self, data: &[u8]) -> Result<String> {
+        self.put_blob_with(data, self.config.compression)
+    }
+
+    /// Stores `data` and returns its blob ID, the hash of the raw bytes.
+    ///
+    /// `compression` is a storage detail recorded in `blobs.compression` and
+    /// undone by `get_blob`. Content already stored keeps its encoding.
+    pub fn put_blob_with(&self, data: &[u8], compression: Compression) -> Result<String> {
+        let hash = content_hash(data);
+
+        let _gc = self.gc_lock.read().unwrap();
+        let existing: Option<(String, String)> = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT storage, compression FROM blobs WHERE hash = ?1",
+                params![hash],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        // A blob already stored elsewhere stays there; migrate_blobs moves it.
+        let (backend, compression) = match &existing {
+            Some((storage, alg)) => (
+                BlobBackend::parse(storage).unwrap_or(*self.backend.read().unwrap()),
+                if alg == "zstd" {
+                    Compression::Zstd
+                } else {
+                    Compression::None
+                },
+            ),
+            None => (*self.backend.read().unwrap(), compression),
+        };
+        let (stored_data, alg) = match compression {
+            Compression::Zstd => (zstd::stream::encode_all(data, 3)?, "zstd"),
             Compression::None => (data.to_vec(), "none"),
         };
+        // Without a row, bytes already under this ID are a leftover (say, of
+        // a sweep that failed after deleting the row) in an unknown encoding.
+        if existing.is_some() {
+            self.blob_store(backend).put(&hash, &stored_data)?;
+        } else {
+            self.blob_store(backend).replace(&hash, &stored_data)?;
+        }
 
-        // Note: BlobStore::put computes hash of *provided* data.
-        // If we pass compressed data, the hash will be of compressed data.
-        // Wait, spec says "hash of raw bytes" usually?
-        // "content-addressed" implies hash is of content.
-        // If we store compressed, we usually want hash of *original* content to look it up?
-        // But FsBlobStore writes to `path_for(hash)`.
-        // If `hash` is sha256(compressed), then we have a CompressedBlobStore.
-        // But if we want `get(original_hash)` to work, we have a problem if we only store compressed.
-
-        // Correct approach for opaque blob store:
-        // 1. Compute hash of ORIGINAL data (ID).
-        // 2. We want to store `compressed` data but indexed by `original_hash`?
-        // FsBlobStore::put computes hash internally.
-
-        // If FsBlobStore computes hash of what it gets, then we get hash(compressed).
-        // That breaks "content-addressed" if we expect hash(original).
-
-        // Solution:
-        // We need explicit `put_with_hash` or `put` returns the hash of what was stored.
-        // If we use hash(compressed), then the Manifest must store hash(compressed).
-        // But `Entry` usually stores hash of content?
-        // The user said: "get() must decompress based on SQLite metadata".
-        // If I request `get(H)`, and H is hash(original), and DB says "H is zstd",
-        // then `FsBlobStore` must have stored it under H?
-        // But `FsBlobStore::put` derives path from data.
-
-        // If `FsBlobStore` enforces "path = hash(content)", then storing compressed data under `hash(original)` violates that if checked.
-        // But `FsBlobStore` *implementation* currently does: `let digest = Sha256::digest(data); ... path_for(hash)`.
-        // So it stores under `hash(compressed)`.
-
-        // This means if we compress, the "blob hash" returned is the hash of the compressed bytes.
-        // The Manifest will contain `hash(compressed)`.
-        // When we read, we get `hash(compressed)`, we read bytes, we see in DB that `hash(compressed)` is zstd, we decompress.
-        // The decompressed data has `hash(original)`.
-        // This is fine! The "blob pointer" in manifest points to the physical blob.
-
-        let hash = self
-            .blob_store
-            .put(&stored_data, self.config.compression.clone())?;
-
-        // Update metadata
+        // Update metadata. Re-putting an existing blob refreshes last_written_at,
+        // which keeps it out of the sweep until the caller's snapshot references it.
         let conn = self.conn.lock().unwrap();
         conn.execute(
-            "INSERT OR IGNORE INTO blobs (hash, size_bytes, compression, storage, created_at) VALUES (?1, ?2, ?3, ?4, unixepoch())",
+            "INSERT INTO blobs (hash, size_bytes, compression, storage, created_at, last_written_at) VALUES (?1, ?2, ?3, ?4, unixepoch(), unixepoch())
+             ON CONFLICT(hash) DO UPDATE SET last_written_at = unixepoch()",
             params![
                 hash,
                 stored_data.len() as i64,
                 alg,
-                match self.config.blob_backend { BlobBackend::Fs => "fs", BlobBackend::Db => "db" }
+                backend.as_str()
             ]
         )?;
 
         Ok(hash)
     }
 
+    /// Compression for new content of `path`: the configured one, unless its
+    /// extension names an already-compressed format.
+    fn compression_for(&self, path: &str) -> Compression {
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext {
+            Some(ext) if self.uncompressed_extensions.contains(&ext) => Compression::None,
+            _ => self.config.compression,
+        }
+    }
+
+    /// Stores the content of the file at `path` and returns its manifest entry.
+    ///
+    /// Large files are split into content-defined chunks, each stored as a
+    /// blob, so unchanged regions are shared with other versions of the file.
+    pub fn put_content(&self, path: &str, data: &[u8]) -> Result<Entry> {
+        let compression = self.compression_for(path);
+        if !chunking::should_chunk(data.len()) {
+            return Ok(Entry {
+                path: path.to_string(),
+                blob: self.put_blob_with(data, compression)?,
+                size: data.len() as u64,
+                chunks: Vec::new(),
+                executable: false,
+                symlink: None,
+            });
+        }
+        let chunks = chunking::split(data)
+            .into_iter()
+            .map(|chunk| self.put_blob_with(chunk, compression))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Entry {
+            path: path.to_string(),
+            blob: content_hash(data),
+            size: data.len() as u64,
+            chunks,
+            executable: false,
+            symlink: None,
+        })
+    }
+
+    /// Blobs referenced by the stored entries of snapshot `id`, one per use.
+    fn snapshot_blob_refs(conn: &Connection, id: &str) -> Result<Vec<String>> {
+        let mut stmt = conn.prepare(
+            "SELECT blob_hash FROM manifest_entries me
+             WHERE snapshot_id = ?1
+               AND NOT EXISTS (SELECT 1 FROM entry_chunks ec
+                               WHERE ec.snapshot_id = me.snapshot_id AND ec.path = me.path)
+             UNION ALL
+             SELECT chunk_hash FROM entry_chunks WHERE snapshot_id = ?1",
+        )?;
+        let rows = stmt.query_map(params![id], |row| row.get(0))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
     // Snapshot Metadata & Manifest
     // Replaces the legacy put_snapshot with a full version
     #[allow(clippy::too_many_arguments)]
@@ -310,6 +948,91 @@ impl Store {
         derived_from: Option<&str>,
         applied_patch_hash: Option<&str>,
         label: Option<&str>,
+    ) -> Result<()> {
+        self.insert_snapshot(
+            id,
+            repo_root,
+            head_sha,
+            fingerprint_json,
+            manifest_bytes,
+            derived_from,
+            None,
+            applied_patch_hash,
+            label,
+            None,
+        )
+    }
+
+    /// Like [`Self::put_snapshot`], recording the `scope` its files were
+    /// captured with.
+    #[allow(clippy::too_many_arguments)]
+    pub fn put_scoped_snapshot(
+        &self,
+        id: &str,
+        repo_root: &str,
+        head_sha: &str,
+        fingerprint_json: &str,
+        manifest_bytes: &[u8],
+        derived_from: Option<&str>,
+        applied_patch_hash: Option<&str>,
+        label: Option<&str>,
+        scope: Option<Scope>,
+    ) -> Result<()> {
+        self.insert_snapshot(
+            id,
+            repo_root,
+            head_sha,
+            fingerprint_json,
+            manifest_bytes,
+            derived_from,
+            None,
+            applied_patch_hash,
+            label,
+            scope,
+        )
+    }
+
+    /// Like [`Self::put_snapshot`] for the merge of `merged_from` into
+    /// `derived_from`: both parents are written in the same transaction.
+    #[allow(clippy::too_many_arguments)]
+    pub fn put_merge_snapshot(
+        &self,
+        id: &str,
+        repo_root: &str,
+        head_sha: &str,
+        fingerprint_json: &str,
+        manifest_bytes: &[u8],
+        derived_from: &str,
+        merged_from: &str,
+        label: Option<&str>,
+    ) -> Result<()> {
+        self.insert_snapshot(
+            id,
+            repo_root,
+            head_sha,
+            fingerprint_json,
+            manifest_bytes,
+            Some(derived_from),
+            Some(merged_from),
+            None,
+            label,
+            None,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn insert_snapshot(
+        &self,
+        id: &str,
+        repo_root: &str,
+        head_sha: &str,
+        fingerprint_json: &str,
+        manifest_bytes: &[u8],
+        derived_from: Option<&str>,
+        merged_from: Option<&str>,
+        applied_patch_hash: Option<&str>,
+        label: Option<&str>,
+        scope: Option<Scope>,
     ) -> Result<()> {
         let manifest: Manifest = serde_json::from_slice(manifest_bytes)?;
         let manifest_hash = format!("sha256:{}", hex::encode(Sha256::digest(manifest_bytes)));
@@ -330,15 +1053,7 @@ impl Store {
 
         if exists.is_some() {
             // Decrement contents of old snapshot
-            let mut stmt =
-                tx.prepare("SELECT blob_hash FROM manifest_entries WHERE snapshot_id = ?1")?;
-            let blobs = stmt.query_map(params![id], |row| row.get::<_, String>(0))?;
-
-            // Gather to avoid borrow issues while executing updates
-            let blob_hashes: Vec<String> = blobs.collect::<Result<_, _>>()?;
-            drop(stmt);
-
-            for hash in blob_hashes {
+            for hash in Self::snapshot_blob_refs(&tx, id)? {
                 tx.execute(
                     "UPDATE blobs SET refcount = MAX(0, refcount - 1) WHERE hash = ?1",
                     params![hash],
@@ -346,9 +1061,27 @@ impl Store {
             }
         }
 
-        // 2. Insert/Replace snapshot
+        // 2. Insert snapshot. The same content stored again keeps its
+        // creation time and lineage (taken as a whole, from the first call
+        // that had one), and its label unless given a new one; a full scope
+        // is never narrowed.
         tx.execute(
-            "INSERT OR REPLACE INTO snapshots (snapshot_id, repo_root, head_sha, fingerprint_json, manifest_hash, manifest_bytes, created_at, derived_from, applied_patch_hash, label) VALUES (?1, ?2, ?3, ?4, ?5, ?6, unixepoch(), ?7, ?8, ?9)",
+            "INSERT INTO snapshots (snapshot_id, repo_root, head_sha, fingerprint_json, manifest_hash, manifest_bytes, created_at, derived_from, merged_from, applied_patch_hash, label, scope) VALUES (?1, ?2, ?3, ?4, ?5, ?6, unixepoch(), ?7, ?8, ?9, ?10, ?11)
+             ON CONFLICT(snapshot_id) DO UPDATE SET
+                repo_root = excluded.repo_root,
+                head_sha = excluded.head_sha,
+                fingerprint_json = excluded.fingerprint_json,
+                manifest_hash = excluded.manifest_hash,
+                manifest_bytes = excluded.manifest_bytes,
+                created_at = COALESCE(snapshots.created_at, excluded.created_at),
+                merged_from = CASE WHEN snapshots.derived_from IS NULL
+                    THEN excluded.merged_from ELSE snapshots.merged_from END,
+                applied_patch_hash = CASE WHEN snapshots.derived_from IS NULL
+                    THEN excluded.applied_patch_hash ELSE snapshots.applied_patch_hash END,
+                derived_from = COALESCE(snapshots.derived_from, excluded.derived_from),
+                label = COALESCE(excluded.label, snapshots.label),
+                scope = CASE WHEN snapshots.scope IN ('worktree', 'tracked')
+                    THEN snapshots.scope ELSE COALESCE(excluded.scope, snapshots.scope) END",
             params![
                 id,
                 repo_root,
@@ -357,8 +1090,10 @@ impl Store {
                 manifest_hash,
                 manifest_bytes,
                 derived_from,
+                merged_from,
                 applied_patch_hash,
-                label
+                label,
+                scope.map(|s| s.as_str())
             ]
         )?;
 
@@ -367,25 +1102,45 @@ impl Store {
             "DELETE FROM manifest_entries WHERE snapshot_id = ?1",
             params![id],
         )?;
+        tx.execute(
+            "DELETE FROM entry_chunks WHERE snapshot_id = ?1",
+            params![id],
+        )?;
 
         // 4. Insert new entries
-        let mut stmt = tx.prepare("INSERT INTO manifest_entries (snapshot_id, path, blob_hash, size_bytes) VALUES (?1, ?2, ?3, ?4)")?;
+        let mut stmt = tx.prepare("INSERT INTO manifest_entries (snapshot_id, path, blob_hash, size_bytes, executable, symlink) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+        let mut chunk_stmt = tx.prepare(
+            "INSERT INTO entry_chunks (snapshot_id, path, seq, chunk_hash) VALUES (?1, ?2, ?3, ?4)",
+        )?;
         for entry in &manifest.entries {
-            stmt.execute(params![id, entry.path, entry.blob, entry.size])?;
+            stmt.execute(params![
+                id,
+                entry.path,
+                entry.blob,
+                entry.size,
+                entry.executable,
+                entry.symlink
+            ])?;
+            for (seq, chunk) in entry.chunks.iter().enumerate() {
+                chunk_stmt.execute(params![id, entry.path, seq as i64, chunk])?;
+            }
 
             // Refcount increment
-            let row_count = tx.execute(
-                "UPDATE blobs SET refcount = refcount + 1 WHERE hash = ?1",
-                params![entry.blob],
-            )?;
-            if row_count == 0 {
-                // If blob is missing in DB (e.g. corruption or out of sync), we should probably fail?
-                // Or implicitly trust it exists in FS?
-                // Robustness: Fail to ensure we don't have dangling references in manifest.
-                return Err(anyhow!("Referenced blob not found in DB: {}", entry.blob));
+            for hash in entry.blob_refs() {
+                let row_count = tx.execute(
+                    "UPDATE blobs SET refcount = refcount + 1 WHERE hash = ?1",
+                    params![hash],
+                )?;
+                if row_count == 0 {
+                    // If blob is missing in DB (e.g. corruption or out of sync), we should probably fail?
+                    // Or implicitly trust it exists in FS?
+                    // Robustness: Fail to ensure we don't have dangling references in manifest.
+                    return Err(anyhow!("Referenced blob not found in DB: {}", hash));
+                }
             }
         }
         drop(stmt);
+        drop(chunk_stmt);
 
         tx.commit()?;
 
@@ -409,7 +1164,7 @@ impl Store {
 
     pub fn get_snapshot_info(&self, id: &str) -> Result<Option<SnapshotInfo>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT snapshot_id, repo_root, head_sha, fingerprint_json, manifest_hash, created_at, derived_from, applied_patch_hash, label FROM snapshots WHERE snapshot_id = ?1")?;
+        let mut stmt = conn.prepare("SELECT snapshot_id, repo_root, head_sha, fingerprint_json, manifest_hash, created_at, derived_from, applied_patch_hash, label, merged_from, scope FROM snapshots WHERE snapshot_id = ?1")?;
         let mut rows = stmt.query(params![id])?;
 
         if let Some(row) = rows.next()? {
@@ -423,21 +1178,419 @@ impl Store {
                 derived_from: row.get(6)?,
                 applied_patch_hash: row.get(7)?,
                 label: row.get(8)?,
+                merged_from: row.get(9)?,
+                scope: row
+                    .get::<_, Option<String>>(10)?
+                    .and_then(|s| Scope::parse(&s)),
             }))
         } else {
             Ok(None)
         }
     }
 
-    // List entries from DB (faster than parsing manifest JSON)
-    pub fn list_snapshot_entries(&self, id: &str) -> Result<Vec<Entry>> {
+    /// Snapshots of `repo_root`, newest first.
+    pub fn list_snapshots(&self, repo_root: &str) -> Result<Vec<SnapshotInfo>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT path, blob_hash, size_bytes FROM manifest_entries WHERE snapshot_id = ?1 ORDER BY path ASC")?;
+        let mut stmt = conn.prepare("SELECT snapshot_id, repo_root, head_sha, fingerprint_json, manifest_hash, created_at, derived_from, applied_patch_hash, label, merged_from, scope FROM snapshots WHERE repo_root = ?1 ORDER BY created_at DESC, rowid DESC")?;
+        let rows = stmt.query_map(params![repo_root], |row| {
+            Ok(SnapshotInfo {
+                snapshot_id: row.get(0)?,
+                repo_root: row.get(1)?,
+                head_sha: row.get(2)?,
+                fingerprint_json: row.get(3)?,
+                manifest_hash: row.get(4)?,
+                created_at: row.get(5)?,
+                derived_from: row.get(6)?,
+                applied_patch_hash: row.get(7)?,
+                label: row.get(8)?,
+                merged_from: row.get(9)?,
+                scope: row
+                    .get::<_, Option<String>>(10)?
+                    .and_then(|s| Scope::parse(&s)),
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Snapshots derived from `id` or merging it.
+    pub fn derived_snapshots(&self, id: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT snapshot_id FROM snapshots WHERE derived_from = ?1 OR merged_from = ?1
+             ORDER BY snapshot_id",
+        )?;
+        let rows = stmt.query_map(params![id], |row| row.get(0))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Points tag `name` of `repo_root` at `snapshot_id` and returns its
+    /// previous target. An existing tag pointing elsewhere is only moved with
+    /// `force`. Every change is appended to the ref log.
+    pub fn set_ref(
+        &self,
+        repo_root: &str,
+        name: &str,
+        snapshot_id: &str,
+        force: bool,
+    ) -> Result<Option<String>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let previous: Option<String> = tx
+            .query_row(
+                "SELECT snapshot_id FROM refs WHERE repo_root = ?1 AND name = ?2",
+                params![repo_root, name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if previous.as_deref() == Some(snapshot_id) {
+            return Ok(previous);
+        }
+        if let Some(current) = &previous
+            && !force
+        {
+            return Err(AxiomRegentError::InvalidArgument(format!(
+                "Tag '{}' already points at {}; pass force to move it",
+                name, current
+            ))
+            .with_snapshot(current)
+            .into());
+        }
+
+        tx.execute(
+            "INSERT OR REPLACE INTO refs (repo_root, name, snapshot_id, updated_at) VALUES (?1, ?2, ?3, unixepoch())",
+            params![repo_root, name, snapshot_id],
+        )?;
+        tx.execute(
+            "INSERT INTO ref_log (repo_root, name, old_snapshot_id, new_snapshot_id, at) VALUES (?1, ?2, ?3, ?4, unixepoch())",
+            params![repo_root, name, previous, snapshot_id],
+        )?;
+        tx.commit()?;
+        Ok(previous)
+    }
+
+    /// Removes tag `name` of `repo_root`, returning the snapshot it pointed at.
+    pub fn delete_ref(&self, repo_root: &str, name: &str) -> Result<Option<String>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let previous: Option<String> = tx
+            .query_row(
+                "SELECT snapshot_id FROM refs WHERE repo_root = ?1 AND name = ?2",
+                params![repo_root, name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if previous.is_some() {
+            tx.execute(
+                "DELETE FROM refs WHERE repo_root = ?1 AND name = ?2",
+                params![repo_root, name],
+            )?;
+            tx.execute(
+                "INSERT INTO ref_log (repo_root, name, old_snapshot_id, new_snapshot_id, at) VALUES (?1, ?2, ?3, NULL, unixepoch())",
+                params![repo_root, name, previous],
+            )?;
+        }
+        tx.commit()?;
+        Ok(previous)
+    }
+
+    /// Snapshot tag `name` of `repo_root` points at.
+    pub fn get_ref(&self, repo_root: &str, name: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT snapshot_id FROM refs WHERE repo_root = ?1 AND name = ?2",
+                params![repo_root, name],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Tags of `repo_root`, by name.
+    pub fn list_refs(&self, repo_root: &str) -> Result<Vec<RefInfo>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name, snapshot_id, updated_at FROM refs WHERE repo_root = ?1 ORDER BY name",
+        )?;
+        let rows = stmt.query_map(params![repo_root], |row| {
+            Ok(RefInfo {
+                name: row.get(0)?,
+                snapshot_id: row.get(1)?,
+                updated_at: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Changes of the tags of `repo_root` (only of `name` if given), oldest first.
+    pub fn ref_log(&self, repo_root: &str, name: Option<&str>) -> Result<Vec<RefLogEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name, old_snapshot_id, new_snapshot_id, at FROM ref_log
+             WHERE repo_root = ?1 AND (?2 IS NULL OR name = ?2) ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![repo_root, name], |row| {
+            Ok(RefLogEntry {
+                name: row.get(0)?,
+                from: row.get(1)?,
+                to: row.get(2)?,
+                at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Records a newly issued lease. An unset `issued_at` is now.
+    pub fn put_lease(&self, lease: &StoredLease) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO leases
+                 (lease_id, repo_root, fingerprint_json, touched_json, issued_at, holder, purpose, ttl_secs, expires_at, reserved_json)
+             VALUES (?1, ?2, ?3, ?4, COALESCE(?5, unixepoch()), ?6, ?7, ?8, ?9, ?10)",
+            params![
+                lease.lease_id,
+                lease.repo_root,
+                lease.fingerprint_json,
+                serde_json::to_string(&lease.touched)?,
+                lease.issued_at,
+                lease.holder,
+                lease.purpose,
+                lease.ttl_secs.map(|t| t as i64),
+                lease.expires_at,
+                serde_json::to_string(&lease.reserved)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces the touched files recorded for lease `lease_id`.
+    pub fn set_lease_touched(&self, lease_id: &str, touched: &[String]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE leases SET touched_json = ?2 WHERE lease_id = ?1",
+            params![lease_id, serde_json::to_string(touched)?],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces the path patterns reserved by lease `lease_id`.
+    pub fn set_lease_reserved(&self, lease_id: &str, reserved: &[String]) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE leases SET reserved_json = ?2 WHERE lease_id = ?1",
+            params![lease_id, serde_json::to_string(reserved)?],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces the fingerprint lease `lease_id` is valid for.
+    pub fn set_lease_fingerprint(&self, lease_id: &str, fingerprint_json: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE leases SET fingerprint_json = ?2 WHERE lease_id = ?1",
+            params![lease_id, fingerprint_json],
+        )?;
+        Ok(())
+    }
+
+    /// Records a renewal of lease `lease_id`.
+    pub fn set_lease_expiry(&self, lease_id: &str, ttl_secs: u64, expires_at: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE leases SET ttl_secs = ?2, expires_at = ?3 WHERE lease_id = ?1",
+            params![lease_id, ttl_secs as i64, expires_at],
+        )?;
+        Ok(())
+    }
+
+    /// Forgets lease `lease_id`. Returns false if it was not persisted.
+    pub fn delete_lease(&self, lease_id: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM leases WHERE lease_id = ?1", params![lease_id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Every persisted lease, oldest first.
+    pub fn list_leases(&self) -> Result<Vec<StoredLease>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT lease_id, repo_root, fingerprint_json, touched_json, issued_at,
+                    holder, purpose, ttl_secs, expires_at, reserved_json
+             FROM leases ORDER BY issued_at, rowid",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                StoredLease {
+                    lease_id: row.get(0)?,
+                    repo_root: row.get(1)?,
+                    fingerprint_json: row.get(2)?,
+                    touched: Vec::new(),
+                    issued_at: row.get(4)?,
+                    holder: row.get(5)?,
+                    purpose: row.get(6)?,
+                    ttl_secs: row.get::<_, Option<i64>>(7)?.map(|t| t as u64),
+                    expires_at: row.get(8)?,
+                    reserved: Vec::new(),
+                },
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(9)?,
+            ))
+        })?;
+        let mut leases = Vec::new();
+        for row in rows {
+            let (mut lease, touched_json, reserved_json) = row?;
+            lease.touched = serde_json::from_str(&touched_json)?;
+            if let Some(reserved_json) = reserved_json {
+                lease.reserved = serde_json::from_str(&reserved_json)?;
+            }
+            leases.push(lease);
+        }
+        Ok(leases)
+    }
+
+    /// Snapshots of `repo_root` that `policy` does not keep, newest first.
+    ///
+    /// Tagged snapshots are always kept, and so are the ancestors
+    /// (`derived_from` and `merged_from`) of kept snapshots.
+    pub fn plan_retention(&self, repo_root: &str, policy: &RetentionPolicy) -> Result<Vec<String>> {
+        let snapshots = self.list_snapshots(repo_root)?;
+        let tagged: HashSet<String> = self
+            .list_refs(repo_root)?
+            .into_iter()
+            .map(|r| r.snapshot_id)
+            .collect();
+        let mut keep: HashSet<String> = HashSet::new();
+        for (i, info) in snapshots.iter().enumerate() {
+            if policy.keep_last.is_none_or(|n| i < n)
+                || (policy.keep_labeled && info.label.is_some())
+                || policy.pinned.contains(&info.snapshot_id)
+                || tagged.contains(&info.snapshot_id)
+            {
+                keep.insert(info.snapshot_id.clone());
+            }
+        }
+
+        // Both parents of a merge are kept.
+        let parents: std::collections::HashMap<&str, Vec<&str>> = snapshots
+            .iter()
+            .map(|s| {
+                let parents = [s.derived_from.as_deref(), s.merged_from.as_deref()];
+                (
+                    s.snapshot_id.as_str(),
+                    parents.into_iter().flatten().collect(),
+                )
+            })
+            .collect();
+        let mut chains: Vec<String> = keep.iter().cloned().collect();
+        while let Some(id) = chains.pop() {
+            for parent in parents.get(id.as_str()).into_iter().flatten() {
+                if keep.insert(parent.to_string()) {
+                    chains.push(parent.to_string());
+                }
+            }
+        }
+
+        Ok(snapshots
+            .into_iter()
+            .map(|s| s.snapshot_id)
+            .filter(|id| !keep.contains(id))
+            .collect())
+    }
+
+    /// Deletes a snapshot and releases its blob references. Returns false if
+    /// it did not exist. Blobs are reclaimed by [`Store::sweep_blobs`].
+    pub fn delete_snapshot(&self, id: &str) -> Result<bool> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for hash in Self::snapshot_blob_refs(&tx, id)? {
+            tx.execute(
+                "UPDATE blobs SET refcount = MAX(0, refcount - 1) WHERE hash = ?1",
+                params![hash],
+            )?;
+        }
+        tx.execute(
+            "DELETE FROM manifest_entries WHERE snapshot_id = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "DELETE FROM entry_chunks WHERE snapshot_id = ?1",
+            params![id],
+        )?;
+        let deleted = tx.execute("DELETE FROM snapshots WHERE snapshot_id = ?1", params![id])?;
+
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Removes unreferenced blobs last written more than `grace` ago.
+    ///
+    /// Rows are deleted in one transaction before their files, so a crash
+    /// leaves at most orphaned files; those are swept here as well. The grace
+    /// period protects blobs put for a snapshot that has not been stored yet.
+    pub fn sweep_blobs(&self, grace: Duration) -> Result<SweepReport> {
+        let _gc = self.gc_lock.write().unwrap();
+        let mut report = SweepReport::default();
+
+        let swept: Vec<(String, String)> = {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            let hashes: Vec<(String, String)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT hash, storage FROM blobs
+                     WHERE refcount <= 0
+                       AND COALESCE(last_written_at, created_at, 0) <= unixepoch() - ?1
+                       AND hash NOT IN (SELECT blob_hash FROM manifest_entries)
+                       AND hash NOT IN (SELECT chunk_hash FROM entry_chunks)
+                     ORDER BY hash",
+                )?;
+                let rows = stmt.query_map(params![grace.as_secs() as i64], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?;
+                rows.collect::<Result<_, _>>()?
+            };
+            for (hash, _) in &hashes {
+                tx.execute("DELETE FROM blobs WHERE hash = ?1", params![hash])?;
+            }
+            tx.commit()?;
+            hashes
+        };
+        for (hash, storage) in &swept {
+            let backend = BlobBackend::parse(storage).unwrap_or(*self.backend.read().unwrap());
+            report.bytes_reclaimed += self.blob_store(backend).delete(hash)?;
+            report.blobs_removed += 1;
+        }
+
+        // Content without a row pointing at it: left behind by a crash before
+        // its row was written (put_blob), after it was deleted (above), or
+        // after it was copied to another backend (migrate_blobs).
+        let cutoff = SystemTime::now()
+            .checked_sub(grace)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        for backend in [BlobBackend::Fs, BlobBackend::Db] {
+            let store = self.blob_store(backend);
+            for hash in store.list_written_before(cutoff)? {
+                if self.stored_in(&hash)? != Some(backend) {
+                    report.bytes_reclaimed += store.delete(&hash)?;
+                    report.blobs_removed += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // List entries from DB (faster than parsing manifest JSON)
+    pub fn list_snapshot_entries(&self, id: &str) -> Result<Vec<Entry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT path, blob_hash, size_bytes, executable, symlink FROM manifest_entries WHERE snapshot_id = ?1 ORDER BY path ASC")?;
         let rows = stmt.query_map(params![id], |row| {
             Ok(Entry {
                 path: row.get(0)?,
                 blob: row.get(1)?,
                 size: row.get(2)?,
+                chunks: Vec::new(),
+                executable: row.get(3)?,
+                symlink: row.get(4)?,
             })
         })?;
 
@@ -445,6 +1598,26 @@ impl Store {
         for r in rows {
             entries.push(r?);
         }
+        drop(stmt);
+
+        let mut chunks: std::collections::HashMap<String, Vec<String>> =
+            std::collections::HashMap::new();
+        let mut stmt = conn.prepare(
+            "SELECT path, chunk_hash FROM entry_chunks WHERE snapshot_id = ?1 ORDER BY path, seq",
+        )?;
+        let rows = stmt.query_map(params![id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for r in rows {
+            let (path, chunk) = r?;
+            chunks.entry(path).or_default().push(chunk);
+        }
+        for entry in &mut entries {
+            entry.chunks = chunks.remove(&entry.path).unwrap_or_default();
+        }
+        // get_snapshot below takes the connection lock again.
+        drop(stmt);
+        drop(conn);
 
         // If empty, maybe we have the snapshot but not entries? (e.g. legacy or not populated)
         // Fallback to parsing manifest_bytes?
@@ -458,9 +1631,217 @@ impl Store {
         Ok(entries)
     }
 
+    /// Moves the content of every blob to `target`, which also receives new
+    /// blobs from now on.
+    ///
+    /// Each blob is copied, its row repointed, then the source copy deleted; a
+    /// crash in between leaves a stray copy that the next sweep removes.
+    pub fn migrate_blobs(&self, target: BlobBackend) -> Result<MigrateReport> {
+        let _gc = self.gc_lock.write().unwrap();
+        *self.backend.write().unwrap() = target;
+        let mut report = MigrateReport::default();
+
+        let pending: Vec<(String, String)> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt =
+                conn.prepare("SELECT hash, storage FROM blobs WHERE storage != ?1 ORDER BY hash")?;
+            let rows = stmt.query_map(params![target.as_str()], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?;
+            rows.collect::<Result<_, _>>()?
+        };
+        for (hash, storage) in pending {
+            let Some(source) = BlobBackend::parse(&storage) else {
+                report.missing.push(hash);
+                continue;
+            };
+            let Some(data) = self.blob_store(source).get(&hash)? else {
+                report.missing.push(hash);
+                continue;
+            };
+            // The target may hold a leftover copy in another encoding.
+            self.blob_store(target).replace(&hash, &data)?;
+            self.conn.lock().unwrap().execute(
+                "UPDATE blobs SET storage = ?1 WHERE hash = ?2",
+                params![target.as_str(), hash],
+            )?;
+            self.blob_store(source).delete(&hash)?;
+            report.blobs_moved += 1;
+            report.bytes_moved += data.len() as u64;
+        }
+        Ok(report)
+    }
+
+    /// Entry stored for `path` of `repo_root` when the file had `stat`, if all
+    /// its blobs are still stored.
+    ///
+    /// Their `last_written_at` is refreshed as by `put_blob`, so the sweep
+    /// spares them until the caller's snapshot references them.
+    pub fn cached_entry(
+        &self,
+        repo_root: &str,
+        path: &str,
+        stat: &FileStat,
+    ) -> Result<Option<Entry>> {
+        let _gc = self.gc_lock.read().unwrap();
+        let conn = self.conn.lock().unwrap();
+        let json: Option<String> = conn
+            .query_row(
+                "SELECT entry_json FROM stat_cache
+                 WHERE repo_root = ?1 AND path = ?2 AND size_bytes = ?3 AND mtime_ns = ?4
+                   AND inode = ?5 AND executable = ?6",
+                params![
+                    repo_root,
+                    path,
+                    stat.size as i64,
+                    stat.mtime_ns,
+                    stat.inode as i64,
+                    stat.executable
+                ],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(json) = json else {
+            return Ok(None);
+        };
+        let entry: Entry = serde_json::from_str(&json)?;
+        for hash in entry.blob_refs() {
+            let found = conn.execute(
+                "UPDATE blobs SET last_written_at = unixepoch() WHERE hash = ?1",
+                params![hash],
+            )?;
+            if found == 0 {
+                return Ok(None);
+            }
+        }
+        Ok(Some(entry))
+    }
+
+    /// Records `entry` as the content of `path` of `repo_root` while it has `stat`.
+    pub fn cache_entry(
+        &self,
+        repo_root: &str,
+        path: &str,
+        stat: &FileStat,
+        entry: &Entry,
+    ) -> Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO stat_cache (repo_root, path, size_bytes, mtime_ns, inode, executable, entry_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                repo_root,
+                path,
+                stat.size as i64,
+                stat.mtime_ns,
+                stat.inode as i64,
+                stat.executable,
+                serde_json::to_string(entry)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Drops the stat cache rows of `repo_root` for paths outside `paths`.
+    pub fn prune_stat_cache(&self, repo_root: &str, paths: &HashSet<String>) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let cached: Vec<String> = {
+            let mut stmt = tx.prepare("SELECT path FROM stat_cache WHERE repo_root = ?1")?;
+            let rows = stmt.query_map(params![repo_root], |row| row.get(0))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        let mut pruned = 0;
+        for path in cached.iter().filter(|p| !paths.contains(*p)) {
+            pruned += tx.execute(
+                "DELETE FROM stat_cache WHERE repo_root = ?1 AND path = ?2",
+                params![repo_root, path],
+            )?;
+        }
+        tx.commit()?;
+        Ok(pruned)
+    }
+
+    /// Sizes of the store, before and after deduplication.
+    pub fn stats(&self) -> Result<StoreStats> {
+        let conn = self.conn.lock().unwrap();
+        let count = |sql: &str| -> Result<u64> {
+            Ok(conn.query_row(sql, [], |row| row.get::<_, i64>(0))? as u64)
+        };
+        let mut stats = StoreStats {
+            snapshots: count("SELECT COUNT(*) FROM snapshots")?,
+            files: count("SELECT COUNT(*) FROM manifest_entries")?,
+            chunked_files: count(
+                "SELECT COUNT(*) FROM (SELECT DISTINCT snapshot_id, path FROM entry_chunks)",
+            )?,
+            blobs: count("SELECT COUNT(*) FROM blobs")?,
+            logical_bytes: count("SELECT COALESCE(SUM(size_bytes), 0) FROM manifest_entries")?,
+            stored_bytes: count("SELECT COALESCE(SUM(size_bytes), 0) FROM blobs")?,
+            dedup_ratio: 1.0,
+        };
+        if stats.stored_bytes > 0 {
+            stats.dedup_ratio = stats.logical_bytes as f64 / stats.stored_bytes as f64;
+        }
+        Ok(stats)
+    }
+
+    /// Content of a manifest entry, reassembled from its chunks if it has any.
+    pub fn read_entry(&self, entry: &Entry) -> Result<Option<Vec<u8>>> {
+        let _gc = self.gc_lock.read().unwrap();
+        if entry.chunks.is_empty() {
+            self.read_blob(&entry.blob)
+        } else {
+            self.assemble(&entry.chunks)
+        }
+    }
+
+    fn assemble(&self, chunks: &[String]) -> Result<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+        for chunk in chunks {
+            match self.read_blob(chunk)? {
+                Some(bytes) => data.extend_from_slice(&bytes),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(data))
+    }
+
+    /// Chunks of the content hashed `hash`, from any stored entry chunked that way.
+    fn chunk_list(&self, hash: &str) -> Result<Option<Vec<String>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT ec.chunk_hash FROM entry_chunks ec
+             JOIN (SELECT me.snapshot_id, me.path FROM manifest_entries me
+                   WHERE me.blob_hash = ?1
+                     AND EXISTS (SELECT 1 FROM entry_chunks x
+                                 WHERE x.snapshot_id = me.snapshot_id AND x.path = me.path)
+                   LIMIT 1) e
+               ON ec.snapshot_id = e.snapshot_id AND ec.path = e.path
+             ORDER BY ec.seq",
+        )?;
+        let rows = stmt.query_map(params![hash], |row| row.get(0))?;
+        let chunks: Vec<String> = rows.collect::<Result<_, _>>()?;
+        Ok((!chunks.is_empty()).then_some(chunks))
+    }
+
     pub fn get_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
-        // Read bytes from backend
-        let maybe_bytes = self.blob_store.get(hash)?;
+        let _gc = self.gc_lock.read().unwrap();
+        self.read_blob(hash)
+    }
+
+    /// `get_blob` for a caller already holding `gc_lock`.
+    fn read_blob(&self, hash: &str) -> Result<Option<Vec<u8>>> {
+        // Read bytes from the backend recorded for the blob
+        let backend = match self.stored_in(hash)? {
+            Some(backend) => backend,
+            None => {
+                // Chunked content has no blob row of its own.
+                if let Some(chunks) = self.chunk_list(hash)? {
+                    return self.assemble(&chunks);
+                }
+                *self.backend.read().unwrap()
+            }
+        };
+        let maybe_bytes = self.blob_store(backend).get(hash)?;
         if let Some(mut bytes) = maybe_bytes {
             // Check compression in DB
             let conn = self.conn.lock().unwrap();
@@ -485,18 +1866,30 @@ impl Store {
 
     pub fn validate_path(path: &str) -> Result<()> {
         if path.starts_with('/') {
-            return Err(anyhow!("Absolute paths not allowed: {}", path));
+            return Err(AxiomRegentError::InvalidArgument(format!(
+                "Absolute paths not allowed: {}",
+                path
+            ))
+            .with_path(path)
+            .into());
         }
         if path.contains('\\') {
-            return Err(anyhow!("Backslashes not allowed: {}", path));
+            return Err(AxiomRegentError::InvalidArgument(format!(
+                "Backslashes not allowed: {}",
+                path
+            ))
+            .with_path(path)
+            .into());
         }
         // Check for .. segments
         for component in std::path::Path::new(path).components() {
             if matches!(component, std::path::Component::ParentDir) {
-                return Err(anyhow!(
+                return Err(AxiomRegentError::InvalidArgument(format!(
                     "Parent directory segments (..) not allowed: {}",
                     path
-                ));
+                ))
+                .with_path(path)
+                .into());
             }
         }
         Ok(())
@@ -516,7 +1909,11 @@ impl Store {
             .unwrap_or(false);
 
         if !exists {
-            return Err(anyhow!("Snapshot not found: {}", id));
+            return Err(
+                AxiomRegentError::NotFound(format!("Snapshot not found: {}", id))
+                    .with_snapshot(id)
+                    .into(),
+            );
         }
 
         // 2. Load manifest entries
@@ -546,32 +1943,42 @@ impl Store {
         // 4. Verify blobs exist
         // We can do a batched check or one by one.
         // For now, one by one check "has".
-        for entry in &entries {
+        for (entry, blob) in entries
+            .iter()
+            .flat_map(|e| e.blob_refs().iter().map(move |b| (e, b)))
+        {
             // Check DB
             let conn = self.conn.lock().unwrap();
             let blob_exists: bool = conn
-                .query_row(
-                    "SELECT 1 FROM blobs WHERE hash = ?1",
-                    params![entry.blob],
-                    |_| Ok(true),
-                )
+                .query_row("SELECT 1 FROM blobs WHERE hash = ?1", params![blob], |_| {
+                    Ok(true)
+                })
                 .optional()?
                 .unwrap_or(false);
             drop(conn);
 
             if !blob_exists {
-                return Err(anyhow!(
+                return Err(AxiomRegentError::Internal(format!(
                     "Snapshot corrupt: missing blob DB entry for {}",
-                    entry.blob
-                ));
+                    blob
+                ))
+                .with_snapshot(id)
+                .with_path(&entry.path)
+                .into());
             }
 
             // Check backend
-            if !self.blob_store.has(&entry.blob)? {
-                return Err(anyhow!(
+            let backend = self
+                .stored_in(blob)?
+                .unwrap_or(*self.backend.read().unwrap());
+            if !self.blob_store(backend).has(blob)? {
+                return Err(AxiomRegentError::Internal(format!(
                     "Snapshot corrupt: missing blob content for {}",
-                    entry.blob
-                ));
+                    blob
+                ))
+                .with_snapshot(id)
+                .with_path(&entry.path)
+                .into());
             }
         }
 
@@ -656,4 +2063,645 @@ mod tests {
         // Should fail
         assert!(store.validate_snapshot(sid).is_err());
     }
+
+    fn test_store(dir: &std::path::Path) -> Store {
+        test_store_with(dir, BlobBackend::Fs, Compression::None)
+    }
+
+    fn test_store_with(
+        dir: &std::path::Path,
+        blob_backend: BlobBackend,
+        compression: Compression,
+    ) -> Store {
+        Store::new(StorageConfig {
+            data_dir: dir.to_path_buf(),
+            blob_backend,
+            compression,
+        })
+        .unwrap()
+    }
+
+    /// Behaviour every `BlobStore` must share.
+    fn check_blob_store(blobs: &dyn BlobStore) {
+        let hash = content_hash(b"hello");
+        blobs.put(&hash, b"hello").unwrap();
+        blobs.put(&hash, b"hello").unwrap();
+        assert!(blobs.has(&hash).unwrap());
+        assert_eq!(blobs.get(&hash).unwrap().unwrap(), b"hello");
+        blobs.put(&hash, b"stale").unwrap();
+        assert_eq!(blobs.get(&hash).unwrap().unwrap(), b"hello");
+        blobs.replace(&hash, b"other").unwrap();
+        assert_eq!(blobs.get(&hash).unwrap().unwrap(), b"other");
+        blobs.replace(&hash, b"hello").unwrap();
+
+        let missing = format!("sha256:{}", "0".repeat(64));
+        assert!(!blobs.has(&missing).unwrap());
+        assert!(blobs.get(&missing).unwrap().is_none());
+        assert_eq!(blobs.delete(&missing).unwrap(), 0);
+
+        let empty = content_hash(b"");
+        blobs.put(&empty, b"").unwrap();
+        assert_eq!(blobs.get(&empty).unwrap().unwrap(), b"");
+
+        // Spans several SQLite chunks.
+        let large: Vec<u8> = (0..SqliteBlobStore::CHUNK_SIZE * 5 / 2)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let large_hash = content_hash(&large);
+        blobs.put(&large_hash, &large).unwrap();
+        assert_eq!(blobs.get(&large_hash).unwrap().unwrap(), large);
+
+        let now = SystemTime::now() + Duration::from_secs(1);
+        let listed = blobs.list_written_before(now).unwrap();
+        for h in [&hash, &empty, &large_hash] {
+            assert!(listed.contains(h), "{} not listed", h);
+        }
+        assert!(
+            blobs
+                .list_written_before(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .is_empty()
+        );
+
+        assert_eq!(blobs.delete(&large_hash).unwrap(), large.len() as u64);
+        assert!(!blobs.has(&large_hash).unwrap());
+        assert!(blobs.get(&large_hash).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_fs_blob_store() {
+        let dir = tempfile::tempdir().unwrap();
+        check_blob_store(&FsBlobStore::new(dir.path().to_path_buf()).unwrap());
+    }
+
+    #[test]
+    fn test_sqlite_blob_store() {
+        let conn = Arc::new(Mutex::new(Connection::open_in_memory().unwrap()));
+        check_blob_store(&SqliteBlobStore::new(conn).unwrap());
+    }
+
+    #[test]
+    fn test_db_backend_compresses_and_migrates() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = test_store_with(dir.path(), BlobBackend::Db, Compression::Zstd);
+        let content = "compressible ".repeat(1000);
+        put(&store, "s1", &content, None, None);
+        let blob = store.list_snapshot_entries("s1").unwrap()[0].blob.clone();
+
+        // Stored inline and compressed; no blob files.
+        assert!(store.blob_store(BlobBackend::Db).has(&blob).unwrap());
+        assert!(
+            FsBlobStore::new(dir.path().join("blobs"))
+                .unwrap()
+                .list_written_before(SystemTime::now() + Duration::from_secs(1))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            store
+                .blob_store(BlobBackend::Db)
+                .get(&blob)
+                .unwrap()
+                .unwrap()
+                .len()
+                < content.len()
+        );
+        assert_eq!(store.get_blob(&blob).unwrap().unwrap(), content.as_bytes());
+        store.validate_snapshot("s1").unwrap();
+
+        let report = store.migrate_blobs(BlobBackend::Fs).unwrap();
+        assert_eq!(report.blobs_moved, 1);
+        assert!(report.missing.is_empty());
+        assert!(!store.blob_store(BlobBackend::Db).has(&blob).unwrap());
+        assert!(store.blob_store(BlobBackend::Fs).has(&blob).unwrap());
+        assert_eq!(store.get_blob(&blob).unwrap().unwrap(), content.as_bytes());
+        store.validate_snapshot("s1").unwrap();
+
+        // New blobs follow the migration.
+        put(&store, "s2", "after", None, None);
+        let after = store.list_snapshot_entries("s2").unwrap()[0].blob.clone();
+        assert!(store.blob_store(BlobBackend::Fs).has(&after).unwrap());
+        assert_eq!(store.migrate_blobs(BlobBackend::Fs).unwrap().blobs_moved, 0);
+    }
+
+    fn blob_compression(store: &Store, hash: &str) -> String {
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT compression FROM blobs WHERE hash = ?1",
+                params![hash],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_put_blob_replaces_bytes_left_without_a_row() {
+        for backend in [BlobBackend::Fs, BlobBackend::Db] {
+            let dir = tempfile::tempdir().unwrap();
+            let store = test_store_with(dir.path(), backend, Compression::Zstd);
+            let content = "left behind ".repeat(100);
+            let hash = store
+                .put_blob_with(content.as_bytes(), Compression::None)
+                .unwrap();
+            // A sweep that deleted the row but failed before the content.
+            store
+                .conn
+                .lock()
+                .unwrap()
+                .execute("DELETE FROM blobs WHERE hash = ?1", params![hash])
+                .unwrap();
+
+            assert_eq!(store.put_blob(content.as_bytes()).unwrap(), hash);
+            assert_eq!(blob_compression(&store, &hash), "zstd");
+            assert_eq!(store.get_blob(&hash).unwrap().unwrap(), content.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_blob_ids_hash_raw_content() {
+        let plain_dir = tempfile::tempdir().unwrap();
+        let zipped_dir = tempfile::tempdir().unwrap();
+        let plain = test_store_with(plain_dir.path(), BlobBackend::Fs, Compression::None);
+        let zipped = test_store_with(zipped_dir.path(), BlobBackend::Fs, Compression::Zstd);
+        let content = "fn main() {}\n".repeat(100);
+
+        let a = plain
+            .put_content("src/main.rs", content.as_bytes())
+            .unwrap();
+        let b = zipped
+            .put_content("src/main.rs", content.as_bytes())
+            .unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.blob, content_hash(content.as_bytes()));
+        assert_eq!(blob_compression(&plain, &a.blob), "none");
+        assert_eq!(blob_compression(&zipped, &b.blob), "zstd");
+        assert_eq!(
+            zipped.get_blob(&b.blob).unwrap().unwrap(),
+            content.as_bytes()
+        );
+
+        // Already-compressed formats are stored as they are.
+        let png = zipped
+            .put_content("assets/Logo.PNG", content.as_bytes())
+            .unwrap();
+        assert_eq!(png.blob, b.blob);
+        let other = zipped.put_content("assets/logo.png", b"\x89PNG").unwrap();
+        assert_eq!(blob_compression(&zipped, &other.blob), "none");
+        let custom = zipped.with_uncompressed_extensions(vec!["RS".to_string()]);
+        let rs = custom.put_content("src/lib.rs", b"pub fn f() {}").unwrap();
+        assert_eq!(blob_compression(&custom, &rs.blob), "none");
+    }
+
+    #[test]
+    fn test_upgrade_rekeys_legacy_compressed_blobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = "legacy ".repeat(500);
+        let repo_root = dir.path().canonicalize().unwrap();
+        let repo_root = repo_root.to_str().unwrap();
+        let (legacy, old_id) = {
+            // Written the old way: keyed by the hash of the compressed bytes.
+            let store = test_store_with(dir.path(), BlobBackend::Fs, Compression::Zstd);
+            let compressed = zstd::stream::encode_all(content.as_bytes(), 3).unwrap();
+            let legacy = content_hash(&compressed);
+            store
+                .blob_store(BlobBackend::Fs)
+                .put(&legacy, &compressed)
+                .unwrap();
+            let conn = store.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO blobs (hash, size_bytes, compression, storage, created_at, last_written_at)
+                 VALUES (?1, ?2, 'zstd', 'fs', 0, 0)",
+                params![legacy, compressed.len() as i64],
+            )
+            .unwrap();
+            conn.execute_batch("PRAGMA user_version = 0").unwrap();
+            drop(conn);
+            let manifest = Manifest::new(vec![Entry {
+                path: "f.txt".to_string(),
+                blob: legacy.clone(),
+                size: content.len() as u64,
+                chunks: Vec::new(),
+                executable: false,
+                symlink: None,
+            }]);
+            let old_id = manifest.compute_snapshot_id("{}").unwrap();
+            store
+                .put_snapshot(
+                    &old_id,
+                    repo_root,
+                    "head",
+                    "{}",
+                    manifest.to_canonical_json().unwrap().as_bytes(),
+                    None,
+                    None,
+                    None,
+                )
+                .unwrap();
+            put(&store, "child", "x", Some(&old_id), None);
+            store.set_ref(repo_root, "release", &old_id, false).unwrap();
+            (legacy, old_id)
+        };
+
+        let store = test_store_with(dir.path(), BlobBackend::Fs, Compression::Zstd);
+        let raw = content_hash(content.as_bytes());
+
+        // The snapshot moved to the ID of its rewritten manifest, and what
+        // named it follows.
+        assert!(store.get_snapshot_info(&old_id).unwrap().is_none());
+        let new_id = store.renamed_snapshot(&old_id).unwrap().unwrap();
+        let manifest: Manifest =
+            serde_json::from_slice(&store.get_snapshot(&new_id).unwrap().unwrap()).unwrap();
+        assert_eq!(new_id, manifest.compute_snapshot_id("{}").unwrap());
+        let resolved = crate::snapshot::refs::resolve(&store, Path::new(repo_root), &old_id);
+        assert_eq!(resolved.unwrap(), new_id);
+        let entries = store.list_snapshot_entries(&new_id).unwrap();
+        assert_eq!(entries[0].blob, raw);
+        assert_eq!(manifest.entries, entries);
+        let child = store.get_snapshot_info("child").unwrap().unwrap();
+        assert_eq!(child.derived_from.as_deref(), Some(new_id.as_str()));
+        assert_eq!(
+            store.get_ref(repo_root, "release").unwrap(),
+            Some(new_id.clone())
+        );
+        assert_eq!(store.get_blob(&raw).unwrap().unwrap(), content.as_bytes());
+        store.validate_snapshot(&new_id).unwrap();
+
+        // The old copy has no row left; the new one is referenced.
+        let report = store.sweep_blobs(Duration::ZERO).unwrap();
+        assert_eq!(report.blobs_removed, 1);
+        assert!(!store.blob_store(BlobBackend::Fs).has(&legacy).unwrap());
+        store.validate_snapshot(&new_id).unwrap();
+
+        // Runs once.
+        assert_eq!(store.upgrade_blob_ids().unwrap(), 0);
+    }
+
+    fn put(
+        store: &Store,
+        id: &str,
+        content: &str,
+        derived_from: Option<&str>,
+        label: Option<&str>,
+    ) {
+        let blob = store.put_blob(content.as_bytes()).unwrap();
+        let manifest = Manifest::new(vec![Entry {
+            path: "f.txt".to_string(),
+            blob,
+            size: content.len() as u64,
+            chunks: Vec::new(),
+            executable: false,
+            symlink: None,
+        }]);
+        store
+            .put_snapshot(
+                id,
+                "/repo",
+                "head",
+                "{}",
+                manifest.to_canonical_json().unwrap().as_bytes(),
+                derived_from,
+                None,
+                label,
+            )
+            .unwrap();
+    }
+
+    fn lockfile(lines: usize, changed: usize) -> Vec<u8> {
+        (0..lines)
+            .map(|i| {
+                if i == changed {
+                    "changed line\n".to_string()
+                } else {
+                    let checksum = (i as u32).wrapping_mul(2654435761);
+                    format!("package-{:06} = \"{:08x}\"\n", i, checksum)
+                }
+            })
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    fn put_large(store: &Store, id: &str, content: &[u8]) -> Entry {
+        let entry = store.put_content("Cargo.lock", content).unwrap();
+        let manifest = Manifest::new(vec![entry.clone()]);
+        store
+            .put_snapshot(
+                id,
+                "/repo",
+                "head",
+                "{}",
+                manifest.to_canonical_json().unwrap().as_bytes(),
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        entry
+    }
+
+    #[test]
+    fn test_chunked_content_is_shared_between_snapshots() {
+        for backend in [BlobBackend::Fs, BlobBackend::Db] {
+            let dir = tempfile::tempdir().unwrap();
+            let store = test_store_with(dir.path(), backend, Compression::None);
+            let v1 = lockfile(40_000, usize::MAX);
+            let v2 = lockfile(40_000, 20_000);
+            let e1 = put_large(&store, "s1", &v1);
+            let e2 = put_large(&store, "s2", &v2);
+
+            assert!(e1.chunks.len() > 1);
+            assert_eq!(e1.blob, content_hash(&v1));
+            let only_in_s1: HashSet<&String> = e1
+                .chunks
+                .iter()
+                .filter(|c| !e2.chunks.contains(c))
+                .collect();
+            assert!(only_in_s1.len() <= 2, "{} chunks changed", only_in_s1.len());
+
+            assert_eq!(
+                store.list_snapshot_entries("s2").unwrap(),
+                std::slice::from_ref(&e2)
+            );
+            assert_eq!(store.read_entry(&e2).unwrap().unwrap(), v2);
+            assert_eq!(store.get_blob(&e1.blob).unwrap().unwrap(), v1);
+            store.validate_snapshot("s1").unwrap();
+
+            let stats = store.stats().unwrap();
+            assert_eq!(stats.files, 2);
+            assert_eq!(stats.chunked_files, 2);
+            assert_eq!(stats.logical_bytes, (v1.len() + v2.len()) as u64);
+            assert!(stats.dedup_ratio > 1.5, "{:?}", stats);
+
+            // Deleting s1 frees only the chunks s2 does not use.
+            assert!(store.delete_snapshot("s1").unwrap());
+            let report = store.sweep_blobs(Duration::ZERO).unwrap();
+            assert_eq!(report.blobs_removed, only_in_s1.len());
+            assert_eq!(store.read_entry(&e2).unwrap().unwrap(), v2);
+            store.validate_snapshot("s2").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_delete_and_sweep_reclaim_unreferenced_blobs() {
+        for backend in [BlobBackend::Fs, BlobBackend::Db] {
+            let dir = tempfile::tempdir().unwrap();
+            let store = test_store_with(dir.path(), backend, Compression::None);
+            check_delete_and_sweep(&store);
+        }
+    }
+
+    fn check_delete_and_sweep(store: &Store) {
+        put(store, "s1", "only in s1", None, None);
+        put(store, "s2", "shared", None, None);
+        put(store, "s3", "shared", None, None);
+
+        // Nothing is unreferenced yet.
+        assert_eq!(
+            store.sweep_blobs(Duration::ZERO).unwrap(),
+            SweepReport::default()
+        );
+
+        assert!(store.delete_snapshot("s1").unwrap());
+        assert!(store.delete_snapshot("s2").unwrap());
+        assert!(!store.delete_snapshot("s2").unwrap());
+        assert!(store.get_snapshot_info("s1").unwrap().is_none());
+
+        let report = store.sweep_blobs(Duration::ZERO).unwrap();
+        assert_eq!(report.blobs_removed, 1);
+        assert_eq!(report.bytes_reclaimed, "only in s1".len() as u64);
+        // s3 still reads its blob.
+        store.validate_snapshot("s3").unwrap();
+    }
+
+    #[test]
+    fn test_sweep_spares_recent_puts_and_removes_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = test_store(dir.path());
+
+        // Put but not yet referenced by a snapshot: protected by the grace period.
+        let pending = store.put_blob(b"pending").unwrap();
+        let report = store.sweep_blobs(Duration::from_secs(3600)).unwrap();
+        assert_eq!(report.blobs_removed, 0);
+        assert!(store.get_blob(&pending).unwrap().is_some());
+
+        // A file without a row, as left by a crash mid-sweep.
+        let orphan = content_hash(b"orphan");
+        store
+            .blob_store(BlobBackend::Fs)
+            .put(&orphan, b"orphan")
+            .unwrap();
+        let report = store.sweep_blobs(Duration::ZERO).unwrap();
+        assert_eq!(report.blobs_removed, 2);
+        assert_eq!(
+            report.bytes_reclaimed,
+            (b"pending".len() + b"orphan".len()) as u64
+        );
+        assert!(!store.blob_store(BlobBackend::Fs).has(&orphan).unwrap());
+        assert!(store.get_blob(&pending).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_plan_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = test_store(dir.path());
+        // Oldest first: base <- patched (derived), labeled, pinned, newest.
+        put(&store, "base", "a", None, None);
+        put(&store, "patched", "b", Some("base"), None);
+        put(&store, "labeled", "c", None, Some("release"));
+        put(&store, "pinned", "d", None, None);
+        put(&store, "old", "e", None, None);
+        put(&store, "newest", "f", Some("patched"), None);
+
+        let policy = RetentionPolicy {
+            keep_last: Some(1),
+            keep_labeled: true,
+            pinned: HashSet::from(["pinned".to_string()]),
+        };
+        // newest is kept, and with it its whole derived_from chain.
+        assert_eq!(store.plan_retention("/repo", &policy).unwrap(), ["old"]);
+
+        let policy = RetentionPolicy {
+            keep_last: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(store.plan_retention("/repo", &policy).unwrap().len(), 6);
+        assert!(
+            store
+                .plan_retention("/repo", &RetentionPolicy::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_merge_snapshot_records_both_parents() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = test_store(dir.path());
+        put(&store, "ours", "a", None, None);
+        put(&store, "theirs", "b", None, None);
+        let manifest = Manifest::new(Vec::new());
+        store
+            .put_merge_snapshot(
+                "merged",
+                "/repo",
+                "head",
+                "{}",
+                manifest.to_canonical_json().unwrap().as_bytes(),
+                "ours",
+                "theirs",
+                None,
+            )
+            .unwrap();
+
+        let info = store.get_snapshot_info("merged").unwrap().unwrap();
+        assert_eq!(info.derived_from.as_deref(), Some("ours"));
+        assert_eq!(info.merged_from.as_deref(), Some("theirs"));
+        let policy = RetentionPolicy {
+            keep_last: Some(1),
+            keep_labeled: false,
+            pinned: HashSet::new(),
+        };
+        assert!(store.plan_retention("/repo", &policy).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_storing_a_snapshot_again_keeps_its_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = test_store(dir.path());
+        put(&store, "base", "a", None, None);
+        put(&store, "child", "b", Some("base"), Some("release"));
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE snapshots SET created_at = 1 WHERE snapshot_id = 'child'",
+                [],
+            )
+            .unwrap();
+
+        // The same content captured again, without a parent or label.
+        put(&store, "child", "b", None, None);
+        let info = store.get_snapshot_info("child").unwrap().unwrap();
+        assert_eq!(info.derived_from.as_deref(), Some("base"));
+        assert_eq!(info.label.as_deref(), Some("release"));
+        assert_eq!(info.created_at, Some(1));
+        let policy = RetentionPolicy {
+            keep_last: Some(0),
+            keep_labeled: true,
+            pinned: HashSet::new(),
+        };
+        assert!(store.plan_retention("/repo", &policy).unwrap().is_empty());
+
+        put(&store, "child", "b", None, Some("v2"));
+        let info = store.get_snapshot_info("child").unwrap().unwrap();
+        assert_eq!(info.label.as_deref(), Some("v2"));
+    }
+
+    #[test]
+    fn test_refs_and_ref_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = test_store(dir.path());
+        put(&store, "s1", "a", None, None);
+        put(&store, "s2", "b", None, None);
+
+        assert_eq!(
+            store.set_ref("/repo", "baseline", "s1", false).unwrap(),
+            None
+        );
+        // Re-tagging the same snapshot is a no-op; moving needs force.
+        assert_eq!(
+            store.set_ref("/repo", "baseline", "s1", false).unwrap(),
+            Some("s1".to_string())
+        );
+        assert!(store.set_ref("/repo", "baseline", "s2", false).is_err());
+        assert_eq!(
+            store.set_ref("/repo", "baseline", "s2", true).unwrap(),
+            Some("s1".to_string())
+        );
+        assert_eq!(store.get_ref("/repo", "baseline").unwrap().unwrap(), "s2");
+        assert!(store.get_ref("/other", "baseline").unwrap().is_none());
+
+        // Tagged snapshots survive retention even with keep_last 0.
+        let policy = RetentionPolicy {
+            keep_last: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(store.plan_retention("/repo", &policy).unwrap(), ["s1"]);
+
+        assert_eq!(
+            store.delete_ref("/repo", "baseline").unwrap(),
+            Some("s2".to_string())
+        );
+        assert!(store.delete_ref("/repo", "baseline").unwrap().is_none());
+        assert!(store.list_refs("/repo").unwrap().is_empty());
+
+        let moves: Vec<(Option<String>, Option<String>)> = store
+            .ref_log("/repo", Some("baseline"))
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.from, e.to))
+            .collect();
+        assert_eq!(
+            moves,
+            [
+                (None, Some("s1".to_string())),
+                (Some("s1".to_string()), Some("s2".to_string())),
+                (Some("s2".to_string()), None),
+            ]
+        );
+        assert!(store.ref_log("/repo", Some("other")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_leases_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let store = test_store(dir.path());
+            let lease = |id: &str| StoredLease {
+                lease_id: id.to_string(),
+                repo_root: "/repo".to_string(),
+                fingerprint_json: "{}".to_string(),
+                touched: Vec::new(),
+                issued_at: None,
+                holder: Some("agent-1".to_string()),
+                purpose: None,
+                ttl_secs: Some(60),
+                expires_at: Some(100),
+                reserved: vec!["src".to_string()],
+            };
+            store.put_lease(&lease("l1")).unwrap();
+            store.put_lease(&lease("l2")).unwrap();
+            store.put_lease(&lease("l3")).unwrap();
+            store
+                .set_lease_touched("l1", &["a.txt".to_string(), "b.txt".to_string()])
+                .unwrap();
+            store.set_lease_expiry("l2", 120, 200).unwrap();
+            store.set_lease_reserved("l2", &[]).unwrap();
+            store
+                .set_lease_fingerprint("l2", r#"{"head_oid":"x"}"#)
+                .unwrap();
+            assert!(store.delete_lease("l3").unwrap());
+            assert!(!store.delete_lease("l3").unwrap());
+        }
+
+        let store = test_store(dir.path());
+        let leases = store.list_leases().unwrap();
+        assert_eq!(leases.len(), 2);
+        assert_eq!(leases[0].lease_id, "l1");
+        assert_eq!(leases[0].repo_root, "/repo");
+        assert_eq!(leases[0].touched, ["a.txt", "b.txt"]);
+        assert_eq!(leases[0].holder.as_deref(), Some("agent-1"));
+        assert!(leases[0].issued_at.is_some());
+        assert!(leases[1].touched.is_empty());
+        assert_eq!(
+            (leases[1].ttl_secs, leases[1].expires_at),
+            (Some(120), Some(200))
+        );
+        assert_eq!(leases[0].reserved, ["src"]);
+        assert!(leases[1].reserved.is_empty());
+        assert_eq!(leases[1].fingerprint_json, r#"{"head_oid":"x"}"#);
+    }
 }
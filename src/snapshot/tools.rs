@@ -3,25 +3,67 @@
 // Feature: MCP_SNAPSHOT_WORKSPACE
 // Spec: spec/core/snapshot-workspace.md
 
-use crate::snapshot::lease::{Fingerprint, LeaseStore};
-use crate::snapshot::store::{Entry, Manifest, Store};
-use anyhow::{Result, anyhow};
+use crate::config::{BlobBackend, Limits, Retention};
+use crate::router::AxiomRegentError;
+use crate::snapshot::lease::{Lease, LeaseStore};
+use crate::snapshot::lineage;
+use crate::snapshot::merge;
+use crate::snapshot::refs;
+use crate::snapshot::store::{Entry, Manifest, RetentionPolicy, SnapshotInfo, Store};
+use crate::snapshot::worktree::{self, Scope};
+use anyhow::Result;
 use base64::Engine;
 use serde_json::json;
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Arc;
+use std::time::Duration;
 use walkdir;
 
+/// Options of [`SnapshotTools::snapshot_create`]; the default captures the
+/// touched files of a newly issued lease.
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// Lease to snapshot under; without it a lease is issued.
+    pub lease_id: Option<String>,
+    /// Files to capture with the `touched` scope, instead of the lease's.
+    pub paths: Option<Vec<String>>,
+    pub scope: Scope,
+    /// Snapshot the new one derives from.
+    pub parent_snapshot_id: Option<String>,
+    /// Free-form label stored with the snapshot.
+    pub label: Option<String>,
+}
+
 pub struct SnapshotTools {
     lease_store: Arc<LeaseStore>,
     store: Arc<Store>,
+    limits: Limits,
+    retention: Retention,
 }
 
 impl SnapshotTools {
     pub fn new(lease_store: Arc<LeaseStore>, store: Arc<Store>) -> Self {
-        Self { lease_store, store }
+        Self {
+            lease_store,
+            store,
+            limits: Limits::default(),
+            retention: Retention::default(),
+        }
+    }
+
+    /// Replaces the default limits (`[limits]` in the server config).
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Replaces the `snapshot.gc` defaults (`[retention]` in the server config).
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
     }
 
     // --- Helpers ---
@@ -36,8 +78,8 @@ impl SnapshotTools {
             Ok(lid.to_string())
         } else {
             // Issue new lease
-            let fp = Fingerprint::compute(repo_root)?;
-            Ok(self.lease_store.issue(fp))
+            let fp = self.lease_store.fingerprint(repo_root)?;
+            self.lease_store.issue(repo_root, fp)
         }
     }
 
@@ -52,17 +94,23 @@ impl SnapshotTools {
 
         // Simple security check on string:
         if rel_path.contains("..") || rel_path.starts_with('/') {
-            return Err(anyhow!(
+            return Err(AxiomRegentError::PermissionDenied(format!(
                 "Invalid path (traversal or absolute): {}",
                 rel_path
-            ));
+            ))
+            .with_path(rel_path)
+            .into());
         }
 
         // Canonical check if exists
         if path.exists() {
             let c = path.canonicalize()?;
             if !c.starts_with(&canonical_root) {
-                return Err(anyhow!("Path escapes repo root"));
+                return Err(
+                    AxiomRegentError::PermissionDenied("Path escapes repo root".into())
+                        .with_path(rel_path)
+                        .into(),
+                );
             }
             Ok(c)
         } else {
@@ -72,6 +120,77 @@ impl SnapshotTools {
         }
     }
 
+    /// Metadata of snapshot `snapshot_id`, which must belong to `repo_root` (canonical).
+    fn repo_snapshot(&self, repo_root: &Path, snapshot_id: &str) -> Result<SnapshotInfo> {
+        let info = self.store.get_snapshot_info(snapshot_id)?.ok_or_else(|| {
+            AxiomRegentError::NotFound(format!("Snapshot not found: {}", snapshot_id))
+                .with_snapshot(snapshot_id)
+        })?;
+        if Path::new(&info.repo_root) != repo_root {
+            return Err(invalid(&format!(
+                "Snapshot belongs to another repository: {}",
+                info.repo_root
+            ))
+            .with_snapshot(snapshot_id)
+            .into());
+        }
+        Ok(info)
+    }
+
+    /// Snapshot ID named by `spec` (an ID, a tag or `HEAD`, with an optional `~N`).
+    fn resolve(&self, repo_root: &Path, spec: &str) -> Result<String> {
+        refs::resolve(&self.store, repo_root, spec)
+    }
+
+    /// Tags of `repo_root` (canonical) pointing at `snapshot_id`.
+    fn tags_of(&self, repo_root: &Path, snapshot_id: &str) -> Result<Vec<String>> {
+        Ok(self
+            .store
+            .list_refs(&repo_root.to_string_lossy())?
+            .into_iter()
+            .filter(|r| r.snapshot_id == snapshot_id)
+            .map(|r| r.name)
+            .collect())
+    }
+
+    /// Tag names of `repo_root` (canonical), by the snapshot they point at.
+    fn tags_by_snapshot(&self, repo_root: &Path) -> Result<HashMap<String, Vec<String>>> {
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        for r in self.store.list_refs(&repo_root.to_string_lossy())? {
+            tags.entry(r.snapshot_id).or_default().push(r.name);
+        }
+        Ok(tags)
+    }
+
+    /// Reads one file of a stored snapshot, returning its manifest entry and content.
+    pub fn read_snapshot_file(&self, snap_id: &str, path: &str) -> Result<(Entry, Vec<u8>)> {
+        // Validate snapshot integrity first
+        self.store.validate_snapshot(snap_id)?;
+
+        // retrieve manifest
+        let manifest_entries = self.store.list_snapshot_entries(snap_id)?;
+
+        // find entry
+        let entry = manifest_entries
+            .into_iter()
+            .find(|e| e.path == path)
+            .ok_or_else(|| {
+                AxiomRegentError::NotFound(format!("File not found in snapshot: {}", path))
+                    .with_path(path)
+                    .with_snapshot(snap_id)
+            })?;
+
+        let content = self.store.read_entry(&entry)?.ok_or_else(|| {
+            AxiomRegentError::Internal(format!(
+                "Snapshot corrupted: referenced blob {} not found in store",
+                entry.blob
+            ))
+            .with_path(path)
+            .with_snapshot(snap_id)
+        })?;
+        Ok((entry, content))
+    }
+
     // --- Tools ---
 
     #[allow(clippy::too_many_arguments)]
@@ -88,7 +207,7 @@ impl SnapshotTools {
         let repo_root = repo_root.canonicalize()?;
         let target_path = self.resolve_path(&repo_root, path)?;
 
-        let limit = limit.unwrap_or(1000); // Default limit? Or unlimited? Let's say 1000 safety cap.
+        let limit = limit.unwrap_or(self.limits.list);
         let offset = offset.unwrap_or(0);
 
         if mode == "worktree" {
@@ -109,7 +228,7 @@ impl SnapshotTools {
                             "path": path,
                             "type": "file",
                         }));
-                        self.lease_store.touch_files(&lid, vec![path.to_string()]);
+                        self.lease_store.touch_files(&lid, vec![path.to_string()])?;
                     }
                 } else {
                     // Dir
@@ -143,6 +262,7 @@ impl SnapshotTools {
                     // Apply paging
                     let end = std::cmp::min(offset + limit, total);
                     if offset < total {
+                        let mut touched = Vec::new();
                         for (rel, type_str) in &raw_entries[offset..end] {
                             entries.push(json!({
                                 "path": rel,
@@ -151,9 +271,10 @@ impl SnapshotTools {
 
                             // Touch child if file?
                             if type_str == "file" {
-                                self.lease_store.touch_files(&lid, vec![rel.clone()]);
+                                touched.push(rel.clone());
                             }
                         }
+                        self.lease_store.touch_files(&lid, touched)?;
                     }
                 }
             } else {
@@ -179,7 +300,8 @@ impl SnapshotTools {
         } else if mode == "snapshot" {
             // Snapshot mode
             let snap_id =
-                snapshot_id.ok_or_else(|| anyhow!("snapshot_id required for snapshot mode"))?;
+                snapshot_id.ok_or_else(|| invalid("snapshot_id required for snapshot mode"))?;
+            let snap_id = self.resolve(&repo_root, &snap_id)?;
 
             self.store.validate_snapshot(&snap_id)?;
             let manifest_entries = self.store.list_snapshot_entries(&snap_id)?;
@@ -254,16 +376,28 @@ impl SnapshotTools {
                 "cache_hint": "immutable"
             }))
         } else {
-            Err(anyhow!("Invalid mode"))
+            Err(invalid("Invalid mode").into())
         }
     }
 
+    /// Captures worktree files into a new snapshot.
+    ///
+    /// Files whose stat matches the store's stat cache are not read again.
+    /// With `parent_snapshot_id`, the `touched` scope starts from the parent's
+    /// manifest: captured paths replace their entries and missing ones are
+    /// removed. The new snapshot is `derived_from` the parent.
     pub fn snapshot_create(
         &self,
         repo_root: &Path,
-        lease_id: Option<String>,
-        paths: Option<Vec<String>>,
+        options: CreateOptions,
     ) -> Result<serde_json::Value> {
+        let CreateOptions {
+            lease_id,
+            paths,
+            scope,
+            parent_snapshot_id,
+            label,
+        } = options;
         // Must have lease or issue one for "touched" set?
         // If paths provided, explicit. If not, touched.
 
@@ -277,59 +411,119 @@ impl SnapshotTools {
             self.lease_store.check_lease(l, &repo_root)?;
         } else {
             // Issue
-            let fp = Fingerprint::compute(&repo_root)?;
-            lid = Some(self.lease_store.issue(fp));
+            let fp = self.lease_store.fingerprint(&repo_root)?;
+            lid = Some(self.lease_store.issue(&repo_root, fp)?);
         }
         let lid_str = lid.unwrap();
 
-        let files_to_capture = if let Some(p) = paths {
-            p
-        } else {
-            // Use touched
-            self.lease_store
-                .get_touched_files(&lid_str)
-                .unwrap_or_default()
-        };
+        if scope != Scope::Touched && paths.is_some() {
+            return Err(invalid("paths only apply to the touched scope").into());
+        }
 
-        let mut entries = Vec::new();
-        for path_str in files_to_capture {
-            // Validate path format
-            Store::validate_path(&path_str)?;
-
-            let p = self.resolve_path(&repo_root, &path_str)?;
-            if p.is_file() {
-                let content = std::fs::read(&p)?;
-                let blob_hash = self.store.put_blob(&content)?;
-                entries.push(Entry {
-                    path: path_str,
-                    blob: blob_hash,
-                    size: content.len() as u64,
-                });
+        let parent_snapshot_id = parent_snapshot_id
+            .map(|p| self.resolve(&repo_root, &p))
+            .transpose()?;
+        let mut entries: BTreeMap<String, Entry> = BTreeMap::new();
+        if let Some(parent) = &parent_snapshot_id
+            && scope == Scope::Touched
+        {
+            for entry in self.store.list_snapshot_entries(parent)? {
+                entries.insert(entry.path.clone(), entry);
             }
         }
 
-        let manifest = Manifest::new(entries);
+        let mut hashed = 0;
+        let mut reused = 0;
+        let mut capture = |path_str: String| -> Result<()> {
+            match worktree::capture_cached(&self.store, &repo_root, &path_str)? {
+                Some(captured) => {
+                    if captured.hashed {
+                        hashed += 1;
+                    } else {
+                        reused += 1;
+                    }
+                    entries.insert(path_str, captured.entry);
+                }
+                None => {
+                    entries.remove(&path_str);
+                }
+            }
+            Ok(())
+        };
+        match scope {
+            Scope::Touched => {
+                let files_to_capture = if let Some(p) = paths {
+                    p
+                } else {
+                    // Use touched
+                    self.lease_store
+                        .get_touched_files(&lid_str)
+                        .unwrap_or_default()
+                };
+                for path_str in files_to_capture {
+                    // Validate path format
+                    Store::validate_path(&path_str)?;
+                    // A symlink may point anywhere; only its directory must be in the repo.
+                    let parent = Path::new(&path_str)
+                        .parent()
+                        .map(|p| p.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    self.resolve_path(&repo_root, &parent)?;
+
+                    capture(path_str)?;
+                }
+            }
+            Scope::Worktree => {
+                let files = worktree::list_files(&repo_root)?;
+                // Forget files that are gone or now ignored.
+                self.store.prune_stat_cache(
+                    &repo_root.to_string_lossy(),
+                    &files.iter().cloned().collect(),
+                )?;
+                for path_str in files {
+                    capture(path_str)?;
+                }
+            }
+            Scope::Tracked => {
+                for path_str in worktree::list_tracked_files(&repo_root)? {
+                    capture(path_str)?;
+                }
+            }
+        }
+
+        let manifest = Manifest::new(entries.into_values().collect());
         let fp = self.lease_store.get_fingerprint(&lid_str).unwrap(); // valid check passed
         let fp_json = fp.to_canonical_json()?;
         let snap_id = manifest.compute_snapshot_id(&fp_json)?;
+        // Nothing changed since the parent: it is the same snapshot.
+        let derived_from = parent_snapshot_id.filter(|p| *p != snap_id);
 
         // Store manifest
         let manifest_bytes = manifest.to_canonical_json()?.into_bytes();
-        self.store.put_snapshot(
+        self.store.put_scoped_snapshot(
             &snap_id,
             &repo_root.to_string_lossy(),
             &fp.head_oid,
             &fp_json,
             &manifest_bytes,
+            derived_from.as_deref(),
             None,
-            None,
-            None,
+            label.as_deref(),
+            Some(scope),
         )?;
+        // A snapshot stored before keeps its lineage and label.
+        let stored = self.store.get_snapshot_info(&snap_id)?.ok_or_else(|| {
+            AxiomRegentError::Internal(format!("Snapshot not stored: {}", snap_id))
+        })?;
 
         Ok(json!({
             "snapshot_id": snap_id,
             "repo_root": repo_root.to_string_lossy(),
             "head_sha": fp.head_oid,
+            "derived_from": stored.derived_from,
+            "label": stored.label,
+            "files_hashed": hashed,
+            "files_reused": reused,
             "cache_key": snap_id,
             "cache_hint": "immutable"
         }))
@@ -350,7 +544,12 @@ impl SnapshotTools {
             let target_path = self.resolve_path(&repo_root, path)?;
 
             if !target_path.exists() || !target_path.is_file() {
-                return Err(anyhow!("File not found or not a file: {}", path));
+                return Err(AxiomRegentError::NotFound(format!(
+                    "File not found or not a file: {}",
+                    path
+                ))
+                .with_path(path)
+                .into());
             }
 
             let content = std::fs::read(&target_path)?;
@@ -359,7 +558,7 @@ impl SnapshotTools {
             let encoded = general_purpose::STANDARD.encode(&content);
             let blob_hash = format!("sha256:{}", hex::encode(Sha256::digest(&content))); // Optional return?
 
-            self.lease_store.touch_files(&lid, vec![path.to_string()]);
+            self.lease_store.touch_files(&lid, vec![path.to_string()])?;
             let fp = self.lease_store.get_fingerprint(&lid).unwrap();
 
             // detect kind?
@@ -387,26 +586,10 @@ impl SnapshotTools {
             }))
         } else if mode == "snapshot" {
             let snap_id =
-                snapshot_id.ok_or_else(|| anyhow!("snapshot_id required for snapshot mode"))?;
-
-            // Validate snapshot integrity first
-            self.store.validate_snapshot(&snap_id)?;
-
-            // retrieve manifest
-            let manifest_entries = self.store.list_snapshot_entries(&snap_id)?;
+                snapshot_id.ok_or_else(|| invalid("snapshot_id required for snapshot mode"))?;
+            let snap_id = self.resolve(&repo_root, &snap_id)?;
 
-            // find entry
-            let entry = manifest_entries
-                .iter()
-                .find(|e| e.path == path)
-                .ok_or_else(|| anyhow!("File not found in snapshot: {}", path))?;
-
-            let content = self.store.get_blob(&entry.blob)?.ok_or_else(|| {
-                anyhow!(
-                    "Snapshot corrupted: referenced blob {} not found in store",
-                    entry.blob
-                )
-            })?;
+            let (entry, content) = self.read_snapshot_file(&snap_id, path)?;
 
             use base64::{Engine as _, engine::general_purpose};
             let encoded = general_purpose::STANDARD.encode(&content);
@@ -428,7 +611,7 @@ impl SnapshotTools {
                 "cache_hint": "immutable"
             }))
         } else {
-            Err(anyhow!("Invalid mode"))
+            Err(invalid("Invalid mode").into())
         }
     }
 
@@ -451,7 +634,7 @@ impl SnapshotTools {
         }
         let re = builder
             .build()
-            .map_err(|e| anyhow!("Invalid regex: {}", e))?;
+            .map_err(|e| invalid(&format!("Invalid regex: {}", e)))?;
 
         if mode == "worktree" {
             let lid = self.check_lease(lease_id.as_deref(), &repo_root)?;
@@ -502,7 +685,7 @@ impl SnapshotTools {
                                     "text": line
                                 }));
                                 total_matches += 1;
-                                if total_matches >= 100 {
+                                if total_matches >= self.limits.grep_matches {
                                     truncated = true;
                                     break;
                                 }
@@ -522,7 +705,7 @@ impl SnapshotTools {
                     }
                 }
             }
-            self.lease_store.touch_files(&lid, candidates_touched);
+            self.lease_store.touch_files(&lid, candidates_touched)?;
 
             let fp = self.lease_store.get_fingerprint(&lid).unwrap();
 
@@ -539,7 +722,8 @@ impl SnapshotTools {
             }))
         } else {
             let sid =
-                snapshot_id.ok_or_else(|| anyhow!("snapshot_id required in snapshot mode"))?;
+                snapshot_id.ok_or_else(|| invalid("snapshot_id required in snapshot mode"))?;
+            let sid = self.resolve(&repo_root, &sid)?;
             // Validate snapshot integrity first
             self.store.validate_snapshot(&sid)?;
 
@@ -579,7 +763,7 @@ impl SnapshotTools {
                 }
 
                 // Get blob content
-                if let Some(content) = self.store.get_blob(&entry.blob)? {
+                if let Some(content) = self.store.read_entry(&entry)? {
                     // Binary check
                     if content.iter().take(512).any(|&b| b == 0) {
                         continue;
@@ -597,7 +781,7 @@ impl SnapshotTools {
                                     "text": line
                                 }));
                                 total_matches += 1;
-                                if total_matches >= 100 {
+                                if total_matches >= self.limits.grep_matches {
                                     truncated = true;
                                     break;
                                 }
@@ -614,7 +798,13 @@ impl SnapshotTools {
                 } else {
                     // Missing blob? store.get_blob returns None if not found in db/fs.
                     // validate_snapshot should have caught this, but safeguard:
-                    return Err(anyhow!("Snapshot missing blob: {}", entry.blob));
+                    return Err(AxiomRegentError::Internal(format!(
+                        "Snapshot missing blob: {}",
+                        entry.blob
+                    ))
+                    .with_path(&entry.path)
+                    .with_snapshot(&sid)
+                    .into());
                 }
             }
 
@@ -647,7 +837,7 @@ impl SnapshotTools {
 
             // `git diff -- path`
             // touches target
-            self.lease_store.touch_files(&lid, vec![path.to_string()]);
+            self.lease_store.touch_files(&lid, vec![path.to_string()])?;
 
             // run git diff
             let output = Command::new("git")
@@ -667,7 +857,8 @@ impl SnapshotTools {
             }))
         } else if mode == "snapshot" {
             let sid =
-                snapshot_id.ok_or_else(|| anyhow!("snapshot_id required in snapshot mode"))?;
+                snapshot_id.ok_or_else(|| invalid("snapshot_id required in snapshot mode"))?;
+            let sid = self.resolve(&repo_root, &sid)?;
             self.store.validate_snapshot(&sid)?;
 
             // Get content from target snapshot
@@ -677,7 +868,7 @@ impl SnapshotTools {
             // Find entry in snapshot
             if let Ok(entries) = self.store.list_snapshot_entries(&sid)
                 && let Some(entry) = entries.iter().find(|e| e.path == path)
-                && let Some(blob) = self.store.get_blob(&entry.blob)?
+                && let Some(blob) = self.store.read_entry(entry)?
             {
                 // Try decode utf8, if binary?
                 // similar can diff bytes but usually we diff text.
@@ -698,10 +889,11 @@ impl SnapshotTools {
             let mut base_found = false;
 
             if let Some(from_sid) = from_snapshot_id {
+                let from_sid = self.resolve(&repo_root, &from_sid)?;
                 self.store.validate_snapshot(&from_sid)?;
                 if let Ok(entries) = self.store.list_snapshot_entries(&from_sid)
                     && let Some(entry) = entries.iter().find(|e| e.path == path)
-                    && let Some(blob) = self.store.get_blob(&entry.blob)?
+                    && let Some(blob) = self.store.read_entry(entry)?
                 {
                     if blob.iter().take(512).any(|&b| b == 0) {
                         // Base is binary.
@@ -720,7 +912,13 @@ impl SnapshotTools {
 
             // If neither found, empty diff (or error?)
             if !target_found && !base_found {
-                return Err(anyhow!("Path not found in either snapshot: {}", path));
+                return Err(AxiomRegentError::NotFound(format!(
+                    "Path not found in either snapshot: {}",
+                    path
+                ))
+                .with_path(path)
+                .with_snapshot(&sid)
+                .into());
             }
 
             // Compute diff
@@ -774,21 +972,23 @@ impl SnapshotTools {
                 "cache_hint": "immutable"
             }))
         } else {
-            Err(anyhow!("Invalid mode"))
+            Err(invalid("Invalid mode").into())
         }
     }
     pub fn snapshot_changes(
         &self,
-        _repo_root: &Path,
+        repo_root: &Path,
         snapshot_id: Option<String>,
         from_snapshot_id: Option<String>,
     ) -> Result<serde_json::Value> {
-        let snap_id = snapshot_id.ok_or_else(|| anyhow!("snapshot_id required"))?;
+        let snap_id = snapshot_id.ok_or_else(|| invalid("snapshot_id required"))?;
+        let snap_id = self.resolve(repo_root, &snap_id)?;
         self.store.validate_snapshot(&snap_id)?;
 
         let mut changes = Vec::new();
 
         if let Some(from_sid) = from_snapshot_id {
+            let from_sid = self.resolve(repo_root, &from_sid)?;
             self.store.validate_snapshot(&from_sid)?;
 
             let target_entries = self.store.list_snapshot_entries(&snap_id)?;
@@ -796,7 +996,6 @@ impl SnapshotTools {
 
             // Use maps for easier lookup? list_snapshot_entries returns sorted Vec<Entry>.
             // Since sorted, we can iterate in parallel or use map. Map is easier.
-            use std::collections::HashMap;
             let target_map: HashMap<_, _> =
                 target_entries.iter().map(|e| (&e.path, &e.blob)).collect();
             let base_map: HashMap<_, _> = base_entries.iter().map(|e| (&e.path, &e.blob)).collect();
@@ -839,10 +1038,11 @@ impl SnapshotTools {
 
     pub fn snapshot_export(
         &self,
-        _repo_root: &Path,
+        repo_root: &Path,
         snapshot_id: Option<String>,
     ) -> Result<serde_json::Value> {
-        let snap_id = snapshot_id.ok_or_else(|| anyhow!("snapshot_id required"))?;
+        let snap_id = snapshot_id.ok_or_else(|| invalid("snapshot_id required"))?;
+        let snap_id = self.resolve(repo_root, &snap_id)?;
         // Validate snapshot integrity first
         self.store.validate_snapshot(&snap_id)?;
 
@@ -854,26 +1054,46 @@ impl SnapshotTools {
 
         let mut included_files = 0;
         let mut total_bytes = 0;
+        let mut truncated = false;
 
         for entry in entries {
-            if let Some(content) = self.store.get_blob(&entry.blob)? {
+            if let Some(content) = self.store.read_entry(&entry)? {
+                // Stop at the first file that would exceed the byte cap.
+                if let Some(cap) = self.limits.export_bytes
+                    && (total_bytes + content.len()) as u64 > cap
+                {
+                    truncated = true;
+                    break;
+                }
                 let mut header = tar::Header::new_gnu();
-                header.set_size(content.len() as u64);
-                header.set_mode(0o644); // Regular file
                 header.set_mtime(0); // Epoch for determinism
                 header.set_uid(0);
                 header.set_gid(0);
-                header.set_cksum();
 
                 // Use entry.path as path in tar
                 // Ensure no leading slash for tar safety?
                 // entry.path is relative "a/b/c.txt".
-                tar_builder.append_data(&mut header, &entry.path, &content[..])?;
+                if let Some(target) = &entry.symlink {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_size(0);
+                    header.set_mode(0o777);
+                    tar_builder.append_link(&mut header, &entry.path, target)?;
+                } else {
+                    header.set_size(content.len() as u64);
+                    header.set_mode(if entry.executable { 0o755 } else { 0o644 });
+                    header.set_cksum();
+                    tar_builder.append_data(&mut header, &entry.path, &content[..])?;
+                }
 
                 included_files += 1;
                 total_bytes += content.len();
             } else {
-                return Err(anyhow!("Missing blob for {}", entry.path));
+                return Err(
+                    AxiomRegentError::Internal(format!("Missing blob for {}", entry.path))
+                        .with_path(&entry.path)
+                        .with_snapshot(&snap_id)
+                        .into(),
+                );
             }
         }
 
@@ -890,7 +1110,7 @@ impl SnapshotTools {
             "summary": {
                 "included_files": included_files,
                 "included_bytes": total_bytes,
-                "truncated": false
+                "truncated": truncated
             },
             "bundle": format!("base64:{}", encoded_bundle),
             "cache_key": snap_id,
@@ -898,6 +1118,408 @@ impl SnapshotTools {
         }))
     }
 
+    /// Deletes one snapshot of `repo_root`. Its blobs are reclaimed by `snapshot.gc`.
+    pub fn snapshot_delete(
+        &self,
+        repo_root: &Path,
+        snapshot_id: &str,
+    ) -> Result<serde_json::Value> {
+        let repo_root = repo_root.canonicalize()?;
+        let snapshot_id = &self.resolve(&repo_root, snapshot_id)?;
+        let tags = self.tags_of(&repo_root, snapshot_id)?;
+        if !tags.is_empty() {
+            return Err(invalid(&format!(
+                "Snapshot is tagged, untag it first: {}",
+                tags.join(", ")
+            ))
+            .with_snapshot(snapshot_id)
+            .into());
+        }
+        let derived = self.store.derived_snapshots(snapshot_id)?;
+        if !derived.is_empty() {
+            return Err(invalid(&format!(
+                "Snapshot has derived snapshots, delete them first: {}",
+                derived.join(", ")
+            ))
+            .with_snapshot(snapshot_id)
+            .into());
+        }
+        if changeset_locked_snapshots(&repo_root).contains(snapshot_id) {
+            return Err(invalid("Snapshot is the base state of a locked changeset")
+                .with_snapshot(snapshot_id)
+                .into());
+        }
+
+        self.store.delete_snapshot(snapshot_id)?;
+        Ok(json!({ "deleted": [snapshot_id] }))
+    }
+
+    /// Points tag `name` of `repo_root` at `snapshot_id`. A tag pointing at
+    /// another snapshot is only moved with `force`.
+    pub fn snapshot_tag(
+        &self,
+        repo_root: &Path,
+        name: &str,
+        snapshot_id: &str,
+        force: bool,
+    ) -> Result<serde_json::Value> {
+        let repo_root = repo_root.canonicalize()?;
+        refs::validate_name(name)?;
+        let snap_id = self.resolve(&repo_root, snapshot_id)?;
+        let previous = self
+            .store
+            .set_ref(&repo_root.to_string_lossy(), name, &snap_id, force)?;
+        Ok(json!({
+            "name": name,
+            "snapshot_id": snap_id,
+            "previous": previous
+        }))
+    }
+
+    /// Removes tag `name` of `repo_root`; the snapshot itself is kept.
+    pub fn snapshot_untag(&self, repo_root: &Path, name: &str) -> Result<serde_json::Value> {
+        let repo_root = repo_root.canonicalize()?;
+        let removed = self
+            .store
+            .delete_ref(&repo_root.to_string_lossy(), name)?
+            .ok_or_else(|| AxiomRegentError::NotFound(format!("Tag not found: {}", name)))?;
+        Ok(json!({
+            "name": name,
+            "snapshot_id": removed
+        }))
+    }
+
+    /// Tags of `repo_root` and its `HEAD`; with `history`, every change of
+    /// the tags (only of `name` if given), oldest first.
+    pub fn snapshot_refs(
+        &self,
+        repo_root: &Path,
+        name: Option<String>,
+        history: bool,
+    ) -> Result<serde_json::Value> {
+        let repo_root = repo_root.canonicalize()?;
+        let repo_key = repo_root.to_string_lossy();
+        let head = self
+            .store
+            .list_snapshots(&repo_key)?
+            .into_iter()
+            .next()
+            .map(|info| info.snapshot_id);
+        let tags: Vec<_> = self
+            .store
+            .list_refs(&repo_key)?
+            .into_iter()
+            .filter(|r| name.as_ref().is_none_or(|n| *n == r.name))
+            .collect();
+
+        let mut result = json!({
+            "head": head,
+            "refs": tags
+        });
+        if history {
+            result["history"] = json!(self.store.ref_log(&repo_key, name.as_deref())?);
+        }
+        Ok(result)
+    }
+
+    /// Walks from `snapshot_id` (default `HEAD`) back to its root along
+    /// `derived_from`, newest first. Hops created outside `since..=until`
+    /// (unix seconds) are skipped; at most `limit` are returned.
+    pub fn snapshot_log(
+        &self,
+        repo_root: &Path,
+        snapshot_id: Option<String>,
+        since: Option<i64>,
+        until: Option<i64>,
+        limit: Option<usize>,
+    ) -> Result<serde_json::Value> {
+        let repo_root = repo_root.canonicalize()?;
+        let start = self.resolve(&repo_root, snapshot_id.as_deref().unwrap_or(refs::HEAD))?;
+        let limit = limit.unwrap_or(self.limits.list);
+
+        let snapshots = self.store.list_snapshots(&repo_root.to_string_lossy())?;
+        let tags = self.tags_by_snapshot(&repo_root)?;
+        let hops: Vec<_> = lineage::ancestry(&snapshots, &start)
+            .into_iter()
+            .filter(|info| lineage::in_range(info, since, until))
+            .collect();
+        let entries: Vec<_> = hops
+            .iter()
+            .take(limit)
+            .map(|info| {
+                json!({
+                    "snapshot_id": info.snapshot_id,
+                    "derived_from": info.derived_from,
+                    "applied_patch_hash": info.applied_patch_hash,
+                    "created_at": info.created_at,
+                    "label": info.label,
+                    "tags": tags.get(&info.snapshot_id).cloned().unwrap_or_default(),
+                    "head_sha": info.head_sha
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "snapshot_id": start,
+            "entries": entries,
+            "truncated": hops.len() > limit
+        }))
+    }
+
+    /// The `derived_from` DAG of the snapshots of `repo_root` created within
+    /// `since..=until`, as JSON nodes and edges and as Graphviz DOT.
+    pub fn snapshot_lineage(
+        &self,
+        repo_root: &Path,
+        since: Option<i64>,
+        until: Option<i64>,
+    ) -> Result<serde_json::Value> {
+        let repo_root = repo_root.canonicalize()?;
+        let snapshots = self.store.list_snapshots(&repo_root.to_string_lossy())?;
+        let tags = self.tags_by_snapshot(&repo_root)?;
+        // Oldest first, so parents come before the snapshots derived from them.
+        let nodes: Vec<&SnapshotInfo> = snapshots
+            .iter()
+            .rev()
+            .filter(|info| lineage::in_range(info, since, until))
+            .collect();
+        let node_values: Vec<_> = nodes
+            .iter()
+            .map(|n| {
+                json!({
+                    "snapshot_id": n.snapshot_id,
+                    "created_at": n.created_at,
+                    "label": n.label,
+                    "tags": tags.get(&n.snapshot_id).cloned().unwrap_or_default(),
+                    "head_sha": n.head_sha
+                })
+            })
+            .collect();
+
+        Ok(json!({
+            "nodes": node_values,
+            "edges": lineage::edges(&nodes),
+            "dot": lineage::to_dot(&nodes, &tags)
+        }))
+    }
+
+    /// Three-way merge of snapshots `ours` and `theirs`, both derived from
+    /// `base`. A clean merge is stored as a new snapshot of `ours` merging
+    /// `theirs`. Conflicts are reported per file; with `conflict_markers` the
+    /// merge is stored anyway as a candidate holding conflict-marker content.
+    pub fn snapshot_merge(
+        &self,
+        repo_root: &Path,
+        base: &str,
+        ours: &str,
+        theirs: &str,
+        conflict_markers: bool,
+        label: Option<String>,
+    ) -> Result<serde_json::Value> {
+        let repo_root = repo_root.canonicalize()?;
+        let base = self.resolve(&repo_root, base)?;
+        let ours = self.resolve(&repo_root, ours)?;
+        let theirs = self.resolve(&repo_root, theirs)?;
+        let ours_info = self.repo_snapshot(&repo_root, &ours)?;
+        let mut manifests = Vec::new();
+        for id in [&base, &ours, &theirs] {
+            self.store.validate_snapshot(id)?;
+            let entries: BTreeMap<String, Entry> = self
+                .store
+                .list_snapshot_entries(id)?
+                .into_iter()
+                .map(|e| (e.path.clone(), e))
+                .collect();
+            manifests.push(entries);
+        }
+        let (base_entries, ours_entries, theirs_entries) =
+            (&manifests[0], &manifests[1], &manifests[2]);
+
+        let paths: BTreeSet<&String> = base_entries
+            .keys()
+            .chain(ours_entries.keys())
+            .chain(theirs_entries.keys())
+            .collect();
+        let mut entries = Vec::new();
+        let mut merged_paths = Vec::new();
+        let mut conflicts = Vec::new();
+        for path in paths {
+            let b = base_entries.get(path);
+            let o = ours_entries.get(path);
+            let t = theirs_entries.get(path);
+            // Changed on one side only, or the same way on both.
+            if o == t || t == b {
+                entries.extend(o.cloned());
+                continue;
+            }
+            if o == b {
+                entries.extend(t.cloned());
+                continue;
+            }
+            let (Some(o), Some(t)) = (o, t) else {
+                // Deleted on one side, changed on the other: keep the change.
+                conflicts.push(json!({ "path": path, "kind": "delete_modify", "hunks": [] }));
+                entries.extend(o.or(t).cloned());
+                continue;
+            };
+            if o.symlink.is_some() || t.symlink.is_some() {
+                conflicts.push(json!({ "path": path, "kind": "symlink", "hunks": [] }));
+                entries.push(o.clone());
+                continue;
+            }
+            let base_text = match b {
+                Some(b) if b.symlink.is_none() => self.entry_text(&base, b)?,
+                // Added on both sides, or replacing a symlink.
+                _ => Some(String::new()),
+            };
+            let (Some(base_text), Some(ours_text), Some(theirs_text)) = (
+                base_text,
+                self.entry_text(&ours, o)?,
+                self.entry_text(&theirs, t)?,
+            ) else {
+                conflicts.push(json!({ "path": path, "kind": "binary", "hunks": [] }));
+                entries.push(o.clone());
+                continue;
+            };
+
+            let result = merge::merge(&base_text, &ours_text, &theirs_text);
+            if !result.conflicts.is_empty() {
+                conflicts
+                    .push(json!({ "path": path, "kind": "content", "hunks": result.conflicts }));
+            }
+            let mut entry = self.store.put_content(path, result.text.as_bytes())?;
+            // The executable bit changed on at most one side, or the same way.
+            let base_executable = b.is_some_and(|b| b.executable);
+            entry.executable = if o.executable != base_executable {
+                o.executable
+            } else {
+                t.executable
+            };
+            merged_paths.push(path.clone());
+            entries.push(entry);
+        }
+
+        if !conflicts.is_empty() && !conflict_markers {
+            return Ok(json!({
+                "snapshot_id": null,
+                "clean": false,
+                "merged_paths": merged_paths,
+                "conflicts": conflicts
+            }));
+        }
+
+        let manifest = Manifest::new(entries);
+        let snap_id = manifest.compute_snapshot_id(&ours_info.fingerprint_json)?;
+        // `theirs` had nothing new, or `ours` nothing `theirs` lacks.
+        if snap_id == ours || snap_id == theirs {
+            return Ok(json!({
+                "snapshot_id": snap_id,
+                "clean": conflicts.is_empty(),
+                "merged_paths": merged_paths,
+                "conflicts": conflicts,
+                "cache_hint": "immutable"
+            }));
+        }
+        let manifest_bytes = manifest.to_canonical_json()?.into_bytes();
+        self.store.put_merge_snapshot(
+            &snap_id,
+            &repo_root.to_string_lossy(),
+            &ours_info.head_sha,
+            &ours_info.fingerprint_json,
+            &manifest_bytes,
+            &ours,
+            &theirs,
+            label.as_deref(),
+        )?;
+
+        Ok(json!({
+            "snapshot_id": snap_id,
+            "clean": conflicts.is_empty(),
+            "derived_from": ours,
+            "merged_from": theirs,
+            "merged_paths": merged_paths,
+            "conflicts": conflicts,
+            "cache_hint": "immutable"
+        }))
+    }
+
+    /// Content of `entry` of snapshot `snap_id` as text; `None` if not UTF-8.
+    fn entry_text(&self, snap_id: &str, entry: &Entry) -> Result<Option<String>> {
+        let content = self.store.read_entry(entry)?.ok_or_else(|| {
+            AxiomRegentError::Internal(format!("Missing blob for {}", entry.path))
+                .with_path(&entry.path)
+                .with_snapshot(snap_id)
+        })?;
+        Ok(String::from_utf8(content).ok())
+    }
+
+    /// Applies the retention policy to the snapshots of `repo_root`, then sweeps
+    /// unreferenced blobs of the whole store. Unset arguments fall back to the
+    /// configured `[retention]` defaults.
+    pub fn snapshot_gc(
+        &self,
+        repo_root: &Path,
+        keep_last: Option<usize>,
+        keep_labeled: Option<bool>,
+        grace_secs: Option<u64>,
+        dry_run: bool,
+    ) -> Result<serde_json::Value> {
+        let repo_root = repo_root.canonicalize()?;
+        // Lock files may name a snapshot by an ID it was moved from.
+        let pinned = changeset_locked_snapshots(&repo_root)
+            .into_iter()
+            .map(|id| Ok(self.store.renamed_snapshot(&id)?.unwrap_or(id)))
+            .collect::<Result<_>>()?;
+        let policy = RetentionPolicy {
+            keep_last: keep_last.or(self.retention.keep_last),
+            keep_labeled: keep_labeled.unwrap_or(self.retention.keep_labeled),
+            pinned,
+        };
+        let grace = Duration::from_secs(grace_secs.unwrap_or(self.retention.grace_secs));
+
+        let doomed = self
+            .store
+            .plan_retention(&repo_root.to_string_lossy(), &policy)?;
+        if dry_run {
+            return Ok(json!({
+                "deleted": [],
+                "would_delete": doomed,
+                "dry_run": true
+            }));
+        }
+
+        let mut deleted = Vec::new();
+        for id in doomed {
+            if self.store.delete_snapshot(&id)? {
+                deleted.push(id);
+            }
+        }
+        let sweep = self.store.sweep_blobs(grace)?;
+        Ok(json!({
+            "deleted": deleted,
+            "blobs_removed": sweep.blobs_removed,
+            "bytes_reclaimed": sweep.bytes_reclaimed,
+            "dry_run": false
+        }))
+    }
+
+    /// Moves all stored blobs to the `to` backend, which also receives new blobs
+    /// until the server restarts with `storage.blob_backend` from the config.
+    pub fn snapshot_migrate_store(&self, to: BlobBackend) -> Result<serde_json::Value> {
+        let report = self.store.migrate_blobs(to)?;
+        Ok(json!({
+            "to": to,
+            "blobs_moved": report.blobs_moved,
+            "bytes_moved": report.bytes_moved,
+            "missing": report.missing
+        }))
+    }
+
+    /// Sizes of the whole store and how much chunking and sharing save.
+    pub fn snapshot_store_stats(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self.store.stats()?)?)
+    }
+
     pub fn snapshot_info(
         &self,
         repo_root: &Path,
@@ -905,10 +1527,11 @@ impl SnapshotTools {
     ) -> Result<serde_json::Value> {
         // If snapshot_id provided, return details about THAT snapshot (metadata, lineage)
         if let Some(sid) = snapshot_id {
-            let info = self
-                .store
-                .get_snapshot_info(&sid)?
-                .ok_or_else(|| anyhow!("Snapshot not found: {}", sid))?;
+            let sid = self.resolve(repo_root, &sid)?;
+            let info = self.store.get_snapshot_info(&sid)?.ok_or_else(|| {
+                AxiomRegentError::NotFound(format!("Snapshot not found: {}", sid))
+                    .with_snapshot(&sid)
+            })?;
 
             // Retrieve stats via manifest? Or just computed?
             // Store saves manifest_hash but not stats directly in snapshots table (only in blobs refcounts or separate entry count).
@@ -922,13 +1545,16 @@ impl SnapshotTools {
                 "created_at": info.created_at,
                 "manifest_hash": info.manifest_hash,
                 "derived_from": info.derived_from,
+                "merged_from": info.merged_from,
                 "applied_patch_hash": info.applied_patch_hash,
                 "label": info.label,
+                "scope": info.scope,
+                "tags": self.tags_of(Path::new(&info.repo_root), &info.snapshot_id)?,
                 "cache_hint": "immutable"
             }))
         } else {
             // Original behavior: return repo fingerprint/status
-            let fp = Fingerprint::compute(repo_root)?;
+            let fp = self.lease_store.fingerprint(repo_root)?;
             Ok(json!({
                 "fingerprint": fp,
                 // "manifest_stats": ... // Deprecated or kept for compat?
@@ -941,6 +1567,126 @@ impl SnapshotTools {
             }))
         }
     }
+
+    /// Repository root of the live lease `lease_id`, if any.
+    pub fn lease_repo(&self, lease_id: &str) -> Option<String> {
+        self.lease_store.repo_root(lease_id)
+    }
+
+    /// Checks the lease of a cached worktree-mode `response` before it is
+    /// served again, as the call itself would: the lease must be live and
+    /// current, and lives for another TTL. The files the call touched were
+    /// recorded under the same lease when the response was computed.
+    pub fn check_cached_lease(&self, repo_root: &Path, response: &serde_json::Value) -> Result<()> {
+        match response.get("lease_id").and_then(|l| l.as_str()) {
+            Some(lid) => self.lease_store.check_lease(lid, repo_root),
+            None => Ok(()),
+        }
+    }
+
+    /// Repository root snapshot `snapshot_id` was taken of, if it exists.
+    pub fn snapshot_repo(&self, snapshot_id: &str) -> Result<Option<String>> {
+        Ok(self
+            .store
+            .get_snapshot_info(snapshot_id)?
+            .map(|info| info.repo_root))
+    }
+
+    /// Issues a lease on the current state of `repo_root`, recording who
+    /// holds it and why, and reserving the paths matching `reserve`.
+    pub fn lease_acquire(
+        &self,
+        repo_root: &Path,
+        holder: Option<String>,
+        purpose: Option<String>,
+        ttl_secs: Option<u64>,
+        reserve: Vec<String>,
+    ) -> Result<serde_json::Value> {
+        let fp = self.lease_store.fingerprint(repo_root)?;
+        let lease = self
+            .lease_store
+            .acquire(repo_root, fp, holder, purpose, ttl_secs, reserve)?;
+        let mut result = lease_json(&lease);
+        result["fingerprint"] = json!(lease.fingerprint);
+        Ok(result)
+    }
+
+    /// Keeps a lease alive for `ttl_secs` (default: its TTL) from now.
+    pub fn lease_renew(&self, lease_id: &str, ttl_secs: Option<u64>) -> Result<serde_json::Value> {
+        Ok(lease_json(&self.lease_store.renew(lease_id, ttl_secs)?))
+    }
+
+    /// Reserves the paths matching `patterns` for a lease, in addition to
+    /// those it already holds.
+    pub fn lease_reserve(
+        &self,
+        lease_id: &str,
+        patterns: Vec<String>,
+    ) -> Result<serde_json::Value> {
+        Ok(lease_json(&self.lease_store.reserve(lease_id, patterns)?))
+    }
+
+    /// Drops a lease; later calls naming it fail with `NOT_FOUND`.
+    pub fn lease_release(&self, lease_id: &str) -> Result<serde_json::Value> {
+        if !self.lease_store.release(lease_id)? {
+            return Err(
+                AxiomRegentError::NotFound(format!("Lease not found: {}", lease_id))
+                    .with_lease(lease_id)
+                    .into(),
+            );
+        }
+        Ok(json!({ "lease_id": lease_id, "released": true }))
+    }
+
+    /// Unexpired leases, on `repo_root` if given, with their touched files.
+    pub fn lease_list(&self, repo_root: Option<&Path>) -> Result<serde_json::Value> {
+        let repo_key = repo_root
+            .map(|r| r.canonicalize())
+            .transpose()?
+            .map(|r| r.to_string_lossy().into_owned());
+        let leases: Vec<serde_json::Value> = self
+            .lease_store
+            .list(repo_key.as_deref())
+            .iter()
+            .map(|lease| {
+                let mut entry = lease_json(lease);
+                entry["touched_files"] = json!(lease.touched());
+                entry
+            })
+            .collect();
+        Ok(json!({ "leases": leases }))
+    }
+}
+
+fn lease_json(lease: &Lease) -> serde_json::Value {
+    json!({
+        "lease_id": lease.id,
+        "repo_root": lease.repo_root,
+        "holder": lease.holder,
+        "purpose": lease.purpose,
+        "ttl_secs": lease.ttl_secs,
+        "issued_at": lease.issued_at,
+        "expires_at": lease.expires_at,
+        "reserved": lease.reserved,
+    })
+}
+
+fn invalid(msg: &str) -> AxiomRegentError {
+    AxiomRegentError::InvalidArgument(msg.to_string())
+}
+
+/// Base states of the changesets currently locked by `antigravity.execute`
+/// (`changes/.locks/<changeset_id>`). Unreadable lock files are ignored.
+fn changeset_locked_snapshots(repo_root: &Path) -> HashSet<String> {
+    let Ok(locks) = std::fs::read_dir(repo_root.join("changes").join(".locks")) else {
+        return HashSet::new();
+    };
+    locks
+        .filter_map(|l| l.ok())
+        .filter_map(|l| std::fs::read(l.path()).ok())
+        .filter_map(|bytes| serde_json::from_slice::<antigravity::schemas::LockFile>(&bytes).ok())
+        .map(|lock| lock.base_state)
+        .collect()
 }
 
 #[cfg(test)]
@@ -991,7 +1737,7 @@ mod tests {
             t2.len()
         );
 
-        let sid = "snap-grep";
+        let sid = "sha256:snap-grep";
         // put_snapshot uses string, need valid path?
         // put_snapshot arguments are metadata, repo_root doesn't need to exist for put_snapshot storage,
         // BUT snapshot_grep calls canonicalize(repo_root).
@@ -1088,7 +1834,7 @@ mod tests {
             t2.len()
         );
 
-        let sid1 = "snap1";
+        let sid1 = "sha256:snap-1";
         store
             .put_snapshot(
                 sid1,
@@ -1124,7 +1870,7 @@ mod tests {
             t4.len()
         );
 
-        let sid2 = "snap2";
+        let sid2 = "sha256:snap-2";
         store
             .put_snapshot(
                 sid2,
@@ -1226,7 +1972,7 @@ mod tests {
             t2.len()
         );
 
-        let sid = "snap-export";
+        let sid = "sha256:snap-export";
         store
             .put_snapshot(
                 sid,
@@ -1286,6 +2032,20 @@ mod tests {
             .snapshot_export(dir.path(), Some(sid.to_string()))
             .unwrap();
         assert_eq!(res["bundle"], res2["bundle"]);
+        assert_eq!(res["summary"]["truncated"], false);
+
+        // Byte cap: a.txt (9 bytes) fits, b/data.bin would exceed it.
+        let capped =
+            SnapshotTools::new(Arc::new(LeaseStore::new()), store.clone()).with_limits(Limits {
+                export_bytes: Some(10),
+                ..Limits::default()
+            });
+        let res = capped
+            .snapshot_export(dir.path(), Some(sid.to_string()))
+            .unwrap();
+        assert_eq!(res["summary"]["included_files"], 1);
+        assert_eq!(res["summary"]["included_bytes"], 9);
+        assert_eq!(res["summary"]["truncated"], true);
     }
 
     #[test]
@@ -1319,7 +2079,7 @@ mod tests {
             t2.len()
         );
 
-        let sid1 = "snap-base";
+        let sid1 = "sha256:snap-base";
         store
             .put_snapshot(
                 sid1,
@@ -1352,7 +2112,7 @@ mod tests {
             t4.len()
         );
 
-        let sid2 = "snap-target";
+        let sid2 = "sha256:snap-target";
         store
             .put_snapshot(
                 sid2,
@@ -0,0 +1,400 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! Moving files between a directory tree and the store, keeping their
+//! executable bit and symlinks.
+
+use crate::router::AxiomRegentError;
+use crate::snapshot::store::{Entry, FileStat, Store, content_hash};
+use anyhow::Result;
+use ignore::WalkBuilder;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+// Files captured by `snapshot.create`. Plain comments: doc comments would
+// turn the variants into a `oneOf` in the tool's input schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    // The `paths` given, or the files touched under the lease.
+    #[default]
+    Touched,
+    // Every file of the worktree not excluded by `.gitignore`.
+    Worktree,
+    // Only the files tracked by git (`git ls-files`).
+    Tracked,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Touched => "touched",
+            Scope::Worktree => "worktree",
+            Scope::Tracked => "tracked",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "touched" => Some(Scope::Touched),
+            "worktree" => Some(Scope::Worktree),
+            "tracked" => Some(Scope::Tracked),
+            _ => None,
+        }
+    }
+}
+
+// Files modified this recently are not cached: a second write within the
+// mtime granularity would leave the stat unchanged (git's "racily clean").
+pub const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+// Never captured: git metadata and the server's own data directory.
+const SKIPPED_NAMES: &[&str] = &[".git", ".axiomregent"];
+
+/// Repo-relative paths of the files and symlinks under `root`, sorted.
+/// Honors `.gitignore`, `.git/info/exclude` and the global excludes file.
+pub fn list_files(root: &Path) -> Result<Vec<String>> {
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .git_ignore(true)
+        .require_git(false)
+        // Only git's rules: no `.ignore` files, nothing above the repo root.
+        .ignore(false)
+        .parents(false)
+        .filter_entry(|e| {
+            !e.file_name()
+                .to_str()
+                .is_some_and(|name| SKIPPED_NAMES.contains(&name))
+        })
+        .build();
+
+    let mut paths = Vec::new();
+    for entry in walker {
+        let entry = entry?;
+        // Symlinks are not followed, so a linked directory is listed as a link.
+        if entry.file_type().is_none_or(|t| t.is_dir()) {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(root)?;
+        paths.push(rel.to_string_lossy().replace('\\', "/"));
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Paths in the git index of the repository at `root`, sorted.
+pub fn list_tracked_files(root: &Path) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(["ls-files", "-z"])
+        .current_dir(root)
+        .output()?;
+    if !output.status.success() {
+        return Err(AxiomRegentError::InvalidArgument(format!(
+            "git ls-files failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into());
+    }
+    let mut paths: Vec<String> = output
+        .stdout
+        .split(|b| *b == 0)
+        .filter(|p| !p.is_empty())
+        .map(|p| String::from_utf8_lossy(p).into_owned())
+        .collect();
+    // Unmerged paths are listed once per stage.
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Paths under `root`, relative to it, that `git status` reports as changed:
+/// modified, staged, unmerged or untracked (ignored files are not listed).
+///
+/// Empty when `root` is not inside a git repository: there is no committed
+/// state to compare with.
+pub fn dirty_files(root: &Path) -> Result<HashSet<String>> {
+    let paths = status_paths(root)?.unwrap_or_default();
+    Ok(paths
+        .into_iter()
+        .filter(|p| !p.starts_with("../"))
+        .collect())
+}
+
+/// Every path `git status` reports as changed, like [`dirty_files`] but
+/// across the whole repository, with what is there now: `None` for a
+/// deleted path. Paths outside `root` start with `../`.
+///
+/// `None` when `root` is not inside a git repository.
+pub fn changed_files(root: &Path) -> Result<Option<BTreeMap<String, Option<Entry>>>> {
+    let Some(paths) = status_paths(root)? else {
+        return Ok(None);
+    };
+    let mut changed = BTreeMap::new();
+    for path in paths {
+        let entry = read(&root.join(&path), &path)?.map(|(entry, _)| entry);
+        changed.insert(path, entry);
+    }
+    Ok(Some(changed))
+}
+
+/// Paths of the whole repository that `git status` reports, relative to
+/// `root`; `None` outside git.
+fn status_paths(root: &Path) -> Result<Option<HashSet<String>>> {
+    // `git status` reports paths relative to the top level, which `root`
+    // may lie below.
+    let output = match Command::new("git")
+        .args(["rev-parse", "--show-cdup", "--show-prefix"])
+        .current_dir(root)
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return Ok(None),
+    };
+    let location = String::from_utf8_lossy(&output.stdout);
+    let mut lines = location.lines();
+    let cdup = lines.next().unwrap_or_default();
+    let prefix = lines.next().unwrap_or_default();
+
+    let output = Command::new("git")
+        .args(["status", "--porcelain=v1", "-z", "--untracked-files=all"])
+        .current_dir(root)
+        .output()?;
+    if !output.status.success() {
+        return Err(AxiomRegentError::InvalidArgument(format!(
+            "git status failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into());
+    }
+    let mut paths = HashSet::new();
+    let mut insert = |path: &[u8]| {
+        let path = String::from_utf8_lossy(path);
+        match path.strip_prefix(prefix) {
+            Some(rel) => paths.insert(rel.to_string()),
+            None => paths.insert(format!("{}{}", cdup, path)),
+        };
+    };
+    let mut records = output.stdout.split(|b| *b == 0);
+    while let Some(record) = records.next() {
+        if record.len() < 4 {
+            continue;
+        }
+        insert(&record[3..]);
+        // Renames and copies are followed by their source path.
+        if matches!(record[0], b'R' | b'C')
+            && let Some(source) = records.next()
+        {
+            insert(source);
+        }
+    }
+    Ok(Some(paths))
+}
+
+/// Whether the file or symlink at `path` already is `entry`: same content,
+/// executable bit and link target. False if `path` is missing.
+pub fn is_materialized(path: &Path, entry: &Entry) -> Result<bool> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    if meta.file_type().is_symlink() {
+        let target = fs::read_link(path)?.to_string_lossy().replace('\\', "/");
+        return Ok(entry.symlink.as_deref() == Some(target.as_str()));
+    }
+    Ok(meta.is_file()
+        && entry.symlink.is_none()
+        && meta.len() == entry.size
+        && is_executable(&meta) == entry.executable
+        && content_hash(&fs::read(path)?) == entry.blob)
+}
+
+/// Stores the file or symlink at `rel_path` under `root` and returns its
+/// manifest entry; `None` for directories and missing paths.
+///
+/// A symlink is stored as its target path, like git does.
+pub fn capture(store: &Store, root: &Path, rel_path: &str) -> Result<Option<Entry>> {
+    let path = root.join(rel_path);
+    let meta = match fs::symlink_metadata(&path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if meta.file_type().is_symlink() {
+        let target = fs::read_link(&path)?.to_string_lossy().replace('\\', "/");
+        let mut entry = store.put_content(rel_path, target.as_bytes())?;
+        entry.symlink = Some(target);
+        return Ok(Some(entry));
+    }
+    if !meta.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read(&path)?;
+    let mut entry = store.put_content(rel_path, &content)?;
+    entry.executable = is_executable(&meta);
+    Ok(Some(entry))
+}
+
+/// Reads the file or symlink at `path` as the entry of `rel_path`, like
+/// [`capture`] without storing it; `None` for directories and missing paths.
+pub fn read(path: &Path, rel_path: &str) -> Result<Option<(Entry, Vec<u8>)>> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let (content, executable, symlink) = if meta.file_type().is_symlink() {
+        let target = fs::read_link(path)?.to_string_lossy().replace('\\', "/");
+        (target.clone().into_bytes(), false, Some(target))
+    } else if meta.is_file() {
+        (fs::read(path)?, is_executable(&meta), None)
+    } else {
+        return Ok(None);
+    };
+    let entry = Entry {
+        blob: content_hash(&content),
+        path: rel_path.to_string(),
+        size: content.len() as u64,
+        chunks: Vec::new(),
+        executable,
+        symlink,
+    };
+    Ok(Some((entry, content)))
+}
+
+/// A captured worktree file, and whether its content had to be read.
+pub struct Captured {
+    pub entry: Entry,
+    pub hashed: bool,
+}
+
+/// Like [`capture`], but a regular file whose size, mtime, inode and
+/// executable bit match the stat cache of `root` reuses its cached entry
+/// without being read.
+pub fn capture_cached(store: &Store, root: &Path, rel_path: &str) -> Result<Option<Captured>> {
+    let path = root.join(rel_path);
+    let meta = match fs::symlink_metadata(&path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if !meta.is_file() {
+        let entry = capture(store, root, rel_path)?;
+        return Ok(entry.map(|entry| Captured {
+            entry,
+            hashed: true,
+        }));
+    }
+
+    let repo_key = root.to_string_lossy();
+    let stat = file_stat(&meta)?;
+    if let Some(entry) = store.cached_entry(&repo_key, rel_path, &stat)? {
+        return Ok(Some(Captured {
+            entry,
+            hashed: false,
+        }));
+    }
+    let content = fs::read(&path)?;
+    let mut entry = store.put_content(rel_path, &content)?;
+    entry.executable = stat.executable;
+    if meta
+        .modified()?
+        .elapsed()
+        .is_ok_and(|age| age >= RACY_WINDOW)
+    {
+        store.cache_entry(&repo_key, rel_path, &stat, &entry)?;
+    }
+    Ok(Some(Captured {
+        entry,
+        hashed: true,
+    }))
+}
+
+/// What the stat cache records of a file with metadata `meta`.
+pub fn file_stat(meta: &fs::Metadata) -> Result<FileStat> {
+    let mtime = meta
+        .modified()?
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(FileStat {
+        size: meta.len(),
+        mtime_ns: mtime.as_nanos() as i64,
+        inode: inode(meta),
+        executable: is_executable(meta),
+    })
+}
+
+#[cfg(unix)]
+fn inode(meta: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_meta: &fs::Metadata) -> u64 {
+    0
+}
+
+/// Writes `entry` under `root`, given its `content` as returned by
+/// [`Store::read_entry`]: a symlink, or a file with its executable bit.
+pub fn materialize(root: &Path, entry: &Entry, content: &[u8]) -> Result<()> {
+    let path = root.join(&entry.path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_entry(&path, entry, content)
+}
+
+/// Writes `entry` at `path`, whose directory must exist, like [`materialize`].
+pub fn write_entry(path: &Path, entry: &Entry, content: &[u8]) -> Result<()> {
+    if let Some(target) = &entry.symlink {
+        return symlink(target, path);
+    }
+    fs::write(path, content)?;
+    set_executable(path, entry.executable)
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = if executable { 0o755 } else { 0o644 };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &str, path: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(target, path)?;
+    Ok(())
+}
+
+// Without symlink support the link is written as a file holding its target,
+// as git does with `core.symlinks = false`.
+#[cfg(not(unix))]
+fn symlink(target: &str, path: &Path) -> Result<()> {
+    fs::write(path, target)?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: ENCORE_TS_INTEGRATION
+// Spec: spec/core/encore_ts.md
+
+//! MCP registration of the `encore.ts.*` tools.
+
+use crate::router::mounts::Capability;
+use crate::router::registry::{NoArgs, ToolRegistry};
+use crate::tools::encore_ts::tools::EncoreTools;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RootArgs {
+    pub root: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RunStartArgs {
+    pub root: String,
+    pub env: Option<HashMap<String, String>>,
+    pub profile: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RunIdArgs {
+    pub run_id: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct LogsStreamArgs {
+    pub run_id: String,
+    pub from_seq: Option<u64>,
+}
+
+/// Registers the `encore.ts.*` tools.
+pub fn register(registry: &mut ToolRegistry, tools: Arc<EncoreTools>) {
+    let t = tools.clone();
+    registry.tool(
+        "encore.ts.env.check",
+        "Check Encore TS environment",
+        Capability::Execute,
+        move |_, _: NoArgs| Ok(t.env_check()?.into()),
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "encore.ts.parse",
+        "Parse Encore TS application",
+        Capability::ReadFile,
+        move |_, a: RootArgs| Ok(t.parse(Path::new(&a.root))?.into()),
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "encore.ts.meta",
+        "Get Encore TS application metadata",
+        Capability::ReadFile,
+        move |_, a: RootArgs| Ok(t.meta(Path::new(&a.root))?.into()),
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "encore.ts.run.start",
+        "Start Encore TS application",
+        Capability::Execute,
+        move |ctx, a: RunStartArgs| {
+            let progress = ctx.progress();
+            Ok(
+                t.run_start(Path::new(&a.root), a.env, a.profile, &progress)?
+                    .into(),
+            )
+        },
+    );
+
+    let t = tools.clone();
+    registry.tool(
+        "encore.ts.run.stop",
+        "Stop Encore TS application",
+        Capability::Execute,
+        move |_, a: RunIdArgs| Ok(t.run_stop(&a.run_id)?.into()),
+    );
+
+    let t = tools;
+    registry.tool(
+        "encore.ts.logs.stream",
+        "Stream logs from Encore TS application",
+        Capability::ReadFile,
+        move |_, a: LogsStreamArgs| Ok(t.logs_stream(&a.run_id, a.from_seq)?.into()),
+    );
+}
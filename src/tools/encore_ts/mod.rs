@@ -4,6 +4,7 @@
 // Spec: spec/core/encore_ts.md
 
 pub mod env;
+pub mod mcp;
 pub mod parse;
 pub mod projection;
 pub mod run;
@@ -3,6 +3,7 @@
 // Feature: ENCORE_TS_INTEGRATION
 // Spec: spec/core/encore_ts.md
 
+use crate::router::AxiomRegentError;
 use crate::tools::encore_ts::schemas::{ApiInfo, MetaSnapshotV1, ServiceInfo};
 use anyhow::{Context, Result};
 use encore_tsparser::parser::parser::{ParseContext, Parser};
@@ -60,7 +61,11 @@ pub fn parse(root: &Path) -> Result<MetaSnapshotV1> {
             let result = parser.parse();
 
             if handler.has_errors() {
-                anyhow::bail!("Encore TS parsing failed with errors");
+                return Err(AxiomRegentError::InvalidArgument(
+                    "Encore TS parsing failed with errors".into(),
+                )
+                .with_path(root.to_string_lossy())
+                .into());
             }
 
             // Map ParseResult to MetaSnapshotV1
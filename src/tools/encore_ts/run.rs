@@ -3,8 +3,10 @@
 // Feature: ENCORE_TS_INTEGRATION
 // Spec: spec/core/encore_ts.md
 
+use crate::router::AxiomRegentError;
+use crate::router::progress::Progress;
 use crate::tools::encore_ts::state::{EncoreState, RunProcess};
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
@@ -14,11 +16,15 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use uuid::Uuid;
 
+/// Steps of a fresh start reported to `progress`.
+const START_STEPS: u64 = 3;
+
 pub fn start(
     state: &mut EncoreState,
     root: &Path,
     env: Option<HashMap<String, String>>,
     // profile: Option<String>, // TODO: Support profile in future
+    progress: &Progress,
 ) -> Result<String> {
     // Idempotency check
     let root_str = root.to_string_lossy().to_string();
@@ -57,6 +63,11 @@ pub fn start(
 
     let mut child = cmd.spawn().context("Failed to start 'encore run'")?;
     let pid = child.id();
+    progress.report(
+        1,
+        Some(START_STEPS),
+        &format!("Started encore run (pid {})", pid),
+    );
 
     // Create log buffer
     let log_buffer = Arc::new(Mutex::new(Vec::new()));
@@ -116,6 +127,8 @@ pub fn start(
         }
     });
 
+    progress.report(2, Some(START_STEPS), "Capturing logs");
+
     let process = RunProcess {
         pid,
         start_time: std::time::SystemTime::now(),
@@ -131,6 +144,7 @@ pub fn start(
     serde_json::to_writer_pretty(f, &process).context("Failed to write state file")?;
 
     state.processes.insert(run_id.clone(), process);
+    progress.report(3, Some(START_STEPS), "Run state written");
 
     Ok(run_id)
 }
@@ -146,7 +160,7 @@ pub fn stop(state: &mut EncoreState, run_id: &str) -> Result<bool> {
         }
         Ok(true)
     } else {
-        Err(anyhow!("Process not found: {}", run_id))
+        Err(AxiomRegentError::NotFound(format!("Process not found: {}", run_id)).into())
     }
 }
 
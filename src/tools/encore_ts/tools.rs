@@ -3,6 +3,8 @@
 // Feature: ENCORE_TS_INTEGRATION
 // Spec: spec/core/encore_ts.md
 
+use crate::router::AxiomRegentError;
+use crate::router::progress::Progress;
 use crate::tools::encore_ts::state::EncoreState;
 use anyhow::{Context, Result, anyhow};
 use serde_json::Value;
@@ -34,6 +36,9 @@ impl EncoreTools {
     }
 
     pub fn parse(&self, root: &Path) -> Result<Value> {
+        if !root.is_dir() {
+            return Err(AxiomRegentError::repo_not_found(root).into());
+        }
         let snapshot = crate::tools::encore_ts::parse::parse(root)?;
         Ok(serde_json::to_value(snapshot)?)
     }
@@ -47,12 +52,16 @@ impl EncoreTools {
         root: &Path,
         env: Option<HashMap<String, String>>,
         _profile: Option<String>,
+        progress: &Progress,
     ) -> Result<Value> {
+        if !root.is_dir() {
+            return Err(AxiomRegentError::repo_not_found(root).into());
+        }
         let mut state = self
             .state
             .lock()
             .map_err(|e| anyhow!("State lock failed: {}", e))?;
-        let run_id = crate::tools::encore_ts::run::start(&mut state, root, env)?;
+        let run_id = crate::tools::encore_ts::run::start(&mut state, root, env, progress)?;
         Ok(serde_json::json!({ "run_id": run_id }))
     }
 
@@ -107,7 +116,7 @@ impl EncoreTools {
                 let next_seq = lines.len();
                 Ok(serde_json::json!({ "logs": logs, "next_seq": next_seq }))
             } else {
-                Err(anyhow!("Process not found: {}", run_id))
+                Err(AxiomRegentError::NotFound(format!("Process not found: {}", run_id)).into())
             }
         }
     }
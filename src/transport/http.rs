@@ -0,0 +1,454 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+//! Streamable HTTP transport.
+//!
+//! A single `/mcp` endpoint speaks MCP over HTTP/1.1:
+//! - `POST /mcp` carries a JSON-RPC message or batch and answers with `application/json`.
+//! - `GET /mcp` (with `Accept: text/event-stream`) opens a Server-Sent Events stream
+//!   on which server-initiated notifications are delivered. A stream opened with
+//!   an `Mcp-Session-Id` gets the notifications of that session's requests.
+//! - `DELETE /mcp` ends the session named by the `Mcp-Session-Id` header.
+//!
+//! Every POSTed message goes through `Router::handle_session_message` with the
+//! request's `Mcp-Session-Id`, which admits and dispatches it as the stdio loop
+//! does. The listener binds to loopback, or to a Unix socket on Unix platforms,
+//! unless the caller explicitly allows remote addresses.
+
+use crate::router::Router;
+use anyhow::{Result, anyhow};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const ENDPOINT: &str = "/mcp";
+const SESSION_HEADER: &str = "mcp-session-id";
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Delivers server-initiated notifications to the open SSE streams.
+///
+/// Each stream belongs to the `Mcp-Session-Id` it was opened with (`""` if it
+/// had none) and only receives notifications for that session, plus those
+/// meant for every session.
+#[derive(Clone, Default)]
+pub struct EventHub {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+/// An SSE stream: its session and the channel feeding it.
+type Subscriber = (String, Sender<String>);
+
+impl EventHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends a JSON-RPC notification to the SSE clients of `session`, or to all
+    /// of them if `session` is `None`. Streams whose client went away are pruned.
+    pub fn publish(&self, session: Option<&str>, message: &Value) {
+        let payload = message.to_string();
+        let mut subs = self.subscribers.lock().unwrap();
+        subs.retain(|(sid, tx)| {
+            session.is_some_and(|s| s != sid) || tx.send(payload.clone()).is_ok()
+        });
+    }
+
+    fn subscribe(&self, session: &str) -> mpsc::Receiver<String> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap()
+            .push((session.to_string(), tx));
+        rx
+    }
+
+    /// Ends the streams of `session`.
+    fn close(&self, session: &str) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|(sid, _)| sid != session);
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+pub struct HttpServer {
+    listener: Listener,
+    hub: EventHub,
+    sessions: Arc<Mutex<HashSet<String>>>,
+}
+
+impl HttpServer {
+    /// Binds the listener.
+    ///
+    /// `listen` is either `host:port` or, on Unix, `unix:<path>`. Non-loopback
+    /// TCP addresses are refused unless `allow_remote` is set.
+    pub fn bind(listen: &str, allow_remote: bool) -> Result<Self> {
+        let listener = if let Some(path) = listen.strip_prefix("unix:") {
+            bind_unix(path)?
+        } else {
+            let addr: SocketAddr = listen
+                .parse()
+                .map_err(|e| anyhow!("Invalid listen address '{}': {}", listen, e))?;
+            if !addr.ip().is_loopback() && !allow_remote {
+                return Err(anyhow!(
+                    "Refusing to bind non-loopback address {} (set AXIOMREGENT_HTTP_ALLOW_REMOTE=1 to override)",
+                    addr
+                ));
+            }
+            Listener::Tcp(TcpListener::bind(addr)?)
+        };
+
+        Ok(Self {
+            listener,
+            hub: EventHub::new(),
+            sessions: Arc::new(Mutex::new(HashSet::new())),
+        })
+    }
+
+    /// Human-readable address, e.g. `127.0.0.1:8765` or `unix:/tmp/mcp.sock`.
+    pub fn local_addr(&self) -> Result<String> {
+        match &self.listener {
+            Listener::Tcp(l) => Ok(l.local_addr()?.to_string()),
+            #[cfg(unix)]
+            Listener::Unix(_, path) => Ok(format!("unix:{}", path.display())),
+        }
+    }
+
+    /// Handle for publishing notifications to connected SSE clients.
+    pub fn events(&self) -> EventHub {
+        self.hub.clone()
+    }
+
+    /// Accepts connections forever, one thread per connection.
+    pub fn serve(self, router: Arc<Router>) -> Result<()> {
+        match &self.listener {
+            Listener::Tcp(l) => {
+                for stream in l.incoming() {
+                    match stream {
+                        Ok(s) => self.spawn_connection(s, router.clone()),
+                        Err(e) => log::warn!("Failed to accept connection: {}", e),
+                    }
+                }
+            }
+            #[cfg(unix)]
+            Listener::Unix(l, _) => {
+                for stream in l.incoming() {
+                    match stream {
+                        Ok(s) => self.spawn_connection(s, router.clone()),
+                        Err(e) => log::warn!("Failed to accept connection: {}", e),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn spawn_connection<S: Read + Write + Send + 'static>(&self, stream: S, router: Arc<Router>) {
+        let conn = Connection {
+            router,
+            hub: self.hub.clone(),
+            sessions: self.sessions.clone(),
+        };
+        thread::spawn(move || {
+            if let Err(e) = conn.handle(stream) {
+                log::debug!("HTTP connection closed with error: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(unix)]
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        if let Listener::Unix(_, path) = &self.listener {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &str) -> Result<Listener> {
+    let path = PathBuf::from(path);
+    remove_stale_socket(&path)?;
+    Ok(Listener::Unix(UnixListener::bind(&path)?, path))
+}
+
+#[cfg(not(unix))]
+fn bind_unix(path: &str) -> Result<Listener> {
+    Err(anyhow!(
+        "Unix socket listeners are not supported on this platform: unix:{}",
+        path
+    ))
+}
+
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(anyhow!(
+                "Refusing to replace non-socket file at {}",
+                path.display()
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+struct Connection {
+    router: Arc<Router>,
+    hub: EventHub,
+    sessions: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Connection {
+    fn handle<S: Read + Write>(&self, stream: S) -> Result<()> {
+        let mut reader = BufReader::new(stream);
+        let Some(req) = read_request(&mut reader)? else {
+            return Ok(());
+        };
+        let mut stream = reader.into_inner();
+
+        if !origin_allowed(req.header("origin")) {
+            return write_response(&mut stream, 403, "text/plain", &[], b"Forbidden origin");
+        }
+
+        let path = req.path.split('?').next().unwrap_or("");
+        if path != ENDPOINT {
+            return write_response(&mut stream, 404, "text/plain", &[], b"Not found");
+        }
+
+        if let Some(sid) = req.header(SESSION_HEADER)
+            && !self.sessions.lock().unwrap().contains(sid)
+        {
+            return write_response(&mut stream, 404, "text/plain", &[], b"Unknown session");
+        }
+
+        match req.method.as_str() {
+            "POST" => self.handle_post(&mut stream, &req),
+            "GET" => self.handle_sse(&mut stream, &req),
+            "DELETE" => {
+                if let Some(sid) = req.header(SESSION_HEADER) {
+                    self.sessions.lock().unwrap().remove(sid);
+                    self.hub.close(sid);
+                    self.router.end_session(sid);
+                }
+                write_response(&mut stream, 204, "text/plain", &[], b"")
+            }
+            _ => write_response(&mut stream, 405, "text/plain", &[], b"Method not allowed"),
+        }
+    }
+
+    fn handle_post<W: Write>(&self, stream: &mut W, req: &HttpRequest) -> Result<()> {
+        let Ok(payload) = std::str::from_utf8(&req.body) else {
+            return write_response(stream, 400, "text/plain", &[], b"Body must be UTF-8");
+        };
+
+        // Request ids, and the cancellations naming them, belong to the session.
+        let session = req.header(SESSION_HEADER);
+
+        // Each connection has its own thread, so a cancellation posted on another
+        // connection reaches the call while it runs; the router serializes
+        // mutations per repo.
+        let response = self.router.handle_session_message(session, payload);
+
+        // Notifications carry no id and get no JSON-RPC response.
+        let Some(response) = response else {
+            return write_response(stream, 202, "text/plain", &[], b"");
+        };
+
+        let status = if response.pointer("/error/code") == Some(&json!(-32700)) {
+            400
+        } else {
+            200
+        };
+
+        let mut extra = Vec::new();
+        if is_initialize(payload) && response.get("result").is_some_and(|r| !r.is_null()) {
+            let sid = uuid::Uuid::new_v4().to_string();
+            self.sessions.lock().unwrap().insert(sid.clone());
+            extra.push(("Mcp-Session-Id".to_string(), sid));
+        }
+
+        let body = serde_json::to_vec(&response)?;
+        write_response(stream, status, "application/json", &extra, &body)
+    }
+
+    fn handle_sse<W: Write>(&self, stream: &mut W, req: &HttpRequest) -> Result<()> {
+        let accepts_sse = req
+            .header("accept")
+            .map(|a| a.contains("text/event-stream"))
+            .unwrap_or(false);
+        if !accepts_sse {
+            return write_response(
+                stream,
+                405,
+                "text/plain",
+                &[],
+                b"SSE requires Accept: text/event-stream",
+            );
+        }
+
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n"
+        )?;
+        stream.flush()?;
+
+        let rx = self
+            .hub
+            .subscribe(req.header(SESSION_HEADER).unwrap_or_default());
+        loop {
+            match rx.recv_timeout(KEEPALIVE_INTERVAL) {
+                Ok(payload) => write!(stream, "event: message\ndata: {}\n\n", payload)?,
+                Err(RecvTimeoutError::Timeout) => write!(stream, ": keepalive\n\n")?,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+            // A failed flush means the client went away; ending the loop drops
+            // the receiver so the hub prunes this subscriber.
+            stream.flush()?;
+        }
+    }
+}
+
+fn is_initialize(payload: &str) -> bool {
+    serde_json::from_str::<Value>(payload)
+        .ok()
+        .and_then(|v| {
+            v.get("method")
+                .and_then(|m| m.as_str())
+                .map(|m| m == "initialize")
+        })
+        .unwrap_or(false)
+}
+
+/// Browsers send `Origin`; only loopback origins may talk to a local server.
+/// Non-browser clients omit the header and are allowed.
+fn origin_allowed(origin: Option<&str>) -> bool {
+    let Some(origin) = origin else {
+        return true;
+    };
+    let Ok(url) = url::Url::parse(origin) else {
+        return false;
+    };
+    matches!(
+        url.host_str(),
+        Some("localhost") | Some("127.0.0.1") | Some("[::1]")
+    )
+}
+
+fn read_request<R: BufRead>(r: &mut R) -> Result<Option<HttpRequest>> {
+    let mut request_line = String::new();
+    if r.read_line(&mut request_line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts
+        .next()
+        .ok_or_else(|| anyhow!("Malformed request line"))?
+        .to_string();
+    let path = parts
+        .next()
+        .ok_or_else(|| anyhow!("Malformed request line"))?
+        .to_string();
+
+    let mut headers = Vec::new();
+    let mut content_length = 0usize;
+    loop {
+        let mut line = String::new();
+        if r.read_line(&mut line)? == 0 {
+            return Err(anyhow!("EOF while reading HTTP headers"));
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        if let Some((k, v)) = line.split_once(':') {
+            let k = k.trim().to_ascii_lowercase();
+            let v = v.trim().to_string();
+            if k == "content-length" {
+                content_length = v
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid Content-Length value: {}", v))?;
+            }
+            headers.push((k, v));
+        }
+    }
+
+    if content_length > MAX_BODY_BYTES {
+        return Err(anyhow!("Request body too large: {} bytes", content_length));
+    }
+    let mut body = vec![0u8; content_length];
+    r.read_exact(&mut body)?;
+
+    Ok(Some(HttpRequest {
+        method,
+        path,
+        headers,
+        body,
+    }))
+}
+
+fn write_response<W: Write>(
+    w: &mut W,
+    status: u16,
+    content_type: &str,
+    extra_headers: &[(String, String)],
+    body: &[u8],
+) -> Result<()> {
+    let reason = match status {
+        200 => "OK",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Error",
+    };
+    write!(w, "HTTP/1.1 {} {}\r\n", status, reason)?;
+    write!(w, "Content-Type: {}\r\n", content_type)?;
+    write!(w, "Content-Length: {}\r\n", body.len())?;
+    for (k, v) in extra_headers {
+        write!(w, "{}: {}\r\n", k, v)?;
+    }
+    write!(w, "Connection: close\r\n\r\n")?;
+    w.write_all(body)?;
+    w.flush()?;
+    Ok(())
+}
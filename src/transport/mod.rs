@@ -0,0 +1,7 @@
+//! Alternative transports for the MCP router.
+//!
+//! The stdio loop lives in `main.rs`; transports here reuse
+//! `Router::handle_request` unchanged.
+
+#[cfg(feature = "transport-http")]
+pub mod http;
@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! MCP registration of the `workspace.*` tools.
+
+use crate::router::mounts::Capability;
+use crate::router::registry::{ToolOutput, ToolRegistry};
+use crate::snapshot::mcp::Mode;
+use crate::workspace::WorkspaceTools;
+use crate::workspace::transaction::Operation;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct WriteFileArgs {
+    pub repo_root: String,
+    pub path: String,
+    pub content_base64: String,
+    pub lease_id: String,
+    #[serde(default)]
+    pub create_dirs: bool,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeleteArgs {
+    pub repo_root: String,
+    pub path: String,
+    pub lease_id: String,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ApplyPatchArgs {
+    pub repo_root: String,
+    pub patch: String,
+    pub mode: Mode,
+    pub lease_id: Option<String>,
+    pub snapshot_id: Option<String>,
+    pub strip: Option<usize>,
+    #[serde(default)]
+    pub reject_on_conflict: bool,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RestoreArgs {
+    pub repo_root: String,
+    pub snapshot_id: String,
+    pub lease_id: String,
+    /// Files or directories to restore; all files of the snapshot when unset. A file the snapshot lacks is deleted.
+    pub paths: Option<Vec<String>>,
+    /// Overwrite files with uncommitted changes.
+    #[serde(default)]
+    pub force: bool,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct TransactionArgs {
+    pub repo_root: String,
+    pub lease_id: String,
+    /// Edits applied in order: all of them, or none if any fails.
+    pub operations: Vec<Operation>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Registers the `workspace.*` tools.
+pub fn register(registry: &mut ToolRegistry, tools: Arc<WorkspaceTools>) {
+    let t = tools.clone();
+    registry.mutation(
+        "workspace.write_file",
+        "Write file content",
+        Capability::WriteFile,
+        move |_, a: WriteFileArgs| {
+            ToolOutput::from_flag(t.write_file(
+                Path::new(&a.repo_root),
+                &a.path,
+                &a.content_base64,
+                Some(a.lease_id),
+                a.create_dirs,
+                a.dry_run,
+            )?)
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "workspace.delete",
+        "Delete a file or directory",
+        Capability::WriteFile,
+        move |_, a: DeleteArgs| {
+            ToolOutput::from_flag(t.delete(
+                Path::new(&a.repo_root),
+                &a.path,
+                Some(a.lease_id),
+                a.dry_run,
+            )?)
+        },
+    );
+
+    let t = tools.clone();
+    registry.mutation(
+        "workspace.apply_patch",
+        "Apply a patch",
+        Capability::WriteFile,
+        move |_, a: ApplyPatchArgs| {
+            Ok(t.apply_patch(
+                Path::new(&a.repo_root),
+                &a.patch,
+                a.mode.as_str(),
+                a.lease_id,
+                a.snapshot_id,
+                a.strip,
+                a.reject_on_conflict,
+                a.dry_run,
+            )?
+            .into())
+        },
+    );
+    let t = tools.clone();
+    registry.mutation(
+        "workspace.restore",
+        "Write a snapshot's files back into the worktree",
+        Capability::WriteFile,
+        move |_, a: RestoreArgs| {
+            Ok(t.restore(
+                Path::new(&a.repo_root),
+                &a.snapshot_id,
+                Some(a.lease_id),
+                a.paths,
+                a.force,
+                a.dry_run,
+            )?
+            .into())
+        },
+    );
+
+    let t = tools;
+    registry.mutation(
+        "workspace.transaction",
+        "Apply several file edits all-or-nothing",
+        Capability::WriteFile,
+        move |_, a: TransactionArgs| {
+            Ok(t.transaction(
+                Path::new(&a.repo_root),
+                &a.operations,
+                Some(a.lease_id),
+                a.dry_run,
+            )?
+            .into())
+        },
+    );
+}
@@ -3,13 +3,22 @@
 // Feature: MCP_SNAPSHOT_WORKSPACE
 // Spec: spec/core/snapshot-workspace.md
 
-use crate::snapshot::lease::Fingerprint;
+pub mod mcp;
+pub mod transaction;
+
+use crate::router::AxiomRegentError;
 use crate::snapshot::lease::LeaseStore;
-use crate::snapshot::store::Store;
+use crate::snapshot::refs;
+use crate::snapshot::store::{Entry, Store};
+use crate::snapshot::worktree::{self, Scope};
 use anyhow::{Context, Result, anyhow};
+use serde_json::json;
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use uuid::Uuid;
 
 pub struct WorkspaceTools {
     pub lease_store: Arc<LeaseStore>,
@@ -37,25 +46,37 @@ impl WorkspaceTools {
                 .canonicalize()
                 .context("Failed to canonicalize target path")?;
             if !canonical_path.starts_with(&canonical_root) {
-                return Err(anyhow!("Path escapes repo root: {}", rel_path));
+                return Err(AxiomRegentError::PermissionDenied(format!(
+                    "Path escapes repo root: {}",
+                    rel_path
+                ))
+                .with_path(rel_path)
+                .into());
             }
             Ok(canonical_path)
         } else {
             // Path doesn't exist. Check parent.
-            let parent = path.parent().ok_or_else(|| anyhow!("Invalid path"))?;
+            let parent = path.parent().ok_or_else(|| {
+                AxiomRegentError::InvalidArgument("Invalid path".into()).with_path(rel_path)
+            })?;
             if parent.exists() {
                 let canonical_parent = parent
                     .canonicalize()
                     .context("Failed to canonicalize parent")?;
                 if !canonical_parent.starts_with(&canonical_root) {
-                    return Err(anyhow!("Parent path escapes repo root: {}", rel_path));
+                    return Err(AxiomRegentError::PermissionDenied(format!(
+                        "Parent path escapes repo root: {}",
+                        rel_path
+                    ))
+                    .with_path(rel_path)
+                    .into());
                 }
                 // Return the joined path (since we can't canonicalize non-existent file)
                 // But we should use the canonical parent + filename to be safe against some symlink tricks?
                 // join filename
-                let filename = path
-                    .file_name()
-                    .ok_or_else(|| anyhow!("Invalid filename"))?;
+                let filename = path.file_name().ok_or_else(|| {
+                    AxiomRegentError::InvalidArgument("Invalid filename".into()).with_path(rel_path)
+                })?;
                 Ok(canonical_parent.join(filename))
             } else {
                 // Parent doesn't exist. Strict safety: reject deep creation unless create_dirs=true?
@@ -73,7 +94,12 @@ impl WorkspaceTools {
                 }
                 let canonical_base = current.canonicalize()?;
                 if !canonical_base.starts_with(&canonical_root) {
-                    return Err(anyhow!("Path escapes repo root: {}", rel_path));
+                    return Err(AxiomRegentError::PermissionDenied(format!(
+                        "Path escapes repo root: {}",
+                        rel_path
+                    ))
+                    .with_path(rel_path)
+                    .into());
                 }
 
                 // If base safe, and we assume we are not following symlinks in non-existent components (obviously), it is safe.
@@ -99,8 +125,12 @@ impl WorkspaceTools {
         dry_run: bool,
     ) -> Result<serde_json::Value> {
         if mode == "worktree" {
-            let lid = lease_id.ok_or_else(|| anyhow!("lease_id required"))?;
+            let lid = lease_id
+                .ok_or_else(|| AxiomRegentError::InvalidArgument("lease_id required".into()))?;
+            let base = self.lease_store.write_base(&lid, repo_root)?;
             self.lease_store.check_lease(&lid, repo_root)?;
+            self.lease_store
+                .check_reserved(&lid, &parse_patch_paths(patch))?;
 
             let mut cmd = std::process::Command::new("git");
             cmd.arg("apply");
@@ -136,9 +166,14 @@ impl WorkspaceTools {
             if output.status.success() {
                 // Touched files
                 let touched = parse_patch_touched_files(patch);
-                self.lease_store.touch_files(&lid, touched.clone());
+                self.lease_store.touch_files(&lid, touched.clone())?;
 
-                let new_fingerprint = Fingerprint::compute(repo_root)?;
+                let new_fingerprint = if dry_run {
+                    self.lease_store.fingerprint(repo_root)?
+                } else {
+                    self.lease_store
+                        .refresh_after_write(&lid, repo_root, base)?
+                };
 
                 let applied_value: Vec<serde_json::Value> = touched
                     .iter()
@@ -181,20 +216,22 @@ impl WorkspaceTools {
                     "applied": [],
                     "rejects": rejects,
                     "lease_id": lid,
-                    "fingerprint": Fingerprint::compute(repo_root)?, // State didn't change ideally
+                    "fingerprint": self.lease_store.fingerprint(repo_root)?, // State didn't change ideally
                     "cache_key": "conflict",
                     "cache_hint": "until_dirty"
                 }))
             }
         } else if mode == "snapshot" {
-            let snap_id = _snapshot_id.ok_or_else(|| anyhow!("snapshot_id required"))?;
+            let snap_id = _snapshot_id
+                .ok_or_else(|| AxiomRegentError::InvalidArgument("snapshot_id required".into()))?;
+            let snap_id = refs::resolve(&self.store, repo_root, &snap_id)?;
             self.store.validate_snapshot(&snap_id)?;
 
             // Retrieve base snapshot metadata for provenance/determinism
-            let base_info = self
-                .store
-                .get_snapshot_info(&snap_id)?
-                .ok_or_else(|| anyhow::anyhow!("Snapshot metadata not found for {}", snap_id))?;
+            let base_info = self.store.get_snapshot_info(&snap_id)?.ok_or_else(|| {
+                AxiomRegentError::NotFound(format!("Snapshot metadata not found for {}", snap_id))
+                    .with_snapshot(&snap_id)
+            })?;
 
             // 1. Materialize to temp dir
             let temp = tempfile::tempdir()?;
@@ -202,14 +239,16 @@ impl WorkspaceTools {
             let entries = self.store.list_snapshot_entries(&snap_id)?;
 
             for entry in entries {
-                if let Some(content) = self.store.get_blob(&entry.blob)? {
-                    let full_path = temp_path.join(&entry.path);
-                    if let Some(parent) = full_path.parent() {
-                        std::fs::create_dir_all(parent)?;
-                    }
-                    std::fs::write(&full_path, content)?;
+                if let Some(content) = self.store.read_entry(&entry)? {
+                    worktree::materialize(temp_path, &entry, &content)?;
                 } else {
-                    return Err(anyhow::anyhow!("Missing blob for {}", entry.path));
+                    return Err(AxiomRegentError::Internal(format!(
+                        "Missing blob for {}",
+                        entry.path
+                    ))
+                    .with_path(&entry.path)
+                    .with_snapshot(&snap_id)
+                    .into());
                 }
             }
 
@@ -241,19 +280,16 @@ impl WorkspaceTools {
                 let mut new_entries = Vec::new();
                 for entry in WalkDir::new(temp_path) {
                     let entry = entry?;
-                    if entry.file_type().is_file() {
+                    if !entry.file_type().is_dir() {
                         let path = entry.path();
                         let rel_path = path.strip_prefix(temp_path)?.to_str().unwrap().to_string();
                         // Validate path safety/ignored? Assuming temp dir is controlled.
 
-                        let content = std::fs::read(path)?;
-                        let blob_id = self.store.put_blob(&content)?;
-
-                        new_entries.push(crate::snapshot::store::Entry {
-                            path: rel_path,
-                            blob: blob_id,
-                            size: content.len() as u64,
-                        });
+                        if let Some(captured) =
+                            worktree::capture(&self.store, temp_path, &rel_path)?
+                        {
+                            new_entries.push(captured);
+                        }
                     }
                 }
 
@@ -269,7 +305,7 @@ impl WorkspaceTools {
                 let patch_hash =
                     format!("sha256:{}", hex::encode(Sha256::digest(patch.as_bytes())));
 
-                self.store.put_snapshot(
+                self.store.put_scoped_snapshot(
                     &new_snap_id,
                     &base_info.repo_root,        // Preserve base repo_root
                     &base_info.head_sha,         // Preserve base head_sha
@@ -278,6 +314,7 @@ impl WorkspaceTools {
                     Some(&snap_id),    // derived_from
                     Some(&patch_hash), // applied_patch_hash
                     None,              // label
+                    base_info.scope,   // Covers the files the base covers
                 )?;
 
                 Ok(serde_json::json!({
@@ -297,7 +334,7 @@ impl WorkspaceTools {
                 }))
             }
         } else {
-            Err(anyhow!("Invalid mode"))
+            Err(AxiomRegentError::InvalidArgument("Invalid mode".into()).into())
         }
     }
 
@@ -310,20 +347,15 @@ impl WorkspaceTools {
         create_dirs: bool,
         dry_run: bool,
     ) -> Result<bool> {
-        let lid = lease_id.ok_or_else(|| anyhow!("lease_id required"))?;
+        let lid = lease_id
+            .ok_or_else(|| AxiomRegentError::InvalidArgument("lease_id required".into()))?;
+        let base = self.lease_store.write_base(&lid, repo_root)?;
         self.lease_store.check_lease(&lid, repo_root)?;
+        self.lease_store.check_reserved(&lid, &[path.to_string()])?;
 
         let target = self.resolve_target_path(repo_root, path)?;
 
-        use base64::{Engine as _, engine::general_purpose};
-        let content = if let Some(rest) = content_base64.strip_prefix("base64:") {
-            general_purpose::STANDARD
-                .decode(rest)
-                .context("Invalid base64 content")?
-        } else {
-            // accept plain text
-            content_base64.as_bytes().to_vec()
-        };
+        let content = decode_content(content_base64)?;
 
         if let Some(parent) = target.parent()
             && !parent.exists()
@@ -333,15 +365,19 @@ impl WorkspaceTools {
                     std::fs::create_dir_all(parent)?;
                 }
             } else {
-                return Err(anyhow!(
-                    "Parent directory does not exist (set create_dirs=true)"
-                ));
+                return Err(AxiomRegentError::NotFound(
+                    "Parent directory does not exist (set create_dirs=true)".into(),
+                )
+                .with_path(path)
+                .into());
             }
         }
 
         if !dry_run {
             std::fs::write(&target, content)?;
-            self.lease_store.touch_files(&lid, vec![path.to_string()]);
+            self.lease_store.touch_files(&lid, vec![path.to_string()])?;
+            self.lease_store
+                .refresh_after_write(&lid, repo_root, base)?;
         }
 
         Ok(true)
@@ -354,13 +390,18 @@ impl WorkspaceTools {
         lease_id: Option<String>,
         dry_run: bool,
     ) -> Result<bool> {
-        let lid = lease_id.ok_or_else(|| anyhow!("lease_id required"))?;
+        let lid = lease_id
+            .ok_or_else(|| AxiomRegentError::InvalidArgument("lease_id required".into()))?;
+        let base = self.lease_store.write_base(&lid, repo_root)?;
         self.lease_store.check_lease(&lid, repo_root)?; // Verify at start
+        self.lease_store.check_reserved(&lid, &[path.to_string()])?;
 
         let target = self.resolve_target_path(repo_root, path)?;
 
         if !target.exists() {
-            return Err(anyhow!("File not found"));
+            return Err(AxiomRegentError::NotFound("File not found".into())
+                .with_path(path)
+                .into());
         }
 
         if !dry_run {
@@ -369,15 +410,341 @@ impl WorkspaceTools {
             } else {
                 std::fs::remove_file(&target)?;
             }
-            self.lease_store.touch_files(&lid, vec![path.to_string()]);
+            self.lease_store.touch_files(&lid, vec![path.to_string()])?;
+            self.lease_store
+                .refresh_after_write(&lid, repo_root, base)?;
         }
 
         Ok(true)
     }
+
+    /// Writes the files of snapshot `snapshot_id` back into the worktree:
+    /// all of them, or those at or under `paths`. Worktree files the snapshot
+    /// lacks are deleted: the requested paths or, without `paths`, the files
+    /// of the scope a `worktree` or `tracked` snapshot was captured with.
+    /// Other snapshots may hold only some files, so nothing else is deleted.
+    ///
+    /// Files with uncommitted changes (per `git status`) are only replaced
+    /// with `force`. Every file is staged next to its target before any is
+    /// replaced; should replacing them fail part way, the files already
+    /// replaced or deleted are put back.
+    pub fn restore(
+        &self,
+        repo_root: &Path,
+        snapshot_id: &str,
+        lease_id: Option<String>,
+        paths: Option<Vec<String>>,
+        force: bool,
+        dry_run: bool,
+    ) -> Result<serde_json::Value> {
+        let lid = lease_id
+            .ok_or_else(|| AxiomRegentError::InvalidArgument("lease_id required".into()))?;
+        let base = self.lease_store.write_base(&lid, repo_root)?;
+        self.lease_store.check_lease(&lid, repo_root)?;
+
+        // Only resolves snapshots of this repository.
+        let snap_id = refs::resolve(&self.store, repo_root, snapshot_id)?;
+        let info = self.store.get_snapshot_info(&snap_id)?.ok_or_else(|| {
+            AxiomRegentError::NotFound(format!("Snapshot not found: {}", snap_id))
+                .with_snapshot(&snap_id)
+        })?;
+        self.store.validate_snapshot(&snap_id)?;
+
+        let entries = self.store.list_snapshot_entries(&snap_id)?;
+        // Snapshot entries to write, and worktree paths to delete.
+        let (selected, removed): (Vec<Entry>, Vec<String>) = match &paths {
+            None => {
+                let known: HashSet<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+                let files = match info.scope {
+                    Some(Scope::Worktree) => worktree::list_files(repo_root)?,
+                    Some(Scope::Tracked) => worktree::list_tracked_files(repo_root)?,
+                    Some(Scope::Touched) | None => Vec::new(),
+                };
+                // The index may list files already deleted from the worktree.
+                let removed = files
+                    .into_iter()
+                    .filter(|p| !known.contains(p.as_str()))
+                    .filter(|p| fs::symlink_metadata(repo_root.join(p)).is_ok())
+                    .collect();
+                (entries, removed)
+            }
+            Some(paths) => {
+                let mut removed = Vec::new();
+                for p in paths {
+                    Store::validate_path(p)?;
+                    if entries.iter().any(|e| is_within(&e.path, p)) {
+                        continue;
+                    }
+                    if fs::symlink_metadata(repo_root.join(p)).is_ok_and(|m| !m.is_dir()) {
+                        removed.push(p.clone());
+                    } else {
+                        return Err(AxiomRegentError::NotFound(
+                            "Path is neither in the snapshot nor a file of the worktree".into(),
+                        )
+                        .with_path(p)
+                        .with_snapshot(&snap_id)
+                        .into());
+                    }
+                }
+                let selected = entries
+                    .into_iter()
+                    .filter(|e| paths.iter().any(|p| is_within(&e.path, p)))
+                    .collect();
+                (selected, removed)
+            }
+        };
+
+        // Plan: what each path becomes, and which of them hold uncommitted work.
+        let dirty_files = worktree::dirty_files(repo_root)?;
+        let mut files = Vec::new();
+        let mut writes = Vec::new();
+        let mut deletes = Vec::new();
+        let mut dirty = Vec::new();
+        let mut unchanged = 0;
+        for entry in selected {
+            let target = self.restore_target(repo_root, &entry.path)?;
+            if worktree::is_materialized(&target, &entry)? {
+                unchanged += 1;
+                continue;
+            }
+            let action = match fs::symlink_metadata(&target) {
+                Ok(meta) if meta.is_dir() => {
+                    return Err(AxiomRegentError::InvalidArgument(
+                        "A directory is in the way of a snapshot file".into(),
+                    )
+                    .with_path(&entry.path)
+                    .into());
+                }
+                Ok(_) => "overwrite",
+                Err(_) => "create",
+            };
+            if action == "overwrite" && dirty_files.contains(&entry.path) {
+                dirty.push(entry.path.clone());
+            }
+            files.push(json!({ "path": entry.path, "action": action }));
+            writes.push((target, entry));
+        }
+        for path in removed {
+            if dirty_files.contains(&path) {
+                dirty.push(path.clone());
+            }
+            files.push(json!({ "path": path, "action": "delete" }));
+            deletes.push((self.restore_target(repo_root, &path)?, path));
+        }
+        let planned: Vec<String> = writes
+            .iter()
+            .map(|(_, entry)| entry.path.clone())
+            .chain(deletes.iter().map(|(_, path)| path.clone()))
+            .collect();
+        self.lease_store.check_reserved(&lid, &planned)?;
+
+        if dry_run {
+            return Ok(json!({
+                "snapshot_id": snap_id,
+                "files": files,
+                "unchanged": unchanged,
+                "dirty": dirty,
+                "dry_run": true
+            }));
+        }
+        if !dirty.is_empty() && !force {
+            return Err(AxiomRegentError::InvalidArgument(format!(
+                "Refusing to overwrite uncommitted changes (pass force): {}",
+                dirty.join(", ")
+            ))
+            .with_path(&dirty[0])
+            .into());
+        }
+
+        // The files about to change, as they are now, to roll back to.
+        let mut before = Vec::new();
+        for path in &planned {
+            if let Some(entry) = worktree::capture(&self.store, repo_root, path)? {
+                before.push(entry);
+            }
+        }
+
+        // Stage every file first: until the renames nothing is replaced.
+        let mut staged = Vec::new();
+        for (target, entry) in &writes {
+            match self.stage(target, entry) {
+                Ok(tmp) => staged.push(tmp),
+                Err(e) => {
+                    for tmp in &staged {
+                        let _ = fs::remove_file(tmp);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        let replacements: Vec<(&Path, &str, &Path)> = writes
+            .iter()
+            .zip(&staged)
+            .map(|((target, entry), tmp)| (target.as_path(), entry.path.as_str(), tmp.as_path()))
+            .collect();
+        if let Err(failure) = self.replace_files(&replacements, &deletes, &before) {
+            let message = match failure.rolled_back {
+                Ok(()) => format!(
+                    "Restore rolled back after failing on {}: {}",
+                    failure.path, failure.error
+                ),
+                Err(rollback) => format!(
+                    "Restore failed on {} ({}) and could not be rolled back ({})",
+                    failure.path, failure.error, rollback
+                ),
+            };
+            return Err(AxiomRegentError::Internal(message)
+                .with_path(&failure.path)
+                .into());
+        }
+        let touched = planned;
+        self.lease_store.touch_files(&lid, touched.clone())?;
+        let fingerprint = self
+            .lease_store
+            .refresh_after_write(&lid, repo_root, base)?;
+
+        Ok(json!({
+            "snapshot_id": snap_id,
+            "files": files,
+            "unchanged": unchanged,
+            "dirty": dirty,
+            "dry_run": false,
+            "lease_id": lid,
+            "fingerprint": fingerprint
+        }))
+    }
+
+    /// Where `rel_path` lives in the worktree. Unlike [`Self::resolve_target_path`]
+    /// a symlink at `rel_path` itself is not followed, as it gets replaced.
+    fn restore_target(&self, repo_root: &Path, rel_path: &str) -> Result<PathBuf> {
+        Store::validate_path(rel_path)?;
+        let rel = Path::new(rel_path);
+        let name = rel.file_name().ok_or_else(|| {
+            AxiomRegentError::InvalidArgument("Invalid filename".into()).with_path(rel_path)
+        })?;
+        let dir = match rel.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                self.resolve_target_path(repo_root, &parent.to_string_lossy())?
+            }
+            _ => repo_root.canonicalize()?,
+        };
+        Ok(dir.join(name))
+    }
+
+    /// Writes `entry` to a temporary file next to `target`, creating its
+    /// directory, and returns the temporary path.
+    fn stage(&self, target: &Path, entry: &Entry) -> Result<PathBuf> {
+        let content = self.store.read_entry(entry)?.ok_or_else(|| {
+            AxiomRegentError::Internal(format!("Missing blob for {}", entry.path))
+                .with_path(&entry.path)
+        })?;
+        stage_content(target, entry, &content)
+    }
+
+    /// Moves each staged file of `writes` (target, path, staged file) onto its
+    /// target, then removes the targets of `deletes`. Should one of them fail,
+    /// the staged files are discarded and the paths already replaced or removed
+    /// are put back as `before` has them.
+    fn replace_files(
+        &self,
+        writes: &[(&Path, &str, &Path)],
+        deletes: &[(PathBuf, String)],
+        before: &[Entry],
+    ) -> std::result::Result<(), ReplaceFailure> {
+        let mut done: Vec<(&Path, &str)> = Vec::new();
+        let mut failure = None;
+        for (target, path, tmp) in writes {
+            if let Err(e) = fs::rename(tmp, target) {
+                failure = Some((*path, e));
+                break;
+            }
+            done.push((target, path));
+        }
+        if failure.is_none() {
+            for (target, path) in deletes {
+                if let Err(e) = fs::remove_file(target) {
+                    failure = Some((path.as_str(), e));
+                    break;
+                }
+                done.push((target.as_path(), path.as_str()));
+            }
+        }
+        let Some((path, error)) = failure else {
+            return Ok(());
+        };
+        for (_, _, tmp) in writes {
+            let _ = fs::remove_file(tmp);
+        }
+        Err(ReplaceFailure {
+            path: path.to_string(),
+            error,
+            rolled_back: self.roll_back(before, &done),
+        })
+    }
+
+    /// Puts each of `done` back as `before` has it, removing those it lacks.
+    fn roll_back(&self, before: &[Entry], done: &[(&Path, &str)]) -> Result<()> {
+        for (target, path) in done.iter().rev() {
+            match before.iter().find(|e| e.path == *path) {
+                Some(entry) => {
+                    let tmp = self.stage(target, entry)?;
+                    fs::rename(tmp, target)?;
+                }
+                None => fs::remove_file(target)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why [`WorkspaceTools::replace_files`] failed, and whether the paths it had
+/// already replaced were put back.
+struct ReplaceFailure {
+    path: String,
+    error: std::io::Error,
+    rolled_back: Result<()>,
 }
 
 // Helpers
 
+/// Decodes `workspace.write_file` content: base64 after a `base64:` prefix,
+/// plain text otherwise.
+fn decode_content(content_base64: &str) -> Result<Vec<u8>> {
+    use base64::{Engine as _, engine::general_purpose};
+    if let Some(rest) = content_base64.strip_prefix("base64:") {
+        Ok(general_purpose::STANDARD
+            .decode(rest)
+            .context("Invalid base64 content")?)
+    } else {
+        // accept plain text
+        Ok(content_base64.as_bytes().to_vec())
+    }
+}
+
+/// Writes `content` as `entry` to a temporary file next to `target`,
+/// creating its directory, and returns the temporary path.
+fn stage_content(target: &Path, entry: &Entry, content: &[u8]) -> Result<PathBuf> {
+    let dir = target
+        .parent()
+        .ok_or_else(|| anyhow!("No parent directory: {}", target.display()))?;
+    fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!(".axiomregent-restore-{}", Uuid::new_v4()));
+    if let Err(e) = worktree::write_entry(&tmp, entry, content) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(tmp)
+}
+
+/// Whether `path` is `dir_or_file` or lies under it.
+fn is_within(path: &str, dir_or_file: &str) -> bool {
+    let prefix = dir_or_file.trim_end_matches('/');
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
 fn parse_patch_touched_files(patch: &str) -> Vec<String> {
     let mut files = Vec::new();
     for line in patch.lines() {
@@ -398,6 +765,29 @@ fn parse_patch_touched_files(patch: &str) -> Vec<String> {
     files
 }
 
+/// Paths a patch writes or deletes: both sides of each file header.
+fn parse_patch_paths(patch: &str) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for line in patch.lines() {
+        let Some(header) = line
+            .strip_prefix("--- ")
+            .or_else(|| line.strip_prefix("+++ "))
+        else {
+            continue;
+        };
+        // A tab separates an optional timestamp from the path.
+        let path = header.split('\t').next().unwrap_or_default().trim();
+        let path = path
+            .strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path);
+        if path != "/dev/null" && !paths.iter().any(|p| p == path) {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
 fn parse_git_apply_errors(stderr: &str, patch: &str) -> Vec<serde_json::Value> {
     // Parse "error: patch failed: <file>:<line>"
     // Return structured rejects
@@ -462,7 +852,7 @@ mod tests {
             h1,
             t1.len()
         );
-        let sid = "snap-base";
+        let sid = "sha256:snap-base";
         store
             .put_snapshot(
                 sid,
@@ -532,4 +922,65 @@ index 0000000..9daeafb
         assert!(found_a);
         assert!(found_b);
     }
+
+    #[test]
+    fn test_replace_files_rolls_back_on_failure() {
+        let data = tempfile::tempdir().unwrap();
+        let repo = tempfile::tempdir().unwrap();
+        let config = StorageConfig {
+            data_dir: data.path().to_path_buf(),
+            blob_backend: BlobBackend::Fs,
+            compression: Compression::None,
+        };
+        let store = Arc::new(Store::new(config).unwrap());
+        let tools = WorkspaceTools::new(Arc::new(LeaseStore::new()), store.clone());
+        std::fs::write(repo.path().join("a.txt"), "old\n").unwrap();
+        let before = vec![
+            worktree::capture(&store, repo.path(), "a.txt")
+                .unwrap()
+                .unwrap(),
+        ];
+
+        let a = repo.path().join("a.txt");
+        let new = store.put_content("a.txt", b"new\n").unwrap();
+        let a_tmp = stage_content(&a, &new, b"new\n").unwrap();
+        let b = repo.path().join("b.txt");
+        let b_tmp = stage_content(&b, &new, b"new\n").unwrap();
+        // Deleting a file that is gone fails after both writes were done.
+        let deletes = vec![(repo.path().join("gone.txt"), "gone.txt".to_string())];
+
+        let failure = tools
+            .replace_files(
+                &[
+                    (a.as_path(), "a.txt", a_tmp.as_path()),
+                    (b.as_path(), "b.txt", b_tmp.as_path()),
+                ],
+                &deletes,
+                &before,
+            )
+            .err()
+            .unwrap();
+        assert_eq!(failure.path, "gone.txt");
+        assert!(failure.rolled_back.is_ok());
+
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "old\n");
+        assert!(!b.exists());
+        let mut names: Vec<String> = std::fs::read_dir(repo.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.txt"]);
+    }
+
+    #[test]
+    fn test_parse_patch_paths() {
+        let patch = "--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-x\n\
+                     --- /dev/null\n+++ b/src/new.rs\t2026-01-01\n@@ -0,0 +1 @@\n+y\n\
+                     --- a/same.txt\n+++ b/same.txt\n";
+        assert_eq!(
+            parse_patch_paths(patch),
+            ["old.txt", "src/new.rs", "same.txt"]
+        );
+    }
 }
@@ -0,0 +1,634 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+//! `workspace.transaction`: an ordered list of file edits applied to the
+//! worktree all or not at all.
+
+use super::{WorkspaceTools, decode_content, stage_content};
+use crate::router::AxiomRegentError;
+use crate::snapshot::store::{Entry, Manifest, content_hash};
+use crate::snapshot::worktree;
+use anyhow::{Context, Result, anyhow};
+use featuregraph::preflight::{
+    PreflightChecker, PreflightIntent, PreflightMode, PreflightRequest, PreflightResponse,
+};
+use featuregraph::scanner::Scanner;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use walkdir::WalkDir;
+
+// What an operation does. Plain comments: doc comments would turn the
+// variants into a `oneOf` in the tool's input schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OpKind {
+    // Create or replace the file `path` with `content_base64`.
+    Write,
+    // Remove the file `path`.
+    Delete,
+    // Move the file `path` to `to`, which must not exist.
+    Rename,
+    // Apply the unified diff `patch`.
+    Patch,
+}
+
+impl OpKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OpKind::Write => "write",
+            OpKind::Delete => "delete",
+            OpKind::Rename => "rename",
+            OpKind::Patch => "patch",
+        }
+    }
+}
+
+/// One edit of a `workspace.transaction`.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct Operation {
+    pub op: OpKind,
+    /// File to write, delete or rename.
+    pub path: Option<String>,
+    /// Content for `write`: base64 after a `base64:` prefix, plain text otherwise.
+    pub content_base64: Option<String>,
+    /// Destination of `rename`.
+    pub to: Option<String>,
+    /// Unified diff for `patch`.
+    pub patch: Option<String>,
+    /// Leading components stripped from the paths of `patch`; 1 when unset.
+    pub strip: Option<usize>,
+}
+
+impl Operation {
+    /// `value`, the argument `name`, which this operation requires.
+    fn require<'a>(&self, index: usize, name: &str, value: &'a Option<String>) -> Result<&'a str> {
+        value
+            .as_deref()
+            .ok_or_else(|| self.invalid(index, &format!("{} required", name)))
+    }
+
+    fn invalid(&self, index: usize, message: &str) -> anyhow::Error {
+        let err = AxiomRegentError::InvalidArgument(format!(
+            "operations[{}] ({}): {}",
+            index,
+            self.op.as_str(),
+            message
+        ));
+        match &self.path {
+            Some(path) => err.with_path(path).into(),
+            None => err.into(),
+        }
+    }
+}
+
+/// The files a transaction names, copied out of the worktree and edited
+/// in a temporary directory until every operation has succeeded.
+struct Staging {
+    dir: TempDir,
+    // Every path an operation named.
+    touched: BTreeSet<String>,
+}
+
+impl Staging {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            dir: tempfile::tempdir()?,
+            touched: BTreeSet::new(),
+        })
+    }
+
+    fn path(&self, rel_path: &str) -> PathBuf {
+        self.dir.path().join(rel_path)
+    }
+
+    /// Copies `rel_path` from the worktree the first time it is named.
+    fn load(&mut self, tools: &WorkspaceTools, repo_root: &Path, rel_path: &str) -> Result<()> {
+        if self.touched.contains(rel_path) {
+            return Ok(());
+        }
+        let target = tools.restore_target(repo_root, rel_path)?;
+        if fs::symlink_metadata(&target).is_ok_and(|m| m.is_dir()) {
+            return Err(
+                AxiomRegentError::InvalidArgument("Path is a directory".into())
+                    .with_path(rel_path)
+                    .into(),
+            );
+        }
+        if let Some((entry, content)) = worktree::read(&target, rel_path)? {
+            worktree::materialize(self.dir.path(), &entry, &content)?;
+        }
+        self.touched.insert(rel_path.to_string());
+        Ok(())
+    }
+
+    /// Plays operation `index` against the staged files.
+    fn apply(
+        &mut self,
+        tools: &WorkspaceTools,
+        repo_root: &Path,
+        index: usize,
+        op: &Operation,
+    ) -> Result<()> {
+        match op.op {
+            OpKind::Write => {
+                let path = op.require(index, "path", &op.path)?;
+                let content =
+                    decode_content(op.require(index, "content_base64", &op.content_base64)?)
+                        .map_err(|e| op.invalid(index, &e.to_string()))?;
+                self.load(tools, repo_root, path)?;
+                let staged = self.path(path);
+                if fs::symlink_metadata(&staged).is_ok_and(|m| m.is_dir()) {
+                    return Err(op.invalid(index, "path is a directory"));
+                }
+                // An overwritten file keeps its executable bit.
+                let executable = match worktree::read(&staged, path)? {
+                    Some((entry, _)) => {
+                        fs::remove_file(&staged)?;
+                        entry.symlink.is_none() && entry.executable
+                    }
+                    None => false,
+                };
+                let entry = Entry {
+                    blob: content_hash(&content),
+                    path: path.to_string(),
+                    size: content.len() as u64,
+                    chunks: Vec::new(),
+                    executable,
+                    symlink: None,
+                };
+                worktree::materialize(self.dir.path(), &entry, &content)
+                    .map_err(|e| op.invalid(index, &e.to_string()))
+            }
+            OpKind::Delete => {
+                let path = op.require(index, "path", &op.path)?;
+                self.load(tools, repo_root, path)?;
+                if !self.is_file(path) {
+                    return Err(not_found(index, op, path));
+                }
+                Ok(fs::remove_file(self.path(path))?)
+            }
+            OpKind::Rename => {
+                let path = op.require(index, "path", &op.path)?;
+                let to = op.require(index, "to", &op.to)?;
+                if path == to {
+                    return Err(op.invalid(index, "to must differ from path"));
+                }
+                self.load(tools, repo_root, path)?;
+                self.load(tools, repo_root, to)?;
+                if !self.is_file(path) {
+                    return Err(not_found(index, op, path));
+                }
+                if fs::symlink_metadata(self.path(to)).is_ok() {
+                    return Err(op.invalid(index, &format!("{} already exists", to)));
+                }
+                let dest = self.path(to);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent).map_err(|e| op.invalid(index, &e.to_string()))?;
+                }
+                Ok(fs::rename(self.path(path), dest)?)
+            }
+            OpKind::Patch => {
+                let patch = op.require(index, "patch", &op.patch)?;
+                let strip = op.strip.unwrap_or(1);
+                let paths = patch_paths(patch, strip);
+                if paths.is_empty() {
+                    return Err(op.invalid(index, "patch names no files"));
+                }
+                for path in &paths {
+                    self.load(tools, repo_root, path)?;
+                }
+                self.git_apply(patch, strip)
+                    .map_err(|e| op.invalid(index, &e.to_string()))?;
+                // A file the headers do not name would never reach the worktree.
+                match self.unnamed_file()? {
+                    Some(path) => Err(op.invalid(
+                        index,
+                        &format!("patch writes {}, which its headers do not name", path),
+                    )),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    fn is_file(&self, rel_path: &str) -> bool {
+        fs::symlink_metadata(self.path(rel_path)).is_ok_and(|m| !m.is_dir())
+    }
+
+    fn git_apply(&self, patch: &str, strip: usize) -> Result<()> {
+        let mut cmd = std::process::Command::new("git");
+        cmd.arg("apply");
+        cmd.arg(format!("-p{}", strip));
+        cmd.current_dir(self.dir.path());
+        // Never pick up a repository around the temporary directory.
+        if let Some(parent) = self.dir.path().parent() {
+            cmd.env("GIT_CEILING_DIRECTORIES", parent);
+        }
+        cmd.stdin(std::process::Stdio::piped());
+        cmd.stdout(std::process::Stdio::piped());
+        cmd.stderr(std::process::Stdio::piped());
+
+        let mut child = cmd.spawn().context("Failed to run git apply")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            use std::io::Write;
+            stdin.write_all(patch.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let errors: Vec<&str> = stderr
+            .lines()
+            .filter_map(|line| line.strip_prefix("error: "))
+            .collect();
+        Err(anyhow!("git apply failed: {}", errors.join("; ")))
+    }
+
+    /// A staged file outside the touched set, if any.
+    fn unnamed_file(&self) -> Result<Option<String>> {
+        for entry in WalkDir::new(self.dir.path()).min_depth(1) {
+            let entry = entry?;
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            let rel = entry.path().strip_prefix(self.dir.path())?;
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            if !self.touched.contains(&rel) {
+                return Ok(Some(rel));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl WorkspaceTools {
+    /// Applies `operations` to the worktree in order, all of them or none.
+    ///
+    /// The operations are first played against copies of the files they
+    /// name; any failure, or a `gov.preflight` denial of the result, leaves
+    /// the worktree untouched. The files about to change are then saved as
+    /// a snapshot and every new file is staged next to its target. Should
+    /// replacing them fail part way, the files already replaced are
+    /// restored from that snapshot.
+    pub fn transaction(
+        &self,
+        repo_root: &Path,
+        operations: &[Operation],
+        lease_id: Option<String>,
+        dry_run: bool,
+    ) -> Result<serde_json::Value> {
+        let lid = lease_id
+            .ok_or_else(|| AxiomRegentError::InvalidArgument("lease_id required".into()))?;
+        let base = self.lease_store.write_base(&lid, repo_root)?;
+        self.lease_store.check_lease(&lid, repo_root)?;
+        if operations.is_empty() {
+            return Err(
+                AxiomRegentError::InvalidArgument("operations must not be empty".into()).into(),
+            );
+        }
+
+        let mut staging = Staging::new()?;
+        for (index, op) in operations.iter().enumerate() {
+            staging.apply(self, repo_root, index, op)?;
+        }
+        let touched: Vec<String> = staging.touched.iter().cloned().collect();
+        self.lease_store.check_reserved(&lid, &touched)?;
+
+        // Plan: what each touched path becomes.
+        let mut files = Vec::new();
+        let mut writes = Vec::new();
+        let mut deletes = Vec::new();
+        for path in &touched {
+            let target = self.restore_target(repo_root, path)?;
+            let exists = fs::symlink_metadata(&target).is_ok();
+            match worktree::read(&staging.path(path), path)? {
+                Some((entry, content)) => {
+                    if worktree::is_materialized(&target, &entry)? {
+                        continue;
+                    }
+                    files.push((path.clone(), if exists { "overwrite" } else { "create" }));
+                    writes.push((target, entry, content));
+                }
+                None if exists => {
+                    files.push((path.clone(), "delete"));
+                    deletes.push((target, path.clone()));
+                }
+                None => {}
+            }
+        }
+
+        let preflight = preflight(
+            repo_root,
+            staging.dir.path(),
+            &touched,
+            intent(operations, &files),
+        )?;
+        let files_json: Vec<serde_json::Value> = files
+            .iter()
+            .map(|(path, action)| json!({ "path": path, "action": action }))
+            .collect();
+        if dry_run {
+            return Ok(json!({
+                "files": files_json,
+                "touched": touched,
+                "preflight": preflight,
+                "dry_run": true
+            }));
+        }
+        if let Some(response) = preflight.as_ref().filter(|p| !p.allowed) {
+            let found: Vec<String> = response
+                .violations
+                .iter()
+                .map(|v| format!("{} ({})", v.code, v.path))
+                .collect();
+            let err = AxiomRegentError::PermissionDenied(format!(
+                "Preflight denied the transaction: {}",
+                found.join(", ")
+            ));
+            return Err(match response.violations.first() {
+                Some(v) => err.with_path(&v.path).into(),
+                None => err.into(),
+            });
+        }
+
+        // The files about to change, as they are now, to roll back to.
+        let mut before = Vec::new();
+        for (path, _) in &files {
+            if let Some(entry) = worktree::capture(&self.store, repo_root, path)? {
+                before.push(entry);
+            }
+        }
+        let snap_id = self.put_pre_snapshot(repo_root, &lid, before.clone())?;
+
+        // Stage every new file first: until the renames nothing is replaced.
+        let mut staged = Vec::new();
+        for (target, entry, content) in &writes {
+            match stage_content(target, entry, content) {
+                Ok(tmp) => staged.push(tmp),
+                Err(e) => {
+                    for tmp in &staged {
+                        let _ = fs::remove_file(tmp);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        let replacements: Vec<(&Path, &str, &Path)> = writes
+            .iter()
+            .zip(&staged)
+            .map(|((target, entry, _), tmp)| (target.as_path(), entry.path.as_str(), tmp.as_path()))
+            .collect();
+        if let Err(failure) = self.replace_files(&replacements, &deletes, &before) {
+            let message = match failure.rolled_back {
+                Ok(()) => format!(
+                    "Transaction rolled back after failing on {}: {}",
+                    failure.path, failure.error
+                ),
+                Err(rollback) => format!(
+                    "Transaction failed on {} ({}) and could not be rolled back ({}); restore snapshot {}",
+                    failure.path, failure.error, rollback, snap_id
+                ),
+            };
+            return Err(AxiomRegentError::Internal(message)
+                .with_path(&failure.path)
+                .with_snapshot(&snap_id)
+                .into());
+        }
+
+        self.lease_store.touch_files(&lid, touched.clone())?;
+        let fingerprint = self
+            .lease_store
+            .refresh_after_write(&lid, repo_root, base)?;
+
+        Ok(json!({
+            "snapshot_id": snap_id,
+            "files": files_json,
+            "touched": touched,
+            "preflight": preflight,
+            "dry_run": false,
+            "lease_id": lid,
+            "fingerprint": fingerprint
+        }))
+    }
+
+    /// Stores `entries` as a snapshot of `repo_root` under the fingerprint
+    /// of lease `lease_id`, and returns its ID. It is left unlabeled, so
+    /// that `snapshot.gc` can collect it like any other untagged snapshot.
+    fn put_pre_snapshot(
+        &self,
+        repo_root: &Path,
+        lease_id: &str,
+        entries: Vec<Entry>,
+    ) -> Result<String> {
+        let fp = self
+            .lease_store
+            .get_fingerprint(lease_id)
+            .ok_or_else(|| anyhow!("No fingerprint for lease {}", lease_id))?;
+        let fp_json = fp.to_canonical_json()?;
+        let manifest = Manifest::new(entries);
+        let snap_id = manifest.compute_snapshot_id(&fp_json)?;
+        let manifest_bytes = manifest.to_canonical_json()?.into_bytes();
+        self.store.put_snapshot(
+            &snap_id,
+            &repo_root.canonicalize()?.to_string_lossy(),
+            &fp.head_oid,
+            &fp_json,
+            &manifest_bytes,
+            None,
+            None,
+            None,
+        )?;
+        Ok(snap_id)
+    }
+}
+
+fn not_found(index: usize, op: &Operation, path: &str) -> anyhow::Error {
+    AxiomRegentError::NotFound(format!(
+        "operations[{}] ({}): file not found",
+        index,
+        op.op.as_str()
+    ))
+    .with_path(path)
+    .into()
+}
+
+/// What the transaction amounts to, for `gov.preflight`.
+fn intent(operations: &[Operation], files: &[(String, &str)]) -> PreflightIntent {
+    if operations.iter().any(|op| op.op == OpKind::Rename) {
+        PreflightIntent::Refactor
+    } else if !files.is_empty() && files.iter().all(|(_, action)| *action == "delete") {
+        PreflightIntent::Delete
+    } else if !files.is_empty() && files.iter().all(|(_, action)| *action == "create") {
+        PreflightIntent::Create
+    } else {
+        PreflightIntent::Edit
+    }
+}
+
+/// Runs `gov.preflight` on `paths` as staged under `staging`, against the
+/// feature graph of `repo_root`. `None` when the repository has no
+/// `spec/features.yaml` to govern it.
+fn preflight(
+    repo_root: &Path,
+    staging: &Path,
+    paths: &[String],
+    intent: PreflightIntent,
+) -> Result<Option<PreflightResponse>> {
+    if !repo_root.join("spec/features.yaml").is_file() {
+        return Ok(None);
+    }
+    let graph = Scanner::new(repo_root)
+        .scan()
+        .context("Failed to scan feature graph")?;
+    let request = PreflightRequest {
+        intent,
+        mode: PreflightMode::Worktree,
+        changed_paths: paths.to_vec(),
+        snapshot_id: None,
+    };
+    Ok(Some(
+        PreflightChecker::new(staging).check(&graph, &request)?,
+    ))
+}
+
+/// Files `patch` reads or writes, with `strip` leading components removed
+/// from the paths of its `---`/`+++` headers as `git apply -p<strip>` does.
+fn patch_paths(patch: &str, strip: usize) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for line in patch.lines() {
+        let path = if let Some(header) = line
+            .strip_prefix("--- ")
+            .or_else(|| line.strip_prefix("+++ "))
+        {
+            // A tab separates an optional timestamp from the path.
+            let path = header.split('\t').next().unwrap_or_default().trim();
+            if path == "/dev/null" {
+                continue;
+            }
+            match path.splitn(strip + 1, '/').nth(strip) {
+                Some(path) => path,
+                None => continue,
+            }
+        } else if let Some(path) = line
+            .strip_prefix("rename from ")
+            .or_else(|| line.strip_prefix("rename to "))
+        {
+            // Extended git headers carry no prefix to strip.
+            path.trim()
+        } else {
+            continue;
+        };
+        if !path.is_empty() && !paths.iter().any(|p| p == path) {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BlobBackend, Compression, StorageConfig};
+    use crate::snapshot::lease::LeaseStore;
+    use crate::snapshot::store::Store;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_roll_back() {
+        let data = tempfile::tempdir().unwrap();
+        let repo = tempfile::tempdir().unwrap();
+        let config = StorageConfig {
+            data_dir: data.path().to_path_buf(),
+            blob_backend: BlobBackend::Fs,
+            compression: Compression::None,
+        };
+        let store = Arc::new(Store::new(config).unwrap());
+        let tools = WorkspaceTools::new(Arc::new(LeaseStore::new()), store.clone());
+        let root = repo.path();
+        fs::write(root.join("a.txt"), "before\n").unwrap();
+        let before = vec![worktree::capture(&store, root, "a.txt").unwrap().unwrap()];
+
+        // A transaction that replaced a.txt and created new.txt, then failed.
+        let (a, new) = (root.join("a.txt"), root.join("new.txt"));
+        fs::write(&a, "after\n").unwrap();
+        fs::write(&new, "new\n").unwrap();
+        tools
+            .roll_back(
+                &before,
+                &[(a.as_path(), "a.txt"), (new.as_path(), "new.txt")],
+            )
+            .unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "before\n");
+        assert!(!new.exists());
+    }
+
+    #[test]
+    fn test_patch_paths() {
+        let patch = "\
+--- a/src/lib.rs\t2026-01-01
++++ b/src/lib.rs
+@@ -1 +1 @@
+-a
++b
+--- /dev/null
++++ b/docs/new.md
+@@ -0,0 +1 @@
++new
+";
+        assert_eq!(patch_paths(patch, 1), vec!["src/lib.rs", "docs/new.md"]);
+        assert_eq!(
+            patch_paths(patch, 0),
+            vec!["a/src/lib.rs", "b/src/lib.rs", "b/docs/new.md"]
+        );
+        assert_eq!(patch_paths(patch, 2), vec!["lib.rs", "new.md"]);
+
+        let rename = "\
+diff --git a/old.txt b/new.txt
+similarity index 100%
+rename from old.txt
+rename to new.txt
+";
+        assert_eq!(patch_paths(rename, 1), vec!["old.txt", "new.txt"]);
+    }
+
+    #[test]
+    fn test_intent() {
+        let op = |kind| Operation {
+            op: kind,
+            path: Some("a".into()),
+            content_base64: None,
+            to: None,
+            patch: None,
+            strip: None,
+        };
+        let created = [("a".to_string(), "create")];
+        let deleted = [("a".to_string(), "delete")];
+        assert!(matches!(
+            intent(&[op(OpKind::Write)], &created),
+            PreflightIntent::Create
+        ));
+        assert!(matches!(
+            intent(&[op(OpKind::Delete)], &deleted),
+            PreflightIntent::Delete
+        ));
+        assert!(matches!(
+            intent(&[op(OpKind::Rename)], &deleted),
+            PreflightIntent::Refactor
+        ));
+        assert!(matches!(
+            intent(
+                &[op(OpKind::Write), op(OpKind::Delete)],
+                &[created[0].clone(), deleted[0].clone()]
+            ),
+            PreflightIntent::Edit
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::config::{BlobBackend, Compression, StorageConfig};
+use axiomregent::router::Router;
+use axiomregent::snapshot::store::Store;
+use base64::Engine;
+use common::{RouterSetup, setup_router_with};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn db_router(data_dir: &Path) -> Router {
+    let setup = RouterSetup {
+        blob_backend: BlobBackend::Db,
+        ..Default::default()
+    };
+    setup_router_with(data_dir, setup).0
+}
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    repo
+}
+
+fn create_snapshot(router: &Router, repo_root: &str, content: &str) -> String {
+    let repo = Path::new(repo_root);
+    std::fs::write(repo.join("a.txt"), content).unwrap();
+    let resp = call(
+        router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["a.txt"] }),
+    );
+    result(&resp)["snapshot_id"].as_str().unwrap().to_string()
+}
+
+fn content(resp: &Value) -> String {
+    let encoded = result(resp)["content"].as_str().unwrap();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.strip_prefix("base64:").unwrap())
+        .unwrap();
+    String::from_utf8(bytes).unwrap()
+}
+
+fn blob_files(data_dir: &Path) -> usize {
+    walkdir::WalkDir::new(data_dir.join("blobs"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .count()
+}
+
+#[test]
+fn test_db_backend_and_migrate_store() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = db_router(data.path());
+
+    let id = create_snapshot(&router, &repo_root, "stored inline\n");
+    assert_eq!(blob_files(data.path()), 0);
+    let read =
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "snapshot", "snapshot_id": id });
+    let resp = call(&router, "snapshot.read", read.clone());
+    assert_eq!(content(&resp), "stored inline\n");
+
+    let resp = call(&router, "snapshot.migrate_store", json!({ "to": "fs" }));
+    let report = result(&resp);
+    assert_eq!(report["to"], "fs");
+    assert_eq!(report["blobs_moved"], 1);
+    assert_eq!(report["missing"], json!([]));
+    assert_eq!(blob_files(data.path()), 1);
+
+    // Bypass the response cache: read through a router over the same store files.
+    drop(router);
+    let router = db_router(data.path());
+    let resp = call(&router, "snapshot.read", read);
+    assert_eq!(content(&resp), "stored inline\n");
+
+    let resp = call(&router, "snapshot.migrate_store", json!({ "to": "tape" }));
+    assert_eq!(resp["error"]["code"], -32602);
+}
+
+#[test]
+fn test_reads_during_migrate_store_find_every_blob() {
+    let data = tempfile::tempdir().unwrap();
+    let store = Arc::new(
+        Store::new(StorageConfig {
+            data_dir: data.path().to_path_buf(),
+            blob_backend: BlobBackend::Fs,
+            compression: Compression::Zstd,
+        })
+        .unwrap(),
+    );
+    let blobs: Vec<(String, Vec<u8>)> = (0..50)
+        .map(|i| {
+            let content = format!("blob {i}\n").repeat(10).into_bytes();
+            (store.put_blob(&content).unwrap(), content)
+        })
+        .collect();
+
+    let migrator = {
+        let store = store.clone();
+        std::thread::spawn(move || {
+            for round in 0..10 {
+                let target = if round % 2 == 0 {
+                    BlobBackend::Db
+                } else {
+                    BlobBackend::Fs
+                };
+                store.migrate_blobs(target).unwrap();
+            }
+        })
+    };
+    while !migrator.is_finished() {
+        for (hash, content) in &blobs {
+            assert_eq!(
+                store.get_blob(hash).unwrap().as_ref(),
+                Some(content),
+                "blob {hash} unreadable during migration"
+            );
+        }
+    }
+    migrator.join().unwrap();
+}
@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use base64::Engine;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    repo
+}
+
+fn lockfile(changed: Option<(usize, &str)>) -> String {
+    (0..40_000u32)
+        .map(|i| match changed {
+            Some((line, text)) if line == i as usize => format!("{}\n", text),
+            _ => format!(
+                "package-{:06} = \"{:08x}\"\n",
+                i,
+                i.wrapping_mul(2654435761)
+            ),
+        })
+        .collect()
+}
+
+fn create_snapshot(router: &Router, repo_root: &str, content: &str) -> String {
+    std::fs::write(Path::new(repo_root).join("Cargo.lock"), content).unwrap();
+    let resp = call(
+        router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["Cargo.lock"] }),
+    );
+    result(&resp)["snapshot_id"].as_str().unwrap().to_string()
+}
+
+fn decode(encoded: &Value) -> Vec<u8> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.as_str().unwrap().strip_prefix("base64:").unwrap())
+        .unwrap()
+}
+
+fn read(router: &Router, repo_root: &str, snapshot_id: &str) -> String {
+    let resp = call(
+        router,
+        "snapshot.read",
+        json!({
+            "repo_root": repo_root,
+            "path": "Cargo.lock",
+            "mode": "snapshot",
+            "snapshot_id": snapshot_id
+        }),
+    );
+    String::from_utf8(decode(&result(&resp)["content"])).unwrap()
+}
+
+#[test]
+fn test_large_file_versions_share_chunks() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let v1 = lockfile(None);
+    let v2 = lockfile(Some((20_000, "changed = \"line\"")));
+    let s1 = create_snapshot(&router, &repo_root, &v1);
+    let s2 = create_snapshot(&router, &repo_root, &v2);
+    assert_eq!(read(&router, &repo_root, &s1), v1);
+    assert_eq!(read(&router, &repo_root, &s2), v2);
+
+    let resp = call(&router, "snapshot.store_stats", json!({}));
+    let stats = result(&resp);
+    assert_eq!(stats["snapshots"], 2);
+    assert_eq!(stats["chunked_files"], 2);
+    assert_eq!(stats["logical_bytes"], (v1.len() + v2.len()) as u64);
+    // The second version only adds the chunks around the edit.
+    assert!(stats["dedup_ratio"].as_f64().unwrap() > 1.5, "{}", stats);
+
+    let resp = call(
+        &router,
+        "snapshot.export",
+        json!({ "repo_root": repo_root, "snapshot_id": s2 }),
+    );
+    let tar_bytes = decode(&result(&resp)["bundle"]);
+    let mut archive = tar::Archive::new(&tar_bytes[..]);
+    let mut file = archive.entries().unwrap().next().unwrap().unwrap();
+    let mut exported = String::new();
+    std::io::Read::read_to_string(&mut file, &mut exported).unwrap();
+    assert_eq!(exported, v2);
+}
+
+#[test]
+fn test_apply_patch_to_chunked_file() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let base = create_snapshot(&router, &repo_root, &lockfile(None));
+    let patch = "diff --git a/Cargo.lock b/Cargo.lock
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -1,2 +1,2 @@
+-package-000000 = \"00000000\"
++package-000000 = \"patched\"
+ package-000001 = \"9e3779b1\"
+";
+    let resp = call(
+        &router,
+        "workspace.apply_patch",
+        json!({
+            "repo_root": repo_root,
+            "patch": patch,
+            "mode": "snapshot",
+            "snapshot_id": base
+        }),
+    );
+    let patched = result(&resp)["snapshot_id"].as_str().unwrap().to_string();
+    assert_ne!(patched, base);
+    assert_eq!(
+        read(&router, &repo_root, &patched),
+        lockfile(Some((0, "package-000000 = \"patched\"")))
+    );
+}
@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+//! Router setup shared by the integration tests.
+
+// Each test crate uses only part of this module.
+#![allow(dead_code)]
+
+use axiomregent::antigravity_tools::AntigravityTools;
+use axiomregent::config::{BlobBackend, Compression, StorageConfig};
+use axiomregent::feature_tools::FeatureTools;
+use axiomregent::featuregraph::tools::FeatureGraphTools;
+use axiomregent::io::fs::RealFs;
+use axiomregent::resolver::order::ResolveEngine;
+use axiomregent::router::Router;
+use axiomregent::router::mounts::MountRegistry;
+use axiomregent::run_tools::RunTools;
+use axiomregent::snapshot::store::Store;
+use axiomregent::snapshot::{lease::LeaseStore, tools::SnapshotTools};
+use axiomregent::tools::encore_ts::tools::EncoreTools;
+use axiomregent::workspace::WorkspaceTools;
+use axiomregent::xray::tools::XrayTools;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Options for [`setup_router_with`]; the defaults are those of [`setup_router`].
+pub struct RouterSetup {
+    pub blob_backend: BlobBackend,
+    pub lease_store: LeaseStore,
+    pub mounts: MountRegistry,
+    pub strict_mounts: bool,
+}
+
+impl Default for RouterSetup {
+    fn default() -> Self {
+        Self {
+            blob_backend: BlobBackend::Fs,
+            lease_store: LeaseStore::new(),
+            mounts: MountRegistry::new(),
+            strict_mounts: false,
+        }
+    }
+}
+
+/// A router with every tool, storing its data under `data_dir`.
+pub fn setup_router(data_dir: &Path) -> Router {
+    setup_router_with(data_dir, RouterSetup::default()).0
+}
+
+/// A router set up as `setup` says, and the lease store its tools share.
+pub fn setup_router_with(data_dir: &Path, setup: RouterSetup) -> (Router, Arc<LeaseStore>) {
+    let resolver = Arc::new(ResolveEngine::new(RealFs, Vec::<PathBuf>::new()));
+
+    let config = StorageConfig {
+        data_dir: data_dir.to_path_buf(),
+        blob_backend: setup.blob_backend,
+        compression: Compression::None,
+    };
+    let store = Arc::new(Store::new(config).unwrap());
+    let lease_store = Arc::new(setup.lease_store);
+
+    let snapshot_tools = Arc::new(SnapshotTools::new(lease_store.clone(), store.clone()));
+    let workspace_tools = Arc::new(WorkspaceTools::new(lease_store.clone(), store.clone()));
+    let feature_tools = Arc::new(FeatureTools::new());
+    let antigravity_tools = Arc::new(AntigravityTools::new(
+        workspace_tools.clone(),
+        snapshot_tools.clone(),
+        feature_tools,
+    ));
+
+    let router = Router::new(
+        resolver,
+        setup.mounts,
+        snapshot_tools,
+        workspace_tools,
+        Arc::new(FeatureGraphTools::new()),
+        Arc::new(XrayTools::new()),
+        antigravity_tools,
+        Arc::new(EncoreTools::new()),
+        Arc::new(RunTools::new(data_dir)),
+    )
+    .with_strict_mounts(setup.strict_mounts);
+    (router, lease_store)
+}
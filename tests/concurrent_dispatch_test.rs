@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::process::Command;
+use std::sync::Arc;
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+fn call(router: &Router, id: u64, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": id
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_concurrent_mutations_are_serialized_per_repo() {
+    let repo = tempfile::tempdir().unwrap();
+    let data = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+
+    let router = Arc::new(setup_router(data.path()));
+    let resp = call(
+        &router,
+        1,
+        "snapshot.list",
+        json!({ "repo_root": repo_root, "path": "", "mode": "worktree" }),
+    );
+    let lease_id = resp["result"]["content"][0]["json"]["lease_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // All writers share one lease. Once one write lands the lease is stale, so
+    // with per-repo serialization exactly one of them can succeed.
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let router = router.clone();
+            let repo_root = repo_root.clone();
+            let lease_id = lease_id.clone();
+            std::thread::spawn(move || {
+                call(
+                    &router,
+                    10 + i,
+                    "workspace.write_file",
+                    json!({
+                        "repo_root": repo_root,
+                        "path": format!("file{}.txt", i),
+                        "content_base64": "hello",
+                        "lease_id": lease_id
+                    }),
+                )
+            })
+        })
+        .collect();
+    let results: Vec<Value> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    let ok = results.iter().filter(|r| r["error"].is_null()).count();
+    let stale = results
+        .iter()
+        .filter(|r| r["error"]["data"]["code"] == "STALE_LEASE")
+        .count();
+    assert_eq!(ok, 1, "results: {:?}", results);
+    assert_eq!(stale, 3, "results: {:?}", results);
+
+    // Read-only calls on the same repo are unaffected.
+    let resp = call(
+        &router,
+        20,
+        "snapshot.info",
+        json!({ "repo_root": repo_root }),
+    );
+    assert!(resp["error"].is_null(), "unexpected error: {:?}", resp);
+}
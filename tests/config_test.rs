@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the server in `cwd` with `user_config` as the user-level config file,
+/// sends `requests` and returns the process output once stdin is closed.
+fn run_server(cwd: &Path, user_config: &Path, requests: &[Value]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axiomregent"))
+        .current_dir(cwd)
+        .env("AXIOMREGENT_CONFIG", user_config)
+        .env_remove("AXIOMREGENT_WORKSPACE_ROOTS")
+        .env_remove("AXIOMREGENT_DATA_DIR")
+        .env_remove("MCP_ALLOW_LINE_JSON")
+        .env_remove("AXIOMREGENT_HTTP_LISTEN")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn mcp");
+
+    let mut stdin = child.stdin.take().unwrap();
+    for req in requests {
+        let body = req.to_string();
+        let _ = write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    }
+    drop(stdin);
+    child.wait_with_output().unwrap()
+}
+
+fn read_responses(stdout: &[u8]) -> Vec<Value> {
+    let mut reader = BufReader::new(stdout);
+    let mut responses = Vec::new();
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).unwrap() == 0 {
+            break;
+        }
+        let len: usize = header
+            .trim()
+            .strip_prefix("Content-Length:")
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        let mut blank = String::new();
+        reader.read_line(&mut blank).unwrap();
+        let mut body = vec![0u8; len];
+        reader.read_exact(&mut body).unwrap();
+        responses.push(serde_json::from_slice(&body).unwrap());
+    }
+    responses
+}
+
+fn by_id(responses: &[Value], id: u64) -> &Value {
+    responses.iter().find(|r| r["id"] == id).unwrap()
+}
+
+#[test]
+fn test_config_files_select_tools_and_are_echoed() {
+    let dir = tempfile::tempdir().unwrap();
+    let user = dir.path().join("user.toml");
+    std::fs::write(
+        &user,
+        "[tools]\nenabled = [\"core\", \"snapshot\", \"workspace\"]\n[limits]\nlist = 50\n",
+    )
+    .unwrap();
+    // The repo-local file overrides the user file key by key.
+    std::fs::create_dir_all(dir.path().join(".axiomregent")).unwrap();
+    std::fs::write(
+        dir.path().join(".axiomregent/config.toml"),
+        "[tools]\nenabled = [\"core\", \"snapshot\"]\n",
+    )
+    .unwrap();
+
+    let output = run_server(
+        dir.path(),
+        &user,
+        &[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+            json!({
+                "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                "params": { "name": "get_capabilities", "arguments": {} }
+            }),
+        ],
+    );
+    let responses = read_responses(&output.stdout);
+
+    let tools: Vec<&str> = by_id(&responses, 1)["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert!(tools.contains(&"get_capabilities"));
+    assert!(tools.contains(&"snapshot.read"));
+    assert!(!tools.iter().any(|t| t.starts_with("workspace.")));
+    assert!(!tools.iter().any(|t| t.starts_with("xray.")));
+
+    let caps = &by_id(&responses, 2)["result"]["content"][0]["json"];
+    let config = &caps["config"];
+    assert_eq!(config["tools"]["enabled"], json!(["core", "snapshot"]));
+    assert_eq!(config["limits"]["list"], 50);
+    assert_eq!(config["limits"]["grep_matches"], 100);
+    assert_eq!(config["storage"]["blob_backend"], "fs");
+    assert_eq!(config["sources"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_invalid_config_fails_fast() {
+    let dir = tempfile::tempdir().unwrap();
+    let user = dir.path().join("user.toml");
+    std::fs::write(&user, "[storage]\ncompression = \"lz4\"\n").unwrap();
+
+    let output = run_server(
+        dir.path(),
+        &user,
+        &[json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" })],
+    );
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("user.toml"), "{}", stderr);
+    assert!(stderr.contains("lz4"), "{}", stderr);
+}
@@ -1,4 +1,5 @@
 use anyhow::{Context, Result};
+use axiomregent::router::progress::Progress;
 use axiomregent::tools::encore_ts::{parse, run, state};
 use std::path::PathBuf;
 
@@ -73,7 +74,7 @@ fn test_run_persistence() -> Result<()> {
     let mut state = state::EncoreState::new();
 
     // Start
-    let run_id = run::start(&mut state, &root, None)?;
+    let run_id = run::start(&mut state, &root, None, &Progress::default())?;
 
     // Check if .axiomregent/runs/<run_id>/state.json exists
     let cwd = std::env::current_dir()?;
@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+#![cfg(feature = "transport-http")]
+
+mod common;
+
+use axiomregent::transport::http::HttpServer;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+/// Sends one raw HTTP request and returns (status, headers, body).
+fn send(addr: &str, method: &str, headers: &[(&str, &str)], body: &str) -> (u16, String, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let mut req = format!(
+        "{} /mcp HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n",
+        method,
+        addr,
+        body.len()
+    );
+    for (k, v) in headers {
+        req.push_str(&format!("{}: {}\r\n", k, v));
+    }
+    req.push_str("\r\n");
+    req.push_str(body);
+    stream.write_all(req.as_bytes()).unwrap();
+
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).unwrap();
+    let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    (status, head.to_string(), body.to_string())
+}
+
+#[test]
+fn test_http_post_round_trip() {
+    let data = tempfile::tempdir().unwrap();
+    let router = Arc::new(setup_router(data.path()));
+
+    let server = HttpServer::bind("127.0.0.1:0", false).unwrap();
+    let addr = server.local_addr().unwrap();
+    std::thread::spawn(move || server.serve(router));
+
+    // 1. initialize issues a session id
+    let init = json!({"jsonrpc": "2.0", "method": "initialize", "params": {}, "id": 1});
+    let (status, head, body) = send(&addr, "POST", &[], &init.to_string());
+    assert_eq!(status, 200);
+    assert!(head.to_ascii_lowercase().contains("mcp-session-id:"));
+    let resp: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(resp["id"], 1);
+    assert!(resp["result"]["serverInfo"].is_object());
+
+    // 2. tools/list goes through the same router
+    let list = json!({"jsonrpc": "2.0", "method": "tools/list", "id": 2});
+    let (status, _, body) = send(&addr, "POST", &[], &list.to_string());
+    assert_eq!(status, 200);
+    let resp: Value = serde_json::from_str(&body).unwrap();
+    assert!(!resp["result"]["tools"].as_array().unwrap().is_empty());
+
+    // 3. Notifications are accepted without a body
+    let note = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+    let (status, _, body) = send(&addr, "POST", &[], &note.to_string());
+    assert_eq!(status, 202);
+    assert!(body.is_empty());
+
+    // 4. Malformed JSON yields a parse error
+    let (status, _, body) = send(&addr, "POST", &[], "{not json");
+    assert_eq!(status, 400);
+    let resp: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(resp["error"]["code"], -32700);
+
+    // 5. Foreign browser origins are rejected
+    let (status, _, _) = send(
+        &addr,
+        "POST",
+        &[("Origin", "https://evil.example")],
+        &list.to_string(),
+    );
+    assert_eq!(status, 403);
+}
+
+/// Opens an SSE stream for `session` and returns it once the headers are read.
+fn open_stream(addr: &str, session: &str) -> BufReader<TcpStream> {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let req = format!(
+        "GET /mcp HTTP/1.1\r\nHost: {}\r\nAccept: text/event-stream\r\nMcp-Session-Id: {}\r\n\r\n",
+        addr, session
+    );
+    stream.write_all(req.as_bytes()).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_millis(500)))
+        .unwrap();
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" {
+            return reader;
+        }
+    }
+}
+
+/// The next `data:` payload of an SSE stream, or `None` if none arrives in time.
+fn next_event(reader: &mut BufReader<TcpStream>) -> Option<Value> {
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => {
+                if let Some(data) = line.strip_prefix("data: ") {
+                    return Some(serde_json::from_str(data.trim()).unwrap());
+                }
+            }
+        }
+    }
+}
+
+fn new_session(addr: &str) -> String {
+    let init = json!({"jsonrpc": "2.0", "method": "initialize", "params": {}, "id": 1});
+    let (_, head, _) = send(addr, "POST", &[], &init.to_string());
+    head.lines()
+        .find_map(|l| {
+            let (k, v) = l.split_once(':')?;
+            k.eq_ignore_ascii_case("mcp-session-id")
+                .then(|| v.trim().to_string())
+        })
+        .unwrap()
+}
+
+#[test]
+fn test_http_notifications_reach_only_their_session() {
+    let data = tempfile::tempdir().unwrap();
+    let server = HttpServer::bind("127.0.0.1:0", false).unwrap();
+    let addr = server.local_addr().unwrap();
+    let events = server.events();
+    let router = Arc::new(setup_router(data.path()));
+    std::thread::spawn(move || server.serve(router));
+
+    let a = new_session(&addr);
+    let b = new_session(&addr);
+    let mut stream_a = open_stream(&addr, &a);
+    let mut stream_b = open_stream(&addr, &b);
+    // The streams subscribe after their headers are written.
+    std::thread::sleep(Duration::from_millis(100));
+
+    let progress = json!({"jsonrpc": "2.0", "method": "notifications/progress", "params": {}});
+    events.publish(Some(&a), &progress);
+    assert_eq!(next_event(&mut stream_a), Some(progress));
+    assert_eq!(next_event(&mut stream_b), None);
+
+    let message = json!({"jsonrpc": "2.0", "method": "notifications/message", "params": {}});
+    events.publish(None, &message);
+    assert_eq!(next_event(&mut stream_a), Some(message.clone()));
+    assert_eq!(next_event(&mut stream_b), Some(message));
+}
+
+#[test]
+fn test_http_refuses_remote_bind_by_default() {
+    assert!(HttpServer::bind("0.0.0.0:0", false).is_err());
+}
@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::setup_router;
+use serde_json::{Value, json};
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+fn handle(router: &Router, payload: Value) -> Option<Value> {
+    router.handle_message(&payload.to_string())
+}
+
+#[test]
+fn test_batch_returns_responses_for_calls_only() {
+    let data = tempfile::tempdir().unwrap();
+    let router = setup_router(data.path());
+
+    let resp = handle(
+        &router,
+        json!([
+            {"jsonrpc": "2.0", "method": "initialize", "params": {}, "id": 1},
+            {"jsonrpc": "2.0", "method": "notifications/initialized"},
+            {"jsonrpc": "2.0", "method": "tools/list", "id": "two"},
+            {"jsonrpc": "2.0", "id": 3},
+            42
+        ]),
+    )
+    .expect("batch with calls must produce a response");
+
+    let items = resp.as_array().unwrap();
+    assert_eq!(items.len(), 4);
+    assert_eq!(items[0]["id"], 1);
+    assert!(items[0]["result"]["serverInfo"].is_object());
+    assert_eq!(items[1]["id"], "two");
+    assert!(items[1]["result"]["tools"].is_array());
+    assert_eq!(items[2]["id"], 3);
+    assert_eq!(items[2]["error"]["code"], -32600);
+    assert_eq!(items[3]["id"], Value::Null);
+    assert_eq!(items[3]["error"]["code"], -32600);
+}
+
+#[test]
+fn test_notifications_get_no_response() {
+    let data = tempfile::tempdir().unwrap();
+    let router = setup_router(data.path());
+
+    assert!(
+        handle(
+            &router,
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"})
+        )
+        .is_none()
+    );
+    assert!(
+        handle(
+            &router,
+            json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 99}})
+        )
+        .is_none()
+    );
+    assert!(
+        handle(
+            &router,
+            json!([{"jsonrpc": "2.0", "method": "notifications/initialized"}])
+        )
+        .is_none()
+    );
+}
+
+#[test]
+fn test_parse_and_invalid_request_errors() {
+    let data = tempfile::tempdir().unwrap();
+    let router = setup_router(data.path());
+
+    let resp = router.handle_message("{not json").unwrap();
+    assert_eq!(resp["error"]["code"], -32700);
+    assert_eq!(resp["id"], Value::Null);
+
+    let resp = handle(&router, json!([])).unwrap();
+    assert_eq!(resp["error"]["code"], -32600);
+}
+
+#[test]
+fn test_admitted_calls_can_be_cancelled_before_dispatch() {
+    let data = tempfile::tempdir().unwrap();
+    let router = setup_router(data.path());
+    let call = |id: i64| json!({"jsonrpc": "2.0", "method": "tools/list", "id": id}).to_string();
+
+    // Queued, then cancelled before a worker picks it up.
+    let queued = router.admit(None, &call(1));
+    let cancel = router.admit(
+        None,
+        &json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 1}})
+            .to_string(),
+    );
+    assert!(router.dispatch(cancel).is_none());
+    assert!(router.dispatch(queued).is_none());
+
+    // An id is rejected while a request holding it waits, in that session only.
+    let queued = router.admit(Some("a"), &call(2));
+    let resp = router.dispatch(router.admit(Some("a"), &call(2))).unwrap();
+    assert_eq!(resp["error"]["code"], -32600);
+    let resp = router.dispatch(router.admit(Some("b"), &call(2))).unwrap();
+    assert!(resp["result"]["tools"].is_array());
+    assert_eq!(router.dispatch(queued).unwrap()["id"], 2);
+
+    // Dropping an admitted payload releases its ids.
+    drop(router.admit(None, &call(3)));
+    assert_eq!(
+        router.dispatch(router.admit(None, &call(3))).unwrap()["id"],
+        3
+    );
+}
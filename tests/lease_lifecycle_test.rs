@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use axiomregent::snapshot::fingerprint::NativeFingerprinter;
+use axiomregent::snapshot::lease::LeaseStore;
+use common::{RouterSetup, setup_router, setup_router_with};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn git(root: &Path, args: &[&str]) {
+    Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap();
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    let root = repo.path();
+    git(root, &["init"]);
+    git(root, &["config", "user.email", "test@example.com"]);
+    git(root, &["config", "user.name", "Test"]);
+    std::fs::write(root.join("a.txt"), "v1\n").unwrap();
+    std::fs::write(root.join("b.txt"), "b\n").unwrap();
+    git(root, &["add", "."]);
+    git(root, &["commit", "-m", "initial"]);
+    repo
+}
+
+fn code(resp: &Value) -> &str {
+    resp["error"]["data"]["code"].as_str().unwrap_or_default()
+}
+
+#[test]
+fn test_lease_acquire_list_renew_release() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let resp = call(
+        &router,
+        "lease.acquire",
+        json!({ "repo_root": repo_root, "holder": "agent-7", "purpose": "refactor a.txt", "ttl_secs": 120 }),
+    );
+    let lease = result(&resp).clone();
+    let lease_id = lease["lease_id"].as_str().unwrap().to_string();
+    assert_eq!(lease["holder"], "agent-7");
+    assert_eq!(lease["ttl_secs"], 120);
+    assert_eq!(
+        lease["expires_at"].as_i64().unwrap(),
+        lease["issued_at"].as_i64().unwrap() + 120
+    );
+    assert!(lease["fingerprint"]["status_hash"].is_string());
+
+    // Using the lease records what it touched.
+    let resp = call(
+        &router,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "worktree", "lease_id": lease_id }),
+    );
+    result(&resp);
+    // An implicit lease on another repository.
+    let other = setup_repo();
+    let other_root = other.path().to_string_lossy().to_string();
+    call(
+        &router,
+        "snapshot.list",
+        json!({ "repo_root": other_root, "path": ".", "mode": "worktree" }),
+    );
+
+    let resp = call(&router, "lease.list", json!({ "repo_root": repo_root }));
+    let leases = result(&resp)["leases"].as_array().unwrap().clone();
+    assert_eq!(leases.len(), 1);
+    assert_eq!(leases[0]["lease_id"], lease_id.as_str());
+    assert_eq!(leases[0]["purpose"], "refactor a.txt");
+    assert_eq!(leases[0]["touched_files"], json!(["a.txt"]));
+    let resp = call(&router, "lease.list", json!({}));
+    let all = result(&resp)["leases"].as_array().unwrap().clone();
+    assert_eq!(all.len(), 2);
+    // Leases issued within the same second are listed in id order.
+    let implicit = all.iter().find(|l| l["lease_id"] != lease_id.as_str());
+    assert!(implicit.unwrap()["holder"].is_null());
+
+    let resp = call(
+        &router,
+        "lease.renew",
+        json!({ "lease_id": lease_id, "ttl_secs": 3600 }),
+    );
+    assert_eq!(result(&resp)["ttl_secs"], 3600);
+    assert!(
+        result(&resp)["expires_at"].as_i64().unwrap()
+            >= lease["expires_at"].as_i64().unwrap() + 3480
+    );
+
+    let resp = call(&router, "lease.release", json!({ "lease_id": lease_id }));
+    assert_eq!(result(&resp)["released"], true);
+    let resp = call(&router, "lease.release", json!({ "lease_id": lease_id }));
+    assert_eq!(code(&resp), "NOT_FOUND");
+    let resp = call(&router, "lease.renew", json!({ "lease_id": lease_id }));
+    assert_eq!(code(&resp), "NOT_FOUND");
+    let resp = call(
+        &router,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "worktree", "lease_id": lease_id }),
+    );
+    assert_eq!(code(&resp), "NOT_FOUND");
+
+    let resp = call(
+        &router,
+        "lease.acquire",
+        json!({ "repo_root": repo_root, "ttl_secs": 0 }),
+    );
+    assert_eq!(code(&resp), "INVALID_ARGUMENT");
+}
+
+#[test]
+fn test_leases_expire() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let (router, lease_store) = setup_router_with(data.path(), RouterSetup::default());
+
+    let resp = call(
+        &router,
+        "lease.acquire",
+        json!({ "repo_root": repo_root, "holder": "abandoned", "ttl_secs": 1 }),
+    );
+    let lease_id = result(&resp)["lease_id"].as_str().unwrap().to_string();
+    std::thread::sleep(std::time::Duration::from_millis(2100));
+
+    // Expired leases are gone from the listing and refused before the sweep.
+    let resp = call(&router, "lease.list", json!({ "repo_root": repo_root }));
+    assert!(result(&resp)["leases"].as_array().unwrap().is_empty());
+    let resp = call(
+        &router,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "worktree", "lease_id": lease_id }),
+    );
+    assert_eq!(code(&resp), "NOT_FOUND");
+    assert!(
+        resp["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("expired"),
+        "{}",
+        resp
+    );
+
+    let expired = lease_store.expire().unwrap();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].holder.as_deref(), Some("abandoned"));
+    assert!(lease_store.get_fingerprint(&lease_id).is_none());
+}
+
+#[test]
+fn test_native_leases_go_stale_on_every_edit() {
+    let data = tempfile::tempdir().unwrap();
+    let native = LeaseStore::new().with_fingerprinter(Arc::new(NativeFingerprinter::new()));
+    let setup = RouterSetup {
+        lease_store: native,
+        ..Default::default()
+    };
+    let router = setup_router_with(data.path(), setup).0;
+
+    // A second edit of an already modified file, and a plain directory.
+    let repo = setup_repo();
+    std::fs::write(repo.path().join("a.txt"), "v2\n").unwrap();
+    let plain = tempfile::tempdir().unwrap();
+    std::fs::write(plain.path().join("a.txt"), "v1\n").unwrap();
+
+    for root in [repo.path(), plain.path()] {
+        let repo_root = root.to_string_lossy().to_string();
+        let resp = call(&router, "lease.acquire", json!({ "repo_root": repo_root }));
+        let lease_id = result(&resp)["lease_id"].as_str().unwrap().to_string();
+        let read = json!({ "repo_root": repo_root, "path": "a.txt", "mode": "worktree", "lease_id": lease_id });
+        result(&call(&router, "snapshot.read", read.clone()));
+
+        std::fs::write(root.join("a.txt"), "v3\n").unwrap();
+        let resp = call(&router, "snapshot.read", read);
+        assert_eq!(code(&resp), "STALE_LEASE", "{}", repo_root);
+    }
+}
@@ -0,0 +1,184 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::{RouterSetup, setup_router, setup_router_with};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn git(root: &Path, args: &[&str]) {
+    Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap();
+}
+
+fn read(repo: &Path, path: &str) -> String {
+    std::fs::read_to_string(repo.join(path)).unwrap()
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    let root = repo.path();
+    git(root, &["init"]);
+    git(root, &["config", "user.email", "test@example.com"]);
+    git(root, &["config", "user.name", "Test"]);
+    std::fs::write(root.join("a.txt"), "v1\n").unwrap();
+    std::fs::write(root.join("b.txt"), "b\n").unwrap();
+    git(root, &["add", "."]);
+    git(root, &["commit", "-m", "initial"]);
+    repo
+}
+
+fn code(resp: &Value) -> &str {
+    resp["error"]["data"]["code"].as_str().unwrap_or_default()
+}
+
+fn acquire(router: &Router, repo_root: &str, holder: &str, reserve: &[&str]) -> String {
+    let resp = call(
+        router,
+        "lease.acquire",
+        json!({ "repo_root": repo_root, "holder": holder, "reserve": reserve }),
+    );
+    result(&resp)["lease_id"].as_str().unwrap().to_string()
+}
+
+fn write(router: &Router, repo_root: &str, lease_id: &str, path: &str, content: &str) -> Value {
+    call(
+        router,
+        "workspace.write_file",
+        json!({ "repo_root": repo_root, "path": path, "content_base64": content, "lease_id": lease_id }),
+    )
+}
+
+#[test]
+fn test_reservations_are_exclusive() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    std::fs::create_dir(repo.path().join("src")).unwrap();
+
+    let alice = acquire(&router, &repo_root, "alice", &["src"]);
+    let resp = call(
+        &router,
+        "lease.acquire",
+        json!({ "repo_root": repo_root, "holder": "bob", "reserve": ["**/*.rs"] }),
+    );
+    assert_eq!(code(&resp), "PERMISSION_DENIED");
+    assert!(
+        resp["error"]["message"].as_str().unwrap().contains("alice"),
+        "{}",
+        resp
+    );
+
+    let bob = acquire(&router, &repo_root, "bob", &["./docs/"]);
+    let resp = call(
+        &router,
+        "lease.reserve",
+        json!({ "lease_id": bob, "paths": ["src/lib.rs"] }),
+    );
+    assert_eq!(code(&resp), "PERMISSION_DENIED");
+    let resp = call(
+        &router,
+        "lease.reserve",
+        json!({ "lease_id": bob, "paths": ["tests/*.rs"] }),
+    );
+    assert_eq!(result(&resp)["reserved"], json!(["docs", "tests/*.rs"]));
+
+    let resp = call(&router, "lease.list", json!({ "repo_root": repo_root }));
+    let leases = result(&resp)["leases"].as_array().unwrap().clone();
+    let listed = leases.iter().find(|l| l["holder"] == "alice").unwrap();
+    assert_eq!(listed["reserved"], json!(["src"]));
+
+    // Bob may not write into Alice's reservation, not even as a new file.
+    let resp = write(&router, &repo_root, &bob, "src/new.rs", "x");
+    assert_eq!(code(&resp), "PERMISSION_DENIED");
+    assert!(!repo.path().join("src/new.rs").exists());
+
+    // Once Alice releases, the path is free.
+    call(&router, "lease.release", json!({ "lease_id": alice }));
+    let resp = write(&router, &repo_root, &bob, "src/new.rs", "x");
+    result(&resp);
+}
+
+#[test]
+fn test_reserved_writes_do_not_invalidate_other_leases() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let alice = acquire(&router, &repo_root, "alice", &["a.txt"]);
+    let bob = acquire(&router, &repo_root, "bob", &["b.txt"]);
+    let carol = acquire(&router, &repo_root, "carol", &[]);
+
+    // Writes inside one's own reservation keep both reserving leases valid.
+    result(&write(&router, &repo_root, &alice, "a.txt", "alice 1\n"));
+    result(&write(&router, &repo_root, &bob, "b.txt", "bob 1\n"));
+    result(&write(&router, &repo_root, &alice, "a.txt", "alice 2\n"));
+    assert_eq!(read(repo.path(), "a.txt"), "alice 2\n");
+    assert_eq!(read(repo.path(), "b.txt"), "bob 1\n");
+
+    // Carol reserved nothing, so any change makes her lease stale.
+    let resp = write(&router, &repo_root, &carol, "c.txt", "carol\n");
+    assert_eq!(code(&resp), "STALE_LEASE");
+
+    // A write outside Alice's reservation invalidates everyone, Alice included.
+    result(&write(&router, &repo_root, &alice, "c.txt", "alice\n"));
+    let resp = write(&router, &repo_root, &bob, "b.txt", "bob 2\n");
+    assert_eq!(code(&resp), "STALE_LEASE");
+    let resp = write(&router, &repo_root, &alice, "a.txt", "alice 3\n");
+    assert_eq!(code(&resp), "STALE_LEASE");
+}
+
+#[test]
+fn test_changes_made_during_a_reserved_write_invalidate_other_leases() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let (router, lease_store) = setup_router_with(data.path(), RouterSetup::default());
+
+    let alice = acquire(&router, &repo_root, "alice", &["a.txt"]);
+    let bob = acquire(&router, &repo_root, "bob", &["b.txt"]);
+
+    // Alice writes her file while someone else edits an unreserved one: the
+    // write's paths are all hers, but its delta is not.
+    let base = lease_store.write_base(&alice, repo.path()).unwrap();
+    std::fs::write(repo.path().join("a.txt"), "alice\n").unwrap();
+    std::fs::write(repo.path().join("c.txt"), "someone else\n").unwrap();
+    lease_store
+        .refresh_after_write(&alice, repo.path(), base)
+        .unwrap();
+
+    let resp = write(&router, &repo_root, &bob, "b.txt", "bob\n");
+    assert_eq!(code(&resp), "STALE_LEASE");
+    let resp = write(&router, &repo_root, &alice, "a.txt", "alice 2\n");
+    assert_eq!(code(&resp), "STALE_LEASE");
+}
@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::mounts::{Mount, MountRegistry};
+use axiomregent::router::{JsonRpcRequest, Router};
+use common::{RouterSetup, setup_router_with};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+fn strict_router(data_dir: &Path, mounts: MountRegistry) -> Router {
+    let setup = RouterSetup {
+        mounts,
+        strict_mounts: true,
+        ..Default::default()
+    };
+    setup_router_with(data_dir, setup).0
+}
+
+fn call(router: &Router, name: &str, arguments: Value) -> axiomregent::router::JsonRpcResponse {
+    let req = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        method: "tools/call".to_string(),
+        params: Some(json!({ "name": name, "arguments": arguments })),
+        id: Some(json!(1)),
+    };
+    router.handle_request(&req)
+}
+
+#[test]
+fn test_strict_mounts_scope_repo_root() {
+    let repo = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let data = tempfile::tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    std::fs::write(repo.path().join("file.txt"), "hello").unwrap();
+
+    let mounts = MountRegistry::new();
+    mounts.register(Mount {
+        name: "demo".to_string(),
+        root: repo.path().to_string_lossy().to_string(),
+        resolved_id: None,
+        kind: Some("local".to_string()),
+        capabilities: vec![
+            "read_file".to_string(),
+            "list_files".to_string(),
+            "search".to_string(),
+        ],
+    });
+    let router = strict_router(data.path(), mounts);
+
+    // 1. Mount name is accepted in place of a path.
+    let resp = call(
+        &router,
+        "snapshot.list",
+        json!({ "repo_root": "demo", "path": "", "mode": "worktree" }),
+    );
+    assert!(resp.error.is_none(), "unexpected error: {:?}", resp.error);
+    let entries = &resp.result.unwrap()["content"][0]["json"]["entries"];
+    assert!(
+        entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|e| e["path"] == "file.txt")
+    );
+
+    // 2. A root outside every mount is rejected.
+    let resp = call(
+        &router,
+        "snapshot.list",
+        json!({ "repo_root": outside.path(), "path": "", "mode": "worktree" }),
+    );
+    let err = resp.error.expect("outside root must be rejected");
+    assert_eq!(err["data"]["code"], "PERMISSION_DENIED");
+
+    // 3. Tools needing a capability the mount lacks are rejected.
+    let resp = call(
+        &router,
+        "workspace.write_file",
+        json!({
+            "repo_root": "demo",
+            "path": "file.txt",
+            "content_base64": "changed",
+            "lease_id": "irrelevant"
+        }),
+    );
+    let err = resp
+        .error
+        .expect("write without capability must be rejected");
+    assert_eq!(err["data"]["code"], "PERMISSION_DENIED");
+    assert_eq!(
+        std::fs::read_to_string(repo.path().join("file.txt")).unwrap(),
+        "hello"
+    );
+}
+
+#[test]
+fn test_strict_mounts_scope_tools_without_root() {
+    let repo = tempfile::tempdir().unwrap();
+    let data = tempfile::tempdir().unwrap();
+    let mounts = MountRegistry::new();
+    let router = strict_router(data.path(), mounts.clone());
+
+    // Every tool but the core ones declares a capability.
+    for name in router.tool_names() {
+        let core = axiomregent::config::tool_group(&name) == "core";
+        let capability = router.tool(&name).unwrap().capability();
+        assert_eq!(capability.is_none(), core, "{}", name);
+    }
+
+    // A tool naming no repository needs some mount to grant its capability.
+    let resp = call(&router, "run.status", json!({ "run_id": "missing" }));
+    assert_eq!(resp.error.unwrap()["data"]["code"], "PERMISSION_DENIED");
+
+    mounts.register(mount("demo", repo.path(), &["read_file"]));
+    let resp = call(&router, "run.status", json!({ "run_id": "missing" }));
+    assert!(resp.error.is_none(), "unexpected error: {:?}", resp.error);
+
+    // Store-wide tools act beyond any one mount: they need `admin`.
+    let resp = call(&router, "snapshot.store_stats", json!({}));
+    assert_eq!(resp.error.unwrap()["data"]["code"], "PERMISSION_DENIED");
+    let resp = call(&router, "snapshot.migrate_store", json!({ "to": "db" }));
+    assert_eq!(resp.error.unwrap()["data"]["code"], "PERMISSION_DENIED");
+    mounts.register(mount("ops", repo.path(), &["admin"]));
+    let resp = call(&router, "snapshot.store_stats", json!({}));
+    assert!(resp.error.is_none(), "unexpected error: {:?}", resp.error);
+}
+
+fn mount(name: &str, root: &Path, capabilities: &[&str]) -> Mount {
+    Mount {
+        name: name.to_string(),
+        root: root.to_string_lossy().to_string(),
+        resolved_id: None,
+        kind: Some("local".to_string()),
+        capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+    }
+}
+
+fn result(resp: axiomregent::router::JsonRpcResponse) -> Value {
+    assert!(resp.error.is_none(), "unexpected error: {:?}", resp.error);
+    resp.result.unwrap()["content"][0]["json"].clone()
+}
+
+#[test]
+fn test_strict_mounts_scope_leases_and_snapshot_resources() {
+    let app = tempfile::tempdir().unwrap();
+    let vendor = tempfile::tempdir().unwrap();
+    let data = tempfile::tempdir().unwrap();
+    for repo in [&app, &vendor] {
+        Command::new("git")
+            .arg("init")
+            .current_dir(repo.path())
+            .output()
+            .unwrap();
+        std::fs::write(repo.path().join("file.txt"), "hello").unwrap();
+    }
+
+    let mounts = MountRegistry::new();
+    mounts.register(mount(
+        "app",
+        app.path(),
+        &["read_file", "list_files", "snapshot", "write_file"],
+    ));
+    mounts.register(mount("vendor", vendor.path(), &["list_files", "snapshot"]));
+    let router = strict_router(data.path(), mounts);
+
+    let app_lease = result(call(
+        &router,
+        "lease.acquire",
+        json!({ "repo_root": "app" }),
+    ));
+    let app_lease = app_lease["lease_id"].as_str().unwrap();
+    let vendor_lease = result(call(
+        &router,
+        "snapshot.list",
+        json!({ "repo_root": "vendor", "path": "", "mode": "worktree" }),
+    ));
+    let vendor_lease = vendor_lease["lease_id"].as_str().unwrap();
+
+    // Calls naming only a lease are checked against the lease's repository.
+    let resp = call(&router, "lease.renew", json!({ "lease_id": app_lease }));
+    assert!(resp.error.is_none(), "unexpected error: {:?}", resp.error);
+    let resp = call(
+        &router,
+        "lease.release",
+        json!({ "lease_id": vendor_lease }),
+    );
+    let err = resp.error.unwrap();
+    assert_eq!(err["data"]["code"], "PERMISSION_DENIED");
+    assert_eq!(err["data"]["lease_id"], vendor_lease);
+
+    // Listing every lease leaves out repositories the mounts do not let it read.
+    let leases = result(call(&router, "lease.list", json!({})));
+    let ids: Vec<&str> = leases["leases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l["lease_id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, [app_lease]);
+
+    // Snapshot resources are checked against the repository they were taken of.
+    let read = |root: &str| {
+        let snapshot = result(call(
+            &router,
+            "snapshot.create",
+            json!({ "repo_root": root, "paths": ["file.txt"] }),
+        ));
+        let uri = format!(
+            "axiomregent://snapshot/{}/file.txt",
+            snapshot["snapshot_id"].as_str().unwrap()
+        );
+        router.handle_request(&JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "resources/read".to_string(),
+            params: Some(json!({ "uri": uri })),
+            id: Some(json!(1)),
+        })
+    };
+    let resp = read("app");
+    assert!(resp.error.is_none(), "unexpected error: {:?}", resp.error);
+    let resp = read("vendor");
+    assert_eq!(resp.error.unwrap()["data"]["code"], "PERMISSION_DENIED");
+}
+
+#[test]
+fn test_strict_mounts_keep_snapshot_ids_to_their_repository() {
+    let app = tempfile::tempdir().unwrap();
+    let vendor = tempfile::tempdir().unwrap();
+    let data = tempfile::tempdir().unwrap();
+    for repo in [&app, &vendor] {
+        Command::new("git")
+            .arg("init")
+            .current_dir(repo.path())
+            .output()
+            .unwrap();
+    }
+    std::fs::write(app.path().join("file.txt"), "app").unwrap();
+    std::fs::write(vendor.path().join("file.txt"), "secret").unwrap();
+
+    let mounts = MountRegistry::new();
+    mounts.register(mount(
+        "app",
+        app.path(),
+        &["read_file", "search", "snapshot"],
+    ));
+    mounts.register(mount("vendor", vendor.path(), &["snapshot"]));
+    let router = strict_router(data.path(), mounts);
+
+    let snapshot = result(call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": "vendor", "paths": ["file.txt"] }),
+    ));
+    let vendor_snapshot = snapshot["snapshot_id"].as_str().unwrap();
+
+    // The app mount grants reading, but not another repository's snapshots.
+    for (tool, arguments) in [
+        (
+            "snapshot.read",
+            json!({ "path": "file.txt", "mode": "snapshot" }),
+        ),
+        (
+            "snapshot.grep",
+            json!({ "pattern": "secret", "mode": "snapshot" }),
+        ),
+        ("snapshot.info", json!({})),
+    ] {
+        let mut arguments = arguments;
+        arguments["repo_root"] = json!("app");
+        arguments["snapshot_id"] = json!(vendor_snapshot);
+        let resp = call(&router, tool, arguments);
+        let err = resp.error.expect("foreign snapshot must be rejected");
+        assert_eq!(err["data"]["code"], "INVALID_ARGUMENT", "{}", tool);
+        assert!(!err.to_string().contains("secret"), "{}", tool);
+    }
+}
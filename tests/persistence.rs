@@ -2,8 +2,11 @@
 // Copyright (C) 2026 Bartek Kus
 
 use axiomregent::config::{BlobBackend, Compression, StorageConfig};
+use axiomregent::snapshot::lease::{LeaseStore, StaleLeaseError};
 use axiomregent::snapshot::store::{Entry, Manifest, Store};
 use std::fs;
+use std::process::Command;
+use std::sync::Arc;
 use tempfile::tempdir;
 
 #[test]
@@ -28,6 +31,9 @@ fn test_persistence_survives_restart() -> anyhow::Result<()> {
             path: "file.txt".to_string(),
             blob: hash.clone(),
             size: content.len() as u64,
+            chunks: Vec::new(),
+            executable: false,
+            symlink: None,
         }]);
         let manifest_bytes = serde_json::to_vec(&manifest)?;
 
@@ -110,6 +116,9 @@ fn test_invariant_missing_blob_row() -> anyhow::Result<()> {
         path: "ghost.txt".to_string(),
         blob: fake_hash.to_string(),
         size: 100,
+        chunks: Vec::new(),
+        executable: false,
+        symlink: None,
     }]);
     let manifest_bytes = serde_json::to_vec(&manifest)?;
 
@@ -133,3 +142,38 @@ fn test_invariant_missing_blob_row() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_leases_survive_restart() -> anyhow::Result<()> {
+    let dir = tempdir()?;
+    let repo = tempdir()?;
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()?;
+    fs::write(repo.path().join("a.txt"), "a")?;
+    let config = StorageConfig {
+        data_dir: dir.path().to_path_buf(),
+        blob_backend: BlobBackend::Fs,
+        compression: Compression::None,
+    };
+
+    let lease_id = {
+        let leases = LeaseStore::open(Arc::new(Store::new(config.clone())?))?;
+        let lease_id = leases.issue(repo.path(), leases.fingerprint(repo.path())?)?;
+        leases.touch_files(&lease_id, vec!["a.txt".to_string()])?;
+        lease_id
+    };
+
+    // "Restart": the lease is known, valid and keeps its touched files.
+    let leases = LeaseStore::open(Arc::new(Store::new(config.clone())?))?;
+    leases.check_lease(&lease_id, repo.path())?;
+    assert_eq!(leases.get_touched_files(&lease_id).unwrap(), ["a.txt"]);
+
+    // Once the repo changes, it is stale rather than unknown.
+    fs::write(repo.path().join("b.txt"), "b")?;
+    let leases = LeaseStore::open(Arc::new(Store::new(config)?))?;
+    let err = leases.check_lease(&lease_id, repo.path()).unwrap_err();
+    assert!(err.downcast_ref::<StaleLeaseError>().is_some(), "{}", err);
+    Ok(())
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use axiomregent::router::logging::ClientLog;
+use common::setup_router;
+use log::{Level, Record};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+fn rpc(router: &Router, method: &str, params: Value) -> Value {
+    router
+        .handle_message(
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }).to_string(),
+        )
+        .unwrap()
+}
+
+fn recording_router(data_dir: &Path) -> (Router, Arc<Mutex<Vec<Value>>>) {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let sink = sent.clone();
+    let router =
+        setup_router(data_dir).with_notifier(move |_, msg| sink.lock().unwrap().push(msg.clone()));
+    (router, sent)
+}
+
+#[test]
+fn test_xray_scan_reports_progress() {
+    let data = tempfile::tempdir().unwrap();
+    let repo = tempfile::tempdir().unwrap();
+    for i in 0..150 {
+        std::fs::write(repo.path().join(format!("f{:03}.txt", i)), "x\n").unwrap();
+    }
+    let (router, sent) = recording_router(data.path());
+    let repo_root = repo.path().to_string_lossy();
+
+    let resp = rpc(
+        &router,
+        "tools/call",
+        json!({
+            "name": "xray.scan",
+            "arguments": { "repo_root": repo_root },
+            "_meta": { "progressToken": "scan-1" }
+        }),
+    );
+    assert!(resp["error"].is_null(), "{}", resp);
+
+    let sent = sent.lock().unwrap();
+    let progress: Vec<(u64, Option<u64>)> = sent
+        .iter()
+        .filter(|m| m["method"] == "notifications/progress")
+        .map(|m| {
+            assert_eq!(m["params"]["progressToken"], "scan-1");
+            (
+                m["params"]["progress"].as_u64().unwrap(),
+                m["params"]["total"].as_u64(),
+            )
+        })
+        .collect();
+    assert_eq!(progress, [(100, None), (150, Some(150))]);
+}
+
+#[test]
+fn test_progress_goes_to_the_requesting_session() {
+    let data = tempfile::tempdir().unwrap();
+    let repo = tempfile::tempdir().unwrap();
+    std::fs::write(repo.path().join("a.txt"), "x\n").unwrap();
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let sink = sent.clone();
+    let router = setup_router(data.path()).with_notifier(move |session, msg| {
+        sink.lock()
+            .unwrap()
+            .push((session.map(str::to_string), msg.clone()))
+    });
+
+    let resp = router
+        .handle_session_message(
+            Some("a"),
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": {
+                    "name": "xray.scan",
+                    "arguments": { "repo_root": repo.path().to_string_lossy() },
+                    "_meta": { "progressToken": "scan-a" }
+                },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap();
+    assert!(resp["error"].is_null(), "{}", resp);
+
+    let sent = sent.lock().unwrap();
+    assert!(!sent.is_empty());
+    for (session, msg) in sent.iter() {
+        assert_eq!(msg["method"], "notifications/progress");
+        assert_eq!(session.as_deref(), Some("a"));
+    }
+}
+
+#[test]
+fn test_no_progress_without_token() {
+    let data = tempfile::tempdir().unwrap();
+    let repo = tempfile::tempdir().unwrap();
+    std::fs::write(repo.path().join("a.txt"), "x\n").unwrap();
+    let (router, sent) = recording_router(data.path());
+
+    let resp = rpc(
+        &router,
+        "tools/call",
+        json!({ "name": "xray.scan", "arguments": { "repo_root": repo.path().to_string_lossy() } }),
+    );
+    assert!(resp["error"].is_null(), "{}", resp);
+    assert!(sent.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_logging_set_level() {
+    let data = tempfile::tempdir().unwrap();
+    let (router, _) = recording_router(data.path());
+
+    let resp = rpc(&router, "logging/setLevel", json!({ "level": "warning" }));
+    assert_eq!(resp["result"], json!({}));
+
+    let resp = rpc(&router, "logging/setLevel", json!({ "level": "verbose" }));
+    assert_eq!(resp["error"]["code"], -32602);
+
+    let resp = rpc(&router, "logging/setLevel", json!({}));
+    assert_eq!(resp["error"]["code"], -32602);
+}
+
+#[test]
+fn test_log_records_go_to_the_sessions_that_set_a_level() {
+    let data = tempfile::tempdir().unwrap();
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let sink = sent.clone();
+    let client_log = ClientLog::new();
+    let router = setup_router(data.path())
+        .with_notifier(move |session, msg| {
+            sink.lock()
+                .unwrap()
+                .push((session.map(str::to_string), msg.clone()))
+        })
+        .with_client_log(client_log.clone());
+    let set_level = |session: &str, level: &str| {
+        let msg = json!({
+            "jsonrpc": "2.0",
+            "method": "logging/setLevel",
+            "params": { "level": level },
+            "id": 1
+        });
+        let resp = router
+            .handle_session_message(Some(session), &msg.to_string())
+            .unwrap();
+        assert_eq!(resp["result"], json!({}));
+    };
+    let log_at = |level: Level| {
+        client_log.forward(
+            &Record::builder()
+                .level(level)
+                .args(format_args!("record"))
+                .build(),
+        );
+    };
+    set_level("a", "info");
+    set_level("b", "error");
+
+    log_at(Level::Info);
+    let recipients: Vec<Option<String>> = sent.lock().unwrap().drain(..).map(|(s, _)| s).collect();
+    assert_eq!(recipients, [Some("a".to_string())]);
+
+    router.end_session("a");
+    log_at(Level::Info);
+    log_at(Level::Error);
+    let sent = sent.lock().unwrap();
+    let recipients: Vec<Option<String>> = sent.iter().map(|(s, _)| s.clone()).collect();
+    assert_eq!(recipients, [Some("b".to_string())]);
+    assert_eq!(sent[0].1["method"], "notifications/message");
+    assert_eq!(sent[0].1["params"]["level"], "error");
+}
@@ -0,0 +1,230 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use axiomregent::router::mounts::{Mount, MountRegistry};
+use common::{RouterSetup, setup_router, setup_router_with};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+fn mounted_router(data_dir: &Path, repo: &Path) -> Router {
+    let setup = RouterSetup {
+        mounts: mount(repo),
+        ..Default::default()
+    };
+    setup_router_with(data_dir, setup).0
+}
+
+fn call(router: &Router, id: u64, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": id
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn rpc(router: &Router, method: &str, params: Value) -> Value {
+    router
+        .handle_message(
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 }).to_string(),
+        )
+        .unwrap()
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    let root = repo.path();
+    Command::new("git")
+        .arg("init")
+        .current_dir(root)
+        .output()
+        .unwrap();
+    std::fs::create_dir_all(root.join("spec/core")).unwrap();
+    std::fs::write(
+        root.join("spec/features.yaml"),
+        "features:\n  - id: DEMO\n    title: \"Demo\"\n    spec: \"spec/core/demo.md\"\n    governance: approved\n    owner: core\n    group: core\n    depends_on: []\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("spec/core/demo.md"), "# Demo\n").unwrap();
+    std::fs::create_dir_all(root.join("changes/001_demo")).unwrap();
+    std::fs::write(
+        root.join("changes/001_demo/05-status.json"),
+        r#"{"status":"pending"}"#,
+    )
+    .unwrap();
+    repo
+}
+
+fn mount(repo: &Path) -> MountRegistry {
+    let mounts = MountRegistry::new();
+    mounts.register(Mount {
+        name: "demo".to_string(),
+        root: repo.to_string_lossy().to_string(),
+        resolved_id: None,
+        kind: None,
+        capabilities: vec![],
+    });
+    mounts
+}
+
+#[test]
+fn test_list_and_read_resources() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let router = mounted_router(data.path(), repo.path());
+    let repo_root = repo.path().to_string_lossy().to_string();
+
+    let resp = rpc(&router, "resources/list", json!({}));
+    let uris: Vec<&str> = resp["result"]["resources"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["uri"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        uris,
+        [
+            "axiomregent://spec/DEMO",
+            "axiomregent://changes/001_demo/05-status.json"
+        ]
+    );
+
+    let resp = rpc(
+        &router,
+        "resources/read",
+        json!({ "uri": "axiomregent://spec/DEMO" }),
+    );
+    let item = &resp["result"]["contents"][0];
+    assert_eq!(item["mimeType"], "text/markdown");
+    assert_eq!(item["text"], "# Demo\n");
+
+    // Snapshot files are immutable and readable without a repo round-trip.
+    let resp = call(
+        &router,
+        1,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["spec/core/demo.md"] }),
+    );
+    let snapshot_id = resp["result"]["content"][0]["json"]["snapshot_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let uri = format!("axiomregent://snapshot/{}/spec/core/demo.md", snapshot_id);
+    let resp = rpc(&router, "resources/read", json!({ "uri": uri }));
+    assert_eq!(resp["result"]["contents"][0]["text"], "# Demo\n");
+
+    let resp = rpc(
+        &router,
+        "resources/read",
+        json!({ "uri": "axiomregent://spec/MISSING" }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "NOT_FOUND");
+
+    let resp = rpc(
+        &router,
+        "resources/read",
+        json!({ "uri": "axiomregent://changes/../secret" }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "INVALID_ARGUMENT");
+
+    let resp = rpc(&router, "resources/templates/list", json!({}));
+    assert_eq!(
+        resp["result"]["resourceTemplates"]
+            .as_array()
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn test_subscribers_are_notified_of_updates() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let sink = sent.clone();
+    let router = mounted_router(data.path(), repo.path())
+        .with_notifier(move |_, msg| sink.lock().unwrap().push(msg.clone()));
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let uri = "axiomregent://changes/001_demo/05-status.json";
+
+    let resp = rpc(&router, "resources/subscribe", json!({ "uri": uri }));
+    assert_eq!(resp["result"], json!({}));
+
+    let resp = call(
+        &router,
+        1,
+        "snapshot.list",
+        json!({ "repo_root": repo_root, "path": "", "mode": "worktree" }),
+    );
+    let lease_id = resp["result"]["content"][0]["json"]["lease_id"].clone();
+    assert!(sent.lock().unwrap().is_empty());
+
+    let resp = call(
+        &router,
+        1,
+        "workspace.write_file",
+        json!({
+            "repo_root": repo_root,
+            "path": "changes/001_demo/05-status.json",
+            "content_base64": "eyJzdGF0dXMiOiJwYXNzIn0=",
+            "lease_id": lease_id
+        }),
+    );
+    assert!(resp["error"].is_null(), "{}", resp);
+
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0]["method"], "notifications/resources/updated");
+    assert_eq!(sent[0]["params"]["uri"], uri);
+}
+
+#[test]
+fn test_prompts() {
+    let data = tempfile::tempdir().unwrap();
+    let router = setup_router(data.path());
+
+    let resp = rpc(&router, "prompts/list", json!({}));
+    let names: Vec<&str> = resp["result"]["prompts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        ["propose_change", "execute_changeset", "verify_changeset"]
+    );
+
+    let resp = rpc(
+        &router,
+        "prompts/get",
+        json!({ "name": "verify_changeset", "arguments": { "repo_root": "/r", "changeset_id": "001_demo" } }),
+    );
+    let text = resp["result"]["messages"][0]["content"]["text"]
+        .as_str()
+        .unwrap();
+    assert!(text.contains("changeset 001_demo"));
+    assert!(text.contains("axiomregent://changes/001_demo/05-status.json"));
+    assert!(text.contains("pr profile"));
+
+    let resp = rpc(
+        &router,
+        "prompts/get",
+        json!({ "name": "verify_changeset", "arguments": { "repo_root": "/r" } }),
+    );
+    assert_eq!(resp["error"]["code"], -32602);
+}
@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::process::Command;
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn cache_stats(router: &Router) -> Value {
+    result(&call(router, "get_capabilities", json!({})))["cache"].clone()
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    std::fs::write(repo.path().join("a.txt"), "one\n").unwrap();
+    repo
+}
+
+#[test]
+fn test_immutable_results_are_served_from_cache() {
+    let data = tempfile::tempdir().unwrap();
+    let repo = setup_repo();
+    let router = setup_router(data.path());
+    let repo_root = repo.path().to_string_lossy();
+
+    let snapshot_id = result(&call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["a.txt"] }),
+    ))["snapshot_id"]
+        .clone();
+
+    let read = json!({
+        "repo_root": repo_root,
+        "path": "a.txt",
+        "mode": "snapshot",
+        "snapshot_id": snapshot_id
+    });
+    let first = result(&call(&router, "snapshot.read", read.clone())).clone();
+    assert_eq!(cache_stats(&router)["hits"], 0);
+
+    let second = result(&call(&router, "snapshot.read", read)).clone();
+    assert_eq!(first, second);
+    let stats = cache_stats(&router);
+    assert_eq!(stats["hits"], 1);
+    assert_eq!(stats["entries"], 1);
+}
+
+#[test]
+fn test_until_dirty_results_follow_the_fingerprint() {
+    let data = tempfile::tempdir().unwrap();
+    let repo = setup_repo();
+    let router = setup_router(data.path());
+    let repo_root = repo.path().to_string_lossy();
+
+    let lease_id = result(&call(
+        &router,
+        "snapshot.list",
+        json!({ "repo_root": repo_root, "mode": "worktree" }),
+    ))["lease_id"]
+        .clone();
+    let grep = json!({
+        "repo_root": repo_root,
+        "pattern": "one",
+        "mode": "worktree",
+        "lease_id": lease_id
+    });
+
+    result(&call(&router, "snapshot.grep", grep.clone()));
+    result(&call(&router, "snapshot.grep", grep.clone()));
+    assert_eq!(cache_stats(&router)["hits"], 1);
+
+    // A change outside the server moves the fingerprint: the entry is dropped and
+    // the tool reports the stale lease instead of a cached answer.
+    std::fs::write(repo.path().join("b.txt"), "one\n").unwrap();
+    let resp = call(&router, "snapshot.grep", grep);
+    assert_eq!(resp["error"]["data"]["code"], "STALE_LEASE");
+    let stats = cache_stats(&router);
+    assert_eq!(stats["hits"], 1);
+    // The first grep and the grep after the change; snapshot.list named no
+    // lease, so it was not cached.
+    assert_eq!(stats["misses"], 2);
+}
+
+#[test]
+fn test_mutations_invalidate_worktree_entries() {
+    let data = tempfile::tempdir().unwrap();
+    let repo = setup_repo();
+    let router = setup_router(data.path());
+    let repo_root = repo.path().to_string_lossy();
+
+    let lease_id = result(&call(
+        &router,
+        "lease.acquire",
+        json!({ "repo_root": repo_root }),
+    ))["lease_id"]
+        .clone();
+    let read = result(&call(
+        &router,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "worktree", "lease_id": lease_id }),
+    ))
+    .clone();
+    assert_eq!(cache_stats(&router)["entries"], 1);
+
+    let resp = call(
+        &router,
+        "workspace.write_file",
+        json!({
+            "repo_root": repo_root,
+            "path": "a.txt",
+            "content_base64": "dHdvCg==",
+            "lease_id": read["lease_id"]
+        }),
+    );
+    assert!(resp["error"].is_null(), "{}", resp);
+    assert_eq!(cache_stats(&router)["entries"], 0);
+}
+
+#[test]
+fn test_cached_worktree_reads_check_their_lease() {
+    let data = tempfile::tempdir().unwrap();
+    let repo = setup_repo();
+    let router = setup_router(data.path());
+    let repo_root = repo.path().to_string_lossy();
+
+    // Without a lease every call is issued its own, so nothing is cached.
+    let read = json!({ "repo_root": repo_root, "path": "a.txt", "mode": "worktree" });
+    let first = result(&call(&router, "snapshot.read", read.clone())).clone();
+    let second = result(&call(&router, "snapshot.read", read)).clone();
+    assert_ne!(first["lease_id"], second["lease_id"]);
+    assert_eq!(cache_stats(&router)["entries"], 0);
+
+    let lease_id = result(&call(
+        &router,
+        "lease.acquire",
+        json!({ "repo_root": repo_root, "ttl_secs": 60 }),
+    ))["lease_id"]
+        .clone();
+    let read = json!({
+        "repo_root": repo_root,
+        "path": "a.txt",
+        "mode": "worktree",
+        "lease_id": lease_id
+    });
+    result(&call(&router, "snapshot.read", read.clone()));
+    let expires_at = |router: &Router| {
+        let leases = result(&call(router, "lease.list", json!({}))).clone();
+        let lease = leases["leases"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|l| l["lease_id"] == lease_id)
+            .unwrap()
+            .clone();
+        lease["expires_at"].as_i64().unwrap()
+    };
+    let before = expires_at(&router);
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    // A hit still extends the lease.
+    result(&call(&router, "snapshot.read", read.clone()));
+    assert_eq!(cache_stats(&router)["hits"], 1);
+    assert!(expires_at(&router) > before);
+
+    // Once released, the lease is refused even though the answer is cached.
+    result(&call(
+        &router,
+        "lease.release",
+        json!({ "lease_id": lease_id }),
+    ));
+    let resp = call(&router, "snapshot.read", read);
+    assert_eq!(resp["error"]["data"]["code"], "NOT_FOUND", "{}", resp);
+    assert!(resp["result"].is_null());
+}
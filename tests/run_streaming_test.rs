@@ -1,5 +1,6 @@
 // tests/run_streaming_test.rs
 
+use axiomregent::router::progress::Progress;
 use axiomregent::run_tools::RunTools;
 use std::thread;
 use std::time::Duration;
@@ -11,7 +12,7 @@ fn test_run_lifecycle_and_streaming() {
 
     // 1. Execute a non-existent skill
     let run_id = tools
-        .execute("non-existent-skill".to_string(), None)
+        .execute("non-existent-skill".to_string(), None, Progress::default())
         .unwrap();
 
     // 2. Wait for it to fail
@@ -37,8 +37,11 @@ fn test_apply_patch_determinism() -> Result<()> {
         t1.len()
     );
 
-    let base_sid = "snap-base";
-    let repo_root = "/repo";
+    let base_sid = "sha256:snap-base";
+    // Snapshots are only resolved within the repository they were taken of.
+    let repo = tempfile::tempdir()?;
+    let repo_path = repo.path().canonicalize()?;
+    let repo_root = repo_path.to_str().unwrap();
     let head_sha = "sha-base";
     let fingerprint = r#"{"head_oid": "sha-base", "status_hash": "status1"}"#; // Approximate FP format
 
@@ -0,0 +1,143 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    repo
+}
+
+fn create_snapshot(router: &Router, repo_root: &str, content: &str) -> String {
+    let repo = Path::new(repo_root);
+    std::fs::write(repo.join("a.txt"), content).unwrap();
+    let resp = call(
+        router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["a.txt"] }),
+    );
+    result(&resp)["snapshot_id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_gc_keeps_latest_and_reclaims_blobs() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let old = create_snapshot(&router, &repo_root, "old contents\n");
+    let new = create_snapshot(&router, &repo_root, "new\n");
+
+    // Cached before the deletion.
+    let read_old =
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "snapshot", "snapshot_id": old });
+    result(&call(&router, "snapshot.read", read_old.clone()));
+
+    let dry = call(
+        &router,
+        "snapshot.gc",
+        json!({ "repo_root": repo_root, "keep_last": 1, "dry_run": true }),
+    );
+    assert_eq!(result(&dry)["would_delete"], json!([old]));
+
+    let gc = call(
+        &router,
+        "snapshot.gc",
+        json!({ "repo_root": repo_root, "keep_last": 1, "grace_secs": 0 }),
+    );
+    let gc = result(&gc);
+    assert_eq!(gc["deleted"], json!([old]));
+    assert_eq!(gc["blobs_removed"], 1);
+    assert_eq!(gc["bytes_reclaimed"], "old contents\n".len());
+
+    // The cached read of the deleted snapshot is gone with it.
+    let resp = call(&router, "snapshot.read", read_old);
+    assert_eq!(resp["error"]["data"]["code"], "NOT_FOUND");
+    let read_new =
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "snapshot", "snapshot_id": new });
+    result(&call(&router, "snapshot.read", read_new));
+}
+
+#[test]
+fn test_delete_refuses_snapshots_in_use() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    let base = create_snapshot(&router, &repo_root, "base\n");
+
+    // Base state of a changeset being executed.
+    let locks = repo.path().join("changes/.locks");
+    std::fs::create_dir_all(&locks).unwrap();
+    std::fs::write(
+        locks.join("001_demo"),
+        json!({
+            "change_set_id": "001_demo",
+            "repo_key": "demo",
+            "base_state": base,
+            "base_state_created_at": "2026-01-01T00:00:00Z"
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let resp = call(
+        &router,
+        "snapshot.delete",
+        json!({ "repo_root": repo_root, "snapshot_id": base }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "INVALID_ARGUMENT");
+    let gc = call(
+        &router,
+        "snapshot.gc",
+        json!({ "repo_root": repo_root, "keep_last": 0, "grace_secs": 0 }),
+    );
+    assert_eq!(result(&gc)["deleted"], json!([]));
+
+    std::fs::remove_file(locks.join("001_demo")).unwrap();
+    let resp = call(
+        &router,
+        "snapshot.delete",
+        json!({ "repo_root": repo_root, "snapshot_id": base }),
+    );
+    assert_eq!(result(&resp)["deleted"], json!([base]));
+    let resp = call(
+        &router,
+        "snapshot.info",
+        json!({ "repo_root": repo_root, "snapshot_id": base }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "NOT_FOUND");
+}
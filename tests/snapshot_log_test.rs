@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    repo
+}
+
+fn create_snapshot(
+    router: &Router,
+    repo_root: &str,
+    content: &str,
+    parent: Option<&str>,
+) -> String {
+    let repo = Path::new(repo_root);
+    std::fs::write(repo.join("a.txt"), content).unwrap();
+    let resp = call(
+        router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["a.txt"], "parent_snapshot_id": parent }),
+    );
+    result(&resp)["snapshot_id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_log_follows_ancestry_and_lineage_renders_the_graph() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let root = create_snapshot(&router, &repo_root, "v1\n", None);
+    let middle = create_snapshot(&router, &repo_root, "v2\n", Some(&root));
+    let tip = create_snapshot(&router, &repo_root, "v3\n", Some(&middle));
+    let unrelated = create_snapshot(&router, &repo_root, "other\n", None);
+    let tag = call(
+        &router,
+        "snapshot.tag",
+        json!({ "repo_root": repo_root, "name": "baseline", "snapshot_id": root }),
+    );
+    result(&tag);
+
+    // HEAD is the unrelated snapshot, whose log is just itself.
+    let log = call(&router, "snapshot.log", json!({ "repo_root": repo_root }));
+    let ids: Vec<&Value> = result(&log)["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| &e["snapshot_id"])
+        .collect();
+    assert_eq!(ids, [&json!(unrelated)]);
+
+    let log = call(
+        &router,
+        "snapshot.log",
+        json!({ "repo_root": repo_root, "snapshot_id": tip }),
+    );
+    let log = result(&log);
+    let ids: Vec<&Value> = log["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| &e["snapshot_id"])
+        .collect();
+    assert_eq!(ids, [&json!(tip), &json!(middle), &json!(root)]);
+    assert_eq!(log["entries"][0]["derived_from"], json!(middle));
+    assert_eq!(log["entries"][2]["tags"], json!(["baseline"]));
+    assert_eq!(log["truncated"], false);
+
+    let limited = call(
+        &router,
+        "snapshot.log",
+        json!({ "repo_root": repo_root, "snapshot_id": tip, "limit": 1 }),
+    );
+    assert_eq!(result(&limited)["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(result(&limited)["truncated"], true);
+
+    // Nothing was created in the future.
+    let created_at = log["entries"][0]["created_at"].as_i64().unwrap();
+    let later = call(
+        &router,
+        "snapshot.log",
+        json!({ "repo_root": repo_root, "snapshot_id": tip, "since": created_at + 3600 }),
+    );
+    assert_eq!(result(&later)["entries"], json!([]));
+
+    let lineage = call(
+        &router,
+        "snapshot.lineage",
+        json!({ "repo_root": repo_root }),
+    );
+    let lineage = result(&lineage);
+    assert_eq!(lineage["nodes"].as_array().unwrap().len(), 4);
+    let edges: Vec<(&Value, &Value)> = lineage["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (&e["from"], &e["to"]))
+        .collect();
+    assert_eq!(
+        edges,
+        [
+            (&json!(root), &json!(middle)),
+            (&json!(middle), &json!(tip))
+        ]
+    );
+    let dot = lineage["dot"].as_str().unwrap();
+    assert!(dot.starts_with("digraph lineage {"));
+    assert!(dot.contains(&format!("\"{}\" -> \"{}\"", root, middle)));
+    assert!(dot.contains("[baseline]"));
+
+    let empty = call(
+        &router,
+        "snapshot.lineage",
+        json!({ "repo_root": repo_root, "until": 0 }),
+    );
+    assert_eq!(result(&empty)["nodes"], json!([]));
+    assert_eq!(result(&empty)["edges"], json!([]));
+}
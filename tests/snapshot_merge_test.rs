@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use base64::Engine;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    repo
+}
+
+fn create_snapshot(
+    router: &Router,
+    repo_root: &str,
+    files: &[(&str, &str)],
+    parent: Option<&str>,
+) -> String {
+    let repo = Path::new(repo_root);
+    for (path, content) in files {
+        std::fs::write(repo.join(path), content).unwrap();
+    }
+    let paths: Vec<&str> = files.iter().map(|(path, _)| *path).collect();
+    let resp = call(
+        router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": paths, "parent_snapshot_id": parent }),
+    );
+    result(&resp)["snapshot_id"].as_str().unwrap().to_string()
+}
+
+fn read(router: &Router, repo_root: &str, snapshot_id: &str, path: &str) -> String {
+    let resp = call(
+        router,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": path, "mode": "snapshot", "snapshot_id": snapshot_id }),
+    );
+    let encoded = result(&resp)["content"].as_str().unwrap();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.strip_prefix("base64:").unwrap())
+        .unwrap();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn test_clean_merge_records_both_parents() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let base = create_snapshot(
+        &router,
+        &repo_root,
+        &[("a.txt", "1\n2\n3\n4\n5\n"), ("b.txt", "b\n")],
+        None,
+    );
+    let ours = create_snapshot(
+        &router,
+        &repo_root,
+        &[("a.txt", "one\n2\n3\n4\n5\n")],
+        Some(&base),
+    );
+    let theirs = create_snapshot(
+        &router,
+        &repo_root,
+        &[("a.txt", "1\n2\n3\n4\nfive\n"), ("c.txt", "c\n")],
+        Some(&base),
+    );
+
+    let resp = call(
+        &router,
+        "snapshot.merge",
+        json!({ "repo_root": repo_root, "base": base, "ours": ours, "theirs": theirs }),
+    );
+    let merged = result(&resp);
+    assert_eq!(merged["clean"], true);
+    assert_eq!(merged["conflicts"], json!([]));
+    assert_eq!(merged["merged_paths"], json!(["a.txt"]));
+    let merge_id = merged["snapshot_id"].as_str().unwrap().to_string();
+
+    assert_eq!(
+        read(&router, &repo_root, &merge_id, "a.txt"),
+        "one\n2\n3\n4\nfive\n"
+    );
+    assert_eq!(read(&router, &repo_root, &merge_id, "b.txt"), "b\n");
+    assert_eq!(read(&router, &repo_root, &merge_id, "c.txt"), "c\n");
+
+    let info = call(
+        &router,
+        "snapshot.info",
+        json!({ "repo_root": repo_root, "snapshot_id": merge_id }),
+    );
+    assert_eq!(result(&info)["derived_from"], json!(ours));
+    assert_eq!(result(&info)["merged_from"], json!(theirs));
+
+    let lineage = call(
+        &router,
+        "snapshot.lineage",
+        json!({ "repo_root": repo_root }),
+    );
+    let merge_edges: Vec<&Value> = result(&lineage)["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["merge"] == true)
+        .collect();
+    assert_eq!(merge_edges.len(), 1);
+    assert_eq!(merge_edges[0]["from"], json!(theirs));
+    assert_eq!(merge_edges[0]["to"], json!(merge_id));
+
+    // The second parent is kept like the first one.
+    let resp = call(
+        &router,
+        "snapshot.delete",
+        json!({ "repo_root": repo_root, "snapshot_id": theirs }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "INVALID_ARGUMENT");
+
+    // Merging something already merged changes nothing.
+    let resp = call(
+        &router,
+        "snapshot.merge",
+        json!({ "repo_root": repo_root, "base": base, "ours": merge_id, "theirs": theirs }),
+    );
+    assert_eq!(result(&resp)["snapshot_id"], json!(merge_id));
+}
+
+#[test]
+fn test_conflicts_are_reported_or_kept_as_markers() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let base = create_snapshot(&router, &repo_root, &[("a.txt", "a\nb\nc\n")], None);
+    let ours = create_snapshot(
+        &router,
+        &repo_root,
+        &[("a.txt", "a\nours\nc\n")],
+        Some(&base),
+    );
+    let theirs = create_snapshot(
+        &router,
+        &repo_root,
+        &[("a.txt", "a\ntheirs\nc\n")],
+        Some(&base),
+    );
+    let args = json!({ "repo_root": repo_root, "base": base, "ours": ours, "theirs": "HEAD" });
+
+    let resp = call(&router, "snapshot.merge", args.clone());
+    let merged = result(&resp);
+    assert_eq!(merged["clean"], false);
+    assert_eq!(merged["snapshot_id"], Value::Null);
+    assert_eq!(
+        merged["conflicts"],
+        json!([{
+            "path": "a.txt",
+            "kind": "content",
+            "hunks": [{
+                "base": { "start": 2, "count": 1 },
+                "ours": { "start": 2, "count": 1 },
+                "theirs": { "start": 2, "count": 1 }
+            }]
+        }])
+    );
+
+    let mut with_markers = args;
+    with_markers["conflict_markers"] = json!(true);
+    let resp = call(&router, "snapshot.merge", with_markers);
+    let candidate = result(&resp);
+    assert_eq!(candidate["clean"], false);
+    assert_eq!(candidate["merged_from"], json!(theirs));
+    let candidate_id = candidate["snapshot_id"].as_str().unwrap();
+    assert_eq!(
+        read(&router, &repo_root, candidate_id, "a.txt"),
+        "a\n<<<<<<< ours\nours\n=======\ntheirs\n>>>>>>> theirs\nc\n"
+    );
+}
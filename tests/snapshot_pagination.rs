@@ -5,7 +5,7 @@ use anyhow::Result;
 use axiomregent::config::{BlobBackend, Compression, StorageConfig};
 use axiomregent::snapshot::lease::LeaseStore;
 use axiomregent::snapshot::store::Store;
-use axiomregent::snapshot::tools::SnapshotTools;
+use axiomregent::snapshot::tools::{CreateOptions, SnapshotTools};
 use std::sync::Arc;
 
 fn setup() -> Result<(
@@ -106,7 +106,11 @@ fn test_snapshot_pagination() -> Result<()> {
         paths.push(name);
     }
 
-    let snap_res = tools.snapshot_create(&root, None, Some(paths))?;
+    let options = CreateOptions {
+        paths: Some(paths),
+        ..Default::default()
+    };
+    let snap_res = tools.snapshot_create(&root, options)?;
     let snap_id = snap_res["snapshot_id"].as_str().unwrap().to_string();
 
     // List all
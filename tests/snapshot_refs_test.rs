@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    repo
+}
+
+fn create_snapshot(
+    router: &Router,
+    repo_root: &str,
+    content: &str,
+    parent: Option<&str>,
+) -> String {
+    let repo = Path::new(repo_root);
+    std::fs::write(repo.join("a.txt"), content).unwrap();
+    let resp = call(
+        router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["a.txt"], "parent_snapshot_id": parent }),
+    );
+    result(&resp)["snapshot_id"].as_str().unwrap().to_string()
+}
+
+fn read_id(router: &Router, repo_root: &str, snapshot_id: &str) -> Value {
+    let resp = call(
+        router,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "snapshot", "snapshot_id": snapshot_id }),
+    );
+    if resp["error"].is_null() {
+        result(&resp)["snapshot_id"].clone()
+    } else {
+        resp["error"]["data"]["code"].clone()
+    }
+}
+
+#[test]
+fn test_names_resolve_wherever_snapshot_ids_are_accepted() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let base = create_snapshot(&router, &repo_root, "v1\n", None);
+    let tag = call(
+        &router,
+        "snapshot.tag",
+        json!({ "repo_root": repo_root, "name": "pre-changeset/004", "snapshot_id": base }),
+    );
+    assert_eq!(result(&tag)["previous"], Value::Null);
+
+    let child = create_snapshot(&router, &repo_root, "v2\n", Some("pre-changeset/004"));
+    let info = call(
+        &router,
+        "snapshot.info",
+        json!({ "repo_root": repo_root, "snapshot_id": "HEAD" }),
+    );
+    assert_eq!(result(&info)["snapshot_id"], json!(child));
+    assert_eq!(result(&info)["derived_from"], json!(base));
+
+    assert_eq!(read_id(&router, &repo_root, "HEAD~1"), json!(base));
+    assert_eq!(
+        read_id(&router, &repo_root, "pre-changeset/004"),
+        json!(base)
+    );
+    assert_eq!(read_id(&router, &repo_root, "HEAD~2"), "NOT_FOUND");
+    assert_eq!(read_id(&router, &repo_root, "missing"), "NOT_FOUND");
+
+    let changes = call(
+        &router,
+        "snapshot.changes",
+        json!({ "repo_root": repo_root, "snapshot_id": "HEAD", "from_snapshot_id": "HEAD~" }),
+    );
+    assert_eq!(
+        result(&changes)["files_changed"],
+        json!([{ "path": "a.txt", "type": "modified" }])
+    );
+
+    // Moving a tag needs force; reads by name follow it, cache or not.
+    let args =
+        json!({ "repo_root": repo_root, "name": "pre-changeset/004", "snapshot_id": "HEAD" });
+    let resp = call(&router, "snapshot.tag", args.clone());
+    assert_eq!(resp["error"]["data"]["code"], "INVALID_ARGUMENT");
+    let mut forced = args;
+    forced["force"] = json!(true);
+    let moved = call(&router, "snapshot.tag", forced);
+    assert_eq!(result(&moved)["previous"], json!(base));
+    assert_eq!(
+        read_id(&router, &repo_root, "pre-changeset/004"),
+        json!(child)
+    );
+
+    let resp = call(
+        &router,
+        "snapshot.tag",
+        json!({ "repo_root": repo_root, "name": "HEAD", "snapshot_id": base }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "INVALID_ARGUMENT");
+}
+
+#[test]
+fn test_tags_protect_snapshots_and_keep_history() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let first = create_snapshot(&router, &repo_root, "first\n", None);
+    let second = create_snapshot(&router, &repo_root, "second\n", None);
+    for (name, id) in [("baseline", &first), ("baseline", &second)] {
+        let resp = call(
+            &router,
+            "snapshot.tag",
+            json!({ "repo_root": repo_root, "name": name, "snapshot_id": id, "force": true }),
+        );
+        result(&resp);
+    }
+
+    let resp = call(
+        &router,
+        "snapshot.delete",
+        json!({ "repo_root": repo_root, "snapshot_id": "baseline" }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "INVALID_ARGUMENT");
+    let gc = call(
+        &router,
+        "snapshot.gc",
+        json!({ "repo_root": repo_root, "keep_last": 0, "grace_secs": 0 }),
+    );
+    assert_eq!(result(&gc)["deleted"], json!([first]));
+
+    let refs = call(
+        &router,
+        "snapshot.refs",
+        json!({ "repo_root": repo_root, "history": true }),
+    );
+    let refs = result(&refs);
+    assert_eq!(refs["head"], json!(second));
+    assert_eq!(refs["refs"][0]["name"], "baseline");
+    assert_eq!(refs["refs"][0]["snapshot_id"], json!(second));
+    let history: Vec<(Value, Value)> = refs["history"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["from"].clone(), e["to"].clone()))
+        .collect();
+    assert_eq!(
+        history,
+        [(Value::Null, json!(first)), (json!(first), json!(second))]
+    );
+
+    let untag = call(
+        &router,
+        "snapshot.untag",
+        json!({ "repo_root": repo_root, "name": "baseline" }),
+    );
+    assert_eq!(result(&untag)["snapshot_id"], json!(second));
+    let resp = call(
+        &router,
+        "snapshot.untag",
+        json!({ "repo_root": repo_root, "name": "baseline" }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "NOT_FOUND");
+    let resp = call(
+        &router,
+        "snapshot.delete",
+        json!({ "repo_root": repo_root, "snapshot_id": "HEAD" }),
+    );
+    assert_eq!(result(&resp)["deleted"], json!([second]));
+}
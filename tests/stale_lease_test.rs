@@ -174,7 +174,7 @@ fn test_stale_lease_error_structure() {
     let err = resp2.error.expect("Should return error for stale lease");
     println!("Error details: {:?}", err);
 
-    assert_eq!(err["code"], "STALE_LEASE");
+    assert_eq!(err["data"]["code"], "STALE_LEASE");
     assert_eq!(err["message"], "Lease is stale (repo changed)");
     let data = &err["data"];
     assert!(data["current_fingerprint"].is_object());
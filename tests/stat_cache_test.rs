@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    repo
+}
+
+/// Writes `content`, dated a minute ago so the stat cache accepts it.
+fn write_old(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, content).unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(60))
+        .unwrap();
+}
+
+fn create(router: &Router, args: Value) -> Value {
+    let resp = call(router, "snapshot.create", args);
+    result(&resp).clone()
+}
+
+fn paths(router: &Router, repo_root: &str, snapshot_id: &str) -> Vec<String> {
+    let resp = call(
+        router,
+        "snapshot.list",
+        json!({ "repo_root": repo_root, "mode": "snapshot", "snapshot_id": snapshot_id }),
+    );
+    result(&resp)["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_unchanged_files_are_not_rehashed() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    for i in 0..5 {
+        write_old(repo.path(), &format!("f{}.txt", i), &format!("file {}", i));
+    }
+    let args = json!({ "repo_root": repo_root, "scope": "worktree" });
+
+    let first = create(&router, args.clone());
+    assert_eq!(first["files_hashed"], 5);
+    assert_eq!(first["files_reused"], 0);
+
+    let second = create(&router, args.clone());
+    assert_eq!(second["files_hashed"], 0);
+    assert_eq!(second["files_reused"], 5);
+    // Same content, same manifest.
+    assert_eq!(second["snapshot_id"], first["snapshot_id"]);
+
+    write_old(repo.path(), "f0.txt", "edited");
+    std::fs::remove_file(repo.path().join("f1.txt")).unwrap();
+    // Too recent to be cached: hashed on every capture until it settles.
+    std::fs::write(repo.path().join("new.txt"), "new").unwrap();
+    let third = create(&router, args.clone());
+    assert_eq!(third["files_hashed"], 2);
+    assert_eq!(third["files_reused"], 3);
+    let fourth = create(&router, args.clone());
+    assert_eq!(fourth["files_hashed"], 1);
+    assert_eq!(fourth["snapshot_id"], third["snapshot_id"]);
+
+    // Blobs reclaimed by gc are not reused from the cache.
+    let resp = call(
+        &router,
+        "snapshot.gc",
+        json!({ "repo_root": repo_root, "keep_last": 0, "grace_secs": 0 }),
+    );
+    result(&resp);
+    let fifth = create(&router, args);
+    assert_eq!(fifth["files_hashed"], 5);
+    assert_eq!(fifth["files_reused"], 0);
+}
+
+#[test]
+fn test_delta_snapshot_inherits_parent_entries() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        write_old(repo.path(), name, name);
+    }
+    let base = create(
+        &router,
+        json!({ "repo_root": repo_root, "paths": ["a.txt", "b.txt", "c.txt"] }),
+    );
+    let base_id = base["snapshot_id"].as_str().unwrap();
+
+    write_old(repo.path(), "a.txt", "changed");
+    std::fs::remove_file(repo.path().join("b.txt")).unwrap();
+    write_old(repo.path(), "d.txt", "d");
+    let delta = create(
+        &router,
+        json!({
+            "repo_root": repo_root,
+            "paths": ["a.txt", "b.txt", "d.txt"],
+            "parent_snapshot_id": base_id
+        }),
+    );
+    assert_eq!(delta["derived_from"], base_id);
+    assert_eq!(delta["files_hashed"], 2);
+    let delta_id = delta["snapshot_id"].as_str().unwrap();
+    assert_eq!(
+        paths(&router, &repo_root, delta_id),
+        ["a.txt", "c.txt", "d.txt"]
+    );
+
+    let resp = call(
+        &router,
+        "snapshot.info",
+        json!({ "repo_root": repo_root, "snapshot_id": delta_id }),
+    );
+    assert_eq!(result(&resp)["derived_from"], base_id);
+
+    let other = setup_repo();
+    let resp = call(
+        &router,
+        "snapshot.create",
+        json!({
+            "repo_root": other.path().to_string_lossy(),
+            "parent_snapshot_id": base_id
+        }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "INVALID_ARGUMENT");
+}
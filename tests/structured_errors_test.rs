@@ -0,0 +1,172 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::process::Command;
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+fn call(router: &Router, id: u64, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": id
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn git_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    std::fs::write(repo.path().join("a.txt"), "a").unwrap();
+    repo
+}
+
+#[test]
+fn test_typed_errors_carry_code_and_context() {
+    let repo = git_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let err = &call(
+        &router,
+        1,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": "missing.txt", "mode": "worktree" }),
+    )["error"];
+    assert_eq!(err["code"], -32001);
+    assert_eq!(err["data"]["code"], "NOT_FOUND");
+    assert_eq!(err["data"]["retryable"], false);
+    assert_eq!(err["data"]["path"], "missing.txt");
+
+    let err = &call(
+        &router,
+        2,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": "a.txt", "mode": "snapshot" }),
+    )["error"];
+    assert_eq!(err["code"], -32602);
+    assert_eq!(err["data"]["code"], "INVALID_ARGUMENT");
+
+    let err = &call(
+        &router,
+        3,
+        "snapshot.read",
+        json!({ "repo_root": repo_root, "path": "../outside", "mode": "worktree" }),
+    )["error"];
+    assert_eq!(err["code"], -32003);
+    assert_eq!(err["data"]["code"], "PERMISSION_DENIED");
+    assert_eq!(err["data"]["path"], "../outside");
+
+    let err = &call(
+        &router,
+        4,
+        "snapshot.info",
+        json!({ "repo_root": repo_root, "snapshot_id": "nope" }),
+    )["error"];
+    assert_eq!(err["data"]["code"], "NOT_FOUND");
+    assert_eq!(err["data"]["snapshot_id"], "nope");
+
+    let err = &call(
+        &router,
+        5,
+        "workspace.write_file",
+        json!({ "repo_root": repo_root, "path": "b.txt", "content_base64": "", "lease_id": "nope" }),
+    )["error"];
+    assert_eq!(err["data"]["code"], "NOT_FOUND");
+    assert_eq!(err["data"]["lease_id"], "nope");
+
+    let missing = repo.path().join("no-such-dir");
+    let err = &call(&router, 6, "gov.drift", json!({ "repo_root": missing }))["error"];
+    assert_eq!(err["data"]["code"], "NOT_FOUND");
+    assert_eq!(err["data"]["path"], missing.to_string_lossy().as_ref());
+}
+
+#[test]
+fn test_stale_lease_is_retryable() {
+    let repo = git_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let resp = call(
+        &router,
+        1,
+        "snapshot.list",
+        json!({ "repo_root": repo_root, "path": "", "mode": "worktree" }),
+    );
+    let lease_id = resp["result"]["content"][0]["json"]["lease_id"].clone();
+    std::fs::write(repo.path().join("b.txt"), "new").unwrap();
+
+    let err = &call(
+        &router,
+        2,
+        "snapshot.list",
+        json!({ "repo_root": repo_root, "path": "", "mode": "worktree", "lease_id": lease_id }),
+    )["error"];
+    assert_eq!(err["code"], -32005);
+    assert_eq!(err["data"]["code"], "STALE_LEASE");
+    assert_eq!(err["data"]["retryable"], true);
+    assert_eq!(err["data"]["lease_id"], lease_id);
+}
+
+#[test]
+fn test_run_not_found_and_invalid_params() {
+    let data = tempfile::tempdir().unwrap();
+    let router = setup_router(data.path());
+
+    let err = &call(
+        &router,
+        1,
+        "encore.ts.run.stop",
+        json!({ "run_id": "nope" }),
+    )["error"];
+    assert_eq!(err["data"]["code"], "NOT_FOUND");
+
+    let err = &call(&router, 2, "snapshot.read", json!({ "repo_root": 1 }))["error"];
+    assert_eq!(err["code"], -32602);
+    assert_eq!(err["data"]["code"], "INVALID_ARGUMENT");
+}
+
+#[test]
+fn test_xray_target_errors_are_typed() {
+    let repo = git_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let err = &call(
+        &router,
+        1,
+        "xray.scan",
+        json!({ "repo_root": repo_root, "path": "missing" }),
+    )["error"];
+    assert_eq!(err["code"], -32001);
+    assert_eq!(err["data"]["code"], "NOT_FOUND");
+    assert_eq!(err["data"]["path"], "missing");
+
+    let err = &call(
+        &router,
+        2,
+        "xray.scan",
+        json!({ "repo_root": repo_root, "path": "../outside" }),
+    )["error"];
+    assert_eq!(err["code"], -32003);
+    assert_eq!(err["data"]["code"], "PERMISSION_DENIED");
+    assert_eq!(err["data"]["path"], "../outside");
+}
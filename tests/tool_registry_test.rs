@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use axiomregent::router::mounts::Capability;
+use axiomregent::router::registry::{ToolOutput, TypedTool};
+use common::setup_router;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+// Feature: MCP_ROUTER
+// Spec: spec/core/router.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct EchoArgs {
+    message: String,
+    repeat: Option<u32>,
+}
+
+#[test]
+fn test_downstream_tool_registration() {
+    let data = tempfile::tempdir().unwrap();
+    let router = setup_router(data.path()).with_tool(
+        TypedTool::new("demo.echo", "Echo a message", |_, a: EchoArgs| {
+            Ok(ToolOutput::Text(
+                a.message.repeat(a.repeat.unwrap_or(1) as usize),
+            ))
+        })
+        .requires(Capability::ReadFile),
+    );
+
+    // Listed with a schema generated from EchoArgs.
+    let list = router
+        .handle_message(&json!({"jsonrpc": "2.0", "method": "tools/list", "id": 1}).to_string())
+        .unwrap();
+    let tool = list["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["name"] == "demo.echo")
+        .expect("demo.echo listed")
+        .clone();
+    assert_eq!(
+        tool["inputSchema"]["properties"]["message"]["type"],
+        "string"
+    );
+    assert_eq!(tool["inputSchema"]["required"], json!(["message"]));
+
+    let resp = call(
+        &router,
+        "demo.echo",
+        json!({ "message": "ab", "repeat": 2 }),
+    );
+    assert_eq!(resp["result"]["content"][0]["text"], "abab");
+
+    // Arguments that don't match the type are rejected as invalid params.
+    let resp = call(&router, "demo.echo", json!({ "repeat": 2 }));
+    assert_eq!(resp["error"]["code"], -32602);
+
+    // Unknown tools stay -32601.
+    let resp = call(&router, "demo.missing", json!({}));
+    assert_eq!(resp["error"]["code"], -32601);
+}
+
+#[test]
+#[should_panic(expected = "declares no mount capability")]
+fn test_tools_must_declare_a_capability() {
+    let data = tempfile::tempdir().unwrap();
+    let _ = setup_router(data.path()).with_tool(TypedTool::new(
+        "demo.echo",
+        "Echo a message",
+        |_, a: EchoArgs| Ok(ToolOutput::Text(a.message)),
+    ));
+}
+
+#[test]
+fn test_builtin_tools_are_registered() {
+    let data = tempfile::tempdir().unwrap();
+    let names = setup_router(data.path()).tool_names();
+    for expected in [
+        "resolve_mcp",
+        "get_capabilities",
+        "features.overview",
+        "gov.preflight",
+        "xray.scan",
+        "snapshot.list",
+        "antigravity.verify",
+        "encore.ts.parse",
+        "run.execute",
+        "workspace.apply_patch",
+    ] {
+        assert!(names.iter().any(|n| n == expected), "{} missing", expected);
+    }
+}
+
+#[test]
+fn test_mutations_are_flagged() {
+    let data = tempfile::tempdir().unwrap();
+    let router = setup_router(data.path());
+    let mutations: Vec<String> = router
+        .tool_names()
+        .into_iter()
+        .filter(|n| router.tool(n).unwrap().mutates())
+        .collect();
+    assert_eq!(
+        mutations,
+        [
+            "snapshot.create",
+            "snapshot.delete",
+            "snapshot.tag",
+            "snapshot.untag",
+            "snapshot.merge",
+            "snapshot.gc",
+            "snapshot.migrate_store",
+            "lease.acquire",
+            "lease.renew",
+            "lease.reserve",
+            "lease.release",
+            "antigravity.propose",
+            "antigravity.execute",
+            "encore.ts.run.start",
+            "workspace.write_file",
+            "workspace.delete",
+            "workspace.apply_patch",
+            "workspace.restore",
+            "workspace.transaction",
+        ]
+    );
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use axiomregent::router::progress::Progress;
 use axiomregent::tools::encore_ts::parse;
 use axiomregent::tools::encore_ts::tools::EncoreTools;
 // use axiomregent::tools::encore_ts::{run, state};
@@ -161,11 +162,11 @@ fn test_run_idempotency_determinism_and_logs() -> Result<()> {
     // Use EncoreTools to test logs.stream as well
     let tools = EncoreTools::new();
 
-    let res = tools.run_start(&root, None, None)?;
+    let res = tools.run_start(&root, None, None, &Progress::default())?;
     let run_id_1 = res.get("run_id").unwrap().as_str().unwrap().to_string();
 
     // Idempotency: call again
-    let res2 = tools.run_start(&root, None, None)?;
+    let res2 = tools.run_start(&root, None, None, &Progress::default())?;
     let run_id_2 = res2.get("run_id").unwrap().as_str().unwrap().to_string();
 
     assert_eq!(run_id_1, run_id_2, "Run ID should be same via tools");
@@ -0,0 +1,331 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use axiomregent::snapshot::fingerprint::NativeFingerprinter;
+use axiomregent::snapshot::lease::LeaseStore;
+use common::{RouterSetup, setup_router, setup_router_with};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn git(root: &Path, args: &[&str]) {
+    Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap();
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    let root = repo.path();
+    git(root, &["init"]);
+    git(root, &["config", "user.email", "test@example.com"]);
+    git(root, &["config", "user.name", "Test"]);
+    std::fs::write(root.join("a.txt"), "v1\n").unwrap();
+    std::fs::write(root.join("b.txt"), "b\n").unwrap();
+    git(root, &["add", "."]);
+    git(root, &["commit", "-m", "initial"]);
+    repo
+}
+
+fn lease(router: &Router, repo_root: &str) -> String {
+    let resp = call(
+        router,
+        "snapshot.list",
+        json!({ "repo_root": repo_root, "path": ".", "mode": "worktree" }),
+    );
+    result(&resp)["lease_id"].as_str().unwrap().to_string()
+}
+
+fn read(repo: &Path, path: &str) -> String {
+    std::fs::read_to_string(repo.join(path)).unwrap()
+}
+
+#[test]
+fn test_restore_rolls_back_a_changeset() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let (router, lease_store) = setup_router_with(data.path(), RouterSetup::default());
+
+    let resp = call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "scope": "tracked" }),
+    );
+    let snapshot_id = result(&resp)["snapshot_id"].as_str().unwrap().to_string();
+
+    // A committed changeset that went wrong, plus uncommitted work in b.txt.
+    std::fs::write(repo.path().join("a.txt"), "broken\n").unwrap();
+    std::fs::write(repo.path().join("c.txt"), "new\n").unwrap();
+    git(repo.path(), &["add", "."]);
+    git(repo.path(), &["commit", "-m", "changeset"]);
+    std::fs::write(repo.path().join("b.txt"), "local\n").unwrap();
+
+    let lease_id = lease(&router, &repo_root);
+    let args = json!({ "repo_root": repo_root, "snapshot_id": snapshot_id, "lease_id": lease_id });
+
+    let mut dry_run = args.clone();
+    dry_run["dry_run"] = json!(true);
+    let resp = call(&router, "workspace.restore", dry_run);
+    let plan = result(&resp);
+    assert_eq!(
+        plan["files"],
+        json!([
+            { "path": "a.txt", "action": "overwrite" },
+            { "path": "b.txt", "action": "overwrite" },
+            { "path": "c.txt", "action": "delete" }
+        ])
+    );
+    assert_eq!(plan["dirty"], json!(["b.txt"]));
+    assert_eq!(read(repo.path(), "a.txt"), "broken\n");
+
+    // Uncommitted work is not clobbered, and nothing is written.
+    let resp = call(&router, "workspace.restore", args.clone());
+    assert_eq!(
+        resp["error"]["data"]["code"], "INVALID_ARGUMENT",
+        "{}",
+        resp
+    );
+    assert_eq!(read(repo.path(), "a.txt"), "broken\n");
+
+    // A subset: c.txt is not in the snapshot, so it goes.
+    let mut subset = args;
+    subset["paths"] = json!(["a.txt", "c.txt"]);
+    let resp = call(&router, "workspace.restore", subset);
+    let restored = result(&resp);
+    assert_eq!(
+        restored["files"],
+        json!([
+            { "path": "a.txt", "action": "overwrite" },
+            { "path": "c.txt", "action": "delete" }
+        ])
+    );
+    assert_eq!(read(repo.path(), "a.txt"), "v1\n");
+    assert_eq!(read(repo.path(), "b.txt"), "local\n");
+    assert!(!repo.path().join("c.txt").exists());
+    let touched = lease_store.get_touched_files(&lease_id).unwrap();
+    assert!(touched.contains(&"a.txt".to_string()));
+    assert!(touched.contains(&"c.txt".to_string()));
+
+    let lease_id = lease(&router, &repo_root);
+    let resp = call(
+        &router,
+        "workspace.restore",
+        json!({ "repo_root": repo_root, "snapshot_id": "HEAD", "lease_id": lease_id, "force": true }),
+    );
+    let restored = result(&resp);
+    assert_eq!(
+        restored["files"],
+        json!([{ "path": "b.txt", "action": "overwrite" }])
+    );
+    assert_eq!(restored["unchanged"], 1);
+    assert_eq!(read(repo.path(), "b.txt"), "b\n");
+    let leftovers: Vec<_> = std::fs::read_dir(repo.path())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with(".axiomregent"))
+        .collect();
+    assert!(leftovers.is_empty());
+}
+
+#[test]
+fn test_full_restore_deletes_files_missing_from_the_snapshot() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let resp = call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "scope": "worktree" }),
+    );
+    let snapshot_id = result(&resp)["snapshot_id"].as_str().unwrap().to_string();
+
+    std::fs::create_dir(repo.path().join("new")).unwrap();
+    std::fs::write(repo.path().join("new/c.txt"), "new\n").unwrap();
+    std::fs::write(repo.path().join(".gitignore"), "*.log\n").unwrap();
+    std::fs::write(repo.path().join("build.log"), "ignored\n").unwrap();
+    git(repo.path(), &["add", "new/c.txt"]);
+    git(repo.path(), &["commit", "-m", "add c"]);
+
+    let lease_id = lease(&router, &repo_root);
+    let resp = call(
+        &router,
+        "workspace.restore",
+        json!({
+            "repo_root": repo_root,
+            "snapshot_id": snapshot_id,
+            "lease_id": lease_id,
+            "force": true
+        }),
+    );
+    let restored = result(&resp);
+    assert_eq!(
+        restored["files"],
+        json!([
+            { "path": ".gitignore", "action": "delete" },
+            { "path": "new/c.txt", "action": "delete" }
+        ])
+    );
+    // Untracked files are uncommitted work too.
+    assert_eq!(restored["dirty"], json!([".gitignore"]));
+    assert!(!repo.path().join("new/c.txt").exists());
+    assert!(!repo.path().join(".gitignore").exists());
+    assert!(repo.path().join("build.log").exists());
+    assert_eq!(read(repo.path(), "a.txt"), "v1\n");
+}
+
+#[test]
+fn test_restore_outside_git() {
+    let repo = tempfile::tempdir().unwrap();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    // Only the native fingerprinter fingerprints plain directories.
+    let native = LeaseStore::new().with_fingerprinter(Arc::new(NativeFingerprinter::new()));
+    let setup = RouterSetup {
+        lease_store: native,
+        ..Default::default()
+    };
+    let router = setup_router_with(data.path(), setup).0;
+    std::fs::write(repo.path().join("a.txt"), "v1\n").unwrap();
+
+    let resp = call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["a.txt"] }),
+    );
+    let snapshot_id = result(&resp)["snapshot_id"].as_str().unwrap().to_string();
+    std::fs::write(repo.path().join("a.txt"), "v2\n").unwrap();
+
+    let lease_id = lease(&router, &repo_root);
+    let resp = call(
+        &router,
+        "workspace.restore",
+        json!({ "repo_root": repo_root, "snapshot_id": snapshot_id, "lease_id": lease_id }),
+    );
+    let restored = result(&resp);
+    assert_eq!(restored["dirty"], json!([]));
+    assert_eq!(read(repo.path(), "a.txt"), "v1\n");
+}
+
+#[test]
+fn test_restoring_a_touched_snapshot_deletes_no_other_file() {
+    let repo = tempfile::tempdir().unwrap();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let native = LeaseStore::new().with_fingerprinter(Arc::new(NativeFingerprinter::new()));
+    let setup = RouterSetup {
+        lease_store: native,
+        ..Default::default()
+    };
+    let router = setup_router_with(data.path(), setup).0;
+    std::fs::write(repo.path().join("a.txt"), "v1\n").unwrap();
+    std::fs::write(repo.path().join("b.txt"), "b\n").unwrap();
+
+    let resp = call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["a.txt"] }),
+    );
+    let snapshot_id = result(&resp)["snapshot_id"].as_str().unwrap().to_string();
+    std::fs::write(repo.path().join("a.txt"), "v2\n").unwrap();
+    std::fs::write(repo.path().join("c.txt"), "new\n").unwrap();
+
+    let lease_id = lease(&router, &repo_root);
+    let resp = call(
+        &router,
+        "workspace.restore",
+        json!({ "repo_root": repo_root, "snapshot_id": snapshot_id, "lease_id": lease_id }),
+    );
+    let restored = result(&resp);
+    assert_eq!(
+        restored["files"],
+        json!([{ "path": "a.txt", "action": "overwrite" }])
+    );
+    assert_eq!(read(repo.path(), "a.txt"), "v1\n");
+    assert_eq!(read(repo.path(), "b.txt"), "b\n");
+    assert_eq!(read(repo.path(), "c.txt"), "new\n");
+}
+
+#[test]
+fn test_restore_in_a_subdirectory_sees_its_dirty_files() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let sub = repo.path().join("sub");
+    std::fs::create_dir(&sub).unwrap();
+    std::fs::write(sub.join("a.txt"), "v1\n").unwrap();
+    git(repo.path(), &["add", "."]);
+    git(repo.path(), &["commit", "-m", "sub"]);
+    let repo_root = sub.to_string_lossy().to_string();
+    let router = setup_router(data.path());
+
+    let resp = call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["a.txt"] }),
+    );
+    let snapshot_id = result(&resp)["snapshot_id"].as_str().unwrap().to_string();
+    // Uncommitted work in the subdirectory, and at the top level.
+    std::fs::write(sub.join("a.txt"), "local\n").unwrap();
+    std::fs::write(repo.path().join("a.txt"), "top\n").unwrap();
+
+    let lease_id = lease(&router, &repo_root);
+    let resp = call(
+        &router,
+        "workspace.restore",
+        json!({
+            "repo_root": repo_root,
+            "snapshot_id": snapshot_id,
+            "lease_id": lease_id,
+            "paths": ["a.txt"],
+            "dry_run": true
+        }),
+    );
+    assert_eq!(result(&resp)["dirty"], json!(["a.txt"]));
+
+    let resp = call(
+        &router,
+        "workspace.restore",
+        json!({
+            "repo_root": repo_root,
+            "snapshot_id": snapshot_id,
+            "lease_id": lease_id,
+            "paths": ["a.txt"]
+        }),
+    );
+    assert_eq!(
+        resp["error"]["data"]["code"], "INVALID_ARGUMENT",
+        "{}",
+        resp
+    );
+    assert_eq!(read(&sub, "a.txt"), "local\n");
+}
@@ -0,0 +1,268 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+mod common;
+
+use axiomregent::router::Router;
+use common::{RouterSetup, setup_router, setup_router_with};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn git(root: &Path, args: &[&str]) {
+    Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap();
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    let root = repo.path();
+    git(root, &["init"]);
+    git(root, &["config", "user.email", "test@example.com"]);
+    git(root, &["config", "user.name", "Test"]);
+    std::fs::write(root.join("a.txt"), "v1\n").unwrap();
+    std::fs::write(root.join("b.txt"), "b\n").unwrap();
+    git(root, &["add", "."]);
+    git(root, &["commit", "-m", "initial"]);
+    repo
+}
+
+fn lease(router: &Router, repo_root: &str) -> String {
+    let resp = call(
+        router,
+        "snapshot.list",
+        json!({ "repo_root": repo_root, "path": ".", "mode": "worktree" }),
+    );
+    result(&resp)["lease_id"].as_str().unwrap().to_string()
+}
+
+fn read(repo: &Path, path: &str) -> String {
+    std::fs::read_to_string(repo.join(path)).unwrap()
+}
+
+fn transaction(router: &Router, repo_root: &str, lease_id: &str, operations: Value) -> Value {
+    call(
+        router,
+        "workspace.transaction",
+        json!({ "repo_root": repo_root, "lease_id": lease_id, "operations": operations }),
+    )
+}
+
+#[test]
+fn test_transaction_applies_every_operation() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let (router, lease_store) = setup_router_with(data.path(), RouterSetup::default());
+    let lease_id = lease(&router, &repo_root);
+
+    let patch = "--- a/c.txt\n+++ b/c.txt\n@@ -1 +1 @@\n-one\n+two\n";
+    let resp = transaction(
+        &router,
+        &repo_root,
+        &lease_id,
+        json!([
+            { "op": "write", "path": "a.txt", "content_base64": "v2\n" },
+            { "op": "rename", "path": "b.txt", "to": "docs/b.md" },
+            { "op": "write", "path": "c.txt", "content_base64": "one\n" },
+            { "op": "patch", "patch": patch }
+        ]),
+    );
+    let applied = result(&resp);
+    assert_eq!(
+        applied["files"],
+        json!([
+            { "path": "a.txt", "action": "overwrite" },
+            { "path": "b.txt", "action": "delete" },
+            { "path": "c.txt", "action": "create" },
+            { "path": "docs/b.md", "action": "create" }
+        ])
+    );
+    assert_eq!(
+        applied["touched"],
+        json!(["a.txt", "b.txt", "c.txt", "docs/b.md"])
+    );
+    assert!(applied["preflight"].is_null());
+    assert_eq!(read(repo.path(), "a.txt"), "v2\n");
+    assert_eq!(read(repo.path(), "c.txt"), "two\n");
+    assert_eq!(read(repo.path(), "docs/b.md"), "b\n");
+    assert!(!repo.path().join("b.txt").exists());
+    let touched = lease_store.get_touched_files(&lease_id).unwrap();
+    assert!(touched.contains(&"docs/b.md".to_string()));
+
+    // The worktree changed under the lease: later writes take a fresh one.
+    let lease_id = lease(&router, &repo_root);
+    let resp = transaction(
+        &router,
+        &repo_root,
+        &lease_id,
+        json!([{ "op": "delete", "path": "c.txt" }]),
+    );
+    result(&resp);
+
+    // The pre-transaction snapshot undoes the first transaction by hand.
+    let lease_id = lease(&router, &repo_root);
+    let snapshot_id = applied["snapshot_id"].as_str().unwrap();
+    let resp = call(
+        &router,
+        "workspace.restore",
+        json!({
+            "repo_root": repo_root,
+            "snapshot_id": snapshot_id,
+            "lease_id": lease_id,
+            "paths": ["a.txt", "b.txt", "docs/b.md"],
+            "force": true
+        }),
+    );
+    result(&resp);
+    assert_eq!(read(repo.path(), "a.txt"), "v1\n");
+    assert_eq!(read(repo.path(), "b.txt"), "b\n");
+    assert!(!repo.path().join("docs/b.md").exists());
+
+    // It is unlabeled, so retention collects it like any other snapshot.
+    let resp = call(
+        &router,
+        "snapshot.gc",
+        json!({ "repo_root": repo_root, "keep_last": 0, "grace_secs": 0 }),
+    );
+    let deleted = result(&resp)["deleted"].as_array().unwrap().clone();
+    assert!(deleted.contains(&json!(snapshot_id)), "{}", resp);
+}
+
+#[test]
+fn test_transaction_is_all_or_nothing() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    let lease_id = lease(&router, &repo_root);
+
+    let resp = transaction(
+        &router,
+        &repo_root,
+        &lease_id,
+        json!([
+            { "op": "write", "path": "a.txt", "content_base64": "v2\n" },
+            { "op": "write", "path": "new.txt", "content_base64": "new\n" },
+            { "op": "delete", "path": "missing.txt" }
+        ]),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "NOT_FOUND", "{}", resp);
+    assert!(
+        resp["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("operations[2]")
+    );
+    assert_eq!(read(repo.path(), "a.txt"), "v1\n");
+    assert!(!repo.path().join("new.txt").exists());
+
+    // A patch that does not apply.
+    let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-nope\n+v3\n";
+    let resp = transaction(
+        &router,
+        &repo_root,
+        &lease_id,
+        json!([
+            { "op": "write", "path": "b.txt", "content_base64": "base64:YjIK" },
+            { "op": "patch", "patch": patch }
+        ]),
+    );
+    assert_eq!(
+        resp["error"]["data"]["code"], "INVALID_ARGUMENT",
+        "{}",
+        resp
+    );
+    assert_eq!(read(repo.path(), "b.txt"), "b\n");
+
+    // A rename onto an existing file.
+    let resp = transaction(
+        &router,
+        &repo_root,
+        &lease_id,
+        json!([{ "op": "rename", "path": "a.txt", "to": "b.txt" }]),
+    );
+    assert_eq!(
+        resp["error"]["data"]["code"], "INVALID_ARGUMENT",
+        "{}",
+        resp
+    );
+    assert_eq!(read(repo.path(), "a.txt"), "v1\n");
+}
+
+#[test]
+fn test_transaction_runs_preflight() {
+    let repo = setup_repo();
+    let root = repo.path();
+    std::fs::create_dir_all(root.join("spec/core")).unwrap();
+    std::fs::write(
+        root.join("spec/features.yaml"),
+        "features:\n  - id: DEMO\n    title: \"Demo\"\n    spec: \"spec/core/demo.md\"\n    governance: approved\n    owner: core\n    group: core\n    depends_on: []\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("spec/core/demo.md"), "# Demo\n").unwrap();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = root.to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    let lease_id = lease(&router, &repo_root);
+
+    let operations = json!([
+        { "op": "write", "path": "docs/notes.md", "content_base64": "notes\n" },
+        { "op": "write", "path": "generated/api.txt", "content_base64": "api\n" }
+    ]);
+    let resp = call(
+        &router,
+        "workspace.transaction",
+        json!({ "repo_root": repo_root, "lease_id": lease_id, "operations": operations, "dry_run": true }),
+    );
+    let plan = result(&resp);
+    assert_eq!(plan["preflight"]["allowed"], false);
+    assert_eq!(
+        plan["preflight"]["violations"][0]["code"],
+        "EDIT_GENERATED_FILE"
+    );
+
+    let resp = transaction(&router, &repo_root, &lease_id, operations);
+    assert_eq!(
+        resp["error"]["data"]["code"], "PERMISSION_DENIED",
+        "{}",
+        resp
+    );
+    assert!(!root.join("docs/notes.md").exists());
+
+    let resp = transaction(
+        &router,
+        &repo_root,
+        &lease_id,
+        json!([{ "op": "write", "path": "docs/notes.md", "content_base64": "notes\n" }]),
+    );
+    let applied = result(&resp);
+    assert_eq!(applied["preflight"]["allowed"], true);
+    assert_eq!(applied["preflight"]["safety_tier"], "tier1");
+    assert_eq!(read(root, "docs/notes.md"), "notes\n");
+}
@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: AGPL-3.0-or-later
+// Copyright (C) 2026 Bartek Kus
+
+// File modes and symlinks are Unix-only.
+#![cfg(unix)]
+
+mod common;
+
+use axiomregent::router::Router;
+use base64::Engine;
+use common::setup_router;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+// Feature: MCP_SNAPSHOT_WORKSPACE
+// Spec: spec/core/snapshot-workspace.md
+
+fn call(router: &Router, name: &str, arguments: Value) -> Value {
+    router
+        .handle_message(
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments },
+                "id": 1
+            })
+            .to_string(),
+        )
+        .unwrap()
+}
+
+fn result(resp: &Value) -> &Value {
+    assert!(resp["error"].is_null(), "{}", resp);
+    &resp["result"]["content"][0]["json"]
+}
+
+fn setup_repo() -> tempfile::TempDir {
+    let repo = tempfile::tempdir().unwrap();
+    Command::new("git")
+        .arg("init")
+        .current_dir(repo.path())
+        .output()
+        .unwrap();
+    repo
+}
+
+fn decode(encoded: &Value) -> Vec<u8> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.as_str().unwrap().strip_prefix("base64:").unwrap())
+        .unwrap()
+}
+
+fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// A repo with ignored, untracked, executable and symlinked files.
+fn populate(root: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+    write(root, ".gitignore", "target/\n*.log\n");
+    write(root, "src/main.rs", "fn main() {}\n");
+    write(root, "run.sh", "#!/bin/sh\necho run\n");
+    std::fs::set_permissions(root.join("run.sh"), std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::os::unix::fs::symlink("src/main.rs", root.join("main.rs")).unwrap();
+    write(root, "target/debug/app", "binary");
+    write(root, "debug.log", "log");
+    Command::new("git")
+        .args(["add", ".gitignore", "src", "run.sh", "main.rs"])
+        .current_dir(root)
+        .output()
+        .unwrap();
+    write(root, "notes.txt", "untracked");
+}
+
+#[derive(Debug, PartialEq)]
+enum Exported {
+    File { mode: u32, content: String },
+    Symlink(String),
+}
+
+fn export(router: &Router, repo_root: &str, snapshot_id: &str) -> BTreeMap<String, Exported> {
+    let resp = call(
+        router,
+        "snapshot.export",
+        json!({ "repo_root": repo_root, "snapshot_id": snapshot_id }),
+    );
+    let tar_bytes = decode(&result(&resp)["bundle"]);
+    let mut archive = tar::Archive::new(&tar_bytes[..]);
+    let mut files = BTreeMap::new();
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let path = entry.path().unwrap().to_string_lossy().to_string();
+        let exported = if entry.header().entry_type() == tar::EntryType::Symlink {
+            let target = entry.link_name().unwrap().unwrap();
+            Exported::Symlink(target.to_string_lossy().to_string())
+        } else {
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut content).unwrap();
+            Exported::File {
+                mode: entry.header().mode().unwrap(),
+                content,
+            }
+        };
+        files.insert(path, exported);
+    }
+    files
+}
+
+fn create(router: &Router, repo_root: &str, scope: &str) -> String {
+    let resp = call(
+        router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "scope": scope }),
+    );
+    result(&resp)["snapshot_id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_worktree_scope_honors_gitignore_and_keeps_modes() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    populate(repo.path());
+
+    let snap = create(&router, &repo_root, "worktree");
+    let files = export(&router, &repo_root, &snap);
+    assert_eq!(
+        files.keys().collect::<Vec<_>>(),
+        [
+            ".gitignore",
+            "main.rs",
+            "notes.txt",
+            "run.sh",
+            "src/main.rs"
+        ]
+    );
+    assert_eq!(
+        files["run.sh"],
+        Exported::File {
+            mode: 0o755,
+            content: "#!/bin/sh\necho run\n".to_string()
+        }
+    );
+    assert_eq!(
+        files["src/main.rs"],
+        Exported::File {
+            mode: 0o644,
+            content: "fn main() {}\n".to_string()
+        }
+    );
+    assert_eq!(
+        files["main.rs"],
+        Exported::Symlink("src/main.rs".to_string())
+    );
+
+    let tracked = create(&router, &repo_root, "tracked");
+    let files = export(&router, &repo_root, &tracked);
+    assert_eq!(
+        files.keys().collect::<Vec<_>>(),
+        [".gitignore", "main.rs", "run.sh", "src/main.rs"]
+    );
+
+    let resp = call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "scope": "worktree", "paths": ["run.sh"] }),
+    );
+    assert_eq!(resp["error"]["data"]["code"], "INVALID_ARGUMENT");
+}
+
+#[test]
+fn test_explicit_paths_capture_symlinks() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    populate(repo.path());
+
+    let resp = call(
+        &router,
+        "snapshot.create",
+        json!({ "repo_root": repo_root, "paths": ["main.rs", "run.sh"] }),
+    );
+    let snap = result(&resp)["snapshot_id"].as_str().unwrap().to_string();
+    let files = export(&router, &repo_root, &snap);
+    assert_eq!(files.len(), 2);
+    assert_eq!(
+        files["main.rs"],
+        Exported::Symlink("src/main.rs".to_string())
+    );
+    assert!(matches!(
+        files["run.sh"],
+        Exported::File { mode: 0o755, .. }
+    ));
+}
+
+#[test]
+fn test_apply_patch_keeps_modes_and_symlinks() {
+    let repo = setup_repo();
+    let data = tempfile::tempdir().unwrap();
+    let repo_root = repo.path().to_string_lossy().to_string();
+    let router = setup_router(data.path());
+    populate(repo.path());
+
+    let base = create(&router, &repo_root, "tracked");
+    let patch = "diff --git a/run.sh b/run.sh
+--- a/run.sh
++++ b/run.sh
+@@ -1,2 +1,2 @@
+ #!/bin/sh
+-echo run
++echo patched
+";
+    let resp = call(
+        &router,
+        "workspace.apply_patch",
+        json!({
+            "repo_root": repo_root,
+            "patch": patch,
+            "mode": "snapshot",
+            "snapshot_id": base
+        }),
+    );
+    let patched = result(&resp)["snapshot_id"].as_str().unwrap().to_string();
+    let files = export(&router, &repo_root, &patched);
+    assert_eq!(
+        files["run.sh"],
+        Exported::File {
+            mode: 0o755,
+            content: "#!/bin/sh\necho patched\n".to_string()
+        }
+    );
+    assert_eq!(
+        files["main.rs"],
+        Exported::Symlink("src/main.rs".to_string())
+    );
+}